@@ -0,0 +1,108 @@
+import { BrowserStore } from '../../../rust-core/pkg/nockchain_multisig.js';
+import { initWasm } from './wasmInterface';
+
+const DB_NAME = 'nockchain-multisig';
+const DB_VERSION = 1;
+const STORE_NAME = 'kv';
+
+type StoreChange =
+  | { op: 'put'; key: string; value: string }
+  | { op: 'delete'; key: string };
+
+function promisify<T>(request: IDBRequest<T>): Promise<T> {
+  return new Promise((resolve, reject) => {
+    request.onsuccess = () => resolve(request.result);
+    request.onerror = () => reject(request.error);
+  });
+}
+
+function openDatabase(): Promise<IDBDatabase> {
+  const request = indexedDB.open(DB_NAME, DB_VERSION);
+  // Object-store layout only; data migrations run in Rust (BrowserStore).
+  request.onupgradeneeded = () => {
+    if (!request.result.objectStoreNames.contains(STORE_NAME)) {
+      request.result.createObjectStore(STORE_NAME);
+    }
+  };
+  return promisify(request);
+}
+
+async function readSnapshot(db: IDBDatabase): Promise<Record<string, string>> {
+  const store = db.transaction(STORE_NAME, 'readonly').objectStore(STORE_NAME);
+  const [keys, values] = await Promise.all([
+    promisify(store.getAllKeys()),
+    promisify(store.getAll()),
+  ]);
+  const snapshot: Record<string, string> = {};
+  keys.forEach((key, i) => {
+    snapshot[String(key)] = values[i];
+  });
+  return snapshot;
+}
+
+/**
+ * IndexedDB-backed persistence for sessions, drafts, and the address book.
+ * Reads go to the in-memory BrowserStore; call `flush()` after changes to
+ * write them back in a single IndexedDB transaction.
+ */
+export class IdbStore {
+  private constructor(
+    private readonly db: IDBDatabase,
+    readonly store: BrowserStore
+  ) {}
+
+  static async open(): Promise<IdbStore> {
+    await initWasm();
+    const db = await openDatabase();
+    const store = new BrowserStore(JSON.stringify(await readSnapshot(db)));
+    const idb = new IdbStore(db, store);
+    // Persist anything schema migrations changed.
+    await idb.flush();
+    return idb;
+  }
+
+  async flush(): Promise<void> {
+    const changes: StoreChange[] = JSON.parse(this.store.take_changes());
+    if (changes.length === 0) {
+      return;
+    }
+    const tx = this.db.transaction(STORE_NAME, 'readwrite');
+    const objects = tx.objectStore(STORE_NAME);
+    for (const change of changes) {
+      if (change.op === 'put') {
+        objects.put(change.value, change.key);
+      } else {
+        objects.delete(change.key);
+      }
+    }
+    await new Promise<void>((resolve, reject) => {
+      tx.oncomplete = () => resolve();
+      tx.onerror = () => reject(tx.error);
+      tx.onabort = () => reject(tx.error);
+    });
+  }
+
+  async saveSession(sessionJson: string): Promise<string> {
+    const key = this.store.save_session(sessionJson);
+    await this.flush();
+    return key;
+  }
+
+  listSessions(): string[] {
+    return JSON.parse(this.store.list_keys('sessions/'));
+  }
+
+  get(key: string): string | undefined {
+    return this.store.get_item(key) ?? undefined;
+  }
+
+  async set(key: string, value: string): Promise<void> {
+    this.store.set_item(key, value);
+    await this.flush();
+  }
+
+  async remove(key: string): Promise<void> {
+    this.store.remove_item(key);
+    await this.flush();
+  }
+}
@@ -0,0 +1,94 @@
+import {
+  get_verification_items as wasmGetVerificationItems,
+  validate_transaction_with_results as wasmValidateWithResults,
+} from '../../../rust-core/pkg/nockchain_multisig.js';
+import { initWasm } from './wasmInterface';
+
+export type VerifyScheme = 'Ed25519' | 'ECDSA-P256';
+
+interface VerificationItem {
+  pubkey: string;
+  message_hash: string;
+  signature: string;
+}
+
+function hexToBytes(hex: string): Uint8Array {
+  const bytes = new Uint8Array(hex.length / 2);
+  for (let i = 0; i < bytes.length; i++) {
+    bytes[i] = parseInt(hex.slice(i * 2, i * 2 + 2), 16);
+  }
+  return bytes;
+}
+
+function params(scheme: VerifyScheme) {
+  return scheme === 'Ed25519'
+    ? { key: { name: 'Ed25519' }, verify: { name: 'Ed25519' } }
+    : {
+        key: { name: 'ECDSA', namedCurve: 'P-256' },
+        verify: { name: 'ECDSA', hash: 'SHA-256' },
+      };
+}
+
+// Keys and signatures are hex: the raw public key, and the raw signature
+// (r || s for ECDSA) over the decoded message hash bytes.
+async function verifyItem(item: VerificationItem, scheme: VerifyScheme): Promise<boolean> {
+  const { key, verify } = params(scheme);
+  try {
+    const publicKey = await crypto.subtle.importKey(
+      'raw',
+      hexToBytes(item.pubkey),
+      key,
+      false,
+      ['verify']
+    );
+    return await crypto.subtle.verify(
+      verify,
+      publicKey,
+      hexToBytes(item.signature),
+      hexToBytes(item.message_hash)
+    );
+  } catch {
+    return false;
+  }
+}
+
+const support = new Map<VerifyScheme, Promise<boolean>>();
+
+// Older browsers lack Ed25519 in WebCrypto; probe once per scheme.
+export function webCryptoSupports(scheme: VerifyScheme): Promise<boolean> {
+  if (!support.has(scheme)) {
+    const probe = async () => {
+      if (typeof crypto === 'undefined' || !crypto.subtle) return false;
+      try {
+        await crypto.subtle.generateKey(params(scheme).key, false, ['sign', 'verify']);
+        return true;
+      } catch {
+        return false;
+      }
+    };
+    support.set(scheme, probe());
+  }
+  return support.get(scheme)!;
+}
+
+/**
+ * Validate a transaction, verifying every signature with WebCrypto when the
+ * browser supports the scheme. The checks run asynchronously and in
+ * parallel instead of blocking the main thread in WASM. The WASM build has
+ * no signature verifier of its own, so without WebCrypto support for the
+ * scheme this rejects rather than accept unverified signatures.
+ */
+export async function validateTransactionWithWebCrypto(
+  txJson: string,
+  scheme: VerifyScheme
+): Promise<string> {
+  await initWasm();
+  if (!(await webCryptoSupports(scheme))) {
+    throw new Error(`Signature verification unavailable: this browser cannot verify ${scheme}`);
+  }
+
+  const items: VerificationItem[] = JSON.parse(wasmGetVerificationItems(txJson));
+  const results = await Promise.all(items.map(item => verifyItem(item, scheme)));
+  const verified = items.filter((_, i) => results[i]);
+  return wasmValidateWithResults(txJson, JSON.stringify(verified));
+}
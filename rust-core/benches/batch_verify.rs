@@ -0,0 +1,120 @@
+// Batch signature verification across a coordinator's pending drafts.
+//
+//   cargo bench --bench batch_verify
+//
+// Signatures are opaque to this crate, so the verifier here is a stand-in
+// with the cost shape of ed25519: expensive per-key setup (point
+// decompression) plus cheap per-message work. Its batch path does the key
+// setup once per distinct key, which is where a real batch verifier saves
+// most; plug in a real one to measure the actual scheme.
+
+use nockchain_multisig::verify::{verify_collected, SignatureVerifier, SignedMessage};
+use nockchain_multisig::{build_transaction, PublicKey, Signature, Transaction};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+const KEY_SETUP_ROUNDS: usize = 200;
+const SIGNERS: [&str; 3] = ["a", "b", "c"];
+
+struct StandInVerifier;
+
+impl StandInVerifier {
+    fn expand(pubkey: &PublicKey) -> [u8; 32] {
+        let mut state: [u8; 32] = Sha256::digest(pubkey.0.as_bytes()).into();
+        for _ in 0..KEY_SETUP_ROUNDS {
+            state = Sha256::digest(state).into();
+        }
+        state
+    }
+
+    fn sign(pubkey: &PublicKey, message_hash: &str) -> Signature {
+        Signature(Self::check(&Self::expand(pubkey), message_hash))
+    }
+
+    fn check(expanded: &[u8; 32], message_hash: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(expanded);
+        hasher.update(message_hash.as_bytes());
+        hex::encode(hasher.finalize())
+    }
+}
+
+impl SignatureVerifier for StandInVerifier {
+    fn verify(&self, pubkey: &PublicKey, message_hash: &str, signature: &Signature) -> bool {
+        Self::check(&Self::expand(pubkey), message_hash) == signature.0
+    }
+
+    fn verify_batch(&self, items: &[SignedMessage<'_>]) -> bool {
+        let mut keys: HashMap<&PublicKey, [u8; 32]> = HashMap::new();
+        items.iter().all(|item| {
+            let expanded = keys
+                .entry(item.pubkey)
+                .or_insert_with(|| Self::expand(item.pubkey));
+            Self::check(expanded, item.message_hash) == item.signature.0
+        })
+    }
+}
+
+fn signed_draft(id: usize, spends: usize) -> Transaction {
+    let notes: Vec<String> = (0..spends)
+        .map(|i| {
+            format!(
+                r#"{{"name":{{"first":"d{id}n{i}","last":"l"}},"value":1,"lock":{{"pkh":{{"threshold":2,"pubkeys":["a","b","c"]}}}}}}"#
+            )
+        })
+        .collect();
+    let outputs = format!(
+        r#"[{{"recipient":"r{id}","value":{spends},"lock":{{"pkh":{{"threshold":1,"pubkeys":["r"]}}}}}}]"#
+    );
+    let json =
+        build_transaction(&format!("[{}]", notes.join(",")), &outputs).expect("build failed");
+    let mut tx: Transaction = serde_json::from_str(&json).expect("parse failed");
+    for spend in &mut tx.spends {
+        for signer in &SIGNERS[..2] {
+            let pubkey = PublicKey(signer.to_string());
+            let signature = StandInVerifier::sign(&pubkey, &spend.seeds.message_hash);
+            spend.seeds.add_signature(pubkey, signature);
+        }
+    }
+    tx
+}
+
+fn ms(elapsed: Duration) -> f64 {
+    elapsed.as_secs_f64() * 1e3
+}
+
+fn main() {
+    for (drafts, spends) in [(10, 5), (50, 10), (200, 10)] {
+        let txs: Vec<Transaction> = (0..drafts).map(|d| signed_draft(d, spends)).collect();
+        let signatures = drafts * spends * 2;
+
+        let start = Instant::now();
+        for tx in &txs {
+            for item in tx.verification_items().expect("invalid draft") {
+                assert!(StandInVerifier.verify(&item.pubkey, &item.message_hash, &item.signature));
+            }
+        }
+        let single = start.elapsed();
+
+        let start = Instant::now();
+        for tx in &txs {
+            tx.verify_signatures(&StandInVerifier)
+                .expect("verification failed");
+        }
+        let per_draft = start.elapsed();
+
+        let start = Instant::now();
+        let results = verify_collected(&txs, &StandInVerifier);
+        let batch = start.elapsed();
+        assert!(results.iter().all(Result::is_ok));
+
+        println!(
+            "{drafts:>4} drafts, {signatures:>5} signatures: one-by-one {:>8.2} ms, per draft {:>8.2} ms, one batch {:>8.2} ms ({:.1}x)",
+            ms(single),
+            ms(per_draft),
+            ms(batch),
+            single.as_secs_f64() / batch.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,63 @@
+// Spend-hash throughput for large consolidations.
+//
+//   cargo bench --bench spend_hash
+//   cargo bench --bench spend_hash --features hash-soft   # portable backend
+//
+// Compare the two runs to see what hardware SHA-256 buys on this machine.
+
+use nockchain_multisig::hashing::active_backend;
+use nockchain_multisig::build_transaction;
+use sha2::{Digest, Sha256};
+use std::time::Instant;
+
+fn notes_json(count: usize) -> String {
+    let notes: Vec<String> = (0..count)
+        .map(|i| {
+            format!(
+                r#"{{"name":{{"first":"n{i}","last":"l{i}"}},"value":1,"lock":{{"pkh":{{"threshold":2,"pubkeys":["a","b","c"]}}}}}}"#
+            )
+        })
+        .collect();
+    format!("[{}]", notes.join(","))
+}
+
+fn outputs_json(count: usize, total: usize) -> String {
+    let outputs: Vec<String> = (0..count)
+        .map(|i| {
+            let value = total / count + usize::from(i < total % count);
+            format!(
+                r#"{{"recipient":"r{i}","value":{value},"lock":{{"pkh":{{"threshold":1,"pubkeys":["r{i}"]}}}}}}"#
+            )
+        })
+        .collect();
+    format!("[{}]", outputs.join(","))
+}
+
+fn main() {
+    println!("backend: {}", active_backend().name());
+
+    let block = vec![0x5au8; 16 * 1024 * 1024];
+    let start = Instant::now();
+    let digest = Sha256::digest(&block);
+    let elapsed = start.elapsed();
+    println!(
+        "raw sha256: {:.1} MiB/s ({:02x}..)",
+        16.0 / elapsed.as_secs_f64(),
+        digest[0]
+    );
+
+    for (spends, outputs) in [(100, 10), (500, 50), (2_000, 100)] {
+        let notes = notes_json(spends);
+        let outs = outputs_json(outputs, spends);
+
+        let start = Instant::now();
+        build_transaction(&notes, &outs).expect("build failed");
+        let build = start.elapsed();
+
+        println!(
+            "{spends:>5} spends x {outputs:>3} outputs: build {:>9.3} ms ({:>6.2} us/spend)",
+            build.as_secs_f64() * 1e3,
+            build.as_secs_f64() * 1e6 / spends as f64,
+        );
+    }
+}
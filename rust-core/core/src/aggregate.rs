@@ -0,0 +1,89 @@
+use crate::merkle::{merkle_root, MerkleProof};
+use crate::{PkhCondition, PublicKey, Signature, Transaction};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// ============================================================================
+// Aggregate Signing
+// ============================================================================
+
+// When every spend is locked to the same signer set, a signer may sign the
+// Merkle root of all spend hashes once instead of each spend hash. The
+// signature is stored in every spend like any other, and the spend lists
+// the pubkey in `seeds.aggregated` so verification checks it against the
+// root. Threshold counting is unchanged, and both modes can be mixed within
+// one transaction.
+
+impl Transaction {
+    pub fn spend_hashes(&self) -> Vec<String> {
+        self.spends
+            .iter()
+            .map(|s| s.seeds.message_hash.clone())
+            .collect()
+    }
+
+    pub fn aggregate_signers(&self) -> Result<&PkhCondition, String> {
+        let first = self
+            .spends
+            .first()
+            .ok_or("Transaction has no spends")?
+            .signers()?;
+        for spend in &self.spends[1..] {
+            if spend.signers()? != first {
+                return Err("Aggregate signing needs every spend to share one signer set".into());
+            }
+        }
+        Ok(first)
+    }
+
+    // What a signer signs in aggregate mode.
+    pub fn aggregate_digest(&self) -> Result<String, String> {
+        self.aggregate_signers()?;
+        merkle_root(self.hash_algorithm, &self.spend_hashes())
+    }
+
+    // Lets a signer (or an auditor shown a single spend) check that the spend
+    // is covered by the aggregate digest.
+    pub fn spend_inclusion_proof(&self, spend_index: usize) -> Result<MerkleProof, String> {
+        self.aggregate_signers()?;
+        MerkleProof::generate(self.hash_algorithm, &self.spend_hashes(), spend_index)
+    }
+
+    pub fn add_aggregate_signature(
+        &mut self,
+        pubkey: PublicKey,
+        signature: Signature,
+    ) -> Result<(), String> {
+        if !self.aggregate_signers()?.pubkeys.contains(&pubkey) {
+            return Err("Public key not allowed for these spends".into());
+        }
+        for spend in &mut self.spends {
+            spend.seeds.add_signature(pubkey.clone(), signature.clone());
+            spend.seeds.aggregated.push(pubkey.clone());
+        }
+        Ok(())
+    }
+
+    // The aggregate digest, if any spend carries an aggregate signature.
+    pub fn signing_root(&self) -> Result<Option<String>, String> {
+        if self.spends.iter().all(|s| s.seeds.aggregated.is_empty()) {
+            return Ok(None);
+        }
+        self.aggregate_digest().map(Some)
+    }
+
+    pub fn validate_aggregates(&self) -> Result<(), String> {
+        for (i, spend) in self.spends.iter().enumerate() {
+            for pubkey in &spend.seeds.aggregated {
+                if !spend.seeds.has_signature(pubkey) {
+                    return Err(alloc::format!(
+                        "Spend {} marks {} as aggregate without a signature",
+                        i,
+                        pubkey.0
+                    ));
+                }
+            }
+        }
+        self.signing_root().map(|_| ())
+    }
+}
@@ -0,0 +1,249 @@
+use crate::hashing::HashAlgorithm;
+use crate::lineage::Lineage;
+use crate::network::Network;
+use crate::schema::SchemaVersion;
+use crate::{Lock, Note, NoteName, Output, PublicKey, Seeds, Signature, Spend, Transaction};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Arena Representation
+// ============================================================================
+
+// Large payouts repeat the same few locks and keys thousands of times. The
+// arena stores each distinct lock and pubkey once and refers to them by
+// index, so a 10k-output draft holds a handful of locks instead of 10k
+// copies. Signatures live in per-spend tables of (pubkey index, signature),
+// which lets a merge touch only those tables rather than cloning the draft.
+// Converting back yields the identical `Transaction`, so spend hashes are
+// unaffected.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct KeyId(pub u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct LockId(pub u32);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ArenaSpend {
+    pub name: NoteName,
+    pub value: u64,
+    pub lock: LockId,
+    #[serde(default, skip_serializing_if = "Network::is_default")]
+    pub network: Network,
+    #[serde(default, skip_serializing_if = "crate::is_false")]
+    pub coinbase: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirmed_at: Option<u64>,
+    pub signatures: Vec<(KeyId, Signature)>,
+    // Everything else in the seeds; its `signatures` is always empty.
+    pub seeds: Seeds,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ArenaOutput {
+    pub recipient: String,
+    pub value: u64,
+    pub lock: LockId,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TransactionArena {
+    #[serde(default, skip_serializing_if = "SchemaVersion::is_current")]
+    pub schema_version: SchemaVersion,
+    pub keys: Vec<PublicKey>,
+    pub locks: Vec<Lock>,
+    pub spends: Vec<ArenaSpend>,
+    pub outputs: Vec<ArenaOutput>,
+    #[serde(default, skip_serializing_if = "HashAlgorithm::is_default")]
+    pub hash_algorithm: HashAlgorithm,
+    #[serde(default, skip_serializing_if = "Network::is_default")]
+    pub network: Network,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lineage: Option<Lineage>,
+    #[serde(skip)]
+    key_index: BTreeMap<PublicKey, KeyId>,
+    #[serde(skip)]
+    lock_index: BTreeMap<Vec<u8>, LockId>,
+}
+
+impl TransactionArena {
+    pub fn intern_key(&mut self, pubkey: &PublicKey) -> KeyId {
+        if self.key_index.len() != self.keys.len() {
+            self.reindex();
+        }
+        if let Some(id) = self.key_index.get(pubkey) {
+            return *id;
+        }
+        let id = KeyId(self.keys.len() as u32);
+        self.keys.push(pubkey.clone());
+        self.key_index.insert(pubkey.clone(), id);
+        id
+    }
+
+    pub fn intern_lock(&mut self, lock: &Lock) -> LockId {
+        if self.lock_index.len() != self.locks.len() {
+            self.reindex();
+        }
+        let encoded = serde_json::to_vec(lock).expect("Serialization failed");
+        if let Some(id) = self.lock_index.get(&encoded) {
+            return *id;
+        }
+        let id = LockId(self.locks.len() as u32);
+        self.locks.push(lock.clone());
+        self.lock_index.insert(encoded, id);
+        id
+    }
+
+    pub fn key(&self, id: KeyId) -> Result<&PublicKey, String> {
+        self.keys
+            .get(id.0 as usize)
+            .ok_or_else(|| format!("Unknown key id {}", id.0))
+    }
+
+    pub fn lock(&self, id: LockId) -> Result<&Lock, String> {
+        self.locks
+            .get(id.0 as usize)
+            .ok_or_else(|| format!("Unknown lock id {}", id.0))
+    }
+
+    // The intern tables are not serialized; rebuilt on first use.
+    fn reindex(&mut self) {
+        self.key_index = self
+            .keys
+            .iter()
+            .enumerate()
+            .map(|(i, k)| (k.clone(), KeyId(i as u32)))
+            .collect();
+        self.lock_index = self
+            .locks
+            .iter()
+            .enumerate()
+            .map(|(i, l)| {
+                let encoded = serde_json::to_vec(l).expect("Serialization failed");
+                (encoded, LockId(i as u32))
+            })
+            .collect();
+    }
+
+    pub fn from_transaction(tx: &Transaction) -> Self {
+        let mut arena = Self {
+            schema_version: tx.schema_version,
+            hash_algorithm: tx.hash_algorithm,
+            network: tx.network,
+            lineage: tx.lineage.clone(),
+            ..Self::default()
+        };
+
+        for spend in &tx.spends {
+            let lock = arena.intern_lock(&spend.note.lock);
+            let signatures = spend
+                .seeds
+                .signatures
+                .iter()
+                .map(|(pk, sig)| (arena.intern_key(pk), sig.clone()))
+                .collect();
+            let mut seeds = spend.seeds.clone();
+            seeds.signatures.clear();
+            arena.spends.push(ArenaSpend {
+                name: spend.note.name.clone(),
+                value: spend.note.value,
+                lock,
+                network: spend.note.network,
+                coinbase: spend.note.coinbase,
+                confirmed_at: spend.note.confirmed_at,
+                signatures,
+                seeds,
+            });
+        }
+
+        for output in &tx.outputs {
+            let lock = arena.intern_lock(&output.lock);
+            arena.outputs.push(ArenaOutput {
+                recipient: output.recipient.clone(),
+                value: output.value,
+                lock,
+            });
+        }
+
+        arena
+    }
+
+    pub fn to_transaction(&self) -> Result<Transaction, String> {
+        let mut spends = Vec::with_capacity(self.spends.len());
+        for spend in &self.spends {
+            let mut seeds = spend.seeds.clone();
+            seeds.signatures = spend
+                .signatures
+                .iter()
+                .map(|(id, sig)| Ok((self.key(*id)?.clone(), sig.clone())))
+                .collect::<Result<_, String>>()?;
+            spends.push(Spend {
+                note: Note {
+                    name: spend.name.clone(),
+                    value: spend.value,
+                    lock: self.lock(spend.lock)?.clone(),
+                    network: spend.network,
+                    coinbase: spend.coinbase,
+                    confirmed_at: spend.confirmed_at,
+                },
+                seeds,
+            });
+        }
+
+        let outputs = self
+            .outputs
+            .iter()
+            .map(|o| {
+                Ok(Output {
+                    recipient: o.recipient.clone(),
+                    value: o.value,
+                    lock: self.lock(o.lock)?.clone(),
+                })
+            })
+            .collect::<Result<_, String>>()?;
+
+        let mut tx = Transaction::new(spends, outputs);
+        tx.schema_version = self.schema_version;
+        tx.set_hash_algorithm(self.hash_algorithm)?;
+        tx.set_network(self.network);
+        tx.set_lineage(self.lineage.clone());
+        Ok(tx)
+    }
+
+    // Adopts signatures `other` has for pubkeys this draft has not signed
+    // with yet. Existing signatures are never replaced; conflict handling is
+    // the session's job. Returns how many were added.
+    pub fn merge_signatures(&mut self, other: &TransactionArena) -> Result<usize, String> {
+        if self.spends.len() != other.spends.len()
+            || self
+                .spends
+                .iter()
+                .zip(&other.spends)
+                .any(|(a, b)| a.seeds.message_hash != b.seeds.message_hash)
+        {
+            return Err("Cannot merge signatures from a different draft".into());
+        }
+
+        let mut added = 0;
+        for (i, theirs) in other.spends.iter().enumerate() {
+            for (id, signature) in &theirs.signatures {
+                let key = self.intern_key(other.key(*id)?);
+                let ours = &mut self.spends[i].signatures;
+                if !ours.iter().any(|(k, _)| *k == key) {
+                    ours.push((key, signature.clone()));
+                    added += 1;
+                }
+            }
+        }
+        Ok(added)
+    }
+}
@@ -0,0 +1,363 @@
+use crate::hashing::HashAlgorithm;
+use crate::limits::TransactionLimits;
+use crate::rng::{random_hex, RandomSource, SeededRng};
+use crate::shuffle::{note_key, output_key, permute, shuffle_rng, shuffled_order};
+use crate::{Lock, Note, Output, Transaction};
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Build Options
+// ============================================================================
+
+// Optional passes applied to the raw notes and outputs before a draft is
+// hashed. Every option defaults to leaving the input exactly as given, so
+// `BuildOptions::default()` builds the same draft as `Transaction::build`.
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateOutputs {
+    #[default]
+    Keep,
+    // Outputs paying the same recipient and lock become one, at the position
+    // of the first, carrying the summed value.
+    Merge,
+    // Kept as given, but reported.
+    Warn,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BuildOptions {
+    pub limits: TransactionLimits,
+    pub duplicates: DuplicateOutputs,
+    // Outputs above this are split into near-equal parts (after any merge).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_output_value: Option<u64>,
+    // Where whatever the outputs leave of the inputs goes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub change: Option<ChangeOptions>,
+    // Reorders spends and outputs after every other pass.
+    #[serde(skip_serializing_if = "crate::is_false")]
+    pub shuffle: bool,
+    // Hex; required by the randomizing passes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<String>,
+}
+
+fn one() -> usize {
+    1
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChangeOptions {
+    pub recipient: String,
+    pub lock: Lock,
+    // Place change at random positions instead of last.
+    #[serde(default)]
+    pub randomize_position: bool,
+    // Split change into up to this many round-looking amounts.
+    #[serde(default = "one")]
+    pub parts: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "warning", rename_all = "snake_case", deny_unknown_fields)]
+pub enum BuildWarning {
+    // Indices into the outputs as given.
+    DuplicateOutput {
+        recipient: String,
+        indices: Vec<usize>,
+        merged: bool,
+    },
+}
+
+// Accounting record for a payment split across several outputs. It is not
+// part of the draft, so the outputs themselves stay unlinked on chain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PaymentSplit {
+    pub recipient: String,
+    pub value: u64,
+    // Indices into the built transaction's outputs.
+    pub outputs: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BuildReport {
+    pub transaction: Transaction,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<BuildWarning>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub splits: Vec<PaymentSplit>,
+    // Indices of the change outputs, for the wallet's own bookkeeping.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub change: Vec<usize>,
+}
+
+// Groups of output indices paying the same recipient and lock, in order of
+// first appearance. Singletons are left out.
+fn duplicate_groups(outputs: &[Output]) -> Vec<Vec<usize>> {
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut seen: BTreeMap<(&str, String), usize> = BTreeMap::new();
+    for (i, output) in outputs.iter().enumerate() {
+        let key = (output.recipient.as_str(), output.lock.fingerprint());
+        match seen.get(&key) {
+            Some(&g) => groups[g].push(i),
+            None => {
+                seen.insert(key, groups.len());
+                groups.push(alloc::vec![i]);
+            }
+        }
+    }
+    groups.retain(|g| g.len() > 1);
+    groups
+}
+
+pub fn normalize_outputs(
+    outputs: Vec<Output>,
+    mode: DuplicateOutputs,
+) -> Result<(Vec<Output>, Vec<BuildWarning>), String> {
+    if mode == DuplicateOutputs::Keep {
+        return Ok((outputs, Vec::new()));
+    }
+    let groups = duplicate_groups(&outputs);
+    let merge = mode == DuplicateOutputs::Merge;
+    let warnings = groups
+        .iter()
+        .map(|g| BuildWarning::DuplicateOutput {
+            recipient: outputs[g[0]].recipient.clone(),
+            indices: g.clone(),
+            merged: merge,
+        })
+        .collect();
+    if !merge {
+        return Ok((outputs, warnings));
+    }
+
+    let mut values: Vec<Option<u64>> = outputs.iter().map(|o| Some(o.value)).collect();
+    for group in &groups {
+        let mut total = 0u64;
+        for &i in group {
+            total = total
+                .checked_add(outputs[i].value)
+                .ok_or("Merged output value overflows")?;
+            values[i] = None;
+        }
+        values[group[0]] = Some(total);
+    }
+    let merged = outputs
+        .into_iter()
+        .zip(values)
+        .filter_map(|(output, value)| value.map(|value| Output { value, ..output }))
+        .collect();
+    Ok((merged, warnings))
+}
+
+// The fewest outputs of at most `max` each, with values differing by at
+// most one so no part stands out as the remainder.
+pub fn split_output(output: &Output, max: u64) -> Result<Vec<Output>, String> {
+    if max == 0 {
+        return Err("Per-output maximum must be positive".into());
+    }
+    let parts = output.value.div_ceil(max).max(1);
+    let (base, extra) = (output.value / parts, output.value % parts);
+    Ok((0..parts)
+        .map(|i| Output {
+            value: base + u64::from(i < extra),
+            ..output.clone()
+        })
+        .collect())
+}
+
+pub fn split_outputs(
+    outputs: Vec<Output>,
+    max: u64,
+) -> Result<(Vec<Output>, Vec<PaymentSplit>), String> {
+    let mut split = Vec::with_capacity(outputs.len());
+    let mut splits = Vec::new();
+    for output in outputs {
+        let parts = split_output(&output, max)?;
+        if parts.len() > 1 {
+            splits.push(PaymentSplit {
+                recipient: output.recipient.clone(),
+                value: output.value,
+                outputs: (split.len()..split.len() + parts.len()).collect(),
+            });
+        }
+        split.extend(parts);
+    }
+    Ok((split, splits))
+}
+
+// Rounds down to two significant digits, the kind of amount people send.
+fn round_amount(value: u64) -> u64 {
+    let mut unit = 1;
+    while value / unit >= 100 {
+        unit *= 10;
+    }
+    value - value % unit
+}
+
+// Each part but the last takes a quarter to three quarters of what remains,
+// rounded; the last takes the rest.
+fn denominations(total: u64, parts: usize, rng: &mut SeededRng) -> Vec<u64> {
+    let mut values = Vec::with_capacity(parts);
+    let mut remaining = total;
+    for _ in 1..parts {
+        let share = remaining / 4 + rng.below(remaining / 2 + 1);
+        let value = round_amount(share);
+        if value == 0 || value >= remaining {
+            break;
+        }
+        values.push(value);
+        remaining -= value;
+    }
+    values.push(remaining);
+    values
+}
+
+impl BuildOptions {
+    // Draws a fresh 32-byte seed for the randomizing passes. Cosigners
+    // rebuilding the draft need the same seed, so share it with the draft.
+    pub fn with_random_seed(mut self, rng: &mut dyn RandomSource) -> Result<Self, String> {
+        self.seed = Some(random_hex(rng, 32)?);
+        Ok(self)
+    }
+}
+
+fn seed(options: &BuildOptions, purpose: &str) -> Result<Vec<u8>, String> {
+    let seed = options
+        .seed
+        .as_deref()
+        .ok_or_else(|| alloc::format!("{} needs a seed", purpose))?;
+    Ok(hex::decode(seed).map_err(|_| "Seed must be hex")?)
+}
+
+// Outputs on their way into a draft, with the report indices that must
+// follow them as passes reorder and extend the list.
+struct Planned {
+    outputs: Vec<Output>,
+    splits: Vec<PaymentSplit>,
+    change: Vec<usize>,
+}
+
+impl Planned {
+    // New position `i` takes the output at old position `order[i]`.
+    fn reorder(&mut self, order: &[usize]) {
+        let mut moved_to = alloc::vec![0; order.len()];
+        for (new, &old) in order.iter().enumerate() {
+            moved_to[old] = new;
+        }
+        self.outputs = permute(core::mem::take(&mut self.outputs), order);
+        for split in &mut self.splits {
+            for i in &mut split.outputs {
+                *i = moved_to[*i];
+            }
+        }
+        for i in &mut self.change {
+            *i = moved_to[*i];
+        }
+    }
+
+    fn add_change(
+        &mut self,
+        notes: &[Note],
+        change: &ChangeOptions,
+        options: &BuildOptions,
+    ) -> Result<(), String> {
+        let inputs: u128 = notes.iter().map(|n| n.value as u128).sum();
+        let outputs: u128 = self.outputs.iter().map(|o| o.value as u128).sum();
+        let total = inputs
+            .checked_sub(outputs)
+            .ok_or("Outputs exceed the notes being spent")?;
+        let total = u64::try_from(total).map_err(|_| "Change value overflows")?;
+        if total == 0 {
+            return Ok(());
+        }
+
+        let randomized = change.parts > 1 || change.randomize_position;
+        let mut rng = if randomized {
+            Some(SeededRng::new(&seed(options, "Change randomization")?))
+        } else {
+            None
+        };
+        let values = match rng.as_mut() {
+            Some(rng) if change.parts > 1 => denominations(total, change.parts, rng),
+            _ => alloc::vec![total],
+        };
+        let start = self.outputs.len();
+        for value in values {
+            self.change.push(self.outputs.len());
+            self.outputs.push(Output {
+                recipient: change.recipient.clone(),
+                value,
+                lock: change.lock.clone(),
+            });
+        }
+
+        if let (Some(rng), true) = (rng.as_mut(), change.randomize_position) {
+            // Payments keep their relative order; each change output is
+            // dropped into a random gap.
+            let mut order: Vec<usize> = (0..start).collect();
+            for i in start..self.outputs.len() {
+                let at = rng.below(order.len() as u64 + 1) as usize;
+                order.insert(at, i);
+            }
+            self.reorder(&order);
+        }
+        Ok(())
+    }
+}
+
+impl Transaction {
+    // Limit violations fail with the JSON form of `LimitExceeded`.
+    pub fn build_with(
+        notes: Vec<Note>,
+        outputs: Vec<Output>,
+        algorithm: HashAlgorithm,
+        options: &BuildOptions,
+    ) -> Result<BuildReport, String> {
+        options
+            .limits
+            .check_parts(&notes, &outputs)
+            .map_err(|e| e.to_json())?;
+        let (outputs, warnings) = normalize_outputs(outputs, options.duplicates)?;
+        let (outputs, splits) = match options.max_output_value {
+            Some(max) => split_outputs(outputs, max)?,
+            None => (outputs, Vec::new()),
+        };
+        let mut planned = Planned {
+            outputs,
+            splits,
+            change: Vec::new(),
+        };
+        if let Some(change) = &options.change {
+            planned.add_change(&notes, change, options)?;
+        }
+        let mut notes = notes;
+        if options.shuffle {
+            let mut rng = shuffle_rng(&seed(options, "Shuffling")?);
+            let keys: Vec<_> = notes.iter().map(note_key).collect();
+            notes = permute(notes, &shuffled_order(&keys, &mut rng));
+            let keys: Vec<_> = planned.outputs.iter().map(output_key).collect();
+            planned.reorder(&shuffled_order(&keys, &mut rng));
+        }
+
+        let transaction = Transaction::build(notes, planned.outputs, algorithm)?;
+        options
+            .limits
+            .check(&transaction)
+            .map_err(|e| e.to_json())?;
+        Ok(BuildReport {
+            transaction,
+            warnings,
+            splits: planned.splits,
+            change: planned.change,
+        })
+    }
+}
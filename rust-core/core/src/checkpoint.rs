@@ -0,0 +1,104 @@
+use crate::hashing::{digest_hex, HashAlgorithm};
+use crate::merkle::merkle_root;
+use crate::network::Network;
+use crate::spv::{note_commitment, EMPTY_ROOT};
+use crate::verify::{SignatureVerifier, VerificationItem};
+use crate::{Note, PkhCondition, PublicKey, Signature};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Signed Checkpoints
+// ============================================================================
+
+// Lets a new watch-only wallet start scanning at a recent height instead of
+// genesis. A checkpoint names a block and commits to the full unspent-note
+// set at that block; it counts once a threshold of the signers the wallet
+// trusts (the app's release keys, or the wallet's own cosigners) have signed
+// it. Anyone holding the note set can recompute `state_hash` to audit a
+// checkpoint before signing.
+
+const CHECKPOINT_DOMAIN: &[u8] = b"nockchain-multisig/checkpoint/v1";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Checkpoint {
+    #[serde(default, skip_serializing_if = "Network::is_default")]
+    pub network: Network,
+    pub height: u64,
+    pub block_hash: String,
+    pub state_hash: String,
+}
+
+impl Checkpoint {
+    // What checkpoint signers sign.
+    pub fn message_hash(&self) -> String {
+        let bytes = serde_json::to_vec(self).expect("Serialization failed");
+        digest_hex(HashAlgorithm::Sha256, &[CHECKPOINT_DOMAIN, &bytes].concat())
+            .expect("sha256 is always available")
+    }
+}
+
+// Merkle root over the sorted commitments of every unspent note.
+pub fn state_hash(notes: &[Note]) -> Result<String, String> {
+    if notes.is_empty() {
+        return Ok(EMPTY_ROOT.into());
+    }
+    let mut commitments = notes
+        .iter()
+        .map(|n| note_commitment(HashAlgorithm::Sha256, n))
+        .collect::<Result<Vec<_>, _>>()?;
+    commitments.sort();
+    merkle_root(HashAlgorithm::Sha256, &commitments)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SignedCheckpoint {
+    pub checkpoint: Checkpoint,
+    pub signatures: Vec<(PublicKey, Signature)>,
+}
+
+impl SignedCheckpoint {
+    pub fn verification_items(&self) -> Vec<VerificationItem> {
+        let message_hash = self.checkpoint.message_hash();
+        self.signatures
+            .iter()
+            .map(|(pubkey, signature)| VerificationItem {
+                pubkey: pubkey.clone(),
+                message_hash: message_hash.clone(),
+                signature: signature.clone(),
+            })
+            .collect()
+    }
+
+    // Signatures from keys outside `signers` are ignored, as are repeats.
+    pub fn verify(
+        &self,
+        signers: &PkhCondition,
+        verifier: &dyn SignatureVerifier,
+    ) -> Result<&Checkpoint, String> {
+        signers.validate()?;
+        let message_hash = self.checkpoint.message_hash();
+        let mut counted: Vec<&PublicKey> = Vec::new();
+        for (pubkey, signature) in &self.signatures {
+            if !signers.pubkeys.contains(pubkey) || counted.contains(&pubkey) {
+                continue;
+            }
+            if !verifier.verify(pubkey, &message_hash, signature) {
+                return Err(format!("Checkpoint signature from {} is invalid", pubkey.0));
+            }
+            counted.push(pubkey);
+        }
+        if counted.len() < signers.threshold {
+            return Err(format!(
+                "Checkpoint has {} of {} required signatures",
+                counted.len(),
+                signers.threshold
+            ));
+        }
+        Ok(&self.checkpoint)
+    }
+}
@@ -0,0 +1,156 @@
+use crate::hashing::{digest_hex, HashAlgorithm};
+use crate::{PublicKey, Signature, Transaction};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Delegated Signing
+// ============================================================================
+
+// A grant lets `delegate` sign in place of `delegator`, within limits. Grants
+// may chain (A -> B -> C); each hop is signed by its delegator and every
+// hop's limits apply. The delegated signature occupies the original
+// cosigner's slot, so threshold counting is unchanged.
+
+const GRANT_DOMAIN: &[u8] = b"nockchain-multisig/delegation/v1";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DelegationGrant {
+    pub delegator: PublicKey,
+    pub delegate: PublicKey,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_value: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at_height: Option<u64>,
+    pub signature: Signature,
+}
+
+#[derive(Serialize)]
+struct GrantTerms<'a> {
+    delegator: &'a PublicKey,
+    delegate: &'a PublicKey,
+    max_value: Option<u64>,
+    expires_at_height: Option<u64>,
+}
+
+impl DelegationGrant {
+    // What the delegator signs: every field except the signature itself.
+    pub fn message(&self) -> String {
+        let terms = GrantTerms {
+            delegator: &self.delegator,
+            delegate: &self.delegate,
+            max_value: self.max_value,
+            expires_at_height: self.expires_at_height,
+        };
+        let bytes = serde_json::to_vec(&terms).expect("Serialization failed");
+        digest_hex(HashAlgorithm::Sha256, &[GRANT_DOMAIN, &bytes].concat())
+            .expect("sha256 is always available")
+    }
+
+    pub fn permits(&self, value: u64, height: Option<u64>) -> Result<(), String> {
+        if let Some(max) = self.max_value {
+            if value >= max {
+                return Err(format!(
+                    "Delegation from {} only covers transactions under {}",
+                    self.delegator.0, max
+                ));
+            }
+        }
+        if let (Some(expiry), Some(height)) = (self.expires_at_height, height) {
+            if height > expiry {
+                return Err(format!(
+                    "Delegation from {} expired at height {}",
+                    self.delegator.0, expiry
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Delegation {
+    pub signer: PublicKey,
+    pub chain: Vec<DelegationGrant>,
+}
+
+impl Delegation {
+    pub fn effective_signer(&self) -> &PublicKey {
+        self.chain.last().map_or(&self.signer, |g| &g.delegate)
+    }
+
+    pub fn validate_chain(&self) -> Result<(), String> {
+        let first = self.chain.first().ok_or("Delegation chain is empty")?;
+        if first.delegator != self.signer {
+            return Err("Delegation chain does not start at the signer".into());
+        }
+        for pair in self.chain.windows(2) {
+            if pair[0].delegate != pair[1].delegator {
+                return Err("Delegation chain is broken".into());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn permits(&self, value: u64, height: Option<u64>) -> Result<(), String> {
+        for grant in &self.chain {
+            grant.permits(value, height)?;
+        }
+        Ok(())
+    }
+}
+
+impl Transaction {
+    // Structural and scope checks. Expiry is only checked when a height is
+    // known; grant signatures are checked by `verify_signatures`.
+    pub fn validate_delegations(&self, height: Option<u64>) -> Result<(), String> {
+        (0..self.spends.len()).try_for_each(|i| self.validate_spend_delegations(i, height))
+    }
+
+    pub fn validate_spend_delegations(
+        &self,
+        index: usize,
+        height: Option<u64>,
+    ) -> Result<(), String> {
+        let spend = self
+            .spends
+            .get(index)
+            .ok_or_else(|| format!("No spend {}", index))?;
+        let value = self.total_output();
+        for delegation in &spend.seeds.delegations {
+            if !spend.seeds.has_signature(&delegation.signer) {
+                return Err(format!(
+                    "Spend {} carries a delegation without a signature",
+                    index
+                ));
+            }
+            delegation
+                .validate_chain()
+                .and_then(|_| delegation.permits(value, height))
+                .map_err(|e| format!("Spend {}: {}", index, e))?;
+        }
+        Ok(())
+    }
+
+    pub fn delegation_grants(&self) -> impl Iterator<Item = &DelegationGrant> {
+        self.spends
+            .iter()
+            .flat_map(|s| &s.seeds.delegations)
+            .flat_map(|d| &d.chain)
+    }
+}
+
+pub fn delegations_from<'a>(
+    delegations: &'a [Delegation],
+    signer: &PublicKey,
+) -> Option<&'a Delegation> {
+    delegations.iter().find(|d| &d.signer == signer)
+}
+
+pub fn grant_messages(tx: &Transaction) -> Vec<(&DelegationGrant, String)> {
+    tx.delegation_grants().map(|g| (g, g.message())).collect()
+}
@@ -0,0 +1,122 @@
+use crate::lineage::Lineage;
+use crate::network::Network;
+use crate::{Lock, NoteName, Output, Transaction};
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Draft Diffs
+// ============================================================================
+
+// What changed between the draft a signer last approved and the one they
+// are asked to approve now. Outputs are matched by recipient, in order, so
+// an edited amount or lock shows up as a change rather than a removal plus
+// an addition.
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutputChange {
+    pub recipient: String,
+    pub before: u64,
+    pub after: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LockChange {
+    // An output recipient, or a spend's note name as `first/last`.
+    pub subject: String,
+    pub before: Lock,
+    pub after: Lock,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TransactionDiff {
+    pub outputs_added: Vec<Output>,
+    pub outputs_removed: Vec<Output>,
+    pub amounts_changed: Vec<OutputChange>,
+    pub locks_changed: Vec<LockChange>,
+    pub spends_added: Vec<NoteName>,
+    pub spends_removed: Vec<NoteName>,
+    // Change in inputs minus outputs; zero for balanced drafts.
+    pub fee_delta: i128,
+    pub total_output_delta: i128,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<(Network, Network)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lineage: Option<(Option<Lineage>, Option<Lineage>)>,
+}
+
+impl TransactionDiff {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+fn fee(tx: &Transaction) -> i128 {
+    tx.total_input() as i128 - tx.total_output() as i128
+}
+
+impl Transaction {
+    pub fn diff(&self, after: &Transaction) -> TransactionDiff {
+        let mut diff = TransactionDiff {
+            fee_delta: fee(after) - fee(self),
+            total_output_delta: after.total_output() as i128 - self.total_output() as i128,
+            ..TransactionDiff::default()
+        };
+
+        let mut unmatched: Vec<&Output> = after.outputs.iter().collect();
+        for old in &self.outputs {
+            let Some(pos) = unmatched.iter().position(|o| o.recipient == old.recipient) else {
+                diff.outputs_removed.push(old.clone());
+                continue;
+            };
+            let new = unmatched.remove(pos);
+            if new.value != old.value {
+                diff.amounts_changed.push(OutputChange {
+                    recipient: old.recipient.clone(),
+                    before: old.value,
+                    after: new.value,
+                });
+            }
+            if new.lock != old.lock {
+                diff.locks_changed.push(LockChange {
+                    subject: old.recipient.clone(),
+                    before: old.lock.clone(),
+                    after: new.lock.clone(),
+                });
+            }
+        }
+        diff.outputs_added = unmatched.into_iter().cloned().collect();
+
+        for old in &self.spends {
+            match after.spends.iter().find(|s| s.note.name == old.note.name) {
+                Some(new) if new.note.lock != old.note.lock => {
+                    diff.locks_changed.push(LockChange {
+                        subject: alloc::format!("{}/{}", old.note.name.first, old.note.name.last),
+                        before: old.note.lock.clone(),
+                        after: new.note.lock.clone(),
+                    });
+                }
+                Some(_) => {}
+                None => diff.spends_removed.push(old.note.name.clone()),
+            }
+        }
+        diff.spends_added = after
+            .spends
+            .iter()
+            .filter(|s| !self.spends.iter().any(|o| o.note.name == s.note.name))
+            .map(|s| s.note.name.clone())
+            .collect();
+
+        if self.network != after.network {
+            diff.network = Some((self.network, after.network));
+        }
+        if self.lineage != after.lineage {
+            diff.lineage = Some((self.lineage.clone(), after.lineage.clone()));
+        }
+        diff
+    }
+}
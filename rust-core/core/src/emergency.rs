@@ -0,0 +1,161 @@
+use crate::hashing::{digest_hex, HashAlgorithm};
+use crate::{Lock, PkhCondition, PublicKey, Signature, Spend, SpendCondition, Transaction};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Emergency Break-Glass
+// ============================================================================
+
+// An emergency branch lets a single designated key spend alone, but only
+// with an incident attestation attached: a reason and timestamp signed by
+// that key and bound to the spend hash. The attestation travels in the
+// spend's seeds, so every export of the transaction carries it.
+
+const INCIDENT_DOMAIN: &[u8] = b"nockchain-multisig/incident/v1";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IncidentAttestation {
+    pub reporter: PublicKey,
+    pub reason: String,
+    pub timestamp: u64,
+    pub signature: Signature,
+}
+
+#[derive(Serialize)]
+struct IncidentTerms<'a> {
+    reporter: &'a PublicKey,
+    reason: &'a str,
+    timestamp: u64,
+    message_hash: &'a str,
+}
+
+impl IncidentAttestation {
+    // What the reporter signs, for the spend with the given message hash.
+    pub fn message(&self, message_hash: &str) -> String {
+        incident_message(&self.reporter, &self.reason, self.timestamp, message_hash)
+    }
+}
+
+pub fn incident_message(
+    reporter: &PublicKey,
+    reason: &str,
+    timestamp: u64,
+    message_hash: &str,
+) -> String {
+    let terms = IncidentTerms {
+        reporter,
+        reason,
+        timestamp,
+        message_hash,
+    };
+    let bytes = serde_json::to_vec(&terms).expect("Serialization failed");
+    digest_hex(HashAlgorithm::Sha256, &[INCIDENT_DOMAIN, &bytes].concat())
+        .expect("sha256 is always available")
+}
+
+impl Lock {
+    pub fn with_emergency_key(mut self, key: &PublicKey) -> Self {
+        self.alternatives.push(SpendCondition {
+            pkh: PkhCondition {
+                threshold: 1,
+                pubkeys: alloc::vec![key.clone()],
+            },
+            hax: None,
+            tim: None,
+            emergency: true,
+        });
+        self
+    }
+
+    pub fn emergency_branch(&self) -> Option<usize> {
+        (0..self.branch_count()).find(|&b| self.condition(b).is_some_and(|c| c.emergency))
+    }
+}
+
+impl Spend {
+    // The lock's emergency branch, provided `reporter` is one of its keys.
+    pub fn emergency_branch_for(&self, reporter: &PublicKey) -> Result<usize, String> {
+        let branch = self
+            .note
+            .lock
+            .emergency_branch()
+            .ok_or("Lock has no emergency branch")?;
+        let condition = self
+            .note
+            .lock
+            .condition(branch)
+            .ok_or_else(|| format!("Lock has no branch {}", branch))?;
+        if !condition.pkh.pubkeys.contains(reporter) {
+            return Err("Reporter is not an emergency key for this lock".into());
+        }
+        Ok(branch)
+    }
+
+    // Switches the spend onto the lock's emergency branch. Signatures
+    // collected for other branches are dropped since they no longer count.
+    pub fn add_emergency_signature(
+        &mut self,
+        signature: Signature,
+        incident: IncidentAttestation,
+    ) -> Result<(), String> {
+        let branch = self.emergency_branch_for(&incident.reporter)?;
+        self.clear_for_branch(branch);
+        self.seeds
+            .add_signature(incident.reporter.clone(), signature);
+        self.seeds.incident = Some(incident);
+        Ok(())
+    }
+
+    // Moves the spend to `branch`, dropping everything collected for the
+    // previous one.
+    pub fn clear_for_branch(&mut self, branch: usize) {
+        let seeds = &mut self.seeds;
+        seeds.branch = branch;
+        seeds.signatures.clear();
+        seeds.delegations.clear();
+        seeds.preapprovals.clear();
+        seeds.aggregated.clear();
+        seeds.summaries.clear();
+    }
+
+    pub fn validate_incident(&self, index: usize) -> Result<(), String> {
+        let emergency = self.condition()?.emergency;
+        match (&self.seeds.incident, emergency) {
+            (None, true) => Err(format!(
+                "Spend {} uses an emergency key without an incident attestation",
+                index
+            )),
+            (Some(_), false) => Err(format!(
+                "Spend {} carries an incident attestation outside an emergency branch",
+                index
+            )),
+            (Some(incident), true) => {
+                if incident.reason.trim().is_empty() {
+                    return Err(format!("Spend {} incident has no reason", index));
+                }
+                if !self.seeds.has_signature(&incident.reporter) {
+                    return Err(format!(
+                        "Spend {} incident reporter has not signed the spend",
+                        index
+                    ));
+                }
+                Ok(())
+            }
+            (None, false) => Ok(()),
+        }
+    }
+}
+
+impl Transaction {
+    pub fn incidents(&self) -> Vec<(usize, &IncidentAttestation)> {
+        self.spends
+            .iter()
+            .enumerate()
+            .filter_map(|(i, s)| s.seeds.incident.as_ref().map(|inc| (i, inc)))
+            .collect()
+    }
+}
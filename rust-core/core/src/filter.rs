@@ -0,0 +1,167 @@
+use crate::Lock;
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+// ============================================================================
+// Compact Block Filters
+// ============================================================================
+
+// The node publishes one small filter per block over the locks of every
+// note the block creates or spends. A watch-only client downloads the
+// filters, matches its own locks locally, and fetches only the blocks that
+// match, so the node learns which blocks were fetched but never which locks
+// are being watched. Filters have false positives (about 1 in 784931 per
+// watched lock) and no false negatives.
+//
+// The encoding is a Golomb-Rice coded set in the style of BIP 158: each item
+// is hashed with the block hash as key, mapped into [0, N * M), sorted, and
+// the gaps are Rice coded with parameter P.
+
+pub const FILTER_P: u8 = 19;
+pub const FILTER_M: u64 = 784_931;
+
+// What a block filter records for a lock: its fingerprint bytes.
+pub fn lock_filter_item(lock: &Lock) -> Vec<u8> {
+    hex::decode(lock.fingerprint()).expect("fingerprints are hex")
+}
+
+fn hash_to_range(key: &[u8], item: &[u8], range: u64) -> u64 {
+    let bytes = Sha256::new()
+        .chain_update(key)
+        .chain_update(item)
+        .finalize();
+    let mut word = [0u8; 8];
+    word.copy_from_slice(&bytes[..8]);
+    ((u64::from_be_bytes(word) as u128 * range as u128) >> 64) as u64
+}
+
+fn hashed_set(key: &[u8], items: &[Vec<u8>], n: u64) -> Vec<u64> {
+    let mut values: Vec<u64> = items
+        .iter()
+        .map(|item| hash_to_range(key, item, n * FILTER_M))
+        .collect();
+    values.sort_unstable();
+    values
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: usize,
+}
+
+impl BitWriter {
+    fn push(&mut self, bit: bool) {
+        if self.bits.is_multiple_of(8) {
+            self.bytes.push(0);
+        }
+        if bit {
+            *self.bytes.last_mut().expect("just pushed") |= 0x80 >> (self.bits % 8);
+        }
+        self.bits += 1;
+    }
+
+    fn push_bits(&mut self, value: u64, count: u8) {
+        for i in (0..count).rev() {
+            self.push((value >> i) & 1 == 1);
+        }
+    }
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    bit: usize,
+}
+
+impl BitReader<'_> {
+    fn next(&mut self) -> Result<bool, String> {
+        let byte = self
+            .bytes
+            .get(self.bit / 8)
+            .ok_or("Filter data is truncated")?;
+        let bit = byte & (0x80 >> (self.bit % 8)) != 0;
+        self.bit += 1;
+        Ok(bit)
+    }
+
+    fn read_bits(&mut self, count: u8) -> Result<u64, String> {
+        let mut value = 0;
+        for _ in 0..count {
+            value = (value << 1) | self.next()? as u64;
+        }
+        Ok(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BlockFilter {
+    pub block_hash: String,
+    pub n: u64,
+    // Hex of the Rice-coded gaps.
+    pub data: String,
+}
+
+impl BlockFilter {
+    pub fn build(block_hash: &str, items: &[Vec<u8>]) -> Result<Self, String> {
+        let key = hex::decode(block_hash).map_err(|_| "Block hash must be hex")?;
+        let mut items = items.to_vec();
+        items.sort();
+        items.dedup();
+        let n = items.len() as u64;
+        let values = hashed_set(&key, &items, n);
+
+        let mut writer = BitWriter::default();
+        let mut last = 0;
+        for value in &values {
+            let delta = value - last;
+            last = *value;
+            for _ in 0..(delta >> FILTER_P) {
+                writer.push(true);
+            }
+            writer.push(false);
+            writer.push_bits(delta, FILTER_P);
+        }
+
+        Ok(Self {
+            block_hash: block_hash.into(),
+            n,
+            data: hex::encode(writer.bytes),
+        })
+    }
+
+    fn decode(&self) -> Result<Vec<u64>, String> {
+        let bytes = hex::decode(&self.data).map_err(|_| "Filter data must be hex")?;
+        let mut reader = BitReader {
+            bytes: &bytes,
+            bit: 0,
+        };
+        // Every value takes at least P + 1 bits, which bounds a hostile `n`.
+        let capacity = self.n.min(bytes.len() as u64 * 8 / (FILTER_P as u64 + 1));
+        let mut values = Vec::with_capacity(capacity as usize);
+        let mut last = 0u64;
+        for _ in 0..self.n {
+            let mut quotient = 0u64;
+            while reader.next()? {
+                quotient += 1;
+            }
+            let delta = (quotient << FILTER_P) | reader.read_bits(FILTER_P)?;
+            last = last.checked_add(delta).ok_or("Filter data is corrupt")?;
+            values.push(last);
+        }
+        Ok(values)
+    }
+
+    // True if any item may be in the block; false means none is.
+    pub fn matches_any(&self, items: &[Vec<u8>]) -> Result<bool, String> {
+        if self.n == 0 || items.is_empty() {
+            return Ok(false);
+        }
+        let key = hex::decode(&self.block_hash).map_err(|_| "Block hash must be hex")?;
+        let set = self.decode()?;
+        let wanted = hashed_set(&key, items, self.n);
+        Ok(wanted.iter().any(|w| set.binary_search(w).is_ok()))
+    }
+}
@@ -0,0 +1,176 @@
+use crate::hashing::{digest_hex, HashAlgorithm};
+use crate::lineage::Lineage;
+use crate::network::Network;
+use crate::{Note, Output, PublicKey, Spend, Transaction};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use serde::Serialize;
+
+// ============================================================================
+// Canonical Form
+// ============================================================================
+
+// Signatures and other seed collections are gathered in whatever order
+// cosigners happened to respond. Before finalizing they are put into one
+// order (lock pubkey order for anything keyed by signer), so every
+// coordinator holding the same signature set serializes the same bytes and
+// computes the same transaction id. Spend and output order are part of the
+// spend hashes and are never touched.
+
+const TXID_DOMAIN: &[u8] = b"nockchain-multisig/txid/v1";
+const DRAFT_DOMAIN: &[u8] = b"nockchain-multisig/draft/v1";
+
+impl Spend {
+    fn signer_rank(&self, pubkey: &PublicKey) -> (usize, PublicKey) {
+        let position = self
+            .signers()
+            .ok()
+            .and_then(|pkh| pkh.pubkeys.iter().position(|pk| pk == pubkey));
+        (position.unwrap_or(usize::MAX), pubkey.clone())
+    }
+
+    pub fn canonicalize(&mut self) {
+        let mut signatures = core::mem::take(&mut self.seeds.signatures);
+        signatures.sort_by_cached_key(|(pk, _)| self.signer_rank(pk));
+        signatures.dedup_by(|a, b| a.0 == b.0);
+        self.seeds.signatures = signatures;
+
+        let mut delegations = core::mem::take(&mut self.seeds.delegations);
+        delegations.sort_by_cached_key(|d| self.signer_rank(&d.signer));
+        self.seeds.delegations = delegations;
+
+        let mut aggregated = core::mem::take(&mut self.seeds.aggregated);
+        aggregated.sort_by_cached_key(|pk| self.signer_rank(pk));
+        aggregated.dedup();
+        self.seeds.aggregated = aggregated;
+
+        let mut summaries = core::mem::take(&mut self.seeds.summaries);
+        summaries.sort_by_cached_key(|(pk, _)| self.signer_rank(pk));
+        summaries.dedup_by(|a, b| a.0 == b.0);
+        self.seeds.summaries = summaries;
+
+        let mut preapprovals = core::mem::take(&mut self.seeds.preapprovals);
+        preapprovals.sort_by_cached_key(|p| self.signer_rank(&p.signer));
+        self.seeds.preapprovals = preapprovals;
+
+        self.seeds.preimages.sort();
+        self.seeds.preimages.dedup();
+    }
+}
+
+impl Transaction {
+    pub fn canonicalize(&mut self) {
+        for spend in &mut self.spends {
+            spend.canonicalize();
+        }
+    }
+
+    // A validated, canonical copy ready for broadcast.
+    pub fn finalize(&self) -> Result<Transaction, String> {
+        self.finalize_at(None)
+    }
+
+    // As `finalize`, checking expiries against `height`; drafts carrying
+    // pre-approvals need one.
+    pub fn finalize_at(&self, height: Option<u64>) -> Result<Transaction, String> {
+        self.validate_structure()?;
+        self.validate_signatures_at(height)?;
+        self.validate_summaries()?;
+        let mut tx = self.clone();
+        tx.canonicalize();
+        Ok(tx)
+    }
+
+    // Identifier over the canonical serialization, using the transaction's
+    // own hash algorithm.
+    pub fn txid(&self) -> Result<String, String> {
+        let mut tx = self.clone();
+        tx.canonicalize();
+        let bytes = serde_json::to_vec(&tx).map_err(|e| e.to_string())?;
+        digest_hex(tx.hash_algorithm, &[TXID_DOMAIN, &bytes].concat())
+    }
+}
+
+// ============================================================================
+// Draft Ids
+// ============================================================================
+
+// A draft is identified by what it does, not by who created it: the notes it
+// spends, its outputs, and the network, hash algorithm and lineage its spend
+// hashes commit to. Seeds are left out, so the id is the same before and
+// after signing and whichever branch a spend takes. Two clients or
+// coordinators that build the same payment independently get the same id
+// and can merge the drafts instead of collecting signatures twice.
+
+#[derive(Serialize)]
+struct UnsignedDraft<'a> {
+    network: Network,
+    hash_algorithm: HashAlgorithm,
+    notes: Vec<&'a Note>,
+    outputs: &'a [Output],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lineage: Option<&'a Lineage>,
+}
+
+impl Transaction {
+    // Always SHA-256, so the id does not depend on which hash backends a
+    // build includes.
+    pub fn draft_id(&self) -> Result<String, String> {
+        let draft = UnsignedDraft {
+            network: self.network,
+            hash_algorithm: self.hash_algorithm,
+            notes: self.spends.iter().map(|s| &s.note).collect(),
+            outputs: &self.outputs,
+            lineage: self.lineage.as_ref(),
+        };
+        let bytes = serde_json::to_vec(&draft).map_err(|e| e.to_string())?;
+        digest_hex(HashAlgorithm::Sha256, &[DRAFT_DOMAIN, &bytes].concat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hashing::HashAlgorithm;
+    use crate::network::Network;
+    use crate::{Lock, Note, NoteName, Output, PublicKey, Signature, Transaction};
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    fn signed() -> Transaction {
+        let a = PublicKey("a".to_string());
+        let note = Note {
+            name: NoteName {
+                first: "n0".into(),
+                last: "l".into(),
+            },
+            value: 5,
+            lock: Lock::single(&a),
+            network: Network::default(),
+            coinbase: false,
+            confirmed_at: None,
+        };
+        let output = Output {
+            recipient: "r".into(),
+            value: 5,
+            lock: Lock::single(&PublicKey("r".into())),
+        };
+        let mut tx =
+            Transaction::build(vec![note], vec![output], HashAlgorithm::default()).unwrap();
+        tx.spends[0].seeds.add_signature(a, Signature("sig".into()));
+        tx
+    }
+
+    #[test]
+    fn finalizes_signed_draft() {
+        let tx = signed();
+        assert_eq!(tx.finalize().unwrap().txid(), tx.txid());
+    }
+
+    #[test]
+    fn rejects_outputs_changed_after_signing() {
+        let mut tx = signed();
+        tx.outputs[0].lock = Lock::single(&PublicKey("mallory".into()));
+        let err = tx.finalize().unwrap_err();
+        assert!(err.contains("hash does not match"), "{err}");
+    }
+}
@@ -0,0 +1,106 @@
+#[cfg(feature = "blake3")]
+use crate::blake3;
+#[cfg(feature = "blake3")]
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+// ============================================================================
+// Hash Algorithms
+// ============================================================================
+
+// Drafts carry the algorithm their spend hashes were produced with. Missing
+// tags mean SHA-256, so drafts stored before tagging existed still validate.
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Blake3,
+    Tip5,
+}
+
+impl HashAlgorithm {
+    pub fn supported() -> &'static [HashAlgorithm] {
+        &[
+            HashAlgorithm::Sha256,
+            #[cfg(feature = "blake3")]
+            HashAlgorithm::Blake3,
+        ]
+    }
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "sha256" => Ok(HashAlgorithm::Sha256),
+            "blake3" => Ok(HashAlgorithm::Blake3),
+            "tip5" => Ok(HashAlgorithm::Tip5),
+            other => Err(format!("Unknown hash algorithm: {}", other)),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Blake3 => "blake3",
+            HashAlgorithm::Tip5 => "tip5",
+        }
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == HashAlgorithm::default()
+    }
+
+    pub fn ensure_supported(self) -> Result<(), String> {
+        if Self::supported().contains(&self) {
+            Ok(())
+        } else {
+            Err(format!("Hash algorithm {} is not supported by this build", self.name()))
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum Digester {
+    Sha256(Sha256),
+    #[cfg(feature = "blake3")]
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl Digester {
+    pub fn new(algorithm: HashAlgorithm) -> Result<Self, String> {
+        algorithm.ensure_supported()?;
+        Ok(match algorithm {
+            HashAlgorithm::Sha256 => Digester::Sha256(Sha256::new()),
+            #[cfg(feature = "blake3")]
+            HashAlgorithm::Blake3 => Digester::Blake3(Box::default()),
+            _ => unreachable!("rejected by ensure_supported"),
+        })
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Digester::Sha256(h) => h.update(bytes),
+            #[cfg(feature = "blake3")]
+            Digester::Blake3(h) => {
+                h.update(bytes);
+            }
+        }
+    }
+
+    pub fn finalize_hex(self) -> String {
+        match self {
+            Digester::Sha256(h) => hex::encode(h.finalize()),
+            #[cfg(feature = "blake3")]
+            Digester::Blake3(h) => hex::encode(h.finalize()),
+        }
+    }
+}
+
+pub fn digest_hex(algorithm: HashAlgorithm, bytes: &[u8]) -> Result<String, String> {
+    let mut digester = Digester::new(algorithm)?;
+    digester.update(bytes);
+    Ok(digester.finalize_hex())
+}
@@ -0,0 +1,683 @@
+#![no_std]
+
+// Everything that determines what a signer signs lives here: the data model,
+// the canonical spend-hash encoding, the hash functions, and the verifier
+// interface. It needs only `alloc`, so hardware signer firmware can embed the
+// same code as the browser build and compute byte-identical spend hashes.
+
+extern crate alloc;
+
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use delegation::Delegation;
+use emergency::IncidentAttestation;
+use hashing::{Digester, HashAlgorithm};
+use lineage::Lineage;
+use network::Network;
+use preapproval::PreApproval;
+use schema::SchemaVersion;
+use serde::{Deserialize, Serialize};
+
+pub mod aggregate;
+pub mod arena;
+pub mod blake3;
+pub mod build;
+pub mod checkpoint;
+pub mod delegation;
+pub mod diff;
+pub mod emergency;
+pub mod filter;
+pub mod finalize;
+pub mod hashing;
+pub mod limits;
+pub mod lineage;
+pub mod mast;
+pub mod maturity;
+pub mod merkle;
+pub mod network;
+pub mod outputs;
+pub mod paths;
+pub mod preapproval;
+pub mod rng;
+pub mod schema;
+pub mod shuffle;
+pub mod spv;
+pub mod summary;
+pub mod template;
+pub mod threshold;
+pub mod verify;
+
+// ============================================================================
+// Core Types
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PublicKey(pub String);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Signature(pub String);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NoteName {
+    pub first: String,
+    pub last: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PkhCondition {
+    pub threshold: usize,
+    pub pubkeys: Vec<PublicKey>,
+}
+
+impl PkhCondition {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.threshold == 0 {
+            return Err("Threshold must be >= 1".into());
+        }
+        if self.threshold > self.pubkeys.len() {
+            return Err("Threshold exceeds number of pubkeys".into());
+        }
+
+        let mut set = BTreeSet::new();
+        for pk in &self.pubkeys {
+            if !set.insert(pk) {
+                return Err("Duplicate public key in multisig set".into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// `%hax`: spendable only by revealing preimages of every listed hash.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HaxCondition {
+    pub hashes: Vec<String>,
+}
+
+impl HaxCondition {
+    pub fn is_satisfied_by(&self, preimages: &[String]) -> bool {
+        let revealed: Vec<String> = preimages.iter().map(|p| preimage_hash(p)).collect();
+        self.hashes.iter().all(|h| revealed.contains(h))
+    }
+}
+
+// Preimages travel hex-encoded; the committed hash is SHA-256 of the bytes.
+pub fn preimage_hash(preimage_hex: &str) -> String {
+    let bytes = hex::decode(preimage_hex).unwrap_or_default();
+    hashing::digest_hex(HashAlgorithm::Sha256, &bytes).expect("sha256 is always available")
+}
+
+// `%tim`: absolute block-height window in which the condition may be used.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TimCondition {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_height: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_height: Option<u64>,
+}
+
+impl TimCondition {
+    pub fn is_satisfied_at(&self, height: u64) -> bool {
+        self.min_height.is_none_or(|min| height >= min)
+            && self.max_height.is_none_or(|max| height <= max)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpendCondition {
+    pub pkh: PkhCondition,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hax: Option<HaxCondition>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tim: Option<TimCondition>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub emergency: bool,
+}
+
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ConditionRef<'a> {
+    pub pkh: &'a PkhCondition,
+    pub hax: Option<&'a HaxCondition>,
+    pub tim: Option<&'a TimCondition>,
+    pub emergency: bool,
+}
+
+impl ConditionRef<'_> {
+    pub fn validate(&self) -> Result<(), String> {
+        self.pkh.validate()?;
+        if let Some(hax) = self.hax {
+            if hax.hashes.is_empty() {
+                return Err("Hash lock must list at least one hash".into());
+            }
+        }
+        if let Some(TimCondition {
+            min_height: Some(min),
+            max_height: Some(max),
+        }) = self.tim
+        {
+            if min > max {
+                return Err("Time lock window is empty".into());
+            }
+        }
+        if self.emergency && self.pkh.threshold != 1 {
+            return Err("Emergency branch must be spendable by a single key".into());
+        }
+        Ok(())
+    }
+}
+
+// The top-level fields are the primary spend condition, which is all most
+// locks use. `alternatives` holds further branches (any one may be used).
+// Empty parts are omitted when serialized, so plain multisig locks keep the
+// exact encoding, and therefore hashes, they always had.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Lock {
+    pub pkh: PkhCondition,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hax: Option<HaxCondition>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tim: Option<TimCondition>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub emergency: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alternatives: Vec<SpendCondition>,
+}
+
+impl Lock {
+    pub fn new(pkh: PkhCondition) -> Self {
+        Self {
+            pkh,
+            hax: None,
+            tim: None,
+            emergency: false,
+            alternatives: Vec::new(),
+        }
+    }
+
+    pub fn multisig(threshold: usize, pubkeys: Vec<PublicKey>) -> Self {
+        Self::new(PkhCondition { threshold, pubkeys })
+    }
+
+    pub fn single(pubkey: &PublicKey) -> Self {
+        Self::multisig(1, alloc::vec![pubkey.clone()])
+    }
+
+    pub fn from_conditions(mut conditions: Vec<SpendCondition>) -> Result<Self, String> {
+        if conditions.is_empty() {
+            return Err("Lock needs at least one spend condition".into());
+        }
+        let primary = conditions.remove(0);
+        Ok(Self {
+            pkh: primary.pkh,
+            hax: primary.hax,
+            tim: primary.tim,
+            emergency: primary.emergency,
+            alternatives: conditions,
+        })
+    }
+
+    pub fn branch_count(&self) -> usize {
+        1 + self.alternatives.len()
+    }
+
+    // Branch 0 is the primary condition, branch i + 1 is `alternatives[i]`.
+    pub fn condition(&self, branch: usize) -> Option<ConditionRef<'_>> {
+        if branch == 0 {
+            return Some(ConditionRef {
+                pkh: &self.pkh,
+                hax: self.hax.as_ref(),
+                tim: self.tim.as_ref(),
+                emergency: self.emergency,
+            });
+        }
+        self.alternatives.get(branch - 1).map(|c| ConditionRef {
+            pkh: &c.pkh,
+            hax: c.hax.as_ref(),
+            tim: c.tim.as_ref(),
+            emergency: c.emergency,
+        })
+    }
+
+    pub fn conditions(&self) -> impl Iterator<Item = ConditionRef<'_>> {
+        (0..self.branch_count()).filter_map(|b| self.condition(b))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for condition in self.conditions() {
+            condition.validate()?;
+        }
+        Ok(())
+    }
+
+    // Stable identifier for a lock, used as its funding address.
+    pub fn fingerprint(&self) -> String {
+        let bytes = serde_json::to_vec(self).expect("Serialization failed");
+        hashing::digest_hex(HashAlgorithm::Sha256, &bytes).expect("sha256 is always available")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Note {
+    pub name: NoteName,
+    pub value: u64,
+    pub lock: Lock,
+    #[serde(default, skip_serializing_if = "Network::is_default")]
+    pub network: Network,
+    // Provenance as reported by the node. Not part of any spend hash.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub coinbase: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirmed_at: Option<u64>,
+}
+
+// ============================================================================
+// Seeds and Signing
+// ============================================================================
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Seeds {
+    pub message_hash: String,
+    pub signatures: Vec<(PublicKey, Signature)>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub branch: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub preimages: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub delegations: Vec<Delegation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub incident: Option<IncidentAttestation>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aggregated: Vec<PublicKey>,
+    // Summary hash each signer approved, where their wallet recorded one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub summaries: Vec<(PublicKey, String)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub preapprovals: Vec<PreApproval>,
+}
+
+impl Seeds {
+    pub fn new(message_hash: String) -> Self {
+        Self {
+            message_hash,
+            signatures: Vec::new(),
+            branch: 0,
+            preimages: Vec::new(),
+            delegations: Vec::new(),
+            incident: None,
+            aggregated: Vec::new(),
+            summaries: Vec::new(),
+            preapprovals: Vec::new(),
+        }
+    }
+
+    pub fn add_signature(&mut self, pubkey: PublicKey, signature: Signature) {
+        self.signatures.retain(|(pk, _)| pk != &pubkey);
+        self.delegations.retain(|d| d.signer != pubkey);
+        self.aggregated.retain(|pk| pk != &pubkey);
+        self.summaries.retain(|(pk, _)| pk != &pubkey);
+        self.preapprovals.retain(|p| p.signer != pubkey);
+        self.signatures.push((pubkey, signature));
+    }
+
+    // Signature made by the last delegate in `delegation.chain`, filling the
+    // slot of `delegation.signer`.
+    pub fn add_delegated_signature(&mut self, delegation: Delegation, signature: Signature) {
+        let slot = delegation.signer.clone();
+        self.add_signature(slot, signature);
+        self.delegations.push(delegation);
+    }
+
+    // Signed terms filling the slot of `preapproval.signer`; see
+    // `Transaction::apply_preapproval`.
+    pub fn add_preapproval(&mut self, preapproval: PreApproval) {
+        self.add_signature(preapproval.signer.clone(), preapproval.signature.clone());
+        self.preapprovals.push(preapproval);
+    }
+
+    pub fn signature_count(&self) -> usize {
+        self.signatures.len()
+    }
+
+    pub fn has_signature(&self, pubkey: &PublicKey) -> bool {
+        self.signatures.iter().any(|(pk, _)| pk == pubkey)
+    }
+}
+
+// ============================================================================
+// Transaction Model
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Spend {
+    pub note: Note,
+    pub seeds: Seeds,
+}
+
+impl Spend {
+    // The lock branch this spend is exercising.
+    pub fn condition(&self) -> Result<ConditionRef<'_>, String> {
+        self.note
+            .lock
+            .condition(self.seeds.branch)
+            .ok_or_else(|| format!("Lock has no branch {}", self.seeds.branch))
+    }
+
+    pub fn signers(&self) -> Result<&PkhCondition, String> {
+        Ok(self.condition()?.pkh)
+    }
+
+    pub fn validate_signers(&self, index: usize) -> Result<(), String> {
+        let condition = self.condition()?;
+        let pkh = condition.pkh;
+        condition.validate()?;
+
+        if let Some(hax) = condition.hax {
+            if !hax.is_satisfied_by(&self.seeds.preimages) {
+                return Err(format!("Spend {} is missing a hash lock preimage", index));
+            }
+        }
+
+        self.validate_incident(index)?;
+
+        if self.seeds.signature_count() < pkh.threshold {
+            return Err(format!("Spend {} has insufficient signatures", index));
+        }
+
+        for (pk, _) in &self.seeds.signatures {
+            if !pkh.pubkeys.contains(pk) {
+                return Err(format!("Spend {} has invalid signer", index));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Output {
+    pub recipient: String,
+    pub value: u64,
+    pub lock: Lock,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Transaction {
+    #[serde(default, skip_serializing_if = "SchemaVersion::is_current")]
+    pub schema_version: SchemaVersion,
+    pub spends: Vec<Spend>,
+    pub outputs: Vec<Output>,
+    #[serde(default, skip_serializing_if = "HashAlgorithm::is_default")]
+    pub hash_algorithm: HashAlgorithm,
+    #[serde(default, skip_serializing_if = "Network::is_default")]
+    pub network: Network,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lineage: Option<Lineage>,
+    #[serde(skip)]
+    hash_cache: SpendHashCache,
+}
+
+impl Transaction {
+    pub fn new(spends: Vec<Spend>, outputs: Vec<Output>) -> Self {
+        Self {
+            schema_version: SchemaVersion::default(),
+            spends,
+            outputs,
+            hash_algorithm: HashAlgorithm::default(),
+            network: Network::default(),
+            lineage: None,
+            hash_cache: SpendHashCache::default(),
+        }
+    }
+
+    // Unsigned draft spending `notes` into `outputs`, with every spend hash
+    // filled in. This is what `build_transaction` does behind the JSON.
+    pub fn build(
+        notes: Vec<Note>,
+        outputs: Vec<Output>,
+        algorithm: HashAlgorithm,
+    ) -> Result<Self, String> {
+        let mut spends = Vec::with_capacity(notes.len());
+
+        for note in notes {
+            note.lock.validate()?;
+
+            spends.push(Spend {
+                note,
+                seeds: Seeds::new(String::new()),
+            });
+        }
+
+        let mut tx = Transaction::new(spends, outputs);
+        tx.set_hash_algorithm(algorithm)?;
+        tx.refresh_spend_hashes()?;
+        tx.validate_balance()?;
+        Ok(tx)
+    }
+
+    pub fn set_hash_algorithm(&mut self, algorithm: HashAlgorithm) -> Result<(), String> {
+        algorithm.ensure_supported()?;
+        self.hash_algorithm = algorithm;
+        Ok(())
+    }
+
+    // Mutations below touch the hashed preimage, so they mark the cache dirty.
+    // Code that edits `outputs` or a lock directly must call `invalidate_hashes`.
+
+    pub fn push_output(&mut self, output: Output) {
+        self.outputs.push(output);
+        self.invalidate_hashes();
+    }
+
+    pub fn set_outputs(&mut self, outputs: Vec<Output>) {
+        self.outputs = outputs;
+        self.invalidate_hashes();
+    }
+
+    pub fn set_spend_lock(&mut self, spend_index: usize, lock: Lock) -> Result<(), String> {
+        let spend = self
+            .spends
+            .get_mut(spend_index)
+            .ok_or("Invalid spend index")?;
+        spend.note.lock = lock;
+        self.invalidate_hashes();
+        Ok(())
+    }
+
+    pub fn invalidate_hashes(&mut self) {
+        self.hash_cache.invalidate();
+    }
+
+    pub fn hashes_dirty(&self) -> bool {
+        self.hash_cache.is_dirty()
+    }
+
+    pub fn spend_hash(&mut self, spend_index: usize) -> Result<String, String> {
+        let preimage = self.hash_cache.preimage(&self.outputs, self.lineage.as_ref());
+        hash_spend_preimage(self.hash_algorithm, self.network, spend_index, preimage)
+    }
+
+    pub fn refresh_spend_hashes(&mut self) -> Result<(), String> {
+        let preimage = self.hash_cache.preimage(&self.outputs, self.lineage.as_ref());
+        for (i, spend) in self.spends.iter_mut().enumerate() {
+            spend.seeds.message_hash =
+                hash_spend_preimage(self.hash_algorithm, self.network, i, preimage)?;
+        }
+        Ok(())
+    }
+
+    pub fn validate_timelocks(&self, height: u64) -> Result<(), String> {
+        for (i, spend) in self.spends.iter().enumerate() {
+            if let Some(tim) = spend.condition()?.tim {
+                if !tim.is_satisfied_at(height) {
+                    return Err(format!("Spend {} time lock not satisfied at height {}", i, height));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn validate_hash_algorithm(&self) -> Result<(), String> {
+        self.hash_algorithm.ensure_supported()?;
+        for (i, spend) in self.spends.iter().enumerate() {
+            let hash = &spend.seeds.message_hash;
+            if hash.len() != 64 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err(format!(
+                    "Spend {} hash is not a {} digest",
+                    i,
+                    self.hash_algorithm.name()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn total_input(&self) -> u64 {
+        self.spends.iter().map(|s| s.note.value).sum()
+    }
+
+    pub fn total_output(&self) -> u64 {
+        self.outputs.iter().map(|o| o.value).sum()
+    }
+
+    pub fn validate_balance(&self) -> Result<(), String> {
+        let input = self
+            .spends
+            .iter()
+            .try_fold(0u64, |sum, s| sum.checked_add(s.note.value))
+            .ok_or("Input values overflow")?;
+        let output = self
+            .outputs
+            .iter()
+            .try_fold(0u64, |sum, o| sum.checked_add(o.value))
+            .ok_or("Output values overflow")?;
+        if input != output {
+            return Err("Input value does not equal output value".into());
+        }
+        Ok(())
+    }
+
+    // Without a height, drafts carrying pre-approvals fail; use
+    // `validate_signatures_at` for those.
+    pub fn validate_signatures(&self) -> Result<(), String> {
+        self.validate_signatures_at(None)
+    }
+
+    pub fn validate_signatures_at(&self, height: Option<u64>) -> Result<(), String> {
+        for (i, spend) in self.spends.iter().enumerate() {
+            spend.validate_signers(i)?;
+        }
+        self.validate_aggregates()?;
+        self.validate_delegations(height)?;
+        self.validate_preapprovals(height)
+    }
+}
+
+// ============================================================================
+// Deterministic Hashing
+// ============================================================================
+
+// Spend hashes are digests over `{"spend_index":N,"transaction":P}` where P is
+// the canonical preimage below. P is identical for every spend, so it is
+// serialized once and cached; each spend only hashes its own short prefix.
+
+#[derive(Serialize)]
+struct CanonicalPreimage<'a> {
+    spends: &'a [Spend],
+    outputs: &'a [Output],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lineage: Option<&'a Lineage>,
+}
+
+pub fn canonical_preimage(outputs: &[Output], lineage: Option<&Lineage>) -> Vec<u8> {
+    let preimage = CanonicalPreimage {
+        spends: &[],
+        outputs,
+        lineage,
+    };
+    serde_json::to_vec(&preimage).expect("Serialization failed")
+}
+
+pub fn hash_spend_preimage(
+    algorithm: HashAlgorithm,
+    network: Network,
+    spend_index: usize,
+    preimage: &[u8],
+) -> Result<String, String> {
+    let mut hasher = SpendHasher::new(algorithm, network, spend_index)?;
+    hasher.update(preimage);
+    Ok(hasher.finalize_hex())
+}
+
+// Spend hash over a preimage fed in chunks of any size, for signers that
+// cannot hold the whole preimage in memory.
+#[derive(Clone)]
+pub struct SpendHasher {
+    digester: Digester,
+}
+
+impl SpendHasher {
+    pub fn new(
+        algorithm: HashAlgorithm,
+        network: Network,
+        spend_index: usize,
+    ) -> Result<Self, String> {
+        let mut digester = Digester::new(algorithm)?;
+        if let Some(tag) = network.domain_tag() {
+            digester.update(tag);
+        }
+        digester.update(b"{\"spend_index\":");
+        digester.update(spend_index.to_string().as_bytes());
+        digester.update(b",\"transaction\":");
+        Ok(Self { digester })
+    }
+
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.digester.update(chunk);
+    }
+
+    pub fn finalize_hex(mut self) -> String {
+        self.digester.update(b"}");
+        self.digester.finalize_hex()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SpendHashCache {
+    preimage: Option<Vec<u8>>,
+}
+
+impl SpendHashCache {
+    pub fn invalidate(&mut self) {
+        self.preimage = None;
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.preimage.is_none()
+    }
+
+    fn preimage(&mut self, outputs: &[Output], lineage: Option<&Lineage>) -> &[u8] {
+        self.preimage
+            .get_or_insert_with(|| canonical_preimage(outputs, lineage))
+    }
+}
@@ -0,0 +1,123 @@
+use crate::{Note, Output, Transaction};
+use alloc::string::String;
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Transaction Limits
+// ============================================================================
+
+// Caps on draft size, checked before a draft is built or stored. They keep a
+// hostile or mistaken draft from exhausting browser memory and keep drafts
+// within what the node will relay. Counts and value are checked on the raw
+// notes and outputs, so an oversized request fails before any hashing.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TransactionLimits {
+    pub max_spends: usize,
+    pub max_outputs: usize,
+    // Bytes of the draft's JSON serialization.
+    pub max_serialized_size: usize,
+    // Total value moved (inputs or outputs, whichever is larger).
+    pub max_value: u64,
+}
+
+impl Default for TransactionLimits {
+    fn default() -> Self {
+        Self {
+            max_spends: 1024,
+            // Room for large payouts (see `arena`).
+            max_outputs: 16_384,
+            max_serialized_size: 8 << 20,
+            max_value: u64::MAX,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitKind {
+    Spends,
+    Outputs,
+    SerializedSize,
+    Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LimitExceeded {
+    pub limit: LimitKind,
+    pub max: u128,
+    pub actual: u128,
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let what = match self.limit {
+            LimitKind::Spends => "spends",
+            LimitKind::Outputs => "outputs",
+            LimitKind::SerializedSize => "serialized bytes",
+            LimitKind::Value => "total value",
+        };
+        write!(
+            f,
+            "Transaction exceeds the limit on {}: {} (max {})",
+            what, self.actual, self.max
+        )
+    }
+}
+
+impl LimitExceeded {
+    // For JS callers, which only see strings.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Serialization failed")
+    }
+}
+
+fn cap(limit: LimitKind, max: u128, actual: u128) -> Result<(), LimitExceeded> {
+    if actual > max {
+        return Err(LimitExceeded { limit, max, actual });
+    }
+    Ok(())
+}
+
+impl TransactionLimits {
+    fn check_shape(
+        &self,
+        inputs: impl ExactSizeIterator<Item = u64>,
+        outputs: &[Output],
+    ) -> Result<(), LimitExceeded> {
+        cap(
+            LimitKind::Spends,
+            self.max_spends as u128,
+            inputs.len() as u128,
+        )?;
+        cap(
+            LimitKind::Outputs,
+            self.max_outputs as u128,
+            outputs.len() as u128,
+        )?;
+        let input_value: u128 = inputs.map(u128::from).sum();
+        let output_value: u128 = outputs.iter().map(|o| o.value as u128).sum();
+        cap(
+            LimitKind::Value,
+            self.max_value as u128,
+            input_value.max(output_value),
+        )
+    }
+
+    pub fn check_parts(&self, notes: &[Note], outputs: &[Output]) -> Result<(), LimitExceeded> {
+        self.check_shape(notes.iter().map(|n| n.value), outputs)
+    }
+
+    pub fn check(&self, tx: &Transaction) -> Result<(), LimitExceeded> {
+        self.check_shape(tx.spends.iter().map(|s| s.note.value), &tx.outputs)?;
+        let size = serde_json::to_vec(tx).expect("Serialization failed").len();
+        cap(
+            LimitKind::SerializedSize,
+            self.max_serialized_size as u128,
+            size as u128,
+        )
+    }
+}
@@ -0,0 +1,125 @@
+use crate::hashing::{digest_hex, HashAlgorithm};
+use crate::Transaction;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Replacement Ordering
+// ============================================================================
+
+// An edited or re-issued draft can circulate alongside the version it
+// replaces. Every version carries the same lineage id and a sequence one
+// higher than the version before it, so coordinators and the node-facing
+// layer can always pick the newest. The lineage is part of the spend-hash
+// preimage, so the sequence is signed with everything else and an old
+// version cannot be promoted. Drafts without a lineage hash as before.
+
+const LINEAGE_DOMAIN: &[u8] = b"nockchain-multisig/lineage/v1";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Lineage {
+    pub id: String,
+    pub sequence: u64,
+}
+
+impl Transaction {
+    // Derived from the notes the draft spends, in any order.
+    pub fn lineage_id(&self) -> String {
+        let mut names: Vec<(&str, &str)> = self
+            .spends
+            .iter()
+            .map(|s| (s.note.name.first.as_str(), s.note.name.last.as_str()))
+            .collect();
+        names.sort_unstable();
+        let bytes = serde_json::to_vec(&names).expect("Serialization failed");
+        digest_hex(HashAlgorithm::Sha256, &[LINEAGE_DOMAIN, &bytes].concat())
+            .expect("sha256 is always available")
+    }
+
+    // A draft without a lineage is sequence 0 of the one named after its
+    // notes.
+    pub fn effective_lineage(&self) -> Lineage {
+        self.lineage.clone().unwrap_or_else(|| Lineage {
+            id: self.lineage_id(),
+            sequence: 0,
+        })
+    }
+
+    pub fn set_lineage(&mut self, lineage: Option<Lineage>) {
+        self.lineage = lineage;
+        self.invalidate_hashes();
+    }
+
+    pub fn spends_any_of(&self, other: &Transaction) -> bool {
+        self.spends
+            .iter()
+            .any(|a| other.spends.iter().any(|b| a.note.name == b.note.name))
+    }
+
+    // Makes this draft the next version of `previous`, which must spend at
+    // least one of the same notes. Spend hashes are refreshed, so signatures
+    // collected before this call no longer match.
+    pub fn supersede(&mut self, previous: &Transaction) -> Result<(), String> {
+        if !self.spends_any_of(previous) {
+            return Err("A replacement must spend at least one of the same notes".into());
+        }
+        let previous = previous.effective_lineage();
+        self.set_lineage(Some(Lineage {
+            id: previous.id,
+            sequence: previous
+                .sequence
+                .checked_add(1)
+                .ok_or("Sequence overflow")?,
+        }));
+        self.refresh_spend_hashes()
+    }
+
+    fn same_draft(&self, other: &Transaction) -> bool {
+        self.spends.len() == other.spends.len()
+            && self
+                .spends
+                .iter()
+                .zip(&other.spends)
+                .all(|(a, b)| a.seeds.message_hash == b.seeds.message_hash)
+    }
+}
+
+// Indices of the newest version in each lineage, in input order. A draft
+// without a lineage counts as sequence 0 of the one named after its notes,
+// so it is dropped once a replacement is present. Two different drafts
+// claiming the same lineage and sequence are an error, since neither can be
+// preferred.
+pub fn newest_versions(txs: &[Transaction]) -> Result<Vec<usize>, String> {
+    let mut newest: BTreeMap<&str, usize> = BTreeMap::new();
+    for (i, tx) in txs.iter().enumerate() {
+        let Some(lineage) = &tx.lineage else {
+            continue;
+        };
+        match newest.get(lineage.id.as_str()) {
+            Some(&j) => {
+                let best = txs[j].lineage.as_ref().expect("indexed by lineage");
+                if lineage.sequence > best.sequence {
+                    newest.insert(&lineage.id, i);
+                } else if lineage.sequence == best.sequence && !tx.same_draft(&txs[j]) {
+                    return Err(format!(
+                        "Drafts {} and {} both claim sequence {} of lineage {}",
+                        j, i, lineage.sequence, lineage.id
+                    ));
+                }
+            }
+            None => {
+                newest.insert(&lineage.id, i);
+            }
+        }
+    }
+    Ok((0..txs.len())
+        .filter(|i| match &txs[*i].lineage {
+            Some(l) => newest.get(l.id.as_str()) == Some(i),
+            None => !newest.contains_key(txs[*i].lineage_id().as_str()),
+        })
+        .collect())
+}
@@ -0,0 +1,304 @@
+use crate::hashing::{digest_hex, HashAlgorithm};
+use crate::lineage::Lineage;
+use crate::merkle::{merkle_root, MerkleProof};
+use crate::network::Network;
+use crate::schema::SchemaVersion;
+use crate::{
+    ConditionRef, Lock, Note, NoteName, Output, Seeds, Spend, SpendCondition, Transaction,
+};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Branch Commitments (MAST)
+// ============================================================================
+
+// A lock commits to its branches through a Merkle root over their hashes.
+// The revealed form of a finalized transaction carries only the exercised
+// branch plus an inclusion proof, so unused branches (recovery keys, time
+// locks, emergency keys) stay private. Spend hashes commit to the outputs,
+// not to the lock being spent, so revealing a branch leaves every signature
+// valid; `seeds.branch` must match the proof's index. `lock_root` comes
+// from whoever revealed the spend, so validation checks it against the
+// root the spent note is known to commit to (`Lock::branch_root`), never
+// against itself.
+
+const BRANCH_DOMAIN: &[u8] = b"nockchain-multisig/branch/v1";
+
+impl ConditionRef<'_> {
+    pub fn to_condition(&self) -> SpendCondition {
+        SpendCondition {
+            pkh: self.pkh.clone(),
+            hax: self.hax.cloned(),
+            tim: self.tim.cloned(),
+            emergency: self.emergency,
+        }
+    }
+}
+
+pub fn branch_hash(algorithm: HashAlgorithm, condition: &SpendCondition) -> Result<String, String> {
+    let bytes = serde_json::to_vec(condition).expect("Serialization failed");
+    digest_hex(algorithm, &[BRANCH_DOMAIN, &bytes].concat())
+}
+
+impl Lock {
+    pub fn branch_hashes(&self, algorithm: HashAlgorithm) -> Result<Vec<String>, String> {
+        self.conditions()
+            .map(|c| branch_hash(algorithm, &c.to_condition()))
+            .collect()
+    }
+
+    pub fn branch_root(&self, algorithm: HashAlgorithm) -> Result<String, String> {
+        merkle_root(algorithm, &self.branch_hashes(algorithm)?)
+    }
+
+    pub fn branch_proof(
+        &self,
+        algorithm: HashAlgorithm,
+        branch: usize,
+    ) -> Result<MerkleProof, String> {
+        MerkleProof::generate(algorithm, &self.branch_hashes(algorithm)?, branch)
+    }
+}
+
+// `proof.spend_hash` is the branch hash of `condition`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BranchWitness {
+    pub condition: SpendCondition,
+    pub proof: MerkleProof,
+}
+
+impl BranchWitness {
+    pub fn verify(&self, lock_root: &str) -> Result<(), String> {
+        if self.proof.root != lock_root {
+            return Err("Branch proof is for a different lock".into());
+        }
+        if self.proof.spend_hash != branch_hash(self.proof.algorithm, &self.condition)? {
+            return Err("Branch proof does not match the revealed condition".into());
+        }
+        self.proof
+            .verify()
+            .map_err(|_| "Revealed branch is not part of the lock".into())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RevealedSpend {
+    pub name: NoteName,
+    pub value: u64,
+    pub lock_root: String,
+    pub witness: BranchWitness,
+    pub seeds: Seeds,
+}
+
+impl RevealedSpend {
+    // The exercised branch as a one-branch spend, so the regular signer,
+    // preimage and incident checks apply unchanged.
+    fn as_spend(&self) -> Spend {
+        let lock = Lock::from_conditions(alloc::vec![self.witness.condition.clone()])
+            .expect("one condition");
+        let mut seeds = self.seeds.clone();
+        seeds.branch = 0;
+        Spend {
+            note: Note {
+                name: self.name.clone(),
+                value: self.value,
+                lock,
+                network: Network::default(),
+                coinbase: false,
+                confirmed_at: None,
+            },
+            seeds,
+        }
+    }
+
+    pub fn validate(&self, index: usize, expected_root: &str) -> Result<(), String> {
+        if self.lock_root != expected_root {
+            return Err(format!(
+                "Spend {} reveals a different lock than it spends",
+                index
+            ));
+        }
+        self.witness
+            .verify(expected_root)
+            .map_err(|e| format!("Spend {}: {}", index, e))?;
+        if self.witness.proof.index != self.seeds.branch {
+            return Err(format!(
+                "Spend {} reveals a different branch than it used",
+                index
+            ));
+        }
+        self.as_spend().validate_signers(index)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RevealedTransaction {
+    #[serde(default, skip_serializing_if = "SchemaVersion::is_current")]
+    pub schema_version: SchemaVersion,
+    pub spends: Vec<RevealedSpend>,
+    pub outputs: Vec<Output>,
+    #[serde(default, skip_serializing_if = "HashAlgorithm::is_default")]
+    pub hash_algorithm: HashAlgorithm,
+    #[serde(default, skip_serializing_if = "Network::is_default")]
+    pub network: Network,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lineage: Option<Lineage>,
+}
+
+impl RevealedTransaction {
+    // `lock_roots[i]` is the branch root of the note spent by spend `i`.
+    pub fn validate(&self, lock_roots: &[String]) -> Result<(), String> {
+        if lock_roots.len() != self.spends.len() {
+            return Err(format!(
+                "Expected {} lock roots, got {}",
+                self.spends.len(),
+                lock_roots.len()
+            ));
+        }
+        let input = self
+            .spends
+            .iter()
+            .try_fold(0u64, |sum, s| sum.checked_add(s.value))
+            .ok_or("Input values overflow")?;
+        let output = self
+            .outputs
+            .iter()
+            .try_fold(0u64, |sum, o| sum.checked_add(o.value))
+            .ok_or("Output values overflow")?;
+        if input != output {
+            return Err("Input value does not equal output value".into());
+        }
+        for (i, (spend, root)) in self.spends.iter().zip(lock_roots).enumerate() {
+            if spend.witness.proof.algorithm != self.hash_algorithm {
+                return Err(format!("Spend {} branch proof uses the wrong hash", i));
+            }
+            spend.validate(i, root)?;
+        }
+        Ok(())
+    }
+}
+
+impl Spend {
+    pub fn reveal(&self, algorithm: HashAlgorithm) -> Result<RevealedSpend, String> {
+        let lock = &self.note.lock;
+        let proof = lock.branch_proof(algorithm, self.seeds.branch)?;
+        Ok(RevealedSpend {
+            name: self.note.name.clone(),
+            value: self.note.value,
+            lock_root: proof.root.clone(),
+            witness: BranchWitness {
+                condition: self.condition()?.to_condition(),
+                proof,
+            },
+            seeds: self.seeds.clone(),
+        })
+    }
+}
+
+impl Transaction {
+    // Finalizes, then replaces every lock with its root and exercised branch.
+    pub fn reveal(&self) -> Result<RevealedTransaction, String> {
+        let tx = self.finalize()?;
+        Ok(RevealedTransaction {
+            schema_version: tx.schema_version,
+            spends: tx
+                .spends
+                .iter()
+                .map(|s| s.reveal(tx.hash_algorithm))
+                .collect::<Result<_, _>>()?,
+            outputs: tx.outputs,
+            hash_algorithm: tx.hash_algorithm,
+            network: tx.network,
+            lineage: tx.lineage,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PkhCondition, PublicKey, Signature};
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    fn key(name: &str) -> PublicKey {
+        PublicKey(name.to_string())
+    }
+
+    // One spend of `lock`, signed by `signer`.
+    fn signed(lock: Lock, signer: &str) -> Transaction {
+        let note = Note {
+            name: NoteName {
+                first: "n0".into(),
+                last: "l".into(),
+            },
+            value: 5,
+            lock,
+            network: Network::default(),
+            coinbase: false,
+            confirmed_at: None,
+        };
+        let output = Output {
+            recipient: "r".into(),
+            value: 5,
+            lock: Lock::single(&key("r")),
+        };
+        let mut tx =
+            Transaction::build(vec![note], vec![output], HashAlgorithm::default()).unwrap();
+        tx.spends[0]
+            .seeds
+            .add_signature(key(signer), Signature("sig".into()));
+        tx
+    }
+
+    fn recoverable() -> Lock {
+        let mut lock = Lock::multisig(1, vec![key("a")]);
+        lock.alternatives.push(SpendCondition {
+            pkh: PkhCondition {
+                threshold: 1,
+                pubkeys: vec![key("recovery")],
+            },
+            hax: None,
+            tim: None,
+            emergency: false,
+        });
+        lock
+    }
+
+    #[test]
+    fn accepts_branch_of_the_spent_lock() {
+        let lock = recoverable();
+        let root = lock.branch_root(HashAlgorithm::default()).unwrap();
+        let revealed = signed(lock, "a").reveal().unwrap();
+        revealed.validate(&[root]).unwrap();
+    }
+
+    #[test]
+    fn rejects_forged_branch() {
+        let root = recoverable().branch_root(HashAlgorithm::default()).unwrap();
+        // A lock the attacker controls, revealed against its own root: the
+        // proof is internally consistent but commits to the wrong lock.
+        let forged = signed(Lock::single(&key("mallory")), "mallory")
+            .reveal()
+            .unwrap();
+        let err = forged.validate(core::slice::from_ref(&root)).unwrap_err();
+        assert!(err.contains("different lock"), "{err}");
+
+        // Claiming the real root with the forged witness fails the proof.
+        let mut spoofed = forged.clone();
+        spoofed.spends[0].lock_root = root.clone();
+        assert!(spoofed.validate(&[root]).is_err());
+    }
+
+    #[test]
+    fn rejects_missing_lock_roots() {
+        let revealed = signed(recoverable(), "a").reveal().unwrap();
+        assert!(revealed.validate(&[]).is_err());
+    }
+}
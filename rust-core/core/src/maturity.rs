@@ -0,0 +1,60 @@
+use crate::network::Network;
+use crate::{Note, Transaction};
+use alloc::format;
+use alloc::string::String;
+
+// ============================================================================
+// Note Maturity
+// ============================================================================
+
+// A node rejects spends of notes it has not confirmed, and of coinbase notes
+// younger than the network's maturity depth. Checking this before a draft is
+// built saves collecting signatures for a transaction that can never land.
+// Notes without a `confirmed_at` height are treated as unconfirmed.
+
+impl Network {
+    // Confirmations a coinbase note needs before it can be spent.
+    pub fn coinbase_maturity(self) -> u64 {
+        match self {
+            Network::Mainnet | Network::Testnet => 100,
+            Network::Regtest => 1,
+        }
+    }
+}
+
+impl Note {
+    // Counts the confirming block, so a note confirmed at the tip has one.
+    pub fn confirmations(&self, height: u64) -> u64 {
+        match self.confirmed_at {
+            Some(at) if at <= height => height - at + 1,
+            _ => 0,
+        }
+    }
+
+    pub fn spendable_at(&self, height: u64) -> Result<(), String> {
+        let confirmations = self.confirmations(height);
+        if confirmations == 0 {
+            return Err(format!("Note is unconfirmed at height {}", height));
+        }
+        let maturity = self.network.coinbase_maturity();
+        if self.coinbase && confirmations < maturity {
+            return Err(format!(
+                "Coinbase note has {} of {} confirmations",
+                confirmations, maturity
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Transaction {
+    pub fn validate_spendability(&self, height: u64) -> Result<(), String> {
+        for (i, spend) in self.spends.iter().enumerate() {
+            spend
+                .note
+                .spendable_at(height)
+                .map_err(|e| format!("Spend {}: {}", i, e))?;
+        }
+        Ok(())
+    }
+}
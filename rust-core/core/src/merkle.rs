@@ -0,0 +1,198 @@
+use crate::hashing::{Digester, HashAlgorithm};
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Merkle Trees
+// ============================================================================
+
+// Binary tree over spend hashes. Leaves and inner nodes use distinct prefixes
+// so a node can never be passed off as a leaf, and an odd node at the end of
+// a level is carried up unchanged rather than paired with itself.
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+fn hash_prefixed(algorithm: HashAlgorithm, prefix: u8, parts: &[&str]) -> Result<String, String> {
+    let mut digester = Digester::new(algorithm)?;
+    digester.update(&[prefix]);
+    for part in parts {
+        digester.update(&hex::decode(part).map_err(|_| "Merkle inputs must be hex digests")?);
+    }
+    Ok(digester.finalize_hex())
+}
+
+pub fn leaf_hash(algorithm: HashAlgorithm, spend_hash: &str) -> Result<String, String> {
+    hash_prefixed(algorithm, LEAF_PREFIX, &[spend_hash])
+}
+
+pub fn node_hash(algorithm: HashAlgorithm, left: &str, right: &str) -> Result<String, String> {
+    hash_prefixed(algorithm, NODE_PREFIX, &[left, right])
+}
+
+// Every level of the tree, leaves first and the root last.
+pub fn tree_levels(
+    algorithm: HashAlgorithm,
+    spend_hashes: &[String],
+) -> Result<Vec<Vec<String>>, String> {
+    if spend_hashes.is_empty() {
+        return Err("Merkle tree needs at least one leaf".into());
+    }
+    let leaves = spend_hashes
+        .iter()
+        .map(|h| leaf_hash(algorithm, h))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut levels = alloc::vec![leaves];
+    while levels.last().is_some_and(|level| level.len() > 1) {
+        let level = levels.last().expect("non-empty");
+        let next = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => node_hash(algorithm, left, right),
+                [single] => Ok(single.clone()),
+                _ => unreachable!("chunks of two"),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        levels.push(next);
+    }
+    Ok(levels)
+}
+
+pub fn merkle_root(algorithm: HashAlgorithm, spend_hashes: &[String]) -> Result<String, String> {
+    let levels = tree_levels(algorithm, spend_hashes)?;
+    Ok(levels.last().expect("at least one level")[0].clone())
+}
+
+// ============================================================================
+// Inclusion Proofs
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProofStep {
+    pub side: Side,
+    pub hash: String,
+}
+
+// Shows that `spend_hash` is leaf `index` of the tree with root `root`,
+// without revealing the other spend hashes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MerkleProof {
+    #[serde(default, skip_serializing_if = "HashAlgorithm::is_default")]
+    pub algorithm: HashAlgorithm,
+    pub index: usize,
+    pub spend_hash: String,
+    pub path: Vec<ProofStep>,
+    pub root: String,
+}
+
+impl MerkleProof {
+    pub fn generate(
+        algorithm: HashAlgorithm,
+        spend_hashes: &[String],
+        index: usize,
+    ) -> Result<Self, String> {
+        let spend_hash = spend_hashes
+            .get(index)
+            .ok_or("Leaf index out of bounds")?
+            .clone();
+        let levels = tree_levels(algorithm, spend_hashes)?;
+
+        let mut path = Vec::new();
+        let mut position = index;
+        for level in &levels[..levels.len() - 1] {
+            let sibling = position ^ 1;
+            // A promoted odd node has no sibling at this level.
+            if let Some(hash) = level.get(sibling) {
+                path.push(ProofStep {
+                    side: if sibling < position {
+                        Side::Left
+                    } else {
+                        Side::Right
+                    },
+                    hash: hash.clone(),
+                });
+            }
+            position /= 2;
+        }
+
+        Ok(Self {
+            algorithm,
+            index,
+            spend_hash,
+            path,
+            root: levels.last().expect("at least one level")[0].clone(),
+        })
+    }
+
+    // Each step's side must follow from `index`: an odd position has its
+    // sibling on the left, an even one on the right unless it is the last
+    // node of its level and was promoted, in which case it has no step. The
+    // proof does not carry the tree's size, so whether an even node was
+    // promoted cannot be checked: a path may also fit another index that
+    // differs only where it allows a skipped level.
+    pub fn verify(&self) -> Result<(), String> {
+        let mut node = leaf_hash(self.algorithm, &self.spend_hash)?;
+        let mut position = self.index;
+        let mut steps = self.path.iter().peekable();
+        while let Some(step) = steps.peek() {
+            match (position % 2, step.side) {
+                (1, Side::Left) => node = node_hash(self.algorithm, &step.hash, &node)?,
+                (0, Side::Right) => node = node_hash(self.algorithm, &node, &step.hash)?,
+                (0, Side::Left) if position > 0 => {
+                    position /= 2;
+                    continue;
+                }
+                _ => return Err("Merkle proof path does not match its index".into()),
+            }
+            steps.next();
+            position /= 2;
+        }
+        if position != 0 {
+            return Err("Merkle proof path does not match its index".into());
+        }
+        if node != self.root {
+            return Err("Spend hash is not included under this root".into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+
+    fn leaves(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("{:064x}", i)).collect()
+    }
+
+    #[test]
+    fn proofs_are_bound_to_their_side() {
+        for n in 1..=9 {
+            let hashes = leaves(n);
+            for index in 0..n {
+                let proof = MerkleProof::generate(HashAlgorithm::Sha256, &hashes, index).unwrap();
+                proof.verify().unwrap();
+                // The sibling's position shares every step but the first.
+                if index ^ 1 < n {
+                    let moved = MerkleProof {
+                        index: index ^ 1,
+                        ..proof.clone()
+                    };
+                    assert!(moved.verify().is_err(), "{} of {}", index, n);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use crate::{Lock, Transaction};
+use alloc::format;
+use alloc::string::String;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Networks
+// ============================================================================
+
+// Drafts, notes, and addresses name the network they belong to, so a signing
+// ceremony rehearsed on testnet can never produce something valid on
+// mainnet. Mainnet is the default and is never written out, so existing
+// drafts keep their encoding and spend hashes. Other networks prefix the
+// spend-hash input with a domain tag, so their signatures do not carry over.
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Network {
+    #[default]
+    Mainnet,
+    Testnet,
+    Regtest,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NetworkProfile {
+    pub network: Network,
+    pub address_prefix: &'static str,
+    pub dust_limit: u64,
+    pub coinbase_maturity: u64,
+    pub rpc_url: &'static str,
+}
+
+impl Network {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "mainnet" => Ok(Network::Mainnet),
+            "testnet" => Ok(Network::Testnet),
+            "regtest" => Ok(Network::Regtest),
+            other => Err(format!("Unknown network: {}", other)),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Network::Mainnet => "mainnet",
+            Network::Testnet => "testnet",
+            Network::Regtest => "regtest",
+        }
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == Network::default()
+    }
+
+    pub fn address_prefix(self) -> &'static str {
+        match self {
+            Network::Mainnet => "nock",
+            Network::Testnet => "tnock",
+            Network::Regtest => "rnock",
+        }
+    }
+
+    // Smallest output value accepted; regtest allows anything for testing.
+    pub fn dust_limit(self) -> u64 {
+        match self {
+            Network::Mainnet | Network::Testnet => 1,
+            Network::Regtest => 0,
+        }
+    }
+
+    // Default JSON-RPC endpoint of a local node.
+    pub fn default_rpc_url(self) -> &'static str {
+        match self {
+            Network::Mainnet => "http://127.0.0.1:5555",
+            Network::Testnet => "http://127.0.0.1:15555",
+            Network::Regtest => "http://127.0.0.1:25555",
+        }
+    }
+
+    pub fn domain_tag(self) -> Option<&'static [u8]> {
+        match self {
+            Network::Mainnet => None,
+            Network::Testnet => Some(b"nockchain-multisig/testnet\0"),
+            Network::Regtest => Some(b"nockchain-multisig/regtest\0"),
+        }
+    }
+
+    pub fn profile(self) -> NetworkProfile {
+        NetworkProfile {
+            network: self,
+            address_prefix: self.address_prefix(),
+            dust_limit: self.dust_limit(),
+            coinbase_maturity: self.coinbase_maturity(),
+            rpc_url: self.default_rpc_url(),
+        }
+    }
+}
+
+// ============================================================================
+// Addresses
+// ============================================================================
+
+//   <prefix>1<lock fingerprint>
+
+impl Lock {
+    pub fn address(&self, network: Network) -> String {
+        format!("{}1{}", network.address_prefix(), self.fingerprint())
+    }
+}
+
+// Returns the network and lock fingerprint of an address.
+pub fn decode_address(address: &str) -> Result<(Network, String), String> {
+    let (prefix, fingerprint) = address
+        .trim()
+        .split_once('1')
+        .ok_or("Address has no network prefix")?;
+    let network = [Network::Mainnet, Network::Testnet, Network::Regtest]
+        .into_iter()
+        .find(|n| n.address_prefix() == prefix)
+        .ok_or_else(|| format!("Unknown address prefix '{}'", prefix))?;
+    if fingerprint.len() != 64 || !fingerprint.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err("Address does not contain a lock fingerprint".into());
+    }
+    Ok((network, fingerprint.into()))
+}
+
+impl Transaction {
+    pub fn set_network(&mut self, network: Network) {
+        self.network = network;
+        self.invalidate_hashes();
+    }
+
+    // Every note and address-shaped recipient must be on the draft's network,
+    // and no output may fall below its dust limit.
+    pub fn validate_network(&self) -> Result<(), String> {
+        let network = self.network;
+        for (i, spend) in self.spends.iter().enumerate() {
+            if spend.note.network != network {
+                return Err(format!(
+                    "Spend {} note is on {} but the draft is for {}",
+                    i,
+                    spend.note.network.name(),
+                    network.name()
+                ));
+            }
+        }
+        for (i, output) in self.outputs.iter().enumerate() {
+            if output.value < network.dust_limit() {
+                return Err(format!(
+                    "Output {} is below the {} dust limit of {}",
+                    i,
+                    network.name(),
+                    network.dust_limit()
+                ));
+            }
+            if let Ok((recipient, _)) = decode_address(&output.recipient) {
+                if recipient != network {
+                    return Err(format!(
+                        "Output {} pays a {} address from a {} draft",
+                        i,
+                        recipient.name(),
+                        network.name()
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+use crate::hashing::{digest_hex, HashAlgorithm};
+use crate::{Output, Transaction};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Output Identifiers
+// ============================================================================
+
+// Tooling outside the draft (child drafts, accounting exports, explorers)
+// needs to name one output. An id is the output's index plus a hash of the
+// output itself (recipient, value, lock). The hash is independent of
+// position, so an id taken before a shuffle still finds its output after
+// it; the index tells identical outputs apart. Ids are taken from the built
+// draft, after duplicate outputs have been merged.
+
+const OUTPUT_DOMAIN: &[u8] = b"nockchain-multisig/output/v1";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutputId {
+    pub index: usize,
+    pub hash: String,
+}
+
+pub fn output_hash(output: &Output) -> String {
+    let bytes = serde_json::to_vec(output).expect("Serialization failed");
+    digest_hex(HashAlgorithm::Sha256, &[OUTPUT_DOMAIN, &bytes].concat())
+        .expect("sha256 is always available")
+}
+
+impl Transaction {
+    pub fn output_id(&self, index: usize) -> Result<OutputId, String> {
+        let output = self
+            .outputs
+            .get(index)
+            .ok_or_else(|| format!("No output {}", index))?;
+        Ok(OutputId {
+            index,
+            hash: output_hash(output),
+        })
+    }
+
+    pub fn output_ids(&self) -> Vec<OutputId> {
+        self.outputs
+            .iter()
+            .enumerate()
+            .map(|(index, output)| OutputId {
+                index,
+                hash: output_hash(output),
+            })
+            .collect()
+    }
+
+    // The current index of the output `id` names: its own index if the
+    // output there still matches, otherwise the one output with its hash.
+    pub fn resolve_output(&self, id: &OutputId) -> Result<usize, String> {
+        let ids = self.output_ids();
+        if ids.get(id.index).is_some_and(|o| o.hash == id.hash) {
+            return Ok(id.index);
+        }
+        let matches: Vec<usize> = ids
+            .iter()
+            .filter(|o| o.hash == id.hash)
+            .map(|o| o.index)
+            .collect();
+        match matches.as_slice() {
+            [index] => Ok(*index),
+            [] => Err(format!("No output {} in this draft", id.hash)),
+            _ => Err(format!(
+                "Output {} appears {} times and has moved; it cannot be told apart",
+                id.hash,
+                matches.len()
+            )),
+        }
+    }
+}
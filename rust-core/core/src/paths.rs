@@ -0,0 +1,293 @@
+use crate::{Lock, PkhCondition, PublicKey, Spend, SpendCondition};
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Dual-Path Locks
+// ============================================================================
+
+// Taproot-style: the primary branch is a single aggregated key (e.g. a MuSig
+// key computed by the signers' wallets) for the cooperative case, and the
+// alternatives form the script paths used when cosigners disagree. Spend
+// hashes do not depend on the branch, so a signature from a key counts
+// towards every path that key appears in, and signers need not decide on a
+// path up front.
+
+impl Lock {
+    pub fn dual_path(aggregate_key: &PublicKey, script_paths: Vec<SpendCondition>) -> Self {
+        let mut lock = Lock::single(aggregate_key);
+        lock.alternatives = script_paths;
+        lock
+    }
+
+    pub fn is_dual_path(&self) -> bool {
+        self.pkh.threshold == 1
+            && self.pkh.pubkeys.len() == 1
+            && self.hax.is_none()
+            && self.tim.is_none()
+            && !self.emergency
+            && !self.alternatives.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PathKind {
+    Key,
+    Script,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PathStatus {
+    pub branch: usize,
+    pub kind: PathKind,
+    pub threshold: usize,
+    pub signed: Vec<PublicKey>,
+    pub missing_signatures: usize,
+    pub preimages_ok: bool,
+    // None when no height was given and the path has a time lock.
+    pub timelock_ok: Option<bool>,
+    pub satisfied: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PathAnalysis {
+    pub current_branch: usize,
+    pub paths: Vec<PathStatus>,
+    // The satisfied path, or else the open path needing fewest signatures.
+    pub on_track: Option<usize>,
+}
+
+fn signed_by(spend: &Spend, pkh: &PkhCondition) -> Vec<PublicKey> {
+    pkh.pubkeys
+        .iter()
+        .filter(|pk| spend.seeds.has_signature(pk))
+        .cloned()
+        .collect()
+}
+
+impl Spend {
+    pub fn analyze_paths(&self, height: Option<u64>) -> PathAnalysis {
+        let lock = &self.note.lock;
+        let dual = lock.is_dual_path();
+
+        let paths: Vec<PathStatus> = lock
+            .conditions()
+            .enumerate()
+            .map(|(branch, condition)| {
+                let signed = signed_by(self, condition.pkh);
+                let missing_signatures = condition.pkh.threshold.saturating_sub(signed.len());
+                let preimages_ok = condition
+                    .hax
+                    .is_none_or(|hax| hax.is_satisfied_by(&self.seeds.preimages));
+                let timelock_ok = match (condition.tim, height) {
+                    (None, _) => Some(true),
+                    (Some(tim), Some(height)) => Some(tim.is_satisfied_at(height)),
+                    (Some(_), None) => None,
+                };
+                PathStatus {
+                    branch,
+                    kind: if dual && branch == 0 {
+                        PathKind::Key
+                    } else {
+                        PathKind::Script
+                    },
+                    threshold: condition.pkh.threshold,
+                    signed,
+                    missing_signatures,
+                    preimages_ok,
+                    timelock_ok,
+                    satisfied: missing_signatures == 0
+                        && preimages_ok
+                        && timelock_ok == Some(true)
+                        && !condition.emergency,
+                }
+            })
+            .collect();
+
+        let on_track = paths
+            .iter()
+            .find(|p| p.satisfied)
+            .or_else(|| {
+                paths
+                    .iter()
+                    .filter(|p| p.timelock_ok != Some(false))
+                    .filter(|p| lock.condition(p.branch).is_some_and(|c| !c.emergency))
+                    .min_by_key(|p| {
+                        (
+                            p.missing_signatures,
+                            !p.preimages_ok,
+                            p.timelock_ok.is_none(),
+                            p.branch,
+                        )
+                    })
+            })
+            .map(|p| p.branch);
+
+        PathAnalysis {
+            current_branch: self.seeds.branch,
+            paths,
+            on_track,
+        }
+    }
+
+    // Moves the spend onto another branch, keeping the signatures that
+    // still count there.
+    pub fn switch_branch(&mut self, branch: usize) -> Result<(), String> {
+        let pubkeys = self
+            .note
+            .lock
+            .condition(branch)
+            .ok_or_else(|| alloc::format!("Lock has no branch {}", branch))?
+            .pkh
+            .pubkeys
+            .clone();
+        self.seeds.branch = branch;
+        self.seeds.signatures.retain(|(pk, _)| pubkeys.contains(pk));
+        self.seeds
+            .delegations
+            .retain(|d| pubkeys.contains(&d.signer));
+        self.seeds.aggregated.retain(|pk| pubkeys.contains(pk));
+        self.seeds
+            .preapprovals
+            .retain(|p| pubkeys.contains(&p.signer));
+        self.seeds.incident = None;
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Signer Substitution
+// ============================================================================
+
+// Which sets of the keys still outstanding would complete a spend, given the
+// signatures collected and the keys that declined. Each set is minimal: no
+// smaller set reaches a threshold on any branch. Emergency branches are left
+// out, since they are not a way to route around a cosigner.
+
+// Enumeration stops here; wide locks can have very many combinations.
+pub const MAX_SIGNER_COMBINATIONS: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SignerCombination {
+    pub branch: usize,
+    pub signers: Vec<PublicKey>,
+    pub preimages_ok: bool,
+    pub timelock_ok: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SubstitutionAnalysis {
+    pub signed: Vec<PublicKey>,
+    pub declined: Vec<PublicKey>,
+    // Smallest first; empty signer lists mean the branch is already signed.
+    pub combinations: Vec<SignerCombination>,
+    pub truncated: bool,
+    // Keys in every combination: the spend cannot complete without them.
+    pub essential: Vec<PublicKey>,
+}
+
+impl SubstitutionAnalysis {
+    pub fn reachable(&self) -> bool {
+        !self.combinations.is_empty()
+    }
+}
+
+// Appends the `k`-subsets of `keys` to `out`, up to `limit` in total.
+fn subsets(keys: &[PublicKey], k: usize, limit: usize, out: &mut Vec<Vec<PublicKey>>) -> bool {
+    let n = keys.len();
+    let mut index: Vec<usize> = (0..k).collect();
+    loop {
+        if out.len() >= limit {
+            return false;
+        }
+        out.push(index.iter().map(|&i| keys[i].clone()).collect());
+        let Some(i) = (0..k).rev().find(|&i| index[i] < n - k + i) else {
+            return true;
+        };
+        index[i] += 1;
+        for j in i + 1..k {
+            index[j] = index[j - 1] + 1;
+        }
+    }
+}
+
+impl Spend {
+    pub fn signer_substitutions(
+        &self,
+        declined: &[PublicKey],
+        height: Option<u64>,
+    ) -> SubstitutionAnalysis {
+        let analysis = self.analyze_paths(height);
+        let mut candidates: Vec<SignerCombination> = Vec::new();
+        let mut truncated = false;
+        for (condition, path) in self.note.lock.conditions().zip(&analysis.paths) {
+            if condition.emergency || path.timelock_ok == Some(false) {
+                continue;
+            }
+            let available: Vec<PublicKey> = condition
+                .pkh
+                .pubkeys
+                .iter()
+                .filter(|pk| !path.signed.contains(pk) && !declined.contains(pk))
+                .cloned()
+                .collect();
+            if path.missing_signatures > available.len() {
+                continue;
+            }
+            let mut sets = Vec::new();
+            let limit = MAX_SIGNER_COMBINATIONS - candidates.len();
+            truncated |= !subsets(&available, path.missing_signatures, limit, &mut sets);
+            candidates.extend(sets.into_iter().map(|signers| SignerCombination {
+                branch: path.branch,
+                signers,
+                preimages_ok: path.preimages_ok,
+                timelock_ok: path.timelock_ok,
+            }));
+            if truncated {
+                break;
+            }
+        }
+
+        // Drop sets that contain another set, keeping the lowest branch for
+        // equal ones.
+        candidates.sort_by_key(|c| (c.signers.len(), c.branch));
+        let mut combinations: Vec<SignerCombination> = Vec::new();
+        for candidate in candidates {
+            let covered = combinations
+                .iter()
+                .any(|c| c.signers.iter().all(|pk| candidate.signers.contains(pk)));
+            if !covered {
+                combinations.push(candidate);
+            }
+        }
+
+        let essential = match combinations.split_first() {
+            Some((first, rest)) => first
+                .signers
+                .iter()
+                .filter(|pk| rest.iter().all(|c| c.signers.contains(pk)))
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        };
+        let mut signed: Vec<PublicKey> = Vec::new();
+        for pk in analysis.paths.iter().flat_map(|p| &p.signed) {
+            if !signed.contains(pk) {
+                signed.push(pk.clone());
+            }
+        }
+        SubstitutionAnalysis {
+            signed,
+            declined: declined.to_vec(),
+            combinations,
+            truncated,
+            essential,
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use crate::hashing::{digest_hex, HashAlgorithm};
+use crate::{Output, PublicKey, Signature, Spend, Transaction};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Pre-Approvals
+// ============================================================================
+
+// A cosigner can approve a spend before it is drafted by signing its terms:
+// the lock the notes come from, the exact outputs, and the last height the
+// spend may confirm at. When a draft matching the terms is built, the terms
+// fill the cosigner's slot on each matching spend. That slot's signature is
+// checked against the terms rather than the spend hash, and the terms are
+// checked against the transaction, so threshold counting is unchanged and
+// nothing beyond the approved outputs can ride along (change back to the
+// spent lock excepted).
+
+const PREAPPROVAL_DOMAIN: &[u8] = b"nockchain-multisig/pre-approval/v1";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PreApproval {
+    pub signer: PublicKey,
+    // `Lock::fingerprint` of the lock the notes are spent from.
+    pub lock: String,
+    pub outputs: Vec<Output>,
+    pub valid_until_height: u64,
+    pub signature: Signature,
+}
+
+#[derive(Serialize)]
+struct PreApprovalTerms<'a> {
+    signer: &'a PublicKey,
+    lock: &'a str,
+    outputs: &'a [Output],
+    valid_until_height: u64,
+}
+
+impl PreApproval {
+    // What the cosigner signs: every field except the signature itself.
+    pub fn message(&self) -> String {
+        let terms = PreApprovalTerms {
+            signer: &self.signer,
+            lock: &self.lock,
+            outputs: &self.outputs,
+            valid_until_height: self.valid_until_height,
+        };
+        let bytes = serde_json::to_vec(&terms).expect("Serialization failed");
+        digest_hex(
+            HashAlgorithm::Sha256,
+            &[PREAPPROVAL_DOMAIN, &bytes].concat(),
+        )
+        .expect("sha256 is always available")
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.outputs.is_empty() {
+            return Err("Pre-approval must name at least one output".into());
+        }
+        if self.outputs.iter().any(|o| o.value == 0) {
+            return Err("Pre-approved outputs must have a positive value".into());
+        }
+        Ok(())
+    }
+
+    // Every approved output must appear exactly once; anything else must be
+    // change back to the spent lock.
+    pub fn matches(&self, tx: &Transaction, spend: &Spend) -> Result<(), String> {
+        if spend.note.lock.fingerprint() != self.lock {
+            return Err(format!(
+                "Pre-approval from {} is for another lock",
+                self.signer.0
+            ));
+        }
+        let mut used = vec![false; self.outputs.len()];
+        for output in &tx.outputs {
+            let approved = self
+                .outputs
+                .iter()
+                .enumerate()
+                .find(|(i, o)| !used[*i] && *o == output);
+            match approved {
+                Some((i, _)) => used[i] = true,
+                None if output.lock == spend.note.lock => {}
+                None => {
+                    return Err(format!(
+                        "Output to {} is not covered by the pre-approval from {}",
+                        output.recipient, self.signer.0
+                    ))
+                }
+            }
+        }
+        if used.contains(&false) {
+            return Err(format!(
+                "Draft is missing outputs pre-approved by {}",
+                self.signer.0
+            ));
+        }
+        Ok(())
+    }
+
+    // Fails without a height: an expiry that cannot be checked must not let
+    // the terms count as a signature.
+    pub fn permits(&self, height: Option<u64>) -> Result<(), String> {
+        match height {
+            None => Err(format!(
+                "Pre-approval from {} expires at height {} and needs a chain height to check",
+                self.signer.0, self.valid_until_height
+            )),
+            Some(height) if height > self.valid_until_height => Err(format!(
+                "Pre-approval from {} expired at height {}",
+                self.signer.0, self.valid_until_height
+            )),
+            Some(_) => Ok(()),
+        }
+    }
+}
+
+pub fn preapproval_from<'a>(
+    preapprovals: &'a [PreApproval],
+    signer: &PublicKey,
+) -> Option<&'a PreApproval> {
+    preapprovals.iter().find(|p| &p.signer == signer)
+}
+
+impl Transaction {
+    // Fills the slots `preapproval` covers: spends its signer may sign, that
+    // it has not signed yet, and whose terms match. Returns the spend indices
+    // filled.
+    pub fn apply_preapproval(
+        &mut self,
+        preapproval: &PreApproval,
+        height: Option<u64>,
+    ) -> Result<Vec<usize>, String> {
+        preapproval.validate()?;
+        preapproval.permits(height)?;
+        let mut filled = Vec::new();
+        for i in 0..self.spends.len() {
+            let spend = &self.spends[i];
+            if spend.seeds.has_signature(&preapproval.signer)
+                || !spend.signers()?.pubkeys.contains(&preapproval.signer)
+                || preapproval.matches(self, spend).is_err()
+            {
+                continue;
+            }
+            self.spends[i].seeds.add_preapproval(preapproval.clone());
+            filled.push(i);
+        }
+        Ok(filled)
+    }
+
+    // Structural, scope and expiry checks; with no height every pre-approval
+    // fails. Signatures over the terms are checked by `verify_signatures`.
+    pub fn validate_preapprovals(&self, height: Option<u64>) -> Result<(), String> {
+        (0..self.spends.len()).try_for_each(|i| self.validate_spend_preapprovals(i, height))
+    }
+
+    pub fn validate_spend_preapprovals(
+        &self,
+        index: usize,
+        height: Option<u64>,
+    ) -> Result<(), String> {
+        let spend = self
+            .spends
+            .get(index)
+            .ok_or_else(|| format!("No spend {}", index))?;
+        for preapproval in &spend.seeds.preapprovals {
+            let slot = spend
+                .seeds
+                .signatures
+                .iter()
+                .find(|(pk, _)| pk == &preapproval.signer);
+            if slot.map(|(_, sig)| sig) != Some(&preapproval.signature) {
+                return Err(format!(
+                    "Spend {} carries a pre-approval that does not fill its slot",
+                    index
+                ));
+            }
+            preapproval
+                .validate()
+                .and_then(|_| preapproval.matches(self, spend))
+                .and_then(|_| preapproval.permits(height))
+                .map_err(|e| format!("Spend {}: {}", index, e))?;
+        }
+        Ok(())
+    }
+}
+
+pub fn preapproval_messages(tx: &Transaction) -> Vec<(&PreApproval, String)> {
+    tx.spends
+        .iter()
+        .flat_map(|s| &s.seeds.preapprovals)
+        .map(|p| (p, p.message()))
+        .collect()
+}
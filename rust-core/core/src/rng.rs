@@ -0,0 +1,86 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use sha2::{Digest, Sha256};
+
+// ============================================================================
+// Seeded Randomness
+// ============================================================================
+
+// The core has no entropy source of its own. Privacy passes that need
+// randomness take a caller-supplied seed (e.g. 32 bytes from
+// `crypto.getRandomValues`) and expand it with SHA-256 in counter mode, so
+// the same seed always gives the same choices.
+//
+// Where a seed, salt or key is generated rather than passed in, it is drawn
+// from a `RandomSource` the caller injects: the main crate's `OsRandom` or
+// `WebCryptoRandom`, an HSM behind the same trait, or a `SeededRng`, which
+// makes a whole run reproducible for tests and audits.
+
+const RNG_DOMAIN: &[u8] = b"nockchain-multisig/rng/v1";
+
+pub struct SeededRng {
+    seed: [u8; 32],
+    counter: u64,
+}
+
+impl SeededRng {
+    pub fn new(seed: &[u8]) -> Self {
+        Self {
+            seed: Sha256::new()
+                .chain_update(RNG_DOMAIN)
+                .chain_update(seed)
+                .finalize()
+                .into(),
+            counter: 0,
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let block = Sha256::new()
+            .chain_update(self.seed)
+            .chain_update(self.counter.to_be_bytes())
+            .finalize();
+        self.counter += 1;
+        let mut word = [0u8; 8];
+        word.copy_from_slice(&block[..8]);
+        u64::from_be_bytes(word)
+    }
+
+    // Uniform in [0, n); `n` must be positive.
+    pub fn below(&mut self, n: u64) -> u64 {
+        let zone = u64::MAX - u64::MAX % n;
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return value % n;
+            }
+        }
+    }
+}
+
+pub trait RandomSource {
+    // Fails if the source cannot deliver (e.g. an HSM is unreachable); a
+    // source never returns weaker bytes instead.
+    fn fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), String>;
+}
+
+impl RandomSource for SeededRng {
+    fn fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), String> {
+        for chunk in dest.chunks_mut(8) {
+            let word = self.next_u64().to_be_bytes();
+            chunk.copy_from_slice(&word[..chunk.len()]);
+        }
+        Ok(())
+    }
+}
+
+pub fn random_bytes(rng: &mut dyn RandomSource, len: usize) -> Result<Vec<u8>, String> {
+    let mut bytes = vec![0u8; len];
+    rng.fill_bytes(&mut bytes)?;
+    Ok(bytes)
+}
+
+pub fn random_hex(rng: &mut dyn RandomSource, len: usize) -> Result<String, String> {
+    Ok(hex::encode(random_bytes(rng, len)?))
+}
@@ -0,0 +1,98 @@
+use crate::Transaction;
+use alloc::format;
+use alloc::string::String;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+// ============================================================================
+// Schema Versions
+// ============================================================================
+
+// The layout of a transaction, carried as `schema_version`. Version 1 is the
+// layout every transaction had before the field existed, so it is implied
+// when the field is absent and never written out: old drafts and their
+// txids are unchanged. A transaction from a newer schema fails to parse with
+// an error naming both versions instead of being read with fields dropped
+// and then hashed differently. `upgrade_transaction` brings older JSON
+// forward one migration at a time.
+
+pub const TRANSACTION_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(transparent)]
+pub struct SchemaVersion(u32);
+
+impl SchemaVersion {
+    pub const CURRENT: SchemaVersion = SchemaVersion(TRANSACTION_SCHEMA_VERSION);
+
+    pub fn new(version: u32) -> Result<Self, String> {
+        check_version(version)?;
+        Ok(Self(version))
+    }
+
+    pub fn get(self) -> u32 {
+        self.0
+    }
+
+    pub fn is_current(&self) -> bool {
+        *self == Self::CURRENT
+    }
+}
+
+impl Default for SchemaVersion {
+    fn default() -> Self {
+        Self::CURRENT
+    }
+}
+
+impl<'de> Deserialize<'de> for SchemaVersion {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let version = u32::deserialize(deserializer)?;
+        SchemaVersion::new(version).map_err(serde::de::Error::custom)
+    }
+}
+
+fn check_version(version: u32) -> Result<(), String> {
+    if version == 0 {
+        return Err("Transaction schema version 0 does not exist".into());
+    }
+    if version > TRANSACTION_SCHEMA_VERSION {
+        return Err(format!(
+            "Transaction schema version {} is newer than this app supports ({}); update the app",
+            version, TRANSACTION_SCHEMA_VERSION
+        ));
+    }
+    Ok(())
+}
+
+// Migration from version `i + 1` to `i + 2`, applied to the raw JSON.
+type Migration = fn(&mut Value) -> Result<(), String>;
+const MIGRATIONS: &[Migration] = &[];
+
+// The version two cosigners can both read: the lower of the two, as long as
+// this side still reads it.
+pub fn negotiate(theirs: u32) -> Result<u32, String> {
+    let agreed = theirs.min(TRANSACTION_SCHEMA_VERSION);
+    check_version(agreed)?;
+    Ok(agreed)
+}
+
+// Parses transaction JSON of any version this app knows and returns it at
+// the current one.
+pub fn upgrade_transaction(mut value: Value) -> Result<Transaction, String> {
+    let object = value
+        .as_object_mut()
+        .ok_or("Transaction must be a JSON object")?;
+    let version = match object.remove("schema_version") {
+        None => 1,
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or("schema_version must be a positive integer")?,
+    };
+    check_version(version)?;
+    for migrate in &MIGRATIONS[version as usize - 1..] {
+        migrate(&mut value)?;
+    }
+    serde_json::from_value(value).map_err(|e| format!("{}", e))
+}
@@ -0,0 +1,72 @@
+use crate::rng::SeededRng;
+use crate::{Note, Output, Transaction};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// ============================================================================
+// Seeded Shuffling
+// ============================================================================
+
+// Spend and output order otherwise reveals how a draft was put together
+// (payments first, change last, notes oldest first). Shuffling with a shared
+// seed hides that while letting every cosigner rebuild the same draft on
+// their own: items are first sorted into a canonical order, so the result
+// depends only on the set of notes and outputs and the seed, never on the
+// order a particular device listed them in.
+
+const SHUFFLE_DOMAIN: &[u8] = b"nockchain-multisig/shuffle/v1";
+
+pub fn shuffle_rng(seed: &[u8]) -> SeededRng {
+    SeededRng::new(&[SHUFFLE_DOMAIN, seed].concat())
+}
+
+// New position `i` takes old index `order[i]`.
+pub fn shuffled_order<K: Ord>(keys: &[K], rng: &mut SeededRng) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..keys.len()).collect();
+    order.sort_by(|a, b| keys[*a].cmp(&keys[*b]));
+    for i in (1..order.len()).rev() {
+        let j = rng.below(i as u64 + 1) as usize;
+        order.swap(i, j);
+    }
+    order
+}
+
+pub fn permute<T>(items: Vec<T>, order: &[usize]) -> Vec<T> {
+    let mut items: Vec<Option<T>> = items.into_iter().map(Some).collect();
+    order
+        .iter()
+        .map(|&i| items[i].take().expect("order is a permutation"))
+        .collect()
+}
+
+pub fn note_key(note: &Note) -> (String, String) {
+    (note.name.first.clone(), note.name.last.clone())
+}
+
+pub fn output_key(output: &Output) -> (String, u64, String) {
+    (
+        output.recipient.clone(),
+        output.value,
+        output.lock.fingerprint(),
+    )
+}
+
+impl Transaction {
+    // Reorders spends and outputs and recomputes spend hashes, so it is only
+    // for drafts nobody has signed yet.
+    pub fn shuffle(&mut self, seed: &[u8]) -> Result<(), String> {
+        if self.spends.iter().any(|s| s.seeds.signature_count() > 0) {
+            return Err("Shuffle a draft before collecting signatures".into());
+        }
+        let mut rng = shuffle_rng(seed);
+        let keys: Vec<_> = self.spends.iter().map(|s| note_key(&s.note)).collect();
+        let order = shuffled_order(&keys, &mut rng);
+        self.spends = permute(core::mem::take(&mut self.spends), &order);
+
+        let keys: Vec<_> = self.outputs.iter().map(output_key).collect();
+        let order = shuffled_order(&keys, &mut rng);
+        let outputs = permute(core::mem::take(&mut self.outputs), &order);
+        self.set_outputs(outputs);
+        self.refresh_spend_hashes()
+    }
+}
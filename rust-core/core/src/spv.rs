@@ -0,0 +1,127 @@
+use crate::hashing::{digest_hex, HashAlgorithm};
+use crate::merkle::MerkleProof;
+use crate::network::Network;
+use crate::{Lock, Note, NoteName};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Light-Client Inclusion Proofs
+// ============================================================================
+
+// Lets a browser coordinator check that a note exists with a given value and
+// lock without taking the RPC endpoint's word for it. The endpoint returns
+// the note, a Merkle path from its commitment to the note root of the block
+// that created it, and the headers from that block up to the tip. The proof
+// is only as good as the tip hash it is checked against, which must come
+// from somewhere the caller trusts (a checkpoint, several independent
+// endpoints).
+//
+// Nockchain's own proofs are Tip5-based, which this crate cannot compute
+// yet (see `HashAlgorithm::Tip5`), so this is the crate's own format built
+// from the same Merkle trees as batch signing.
+
+const NOTE_DOMAIN: &[u8] = b"nockchain-multisig/note/v1";
+const HEADER_DOMAIN: &[u8] = b"nockchain-multisig/header/v1";
+
+// Note root of a block that creates no notes, and parent of the first block.
+pub const EMPTY_ROOT: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+// Covers what the note is, not what a wallet knows about it, so provenance
+// fields do not change the commitment.
+#[derive(Serialize)]
+struct CommittedNote<'a> {
+    name: &'a NoteName,
+    value: u64,
+    lock: &'a Lock,
+    #[serde(skip_serializing_if = "Network::is_default")]
+    network: Network,
+}
+
+pub fn note_commitment(algorithm: HashAlgorithm, note: &Note) -> Result<String, String> {
+    let committed = CommittedNote {
+        name: &note.name,
+        value: note.value,
+        lock: &note.lock,
+        network: note.network,
+    };
+    let bytes = serde_json::to_vec(&committed).expect("Serialization failed");
+    digest_hex(algorithm, &[NOTE_DOMAIN, &bytes].concat())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BlockHeader {
+    pub height: u64,
+    pub parent: String,
+    pub note_root: String,
+}
+
+impl BlockHeader {
+    pub fn hash(&self, algorithm: HashAlgorithm) -> Result<String, String> {
+        let bytes = serde_json::to_vec(self).expect("Serialization failed");
+        digest_hex(algorithm, &[HEADER_DOMAIN, &bytes].concat())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NoteInclusionProof {
+    pub note: Note,
+    // Leaf is the note commitment; root is the first header's note root.
+    pub path: MerkleProof,
+    // The creating block first, then each descendant up to the tip.
+    pub headers: Vec<BlockHeader>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InclusionReport {
+    pub note: Note,
+    pub height: u64,
+    pub tip_height: u64,
+    pub confirmations: u64,
+}
+
+impl NoteInclusionProof {
+    pub fn verify(&self, trusted_tip: &str) -> Result<InclusionReport, String> {
+        let algorithm = self.path.algorithm;
+        let first = self.headers.first().ok_or("Proof has no headers")?;
+
+        if self.path.spend_hash != note_commitment(algorithm, &self.note)? {
+            return Err("Merkle path is for a different note".into());
+        }
+        self.path.verify()?;
+        if self.path.root != first.note_root {
+            return Err("Merkle path does not end at the block's note root".into());
+        }
+        if self.note.confirmed_at.is_some_and(|h| h != first.height) {
+            return Err(format!(
+                "Note claims height {} but was proven at {}",
+                self.note.confirmed_at.unwrap_or_default(),
+                first.height
+            ));
+        }
+
+        let mut hash = first.hash(algorithm)?;
+        for (prev, next) in self.headers.iter().zip(&self.headers[1..]) {
+            if next.parent != hash || next.height != prev.height + 1 {
+                return Err(format!("Header {} does not extend the chain", next.height));
+            }
+            hash = next.hash(algorithm)?;
+        }
+        if !hash.eq_ignore_ascii_case(trusted_tip.trim()) {
+            return Err("Header chain does not end at the trusted tip".into());
+        }
+
+        let tip_height = self.headers.last().expect("non-empty").height;
+        Ok(InclusionReport {
+            note: self.note.clone(),
+            height: first.height,
+            tip_height,
+            confirmations: tip_height - first.height + 1,
+        })
+    }
+}
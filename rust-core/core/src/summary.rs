@@ -0,0 +1,100 @@
+use crate::hashing::{digest_hex, HashAlgorithm};
+use crate::network::Network;
+use crate::{PublicKey, Seeds, Signature, Transaction};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Signed Summaries
+// ============================================================================
+
+// A signer approves what their wallet showed them, not the spend hash. The
+// wallet renders the draft's summary (network, recipients, amounts, lock
+// fingerprints), hashes it, and records that hash next to the signature.
+// Finalizing checks every recorded hash against the transaction, so a
+// wallet that displayed one thing and signed another can be caught after
+// the fact. Signatures without a recorded summary are accepted as before.
+
+const SUMMARY_DOMAIN: &[u8] = b"nockchain-multisig/summary/v1";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SummaryLine {
+    pub recipient: String,
+    pub value: u64,
+    pub lock: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TransactionSummary {
+    pub network: Network,
+    pub outputs: Vec<SummaryLine>,
+    pub total: u64,
+}
+
+impl TransactionSummary {
+    pub fn hash(&self) -> String {
+        let bytes = serde_json::to_vec(self).expect("Serialization failed");
+        digest_hex(HashAlgorithm::Sha256, &[SUMMARY_DOMAIN, &bytes].concat())
+            .expect("sha256 is always available")
+    }
+}
+
+impl Transaction {
+    pub fn summary(&self) -> TransactionSummary {
+        TransactionSummary {
+            network: self.network,
+            outputs: self
+                .outputs
+                .iter()
+                .map(|o| SummaryLine {
+                    recipient: o.recipient.clone(),
+                    value: o.value,
+                    lock: o.lock.fingerprint(),
+                })
+                .collect(),
+            total: self.total_output(),
+        }
+    }
+
+    pub fn summary_hash(&self) -> String {
+        self.summary().hash()
+    }
+
+    pub fn validate_summaries(&self) -> Result<(), String> {
+        let expected = self.summary_hash();
+        for (i, spend) in self.spends.iter().enumerate() {
+            for (pubkey, summary) in &spend.seeds.summaries {
+                if summary != &expected {
+                    return Err(format!(
+                        "Spend {} was signed by {} over a different summary",
+                        i, pubkey.0
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Seeds {
+    pub fn add_signature_with_summary(
+        &mut self,
+        pubkey: PublicKey,
+        signature: Signature,
+        summary: String,
+    ) {
+        self.add_signature(pubkey.clone(), signature);
+        self.summaries.push((pubkey, summary));
+    }
+
+    pub fn summary_of(&self, pubkey: &PublicKey) -> Option<&String> {
+        self.summaries
+            .iter()
+            .find(|(pk, _)| pk == pubkey)
+            .map(|(_, s)| s)
+    }
+}
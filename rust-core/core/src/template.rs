@@ -0,0 +1,134 @@
+use crate::build::{BuildOptions, BuildReport};
+use crate::hashing::HashAlgorithm;
+use crate::{Lock, Note, Output, Transaction};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Draft Templates
+// ============================================================================
+
+// A template is a draft with the notes left out and some amounts written as
+// rules, for standing instructions like "sweep 10% to the ops wallet
+// monthly". Amounts are resolved against whichever notes are attached when
+// the template is instantiated; percentages round down, so any dust goes to
+// the `remaining` output (or change).
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum TemplateAmount {
+    Fixed { value: u64 },
+    // 1000 is 10% of the notes' total value.
+    PercentOfInput { basis_points: u32 },
+    // Whatever the other outputs leave; dropped when that is nothing.
+    Remaining,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TemplateOutput {
+    pub recipient: String,
+    pub lock: Lock,
+    pub amount: TemplateAmount,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DraftTemplate {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    pub outputs: Vec<TemplateOutput>,
+    #[serde(default)]
+    pub options: BuildOptions,
+}
+
+impl DraftTemplate {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.outputs.is_empty() {
+            return Err("Template has no outputs".into());
+        }
+        let mut remaining = 0;
+        let mut basis_points: u64 = 0;
+        for (i, output) in self.outputs.iter().enumerate() {
+            output.lock.validate()?;
+            match output.amount {
+                TemplateAmount::Fixed { value: 0 } => {
+                    return Err(format!("Template output {} has no value", i));
+                }
+                TemplateAmount::PercentOfInput { basis_points: bp } => {
+                    if bp == 0 || bp > 10_000 {
+                        return Err(format!(
+                            "Template output {} must take between 1 and 10000 basis points",
+                            i
+                        ));
+                    }
+                    basis_points += bp as u64;
+                }
+                TemplateAmount::Remaining => remaining += 1,
+                TemplateAmount::Fixed { .. } => {}
+            }
+        }
+        if basis_points > 10_000 {
+            return Err("Template percentages add up to more than 100%".into());
+        }
+        if remaining > 1 {
+            return Err("Template can have only one remaining-balance output".into());
+        }
+        if remaining == 1 && self.options.change.is_some() {
+            return Err("Template has both a remaining-balance output and change".into());
+        }
+        Ok(())
+    }
+
+    // Concrete outputs for these notes, in template order.
+    pub fn resolve(&self, notes: &[Note]) -> Result<Vec<Output>, String> {
+        self.validate()?;
+        let total: u128 = notes.iter().map(|n| n.value as u128).sum();
+        let mut values: Vec<Option<u128>> = Vec::with_capacity(self.outputs.len());
+        for (i, output) in self.outputs.iter().enumerate() {
+            values.push(match output.amount {
+                TemplateAmount::Fixed { value } => Some(value as u128),
+                TemplateAmount::PercentOfInput { basis_points } => {
+                    let value = total * basis_points as u128 / 10_000;
+                    if value == 0 {
+                        return Err(format!("Template output {} resolves to zero", i));
+                    }
+                    Some(value)
+                }
+                TemplateAmount::Remaining => None,
+            });
+        }
+        let committed: u128 = values.iter().flatten().sum();
+        if committed > total {
+            return Err(format!(
+                "Template needs {} but the notes hold {}",
+                committed, total
+            ));
+        }
+
+        let mut outputs = Vec::with_capacity(self.outputs.len());
+        for (output, value) in self.outputs.iter().zip(values) {
+            let value = value.unwrap_or(total - committed);
+            if value == 0 {
+                continue;
+            }
+            outputs.push(Output {
+                recipient: output.recipient.clone(),
+                value: u64::try_from(value).map_err(|_| "Output value overflows")?,
+                lock: output.lock.clone(),
+            });
+        }
+        Ok(outputs)
+    }
+
+    pub fn instantiate(
+        &self,
+        notes: Vec<Note>,
+        algorithm: HashAlgorithm,
+    ) -> Result<BuildReport, String> {
+        let outputs = self.resolve(&notes)?;
+        Transaction::build_with(notes, outputs, algorithm, &self.options)
+    }
+}
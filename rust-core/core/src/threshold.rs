@@ -0,0 +1,190 @@
+use crate::hashing::{digest_hex, HashAlgorithm};
+use crate::{PkhCondition, PublicKey, Signature, Spend};
+use alloc::format;
+use alloc::string::String;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Threshold Proofs
+// ============================================================================
+
+// Lets a treasury publish "k of the n authorized keys signed this spend"
+// without naming the k. The statement is public; the proof comes from a
+// pluggable proof system (e.g. a SNARK over the signature scheme), since
+// this crate treats signatures as opaque and bundles no prover.
+
+const KEYSET_DOMAIN: &[u8] = b"nockchain-multisig/keyset/v1";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ThresholdStatement {
+    pub message_hash: String,
+    // Commitment to the authorized key set, see `keyset_commitment`.
+    pub keyset: String,
+    pub threshold: usize,
+    pub key_count: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ThresholdProof {
+    pub statement: ThresholdStatement,
+    pub system: String,
+    pub proof: String,
+}
+
+pub trait ThresholdProofSystem: Sync {
+    fn name(&self) -> &str;
+
+    // `signatures` are the witness and must never appear in the proof.
+    fn prove(
+        &self,
+        statement: &ThresholdStatement,
+        keys: &[PublicKey],
+        signatures: &[(PublicKey, Signature)],
+    ) -> Result<String, String>;
+
+    fn verify(&self, statement: &ThresholdStatement, proof: &str) -> bool;
+}
+
+pub fn keyset_commitment(pkh: &PkhCondition) -> String {
+    let bytes = serde_json::to_vec(&pkh.pubkeys).expect("Serialization failed");
+    digest_hex(HashAlgorithm::Sha256, &[KEYSET_DOMAIN, &bytes].concat())
+        .expect("sha256 is always available")
+}
+
+impl Spend {
+    pub fn threshold_statement(&self) -> Result<ThresholdStatement, String> {
+        let pkh = self.signers()?;
+        Ok(ThresholdStatement {
+            message_hash: self.seeds.message_hash.clone(),
+            keyset: keyset_commitment(pkh),
+            threshold: pkh.threshold,
+            key_count: pkh.pubkeys.len(),
+        })
+    }
+
+    pub fn prove_threshold(
+        &self,
+        index: usize,
+        system: &dyn ThresholdProofSystem,
+    ) -> Result<ThresholdProof, String> {
+        self.validate_signers(index)?;
+        let statement = self.threshold_statement()?;
+        let proof = system.prove(&statement, &self.signers()?.pubkeys, &self.seeds.signatures)?;
+        Ok(ThresholdProof {
+            statement,
+            system: system.name().into(),
+            proof,
+        })
+    }
+}
+
+impl ThresholdProof {
+    // `keyset` and `threshold` are what the verifier already trusts, e.g.
+    // from the wallet's published lock. The commitment covers only the keys,
+    // so the threshold has to be checked separately.
+    pub fn verify(
+        &self,
+        keyset: &str,
+        threshold: usize,
+        system: &dyn ThresholdProofSystem,
+    ) -> Result<(), String> {
+        if self.system != system.name() {
+            return Err(format!("Proof was made with {}", self.system));
+        }
+        if self.statement.keyset != keyset {
+            return Err("Proof is for a different key set".into());
+        }
+        if self.statement.threshold != threshold {
+            return Err(format!(
+                "Proof claims a threshold of {}, expected {}",
+                self.statement.threshold, threshold
+            ));
+        }
+        if self.statement.threshold == 0 || self.statement.threshold > self.statement.key_count {
+            return Err("Proof statement has an impossible threshold".into());
+        }
+        if !system.verify(&self.statement, &self.proof) {
+            return Err("Threshold proof is invalid".into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    // Accepts any proof, so only the statement checks are exercised.
+    struct TrustingSystem;
+
+    impl ThresholdProofSystem for TrustingSystem {
+        fn name(&self) -> &str {
+            "trusting"
+        }
+
+        fn prove(
+            &self,
+            _: &ThresholdStatement,
+            _: &[PublicKey],
+            _: &[(PublicKey, Signature)],
+        ) -> Result<String, String> {
+            Ok(String::new())
+        }
+
+        fn verify(&self, _: &ThresholdStatement, _: &str) -> bool {
+            true
+        }
+    }
+
+    fn proof(threshold: usize) -> (PkhCondition, ThresholdProof) {
+        let pkh = PkhCondition {
+            threshold: 2,
+            pubkeys: ["a", "b", "c"]
+                .iter()
+                .map(|k| PublicKey(k.to_string()))
+                .collect::<Vec<_>>(),
+        };
+        let proof = ThresholdProof {
+            statement: ThresholdStatement {
+                message_hash: "00".into(),
+                keyset: keyset_commitment(&pkh),
+                threshold,
+                key_count: pkh.pubkeys.len(),
+            },
+            system: "trusting".into(),
+            proof: String::new(),
+        };
+        (pkh, proof)
+    }
+
+    #[test]
+    fn accepts_expected_threshold() {
+        let (pkh, proof) = proof(2);
+        proof
+            .verify(&keyset_commitment(&pkh), pkh.threshold, &TrustingSystem)
+            .unwrap();
+    }
+
+    #[test]
+    fn rejects_lowered_threshold() {
+        let (pkh, proof) = proof(1);
+        let err = proof
+            .verify(&keyset_commitment(&pkh), pkh.threshold, &TrustingSystem)
+            .unwrap_err();
+        assert!(err.contains("threshold of 1"), "{err}");
+    }
+
+    #[test]
+    fn rejects_other_keyset() {
+        let (mut pkh, proof) = proof(2);
+        pkh.pubkeys = vec![PublicKey("d".into())];
+        assert!(proof
+            .verify(&keyset_commitment(&pkh), 2, &TrustingSystem)
+            .is_err());
+    }
+}
@@ -0,0 +1,488 @@
+use crate::delegation::{delegations_from, grant_messages};
+use crate::preapproval::{preapproval_from, preapproval_messages};
+use crate::{canonical_preimage, hash_spend_preimage, PublicKey, Signature, Spend, Transaction};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Signature Verification
+// ============================================================================
+
+// Schemes plug in here. `verify_batch` defaults to one-by-one checks; schemes
+// with a real batch equation (e.g. ed25519) should override it.
+pub trait SignatureVerifier: Sync {
+    fn verify(&self, pubkey: &PublicKey, message_hash: &str, signature: &Signature) -> bool;
+
+    fn verify_batch(&self, items: &[SignedMessage<'_>]) -> bool {
+        items
+            .iter()
+            .all(|item| self.verify(item.pubkey, item.message_hash, item.signature))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SignedMessage<'a> {
+    pub pubkey: &'a PublicKey,
+    pub message_hash: &'a str,
+    pub signature: &'a Signature,
+}
+
+// Matches the current behaviour of the WASM API, which treats signatures as
+// opaque strings supplied by the wallet.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnverifiedSignatures;
+
+impl SignatureVerifier for UnverifiedSignatures {
+    fn verify(&self, _: &PublicKey, _: &str, _: &Signature) -> bool {
+        true
+    }
+}
+
+impl Spend {
+    // Delegated slots are verified against the delegate's key, aggregate
+    // signatures against `aggregate_root` (see `Transaction::signing_root`).
+    // Pre-approved slots sign their terms and are checked with those.
+    pub fn signed_messages<'a>(
+        &'a self,
+        aggregate_root: Option<&'a str>,
+    ) -> Vec<SignedMessage<'a>> {
+        self.seeds
+            .signatures
+            .iter()
+            .filter(|(pubkey, _)| preapproval_from(&self.seeds.preapprovals, pubkey).is_none())
+            .map(|(pubkey, signature)| SignedMessage {
+                pubkey: delegations_from(&self.seeds.delegations, pubkey)
+                    .map_or(pubkey, |d| d.effective_signer()),
+                message_hash: aggregate_root
+                    .filter(|_| self.seeds.aggregated.contains(pubkey))
+                    .unwrap_or(&self.seeds.message_hash),
+                signature,
+            })
+            .collect()
+    }
+
+    pub fn verify_signatures(
+        &self,
+        index: usize,
+        aggregate_root: Option<&str>,
+        verifier: &dyn SignatureVerifier,
+    ) -> Result<(), String> {
+        for item in self.signed_messages(aggregate_root) {
+            if !verifier.verify(item.pubkey, item.message_hash, item.signature) {
+                return Err(format!(
+                    "Spend {} has invalid signature from {}",
+                    index, item.pubkey.0
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // The checks `Transaction::verify_signatures` makes for this spend alone:
+    // its delegation grants, pre-approval terms and incident, then its slots.
+    pub fn verification_items(&self, aggregate_root: Option<&str>) -> Vec<VerificationItem> {
+        let mut items: Vec<VerificationItem> = self
+            .seeds
+            .delegations
+            .iter()
+            .flat_map(|d| &d.chain)
+            .map(|grant| VerificationItem {
+                pubkey: grant.delegator.clone(),
+                message_hash: grant.message(),
+                signature: grant.signature.clone(),
+            })
+            .collect();
+        for preapproval in &self.seeds.preapprovals {
+            items.push(VerificationItem {
+                pubkey: preapproval.signer.clone(),
+                message_hash: preapproval.message(),
+                signature: preapproval.signature.clone(),
+            });
+        }
+        if let Some(incident) = &self.seeds.incident {
+            items.push(VerificationItem {
+                pubkey: incident.reporter.clone(),
+                message_hash: incident.message(&self.seeds.message_hash),
+                signature: incident.signature.clone(),
+            });
+        }
+        items.extend(
+            self.signed_messages(aggregate_root)
+                .into_iter()
+                .map(|m| VerificationItem {
+                    pubkey: m.pubkey.clone(),
+                    message_hash: m.message_hash.into(),
+                    signature: m.signature.clone(),
+                }),
+        );
+        items
+    }
+
+    // As `verification_items`, one at a time, with the error
+    // `Transaction::verify_signatures` would give.
+    pub fn verify_all_signatures(
+        &self,
+        index: usize,
+        aggregate_root: Option<&str>,
+        verifier: &dyn SignatureVerifier,
+    ) -> Result<(), String> {
+        for grant in self.seeds.delegations.iter().flat_map(|d| &d.chain) {
+            if !verifier.verify(&grant.delegator, &grant.message(), &grant.signature) {
+                return Err(format!(
+                    "Delegation grant from {} has an invalid signature",
+                    grant.delegator.0
+                ));
+            }
+        }
+        for preapproval in &self.seeds.preapprovals {
+            if !verifier.verify(
+                &preapproval.signer,
+                &preapproval.message(),
+                &preapproval.signature,
+            ) {
+                return Err(format!(
+                    "Pre-approval from {} has an invalid signature",
+                    preapproval.signer.0
+                ));
+            }
+        }
+        if let Some(incident) = &self.seeds.incident {
+            let message = incident.message(&self.seeds.message_hash);
+            if !verifier.verify(&incident.reporter, &message, &incident.signature) {
+                return Err(format!(
+                    "Spend {} incident attestation has an invalid signature",
+                    index
+                ));
+            }
+        }
+        self.verify_signatures(index, aggregate_root, verifier)
+    }
+}
+
+// Owned form of a check, for verifiers running outside this crate (e.g.
+// WebCrypto in the browser) whose results come back via `PrecomputedVerifier`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VerificationItem {
+    pub pubkey: PublicKey,
+    pub message_hash: String,
+    pub signature: Signature,
+}
+
+impl VerificationItem {
+    pub fn as_signed(&self) -> SignedMessage<'_> {
+        SignedMessage {
+            pubkey: &self.pubkey,
+            message_hash: &self.message_hash,
+            signature: &self.signature,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PrecomputedVerifier {
+    valid: Vec<VerificationItem>,
+}
+
+impl PrecomputedVerifier {
+    pub fn new(valid: Vec<VerificationItem>) -> Self {
+        Self { valid }
+    }
+}
+
+impl SignatureVerifier for PrecomputedVerifier {
+    fn verify(&self, pubkey: &PublicKey, message_hash: &str, signature: &Signature) -> bool {
+        self.valid.iter().any(|item| {
+            &item.pubkey == pubkey
+                && item.message_hash == message_hash
+                && &item.signature == signature
+        })
+    }
+}
+
+impl Transaction {
+    // Every check `verify_signatures` makes: grants, pre-approvals, incidents,
+    // then spends.
+    pub fn verification_items(&self) -> Result<Vec<VerificationItem>, String> {
+        let mut items: Vec<VerificationItem> = grant_messages(self)
+            .into_iter()
+            .map(|(grant, message)| VerificationItem {
+                pubkey: grant.delegator.clone(),
+                message_hash: message,
+                signature: grant.signature.clone(),
+            })
+            .collect();
+
+        for (preapproval, message) in preapproval_messages(self) {
+            items.push(VerificationItem {
+                pubkey: preapproval.signer.clone(),
+                message_hash: message,
+                signature: preapproval.signature.clone(),
+            });
+        }
+
+        for (i, incident) in self.incidents() {
+            items.push(VerificationItem {
+                pubkey: incident.reporter.clone(),
+                message_hash: incident.message(&self.spends[i].seeds.message_hash),
+                signature: incident.signature.clone(),
+            });
+        }
+
+        let root = self.signing_root()?;
+        for spend in &self.spends {
+            items.extend(spend.signed_messages(root.as_deref()).into_iter().map(|m| {
+                VerificationItem {
+                    pubkey: m.pubkey.clone(),
+                    message_hash: m.message_hash.into(),
+                    signature: m.signature.clone(),
+                }
+            }));
+        }
+        Ok(items)
+    }
+
+    pub fn verify_signatures(&self, verifier: &dyn SignatureVerifier) -> Result<(), String> {
+        self.verify_signatures_at(verifier, None)
+    }
+
+    // `height` is the height the transaction would confirm at, for
+    // delegation and pre-approval expiry.
+    pub fn verify_signatures_at(
+        &self,
+        verifier: &dyn SignatureVerifier,
+        height: Option<u64>,
+    ) -> Result<(), String> {
+        self.validate_signatures_at(height)?;
+
+        for (grant, message) in grant_messages(self) {
+            if !verifier.verify(&grant.delegator, &message, &grant.signature) {
+                return Err(format!(
+                    "Delegation grant from {} has an invalid signature",
+                    grant.delegator.0
+                ));
+            }
+        }
+
+        for (preapproval, message) in preapproval_messages(self) {
+            if !verifier.verify(&preapproval.signer, &message, &preapproval.signature) {
+                return Err(format!(
+                    "Pre-approval from {} has an invalid signature",
+                    preapproval.signer.0
+                ));
+            }
+        }
+
+        for (i, incident) in self.incidents() {
+            let message = incident.message(&self.spends[i].seeds.message_hash);
+            if !verifier.verify(&incident.reporter, &message, &incident.signature) {
+                return Err(format!(
+                    "Spend {} incident attestation has an invalid signature",
+                    i
+                ));
+            }
+        }
+
+        let root = self.signing_root()?;
+        let items: Vec<SignedMessage<'_>> = self
+            .spends
+            .iter()
+            .flat_map(|s| s.signed_messages(root.as_deref()))
+            .collect();
+        if verifier.verify_batch(&items) {
+            return Ok(());
+        }
+
+        // Batch failed: locate the offending spend for a useful error.
+        for (i, spend) in self.spends.iter().enumerate() {
+            spend.verify_signatures(i, root.as_deref(), verifier)?;
+        }
+        Err("Signature batch verification failed".into())
+    }
+}
+
+// ============================================================================
+// Per-spend Validation
+// ============================================================================
+
+// What a signer screen checks before signing one spend, cheapest first; each
+// level includes the ones before it. Thresholds are not enforced, since the
+// spend is still collecting signatures, and delegation grants and
+// pre-approval terms are left to full validation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpendCheckLevel {
+    // The lock is well formed, has the branch being exercised, and the note
+    // is on the draft's network.
+    Lock,
+    // The spend hash is what this draft's outputs hash to.
+    Hash,
+    // Every signature so far is from a key on the branch and verifies.
+    Signatures,
+}
+
+impl SpendCheckLevel {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "lock" => Ok(Self::Lock),
+            "hash" => Ok(Self::Hash),
+            "signatures" => Ok(Self::Signatures),
+            _ => Err(format!("Unknown validation level: {}", name)),
+        }
+    }
+}
+
+impl Transaction {
+    pub fn validate_spend(
+        &self,
+        index: usize,
+        level: SpendCheckLevel,
+        verifier: &dyn SignatureVerifier,
+    ) -> Result<(), String> {
+        let spend = self
+            .spends
+            .get(index)
+            .ok_or_else(|| format!("No spend {}", index))?;
+        spend.note.lock.validate()?;
+        spend.condition()?.validate()?;
+        if spend.note.network != self.network {
+            return Err(format!(
+                "Spend {} note is on {} but the draft is for {}",
+                index,
+                spend.note.network.name(),
+                self.network.name()
+            ));
+        }
+        if level == SpendCheckLevel::Lock {
+            return Ok(());
+        }
+
+        self.hash_algorithm.ensure_supported()?;
+        let preimage = canonical_preimage(&self.outputs, self.lineage.as_ref());
+        let expected = hash_spend_preimage(self.hash_algorithm, self.network, index, &preimage)?;
+        if spend.seeds.message_hash != expected {
+            return Err(format!(
+                "Spend {} hash does not match the draft's outputs",
+                index
+            ));
+        }
+        if level == SpendCheckLevel::Hash {
+            return Ok(());
+        }
+
+        let pkh = spend.signers()?;
+        if let Some((pk, _)) = spend
+            .seeds
+            .signatures
+            .iter()
+            .find(|(pk, _)| !pkh.pubkeys.contains(pk))
+        {
+            return Err(format!(
+                "Spend {} has a signature from {}, not a signer",
+                index, pk.0
+            ));
+        }
+        if let Some(incident) = &spend.seeds.incident {
+            spend.validate_incident(index)?;
+            let message = incident.message(&spend.seeds.message_hash);
+            if !verifier.verify(&incident.reporter, &message, &incident.signature) {
+                return Err(format!(
+                    "Spend {} incident attestation has an invalid signature",
+                    index
+                ));
+            }
+        }
+        spend.verify_signatures(index, self.signing_root()?.as_deref(), verifier)
+    }
+}
+
+// Checks the signatures collected so far on many drafts in a single batch,
+// e.g. a coordinator re-checking its pending queue. Thresholds are not
+// enforced, so incomplete drafts pass. Results are in input order.
+pub fn verify_collected(
+    txs: &[Transaction],
+    verifier: &dyn SignatureVerifier,
+) -> Vec<Result<(), String>> {
+    let items: Vec<Result<Vec<VerificationItem>, String>> =
+        txs.iter().map(|tx| tx.verification_items()).collect();
+
+    let batch: Vec<SignedMessage<'_>> = items
+        .iter()
+        .flatten()
+        .flatten()
+        .map(|item| item.as_signed())
+        .collect();
+    if verifier.verify_batch(&batch) {
+        return items.into_iter().map(|r| r.map(|_| ())).collect();
+    }
+
+    items
+        .into_iter()
+        .map(|r| {
+            let failed = r?
+                .into_iter()
+                .find(|item| !verifier.verify(&item.pubkey, &item.message_hash, &item.signature));
+            match failed {
+                Some(item) => Err(format!("Invalid signature from {}", item.pubkey.0)),
+                None => Ok(()),
+            }
+        })
+        .collect()
+}
+
+// ============================================================================
+// Spend Hash Integrity
+// ============================================================================
+
+// The hash stored in each spend's seeds is what signers are shown and sign,
+// so a draft edited after hashing (or crafted by hand) could present a hash
+// that does not commit to its outputs. These recompute every spend hash from
+// the canonical preimage and compare.
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpendHashMismatch {
+    pub spend_index: usize,
+    pub stored: String,
+    pub expected: String,
+}
+
+impl Transaction {
+    pub fn spend_hash_mismatches(&self) -> Result<Vec<SpendHashMismatch>, String> {
+        self.hash_algorithm.ensure_supported()?;
+        let preimage = canonical_preimage(&self.outputs, self.lineage.as_ref());
+        let mut mismatches = Vec::new();
+        for (i, spend) in self.spends.iter().enumerate() {
+            let expected = hash_spend_preimage(self.hash_algorithm, self.network, i, &preimage)?;
+            if spend.seeds.message_hash != expected {
+                mismatches.push(SpendHashMismatch {
+                    spend_index: i,
+                    stored: spend.seeds.message_hash.clone(),
+                    expected,
+                });
+            }
+        }
+        Ok(mismatches)
+    }
+
+    pub fn verify_spend_hashes(&self) -> Result<(), String> {
+        match self.spend_hash_mismatches()?.first() {
+            Some(m) => Err(format!(
+                "Spend {} hash does not match the draft's outputs",
+                m.spend_index
+            )),
+            None => Ok(()),
+        }
+    }
+
+    // Every check short of signatures. Each validation entry point (the WASM
+    // validators, `finalize_at`, parallel validation) runs this first.
+    pub fn validate_structure(&self) -> Result<(), String> {
+        self.validate_hash_algorithm()?;
+        self.verify_spend_hashes()?;
+        self.validate_network()?;
+        self.validate_balance()
+    }
+}
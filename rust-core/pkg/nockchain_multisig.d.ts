@@ -1,51 +0,0 @@
-/* tslint:disable */
-/* eslint-disable */
-
-export function add_signature(tx_json: string, spend_index: number, pubkey: string, signature: string): string;
-
-export function build_transaction(notes_json: string, outputs_json: string): string;
-
-export function get_spend_hash(tx_json: string, spend_index: number): string;
-
-export function get_spend_signing_status(tx_json: string, spend_index: number): string;
-
-export function validate_transaction(tx_json: string): string;
-
-export type InitInput = RequestInfo | URL | Response | BufferSource | WebAssembly.Module;
-
-export interface InitOutput {
-  readonly memory: WebAssembly.Memory;
-  readonly add_signature: (a: number, b: number, c: number, d: number, e: number, f: number, g: number) => [number, number, number, number];
-  readonly build_transaction: (a: number, b: number, c: number, d: number) => [number, number, number, number];
-  readonly get_spend_hash: (a: number, b: number, c: number) => [number, number, number, number];
-  readonly get_spend_signing_status: (a: number, b: number, c: number) => [number, number, number, number];
-  readonly validate_transaction: (a: number, b: number) => [number, number, number, number];
-  readonly __wbindgen_externrefs: WebAssembly.Table;
-  readonly __wbindgen_malloc: (a: number, b: number) => number;
-  readonly __wbindgen_realloc: (a: number, b: number, c: number, d: number) => number;
-  readonly __externref_table_dealloc: (a: number) => void;
-  readonly __wbindgen_free: (a: number, b: number, c: number) => void;
-  readonly __wbindgen_start: () => void;
-}
-
-export type SyncInitInput = BufferSource | WebAssembly.Module;
-
-/**
-* Instantiates the given `module`, which can either be bytes or
-* a precompiled `WebAssembly.Module`.
-*
-* @param {{ module: SyncInitInput }} module - Passing `SyncInitInput` directly is deprecated.
-*
-* @returns {InitOutput}
-*/
-export function initSync(module: { module: SyncInitInput } | SyncInitInput): InitOutput;
-
-/**
-* If `module_or_path` is {RequestInfo} or {URL}, makes a request and
-* for everything else, calls `WebAssembly.instantiate` directly.
-*
-* @param {{ module_or_path: InitInput | Promise<InitInput> }} module_or_path - Passing `InitInput` directly is deprecated.
-*
-* @returns {Promise<InitOutput>}
-*/
-export default function __wbg_init (module_or_path?: { module_or_path: InitInput | Promise<InitInput> } | InitInput | Promise<InitInput>): Promise<InitOutput>;
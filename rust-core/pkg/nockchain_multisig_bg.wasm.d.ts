@@ -1,14 +0,0 @@
-/* tslint:disable */
-/* eslint-disable */
-export const memory: WebAssembly.Memory;
-export const add_signature: (a: number, b: number, c: number, d: number, e: number, f: number, g: number) => [number, number, number, number];
-export const build_transaction: (a: number, b: number, c: number, d: number) => [number, number, number, number];
-export const get_spend_hash: (a: number, b: number, c: number) => [number, number, number, number];
-export const get_spend_signing_status: (a: number, b: number, c: number) => [number, number, number, number];
-export const validate_transaction: (a: number, b: number) => [number, number, number, number];
-export const __wbindgen_externrefs: WebAssembly.Table;
-export const __wbindgen_malloc: (a: number, b: number) => number;
-export const __wbindgen_realloc: (a: number, b: number, c: number, d: number) => number;
-export const __externref_table_dealloc: (a: number) => void;
-export const __wbindgen_free: (a: number, b: number, c: number) => void;
-export const __wbindgen_start: () => void;
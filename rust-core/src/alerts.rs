@@ -0,0 +1,251 @@
+use crate::policy::outgoing_value;
+use crate::session::SigningSession;
+use crate::storage::{Storage, Tenant};
+use crate::PublicKey;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Alerts
+// ============================================================================
+
+// Rules a tenant configures to hear about drafts that deserve a second look.
+// Draft and signer rules are evaluated as the coordinator accepts sessions
+// and signatures; stuck sessions are found by `evaluate_alerts(now)`, which
+// the host calls from a timer. Each alert is raised once, kept in the
+// tenant's alert list (surfaced next to signing status), and queued for
+// delivery on every configured channel. The crate sends nothing itself: the
+// host drains `take_alert_deliveries` and posts the webhooks and emails.
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case", deny_unknown_fields)]
+pub enum AlertRule {
+    // Value leaving the wallet (everything but change) at or above this.
+    LargeDraft { threshold: u64 },
+    // A signature from a key outside `expected`, e.g. a recovery or
+    // break-glass key.
+    UnusualSigner { expected: Vec<PublicKey> },
+    // Still open `after` seconds after the coordinator accepted it.
+    StuckSession { after: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "channel", rename_all = "snake_case", deny_unknown_fields)]
+pub enum AlertChannel {
+    Webhook { url: String },
+    Email { to: String },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertConfig {
+    pub rules: Vec<AlertRule>,
+    #[serde(default)]
+    pub channels: Vec<AlertChannel>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum AlertKind {
+    LargeDraft { value: u64, threshold: u64 },
+    UnusualSigner { pubkey: PublicKey },
+    StuckSession { age: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Alert {
+    // Stable per cause, so the same condition is never raised twice.
+    pub id: String,
+    pub at: u64,
+    pub wallet: String,
+    pub draft: String,
+    #[serde(flatten)]
+    pub kind: AlertKind,
+    #[serde(default)]
+    pub acknowledged: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertDelivery {
+    pub channel: AlertChannel,
+    pub alert: Alert,
+}
+
+impl AlertConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        for channel in &self.channels {
+            match channel {
+                AlertChannel::Webhook { url } if !url.starts_with("https://") => {
+                    return Err(format!("Webhook {} must use https", url));
+                }
+                AlertChannel::Email { to } if !to.contains('@') => {
+                    return Err(format!("Invalid alert email {}", to));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    pub fn check_draft(&self, session: &SigningSession) -> Vec<AlertKind> {
+        let value = outgoing_value(&session.transaction);
+        self.rules
+            .iter()
+            .filter_map(|rule| match rule {
+                AlertRule::LargeDraft { threshold } if value >= *threshold => {
+                    Some(AlertKind::LargeDraft {
+                        value,
+                        threshold: *threshold,
+                    })
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn check_signer(&self, pubkey: &PublicKey) -> Vec<AlertKind> {
+        self.rules
+            .iter()
+            .filter_map(|rule| match rule {
+                AlertRule::UnusualSigner { expected } if !expected.contains(pubkey) => {
+                    Some(AlertKind::UnusualSigner {
+                        pubkey: pubkey.clone(),
+                    })
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn check_age(&self, session: &SigningSession, now: u64) -> Vec<AlertKind> {
+        let Some(created_at) = session.created_at else {
+            return Vec::new();
+        };
+        let age = now.saturating_sub(created_at);
+        self.rules
+            .iter()
+            .filter_map(|rule| match rule {
+                AlertRule::StuckSession { after } if age > *after => {
+                    Some(AlertKind::StuckSession { age })
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+fn alert_id(wallet: &str, draft: &str, kind: &AlertKind) -> String {
+    let cause = match kind {
+        AlertKind::LargeDraft { .. } => "large_draft".to_string(),
+        AlertKind::UnusualSigner { pubkey } => format!("unusual_signer/{}", pubkey.0),
+        AlertKind::StuckSession { .. } => "stuck_session".to_string(),
+    };
+    format!("{}/{}/{}", wallet, draft, cause)
+}
+
+impl<S: Storage> Tenant<'_, S> {
+    pub fn alert_config(&self) -> Result<AlertConfig, String> {
+        Ok(self.load("alert_config")?.unwrap_or_default())
+    }
+
+    pub fn set_alert_config(&mut self, config: &AlertConfig) -> Result<(), String> {
+        config.validate()?;
+        self.store("alert_config", config)
+    }
+
+    // Every alert raised so far, oldest first.
+    pub fn alerts(&self) -> Result<Vec<Alert>, String> {
+        Ok(self.load("alerts")?.unwrap_or_default())
+    }
+
+    // Unacknowledged alerts on one draft, for the status API.
+    pub fn draft_alerts(&self, wallet_id: &str, draft: &str) -> Result<Vec<Alert>, String> {
+        Ok(self
+            .alerts()?
+            .into_iter()
+            .filter(|a| a.wallet == wallet_id && a.draft == draft && !a.acknowledged)
+            .collect())
+    }
+
+    pub fn acknowledge_alert(&mut self, id: &str) -> Result<(), String> {
+        let mut alerts = self.alerts()?;
+        let alert = alerts
+            .iter_mut()
+            .find(|a| a.id == id)
+            .ok_or_else(|| format!("Unknown alert {}", id))?;
+        alert.acknowledged = true;
+        self.store("alerts", &alerts)
+    }
+
+    // Records the alerts not raised before and queues their deliveries.
+    pub(crate) fn raise_alerts(
+        &mut self,
+        wallet_id: &str,
+        draft: &str,
+        kinds: Vec<AlertKind>,
+        now: u64,
+    ) -> Result<Vec<Alert>, String> {
+        if kinds.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut alerts = self.alerts()?;
+        let mut raised = Vec::new();
+        for kind in kinds {
+            let id = alert_id(wallet_id, draft, &kind);
+            if alerts.iter().any(|a| a.id == id) {
+                continue;
+            }
+            let alert = Alert {
+                id,
+                at: now,
+                wallet: wallet_id.to_string(),
+                draft: draft.to_string(),
+                kind,
+                acknowledged: false,
+            };
+            alerts.push(alert.clone());
+            raised.push(alert);
+        }
+        if raised.is_empty() {
+            return Ok(raised);
+        }
+        self.store("alerts", &alerts)?;
+
+        let channels = self.alert_config()?.channels;
+        let mut outbox: Vec<AlertDelivery> = self.load("alert_outbox")?.unwrap_or_default();
+        for alert in &raised {
+            for channel in &channels {
+                outbox.push(AlertDelivery {
+                    channel: channel.clone(),
+                    alert: alert.clone(),
+                });
+            }
+        }
+        self.store("alert_outbox", &outbox)?;
+        Ok(raised)
+    }
+
+    // Raises stuck-session alerts for sessions still collecting signatures.
+    pub fn evaluate_alerts(&mut self, now: u64) -> Result<Vec<Alert>, String> {
+        let config = self.alert_config()?;
+        let mut raised = Vec::new();
+        for id in self.wallet_ids()? {
+            for session in self.wallet(&id)?.sessions {
+                // Fully signed and waiting on broadcast is not stuck.
+                if session.transaction.validate_signatures().is_ok() {
+                    continue;
+                }
+                let kinds = config.check_age(&session, now);
+                raised.extend(self.raise_alerts(&id, &session.draft_hash(), kinds, now)?);
+            }
+        }
+        Ok(raised)
+    }
+
+    // Drains the delivery queue, for whatever posts webhooks and emails.
+    pub fn take_alert_deliveries(&mut self) -> Result<Vec<AlertDelivery>, String> {
+        let outbox = self.load("alert_outbox")?.unwrap_or_default();
+        self.remove("alert_outbox")?;
+        Ok(outbox)
+    }
+}
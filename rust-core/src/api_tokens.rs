@@ -0,0 +1,239 @@
+use crate::encoding::{base64url_decode, base64url_encode};
+use crate::hashing::{constant_time_eq, digest_hex, hmac_sha256, HashAlgorithm};
+use crate::rng::{random_hex, RandomSource};
+use crate::storage::{validate_segment, Coordinator, Storage, Tenant};
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// API Tokens
+// ============================================================================
+
+// Operators mint tokens for automation that should not hold operator
+// credentials: a CI bot that may only submit signatures for one wallet, a
+// monitor that may only read status. A token names its tenant and id and is
+// MACed with a server secret the host keeps (the crate holds no secrets):
+//
+//   nmt1.<base64url(payload json)>.<base64url(hmac-sha256)>
+//
+// What a token may do is kept in the tenant's record of it, not in the
+// token, and the record stores only a hash of the token. Revoking deletes
+// the record; minting the same id again replaces it, and the old string
+// stops working either way. The host calls `Coordinator::authorize_api_token`
+// before serving a request made with a token.
+
+const TOKEN_PREFIX: &str = "nmt1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiAction {
+    // Wallets, sessions, signing status, notifications.
+    ReadStatus,
+    SubmitSignatures,
+    PostComments,
+    SubmitDrafts,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApiScope {
+    pub action: ApiAction,
+    // Wallet ids the action is allowed on; empty means every wallet.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub wallets: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApiToken {
+    pub id: String,
+    pub label: String,
+    pub scopes: Vec<ApiScope>,
+    pub issued_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+    // SHA-256 of the token string.
+    pub token_hash: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TokenPayload {
+    tenant: String,
+    id: String,
+    issued_at: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuthorizedToken {
+    pub tenant: String,
+    pub token: ApiToken,
+}
+
+impl ApiAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ReadStatus => "read_status",
+            Self::SubmitSignatures => "submit_signatures",
+            Self::PostComments => "post_comments",
+            Self::SubmitDrafts => "submit_drafts",
+        }
+    }
+}
+
+impl ApiScope {
+    fn allows(&self, action: ApiAction, wallet: Option<&str>) -> bool {
+        self.action == action
+            && (self.wallets.is_empty()
+                || wallet.is_some_and(|w| self.wallets.iter().any(|id| id == w)))
+    }
+}
+
+impl ApiToken {
+    // `wallet` is the wallet the request touches, or `None` for requests
+    // across the tenant, which only unrestricted scopes allow.
+    pub fn allows(&self, action: ApiAction, wallet: Option<&str>) -> bool {
+        self.scopes.iter().any(|scope| scope.allows(action, wallet))
+    }
+}
+
+// An unguessable token id, for hosts that do not pick their own.
+pub fn random_token_id(rng: &mut dyn RandomSource) -> Result<String, String> {
+    random_hex(rng, 16)
+}
+
+fn token_hash(token: &str) -> String {
+    digest_hex(HashAlgorithm::Sha256, token.as_bytes()).expect("sha256 is always available")
+}
+
+fn token_key(id: &str) -> Result<String, String> {
+    validate_segment("token", id)?;
+    Ok(format!("api_tokens/{}", id))
+}
+
+fn encode_token(payload: &TokenPayload, secret: &[u8]) -> String {
+    let json = serde_json::to_vec(payload).expect("Serialization failed");
+    let body = format!("{}.{}", TOKEN_PREFIX, base64url_encode(&json));
+    let mac = hmac_sha256(secret, body.as_bytes());
+    format!("{}.{}", body, base64url_encode(&mac))
+}
+
+fn decode_token(token: &str, secret: &[u8]) -> Result<TokenPayload, String> {
+    let (body, mac) = token.rsplit_once('.').ok_or("Malformed API token")?;
+    let (prefix, payload) = body.split_once('.').ok_or("Malformed API token")?;
+    if prefix != TOKEN_PREFIX {
+        return Err(format!("Unsupported API token version '{}'", prefix));
+    }
+    let mac = base64url_decode(mac)?;
+    if !constant_time_eq(&mac, &hmac_sha256(secret, body.as_bytes())) {
+        return Err("Invalid API token".into());
+    }
+    let payload = base64url_decode(payload)?;
+    serde_json::from_slice(&payload).map_err(|e| e.to_string())
+}
+
+impl<S: Storage> Coordinator<S> {
+    // Returns the token string, which is shown to the operator once and not
+    // stored, and its record.
+    #[allow(clippy::too_many_arguments)]
+    pub fn mint_api_token(
+        &mut self,
+        tenant_id: &str,
+        id: &str,
+        label: &str,
+        scopes: Vec<ApiScope>,
+        expires_at: Option<u64>,
+        secret: &[u8],
+        now: u64,
+    ) -> Result<(String, ApiToken), String> {
+        if scopes.is_empty() {
+            return Err("A token needs at least one scope".into());
+        }
+        if expires_at.is_some_and(|at| at <= now) {
+            return Err("Token would already be expired".into());
+        }
+        let mut tenant = self.tenant(tenant_id)?;
+        for wallet in scopes.iter().flat_map(|s| &s.wallets) {
+            tenant.wallet(wallet)?;
+        }
+        let payload = TokenPayload {
+            tenant: tenant_id.to_string(),
+            id: id.to_string(),
+            issued_at: now,
+        };
+        let token = encode_token(&payload, secret);
+        let record = ApiToken {
+            id: id.to_string(),
+            label: label.to_string(),
+            scopes,
+            issued_at: now,
+            expires_at,
+            token_hash: token_hash(&token),
+        };
+        tenant.put_api_token(&record)?;
+        Ok((token, record))
+    }
+
+    // Checks the token is genuine, still on record, unexpired, and allows
+    // `action` on `wallet`.
+    pub fn authorize_api_token(
+        &mut self,
+        token: &str,
+        secret: &[u8],
+        action: ApiAction,
+        wallet: Option<&str>,
+        now: u64,
+    ) -> Result<AuthorizedToken, String> {
+        let token = token.trim();
+        let payload = decode_token(token, secret)?;
+        let tenant = self.tenant(&payload.tenant)?;
+        let record = tenant
+            .load::<ApiToken>(&token_key(&payload.id)?)?
+            .filter(|r| constant_time_eq(r.token_hash.as_bytes(), token_hash(token).as_bytes()))
+            .ok_or("API token has been revoked")?;
+        if record.expires_at.is_some_and(|at| now >= at) {
+            return Err("API token has expired".into());
+        }
+        if !record.allows(action, wallet) {
+            return Err(match wallet {
+                Some(wallet) => format!(
+                    "API token {} may not {} on wallet {}",
+                    record.id,
+                    action.name(),
+                    wallet
+                ),
+                None => format!(
+                    "API token {} may not {} across wallets",
+                    record.id,
+                    action.name()
+                ),
+            });
+        }
+        Ok(AuthorizedToken {
+            tenant: payload.tenant,
+            token: record,
+        })
+    }
+}
+
+impl<S: Storage> Tenant<'_, S> {
+    pub fn api_token(&self, id: &str) -> Result<ApiToken, String> {
+        self.load(&token_key(id)?)?
+            .ok_or_else(|| format!("Unknown API token {}", id))
+    }
+
+    pub fn api_tokens(&self) -> Result<Vec<ApiToken>, String> {
+        self.list("api_tokens")?
+            .iter()
+            .map(|id| self.api_token(id))
+            .collect()
+    }
+
+    pub fn put_api_token(&mut self, token: &ApiToken) -> Result<(), String> {
+        self.store(&token_key(&token.id)?, token)
+    }
+
+    pub fn revoke_api_token(&mut self, id: &str) -> Result<bool, String> {
+        self.remove(&token_key(id)?)
+    }
+}
@@ -0,0 +1,87 @@
+use crate::hashing::{digest_hex, HashAlgorithm};
+use crate::verify::VerificationItem;
+use crate::{PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Device Attestations
+// ============================================================================
+
+// Which device produced a signature: hardware model and firmware version,
+// optionally vouched for by a key held inside the device. Attestations are
+// session metadata, never part of a spend hash, so they can be added by any
+// signer without invalidating anything. A device key signs the attestation
+// bound to the spend hash and signer it accompanies, so it cannot be lifted
+// onto another signature.
+
+const DEVICE_DOMAIN: &[u8] = b"nockchain-multisig/device/v1";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeviceAttestation {
+    pub model: String,
+    pub firmware: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_key: Option<PublicKey>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<Signature>,
+}
+
+#[derive(Serialize)]
+struct AttestationTerms<'a> {
+    model: &'a str,
+    firmware: &'a str,
+    device_key: &'a PublicKey,
+    message_hash: &'a str,
+    signer: &'a PublicKey,
+}
+
+impl DeviceAttestation {
+    // What the device key signs. None for attestations without a device key.
+    pub fn message(&self, message_hash: &str, signer: &PublicKey) -> Option<String> {
+        let terms = AttestationTerms {
+            model: &self.model,
+            firmware: &self.firmware,
+            device_key: self.device_key.as_ref()?,
+            message_hash,
+            signer,
+        };
+        let bytes = serde_json::to_vec(&terms).expect("Serialization failed");
+        Some(
+            digest_hex(HashAlgorithm::Sha256, &[DEVICE_DOMAIN, &bytes].concat())
+                .expect("sha256 is always available"),
+        )
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.model.is_empty() || self.firmware.is_empty() {
+            return Err("Device attestation needs a model and firmware version".into());
+        }
+        if self.device_key.is_some() != self.signature.is_some() {
+            return Err("Device key and device signature must be given together".into());
+        }
+        Ok(())
+    }
+
+    // The device-key check, for an external verifier.
+    pub fn verification_item(
+        &self,
+        message_hash: &str,
+        signer: &PublicKey,
+    ) -> Option<VerificationItem> {
+        Some(VerificationItem {
+            pubkey: self.device_key.clone()?,
+            message_hash: self.message(message_hash, signer)?,
+            signature: self.signature.clone()?,
+        })
+    }
+}
+
+// One signature's attestation within a session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SignerDevice {
+    pub spend_index: usize,
+    pub pubkey: PublicKey,
+    pub device: DeviceAttestation,
+}
@@ -0,0 +1,148 @@
+use crate::attestation::DeviceAttestation;
+use crate::hashing::{digest_hex, HashAlgorithm};
+use crate::PublicKey;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Audit Log
+// ============================================================================
+
+// Append-only and hash-chained: each entry commits to the previous entry's
+// hash, so dropping or rewriting history breaks `verify`.
+
+const AUDIT_DOMAIN: &[u8] = b"nockchain-multisig/audit/v1";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case", deny_unknown_fields)]
+pub enum AuditEvent {
+    SignatureAdded {
+        spend_index: usize,
+        pubkey: PublicKey,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        summary: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        device: Option<DeviceAttestation>,
+    },
+    DelegatedSignatureAdded {
+        spend_index: usize,
+        signer: PublicKey,
+        chain: Vec<PublicKey>,
+        grants: Vec<String>,
+    },
+    // A signed pre-approval filled `signer`'s slot on `spends`; `terms` is
+    // the hash it signed.
+    PreApprovalApplied {
+        spends: Vec<usize>,
+        signer: PublicKey,
+        terms: String,
+        valid_until_height: u64,
+    },
+    SignaturesMerged {
+        added: usize,
+    },
+    EmergencySpend {
+        spend_index: usize,
+        reporter: PublicKey,
+        reason: String,
+        timestamp: u64,
+        attestation: String,
+    },
+    // Draft hashes before and after, and whose approvals were withdrawn.
+    DraftEdited {
+        previous_draft: String,
+        draft: String,
+        changed_spends: Vec<usize>,
+        cleared: Vec<PublicKey>,
+    },
+    // `snapshot` is the hash of the statement the coordinator signed.
+    SnapshotSigned {
+        index: u64,
+        snapshot: String,
+        coordinator: PublicKey,
+    },
+    SnapshotCountersigned {
+        index: u64,
+        pubkey: PublicKey,
+    },
+    // Step `step` of the session's reminder schedule fired.
+    ReminderSent {
+        step: usize,
+        notified: Vec<PublicKey>,
+    },
+    SigningEscalated {
+        step: usize,
+        notified: Vec<PublicKey>,
+        pending: Vec<PublicKey>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub sequence: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub at: Option<u64>,
+    #[serde(flatten)]
+    pub event: AuditEvent,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+fn entry_hash(sequence: u64, at: Option<u64>, event: &AuditEvent, prev_hash: &str) -> String {
+    let event_bytes = serde_json::to_vec(event).expect("Serialization failed");
+    let at_bytes = at.map_or([0u8; 8], u64::to_be_bytes);
+    let preimage = [
+        AUDIT_DOMAIN,
+        prev_hash.as_bytes(),
+        &sequence.to_be_bytes(),
+        &at_bytes,
+        &event_bytes,
+    ]
+    .concat();
+    digest_hex(HashAlgorithm::Sha256, &preimage).expect("sha256 is always available")
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuditLog {
+    pub entries: Vec<AuditEntry>,
+}
+
+impl AuditLog {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn head(&self) -> &str {
+        self.entries.last().map_or("", |e| e.hash.as_str())
+    }
+
+    // `at` is a caller-supplied timestamp (e.g. unix seconds); the crate has
+    // no clock of its own.
+    pub fn record(&mut self, event: AuditEvent, at: Option<u64>) -> &AuditEntry {
+        let sequence = self.entries.len() as u64;
+        let prev_hash = self.head().to_string();
+        let hash = entry_hash(sequence, at, &event, &prev_hash);
+        self.entries.push(AuditEntry {
+            sequence,
+            at,
+            event,
+            prev_hash,
+            hash,
+        });
+        self.entries.last().expect("entry was just pushed")
+    }
+
+    pub fn verify(&self) -> Result<(), String> {
+        let mut prev = String::new();
+        for (i, entry) in self.entries.iter().enumerate() {
+            if entry.sequence != i as u64 || entry.prev_hash != prev {
+                return Err(format!("Audit log broken at entry {}", i));
+            }
+            if entry_hash(entry.sequence, entry.at, &entry.event, &entry.prev_hash) != entry.hash {
+                return Err(format!("Audit entry {} has been modified", i));
+            }
+            prev = entry.hash.clone();
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,264 @@
+use crate::alerts::AlertConfig;
+use crate::api_tokens::ApiToken;
+use crate::crypto::{open, seal, Sealed, SecretKey};
+use crate::federation::FederationPeer;
+use crate::inheritance::InheritancePlan;
+use crate::policy::{PolicySet, SpendingHistory};
+use crate::retention::{ArchivedSession, RetentionPolicy};
+use crate::schedule::ScheduledDraft;
+use crate::storage::{Coordinator, Storage, TenantMetrics};
+use crate::treasury::TreasuryTier;
+use crate::wallets::Wallet;
+use crate::PublicKey;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// ============================================================================
+// Coordinator Backups
+// ============================================================================
+
+// One file holds everything a coordinator knows: per tenant, its wallets
+// (with their notes and in-flight sessions, audit logs included), policies,
+// and metrics. Backups may be sealed with a passphrase; the salt is supplied
+// by the caller since the crate has no randomness of its own.
+
+pub const ARCHIVE_FORMAT_VERSION: u32 = 1;
+pub const BACKUP_KDF_ITERATIONS: u32 = 100_000;
+const BACKUP_ASSOCIATED_DATA: &[u8] = b"nockchain-multisig/backup/v1";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TenantArchive {
+    pub wallets: Vec<Wallet>,
+    #[serde(default)]
+    pub policies: PolicySet,
+    #[serde(default)]
+    pub metrics: TenantMetrics,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub schedules: Vec<ScheduledDraft>,
+    // Keyed by wallet id.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub spending: BTreeMap<String, SpendingHistory>,
+    #[serde(default)]
+    pub alert_config: AlertConfig,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inheritance: Vec<InheritancePlan>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub treasuries: Vec<TreasuryTier>,
+    #[serde(default)]
+    pub retention: RetentionPolicy,
+    // Wallet id -> its archived sessions.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub session_archives: BTreeMap<String, Vec<ArchivedSession>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub federation_key: Option<PublicKey>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub federation_peers: Vec<FederationPeer>,
+    // Token records hold only hashes, so restoring them does not leak
+    // usable tokens.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub api_tokens: Vec<ApiToken>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CoordinatorArchive {
+    pub format: u32,
+    pub tenants: BTreeMap<String, TenantArchive>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "encoding", rename_all = "snake_case", deny_unknown_fields)]
+pub enum BackupFile {
+    Plain {
+        archive: CoordinatorArchive,
+    },
+    Encrypted {
+        salt: String,
+        iterations: u32,
+        sealed: Sealed,
+    },
+}
+
+impl CoordinatorArchive {
+    pub fn to_backup(&self, passphrase: Option<(&str, &[u8])>) -> Result<BackupFile, String> {
+        let Some((passphrase, salt)) = passphrase else {
+            return Ok(BackupFile::Plain {
+                archive: self.clone(),
+            });
+        };
+        if salt.len() < 16 {
+            return Err("Backup salt must be at least 16 random bytes".into());
+        }
+        let key = SecretKey::from_passphrase(passphrase, salt, BACKUP_KDF_ITERATIONS);
+        let json = serde_json::to_vec(self).map_err(|e| e.to_string())?;
+        Ok(BackupFile::Encrypted {
+            salt: hex::encode(salt),
+            iterations: BACKUP_KDF_ITERATIONS,
+            sealed: seal(&key, &json, BACKUP_ASSOCIATED_DATA),
+        })
+    }
+
+    pub fn from_backup(file: BackupFile, passphrase: Option<&str>) -> Result<Self, String> {
+        let archive: CoordinatorArchive = match file {
+            BackupFile::Plain { archive } => archive,
+            BackupFile::Encrypted {
+                salt,
+                iterations,
+                sealed,
+            } => {
+                let passphrase =
+                    passphrase.ok_or("Backup is encrypted; a passphrase is required")?;
+                let salt = hex::decode(salt).map_err(|e| e.to_string())?;
+                let key = SecretKey::from_passphrase(passphrase, &salt, iterations);
+                let json = open(&key, &sealed, BACKUP_ASSOCIATED_DATA)?;
+                serde_json::from_slice(&json).map_err(|e| e.to_string())?
+            }
+        };
+        if archive.format > ARCHIVE_FORMAT_VERSION {
+            return Err(format!("Unsupported backup format {}", archive.format));
+        }
+        archive.validate()?;
+        Ok(archive)
+    }
+
+    // Rejects archives whose wallets or audit logs are inconsistent, so a
+    // damaged backup fails before anything is written.
+    pub fn validate(&self) -> Result<(), String> {
+        for (tenant, contents) in &self.tenants {
+            for wallet in &contents.wallets {
+                if wallet.id != wallet.descriptor.id() {
+                    return Err(format!(
+                        "{}: wallet {} id does not match its lock",
+                        tenant, wallet.id
+                    ));
+                }
+                for (i, session) in wallet.sessions.iter().enumerate() {
+                    session.audit.verify().map_err(|e| {
+                        format!("{}: wallet {} session {}: {}", tenant, wallet.id, i, e)
+                    })?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<S: Storage> Coordinator<S> {
+    pub fn export_archive(&mut self) -> Result<CoordinatorArchive, String> {
+        let mut tenants = BTreeMap::new();
+        for id in self.tenants()? {
+            let tenant = self.tenant(&id)?;
+            let wallet_ids = tenant.wallet_ids()?;
+            let wallets = wallet_ids
+                .iter()
+                .map(|w| tenant.wallet(w))
+                .collect::<Result<_, String>>()?;
+            let mut spending = BTreeMap::new();
+            let mut session_archives = BTreeMap::new();
+            for id in &wallet_ids {
+                let history = tenant.spending(id)?;
+                if !history.records.is_empty() {
+                    spending.insert(id.clone(), history);
+                }
+                let archived = tenant.archived_sessions(id)?;
+                if !archived.is_empty() {
+                    session_archives.insert(id.clone(), archived);
+                }
+            }
+            let archive = TenantArchive {
+                wallets,
+                policies: tenant.policies()?,
+                metrics: tenant.metrics()?,
+                schedules: tenant.schedules()?,
+                spending,
+                alert_config: tenant.alert_config()?,
+                inheritance: tenant.inheritance_plans()?,
+                treasuries: tenant.treasuries()?,
+                retention: tenant.retention()?,
+                session_archives,
+                federation_key: tenant.federation_key()?,
+                federation_peers: tenant.federation_peers()?,
+                api_tokens: tenant.api_tokens()?,
+            };
+            tenants.insert(id, archive);
+        }
+        Ok(CoordinatorArchive {
+            format: ARCHIVE_FORMAT_VERSION,
+            tenants,
+        })
+    }
+
+    // Restores into this coordinator. Existing tenants are left untouched
+    // unless `replace` is set, in which case their data is overwritten.
+    pub fn import_archive(
+        &mut self,
+        archive: &CoordinatorArchive,
+        replace: bool,
+    ) -> Result<(), String> {
+        archive.validate()?;
+        let existing = self.tenants()?;
+        if !replace {
+            if let Some(clash) = archive.tenants.keys().find(|t| existing.contains(t)) {
+                return Err(format!("Tenant {} already exists", clash));
+            }
+        }
+
+        for (id, contents) in &archive.tenants {
+            let mut tenant = self.tenant(id)?;
+            if replace {
+                for wallet_id in tenant.wallet_ids()? {
+                    tenant.delete_wallet(&wallet_id)?;
+                }
+                for schedule in tenant.schedules()? {
+                    tenant.delete_schedule(&schedule.id)?;
+                }
+                for plan in tenant.inheritance_plans()? {
+                    tenant.delete_inheritance_plan(&plan.id)?;
+                }
+                for tier in tenant.treasuries()? {
+                    tenant.delete_treasury(&tier.id)?;
+                }
+                for peer in tenant.federation_peers()? {
+                    tenant.remove_federation_peer(&peer.id)?;
+                }
+                tenant.remove("federation_key")?;
+                for token in tenant.api_tokens()? {
+                    tenant.revoke_api_token(&token.id)?;
+                }
+            }
+            for wallet in &contents.wallets {
+                tenant.put_wallet(wallet)?;
+            }
+            for schedule in &contents.schedules {
+                tenant.put_schedule(schedule)?;
+            }
+            for plan in &contents.inheritance {
+                tenant.put_inheritance_plan(plan)?;
+            }
+            for tier in &contents.treasuries {
+                tenant.put_treasury(tier)?;
+            }
+            for (wallet_id, history) in &contents.spending {
+                tenant.set_spending(wallet_id, history)?;
+            }
+            for (wallet_id, archived) in &contents.session_archives {
+                tenant.set_archived_sessions(wallet_id, archived)?;
+            }
+            if let Some(key) = &contents.federation_key {
+                tenant.set_federation_key(key)?;
+            }
+            for peer in &contents.federation_peers {
+                tenant.put_federation_peer(peer)?;
+            }
+            for token in &contents.api_tokens {
+                tenant.put_api_token(token)?;
+            }
+            tenant.set_policies(&contents.policies)?;
+            tenant.set_metrics(&contents.metrics)?;
+            tenant.set_alert_config(&contents.alert_config)?;
+            tenant.set_retention(&contents.retention)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+use crate::{HaxCondition, Lock, PkhCondition, PublicKey, SpendCondition, TimCondition};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Backup Signers
+// ============================================================================
+
+// A payout stuck on an unreachable signer needs a way out that does not
+// exist yet when the funds are locked. A backup route adds branches to the
+// lock in which the primary threshold can also be met with backup keys,
+// usable only once the chain reaches `after_height`, or once someone reveals
+// the preimage of `escalation_hash` (the escalation event: whoever holds the
+// preimage, such as an operations lead, decides when to escalate). Each
+// trigger is its own branch, so either one opens the route.
+//
+// Backup keys are the keys of a branch that are not on the primary branch.
+// Backup branches list the primary keys too, so when a spend is moved onto
+// one (`switch_spend_path`) the primary signatures already given still count
+// and the backups only fill the gap.
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BackupRoute {
+    pub pubkeys: Vec<PublicKey>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after_height: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub escalation_hash: Option<String>,
+}
+
+// A branch of a lock that admits backup keys, and what opens it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BackupEligibility {
+    pub branch: usize,
+    pub backups: Vec<PublicKey>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after_height: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub escalation_hashes: Vec<String>,
+}
+
+impl BackupEligibility {
+    // Whether the branch can be spent at `height` with `preimages` revealed.
+    pub fn is_open(&self, height: u64, preimages: &[String]) -> bool {
+        self.after_height.is_none_or(|after| height >= after)
+            && HaxCondition {
+                hashes: self.escalation_hashes.clone(),
+            }
+            .is_satisfied_by(preimages)
+    }
+}
+
+pub fn add_backup_route(lock: &Lock, route: &BackupRoute) -> Result<Lock, String> {
+    if route.pubkeys.is_empty() {
+        return Err("A backup route needs at least one backup key".into());
+    }
+    if route.after_height.is_none() && route.escalation_hash.is_none() {
+        return Err("A backup route needs a delay or an escalation hash".into());
+    }
+    if let Some(pubkey) = route
+        .pubkeys
+        .iter()
+        .find(|pk| lock.pkh.pubkeys.contains(pk))
+    {
+        return Err(format!("{} is already a primary signer", pubkey.0));
+    }
+    let pkh = PkhCondition {
+        threshold: lock.pkh.threshold,
+        pubkeys: [lock.pkh.pubkeys.as_slice(), route.pubkeys.as_slice()].concat(),
+    };
+    let mut lock = lock.clone();
+    if let Some(min_height) = route.after_height {
+        lock.alternatives.push(SpendCondition {
+            pkh: pkh.clone(),
+            hax: None,
+            tim: Some(TimCondition {
+                min_height: Some(min_height),
+                max_height: None,
+            }),
+            emergency: false,
+        });
+    }
+    if let Some(hash) = &route.escalation_hash {
+        lock.alternatives.push(SpendCondition {
+            pkh,
+            hax: Some(HaxCondition {
+                hashes: vec![hash.clone()],
+            }),
+            tim: None,
+            emergency: false,
+        });
+    }
+    lock.validate()?;
+    Ok(lock)
+}
+
+pub fn backup_keys(lock: &Lock, branch: usize) -> Vec<PublicKey> {
+    lock.condition(branch)
+        .map(|c| {
+            c.pkh
+                .pubkeys
+                .iter()
+                .filter(|pk| !lock.pkh.pubkeys.contains(pk))
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Every branch with backup keys, in branch order.
+pub fn backup_routes(lock: &Lock) -> Vec<BackupEligibility> {
+    (1..lock.branch_count())
+        .filter_map(|branch| {
+            let backups = backup_keys(lock, branch);
+            if backups.is_empty() {
+                return None;
+            }
+            let condition = lock.condition(branch)?;
+            Some(BackupEligibility {
+                branch,
+                backups,
+                after_height: condition.tim.and_then(|t| t.min_height),
+                escalation_hashes: condition.hax.map(|h| h.hashes.clone()).unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+#[wasm_bindgen]
+pub fn create_backup_route_lock(lock_json: &str, route_json: &str) -> Result<String, String> {
+    let lock: Lock = serde_json::from_str(lock_json).map_err(|e| e.to_string())?;
+    let route: BackupRoute = serde_json::from_str(route_json).map_err(|e| e.to_string())?;
+    serde_json::to_string(&add_backup_route(&lock, &route)?).map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+pub fn get_backup_routes(lock_json: &str) -> Result<String, String> {
+    let lock: Lock = serde_json::from_str(lock_json).map_err(|e| e.to_string())?;
+    serde_json::to_string(&backup_routes(&lock)).map_err(|e| e.to_string())
+}
@@ -0,0 +1,251 @@
+use crate::storage::{MemoryStorage, Storage};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Browser Persistence
+// ============================================================================
+
+// IndexedDB is asynchronous and `Storage` is not, so the browser store works
+// from a snapshot: the page reads the whole object store at startup, hands
+// it over, and after each change writes back the journal from
+// `take_changes()` in one IndexedDB transaction. The data set (sessions,
+// drafts, address book) is small enough that this costs nothing, and every
+// Rust API keeps working synchronously.
+//
+// Key layout:
+//   sessions/<draft hash>   SigningSession
+//   drafts/<id>             Transaction
+//   address_book/<name>     contact JSON
+//   meta/schema             schema version
+
+pub const BROWSER_SCHEMA_VERSION: u32 = 1;
+const SCHEMA_KEY: &str = "meta/schema";
+
+type Migration = fn(&mut BTreeMap<String, String>) -> Result<(), String>;
+
+// Migrations[i] upgrades data from schema i to i + 1.
+const MIGRATIONS: &[Migration] = &[migrate_v0_to_v1];
+
+// Version 0 was an unversioned store: a single in-progress draft under
+// `draft`. It becomes the first entry under `drafts/`.
+fn migrate_v0_to_v1(entries: &mut BTreeMap<String, String>) -> Result<(), String> {
+    if let Some(draft) = entries.remove("draft") {
+        entries.insert("drafts/default".to_string(), draft);
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case", deny_unknown_fields)]
+pub enum StoreChange {
+    Put { key: String, value: String },
+    Delete { key: String },
+}
+
+#[wasm_bindgen]
+#[derive(Debug, Default)]
+pub struct BrowserStore {
+    data: MemoryStorage,
+    changes: Vec<StoreChange>,
+}
+
+impl BrowserStore {
+    pub fn from_snapshot(mut entries: BTreeMap<String, String>) -> Result<Self, String> {
+        let version: u32 = match entries.get(SCHEMA_KEY) {
+            Some(v) => v.parse().map_err(|_| "Corrupt schema version")?,
+            None if entries.is_empty() => BROWSER_SCHEMA_VERSION,
+            None => 0,
+        };
+        if version > BROWSER_SCHEMA_VERSION {
+            return Err(format!(
+                "Stored data uses schema {} but this build only knows {}",
+                version, BROWSER_SCHEMA_VERSION
+            ));
+        }
+
+        let before = entries.clone();
+        for migration in &MIGRATIONS[version as usize..] {
+            migration(&mut entries)?;
+        }
+        entries.insert(SCHEMA_KEY.to_string(), BROWSER_SCHEMA_VERSION.to_string());
+
+        // Whatever the migrations changed must be written back as well.
+        let mut store = Self::default();
+        for key in before.keys().filter(|k| !entries.contains_key(*k)) {
+            store.changes.push(StoreChange::Delete { key: key.clone() });
+        }
+        for (key, value) in entries {
+            if before.get(&key) != Some(&value) {
+                store.changes.push(StoreChange::Put {
+                    key: key.clone(),
+                    value: value.clone(),
+                });
+            }
+            store.data.put(&key, value)?;
+        }
+        Ok(store)
+    }
+}
+
+impl Storage for BrowserStore {
+    fn get(&self, key: &str) -> Result<Option<String>, String> {
+        self.data.get(key)
+    }
+
+    fn put(&mut self, key: &str, value: String) -> Result<(), String> {
+        self.changes.push(StoreChange::Put {
+            key: key.to_string(),
+            value: value.clone(),
+        });
+        self.data.put(key, value)
+    }
+
+    fn delete(&mut self, key: &str) -> Result<bool, String> {
+        let existed = self.data.delete(key)?;
+        if existed {
+            self.changes.push(StoreChange::Delete {
+                key: key.to_string(),
+            });
+        }
+        Ok(existed)
+    }
+
+    fn keys(&self, prefix: &str) -> Result<Vec<String>, String> {
+        self.data.keys(prefix)
+    }
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+#[wasm_bindgen]
+impl BrowserStore {
+    // `snapshot_json` is an object mapping every stored key to its value.
+    #[wasm_bindgen(constructor)]
+    pub fn new(snapshot_json: &str) -> Result<BrowserStore, String> {
+        let entries: BTreeMap<String, String> =
+            serde_json::from_str(snapshot_json).map_err(|e| e.to_string())?;
+        Self::from_snapshot(entries)
+    }
+
+    pub fn get_item(&self, key: &str) -> Result<Option<String>, String> {
+        self.get(key)
+    }
+
+    pub fn set_item(&mut self, key: &str, value: &str) -> Result<(), String> {
+        self.put(key, value.to_string())
+    }
+
+    pub fn remove_item(&mut self, key: &str) -> Result<bool, String> {
+        self.delete(key)
+    }
+
+    pub fn list_keys(&self, prefix: &str) -> Result<String, String> {
+        serde_json::to_string(&self.keys(prefix)?).map_err(|e| e.to_string())
+    }
+
+    // Stores a session under its draft hash and returns that key.
+    pub fn save_session(&mut self, session_json: &str) -> Result<String, String> {
+        let session = crate::session::SigningSession::from_json(session_json)?;
+        let key = format!("sessions/{}", session.draft_hash());
+        self.put(&key, session.to_json()?)?;
+        Ok(key)
+    }
+
+    // Pending writes as `[{op: "put", key, value} | {op: "delete", key}]`,
+    // in order. Clears the journal.
+    pub fn take_changes(&mut self) -> Result<String, String> {
+        let changes = std::mem::take(&mut self.changes);
+        serde_json::to_string(&changes).map_err(|e| e.to_string())
+    }
+
+    pub fn schema_version(&self) -> u32 {
+        BROWSER_SCHEMA_VERSION
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn changes(store: &mut BrowserStore) -> Vec<StoreChange> {
+        serde_json::from_str(&store.take_changes().unwrap()).unwrap()
+    }
+
+    fn put(key: &str, value: &str) -> StoreChange {
+        StoreChange::Put {
+            key: key.into(),
+            value: value.into(),
+        }
+    }
+
+    #[test]
+    fn empty_store_starts_at_current_schema() {
+        let mut store = BrowserStore::new("{}").unwrap();
+        assert_eq!(
+            changes(&mut store),
+            vec![put(SCHEMA_KEY, &BROWSER_SCHEMA_VERSION.to_string())]
+        );
+    }
+
+    #[test]
+    fn migrates_unversioned_draft() {
+        let mut store = BrowserStore::new(r#"{"draft":"{}","address_book/bob":"b"}"#).unwrap();
+        assert_eq!(store.get_item("draft").unwrap(), None);
+        assert_eq!(
+            store.get_item("drafts/default").unwrap().as_deref(),
+            Some("{}")
+        );
+        assert_eq!(
+            store.get_item("address_book/bob").unwrap().as_deref(),
+            Some("b")
+        );
+        assert_eq!(
+            changes(&mut store),
+            vec![
+                StoreChange::Delete {
+                    key: "draft".into()
+                },
+                put("drafts/default", "{}"),
+                put(SCHEMA_KEY, "1"),
+            ]
+        );
+    }
+
+    #[test]
+    fn current_store_loads_without_writes() {
+        let mut store = BrowserStore::new(r#"{"meta/schema":"1","drafts/x":"{}"}"#).unwrap();
+        assert!(changes(&mut store).is_empty());
+        assert_eq!(store.list_keys("drafts/").unwrap(), r#"["drafts/x"]"#);
+    }
+
+    #[test]
+    fn rejects_unknown_or_corrupt_schema() {
+        let err = BrowserStore::new(r#"{"meta/schema":"2"}"#).unwrap_err();
+        assert!(err.contains("only knows 1"), "{err}");
+        assert!(BrowserStore::new(r#"{"meta/schema":"one"}"#).is_err());
+        assert!(BrowserStore::new(r#"{"meta/schema":1}"#).is_err());
+        assert!(BrowserStore::new("[]").is_err());
+    }
+
+    #[test]
+    fn journals_writes_in_order() {
+        let mut store = BrowserStore::new(r#"{"meta/schema":"1"}"#).unwrap();
+        store.set_item("drafts/a", "1").unwrap();
+        assert!(store.remove_item("drafts/a").unwrap());
+        assert!(!store.remove_item("drafts/missing").unwrap());
+        assert_eq!(
+            changes(&mut store),
+            vec![
+                put("drafts/a", "1"),
+                StoreChange::Delete {
+                    key: "drafts/a".into()
+                },
+            ]
+        );
+        assert!(changes(&mut store).is_empty());
+    }
+}
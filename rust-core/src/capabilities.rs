@@ -0,0 +1,285 @@
+use crate::hashing::{digest_hex, HashAlgorithm};
+use crate::offline::{export_bundle, SigningBundle};
+use crate::session::SigningSession;
+use crate::storage::{Storage, Tenant};
+use crate::verify::SignatureVerifier;
+use crate::{PublicKey, Signature, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Signer Capabilities
+// ============================================================================
+
+// A signer tells the coordinator what it can handle in a signed
+// advertisement: which hash algorithms it can recompute, which signature
+// schemes it holds keys for, the largest payload it takes in one message,
+// whether it signs aggregate digests, and its transports in order of
+// preference. The coordinator keeps the latest advertisement per key and
+// shapes each signing request to fit: the full session where it fits, the
+// compact offline bundle where it does not, one aggregate digest instead of
+// per-spend hashes where the signer supports it. Signers that never
+// advertised get the full session as JSON, as before.
+
+const CAPABILITIES_DOMAIN: &[u8] = b"nockchain-multisig/capabilities/v1";
+
+pub const CAPABILITIES_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transport {
+    Json,
+    File,
+    Qr,
+    Nfc,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SignerCapabilities {
+    pub version: u32,
+    pub pubkey: PublicKey,
+    pub hash_algorithms: Vec<HashAlgorithm>,
+    #[serde(default)]
+    pub signature_schemes: Vec<String>,
+    // Bytes per message: the QR fragment size, the NFC tag capacity, or a
+    // hardware wallet's buffer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_payload: Option<usize>,
+    #[serde(default)]
+    pub aggregate: bool,
+    // Most preferred first.
+    pub transports: Vec<Transport>,
+    pub at: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CapabilityAdvertisement {
+    pub capabilities: SignerCapabilities,
+    pub signature: Signature,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "format", rename_all = "snake_case", deny_unknown_fields)]
+pub enum RequestBody {
+    Session { session: SigningSession },
+    Bundle { bundle: SigningBundle },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SigningRequest {
+    pub signer: PublicKey,
+    pub transport: Transport,
+    // Sign this once in place of every spend hash.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aggregate_digest: Option<String>,
+    // For QR, the fragment length to give `QrEncoder`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_fragment_len: Option<usize>,
+    #[serde(flatten)]
+    pub body: RequestBody,
+}
+
+// Fragment length for QR signers that did not name one.
+const DEFAULT_QR_FRAGMENT: usize = 200;
+
+impl SignerCapabilities {
+    // What the signer signs to advertise.
+    pub fn message(&self) -> String {
+        let bytes = serde_json::to_vec(self).expect("Serialization failed");
+        digest_hex(
+            HashAlgorithm::Sha256,
+            &[CAPABILITIES_DOMAIN, &bytes].concat(),
+        )
+        .expect("sha256 is always available")
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.version > CAPABILITIES_VERSION {
+            return Err(format!("Unsupported capabilities version {}", self.version));
+        }
+        if self.hash_algorithms.is_empty() {
+            return Err("Signer must support at least one hash algorithm".into());
+        }
+        if self.transports.is_empty() {
+            return Err("Signer must offer at least one transport".into());
+        }
+        if self.max_payload == Some(0) {
+            return Err("Maximum payload must be positive".into());
+        }
+        Ok(())
+    }
+}
+
+impl CapabilityAdvertisement {
+    pub fn verify(&self, verifier: &dyn SignatureVerifier) -> Result<(), String> {
+        let capabilities = &self.capabilities;
+        capabilities.validate()?;
+        if !verifier.verify(
+            &capabilities.pubkey,
+            &capabilities.message(),
+            &self.signature,
+        ) {
+            return Err("Invalid capabilities signature".into());
+        }
+        Ok(())
+    }
+}
+
+fn fits(body: &RequestBody, max_payload: Option<usize>) -> Result<bool, String> {
+    let Some(max) = max_payload else {
+        return Ok(true);
+    };
+    let len = serde_json::to_vec(body).map_err(|e| e.to_string())?.len();
+    Ok(len <= max)
+}
+
+// Shapes the request for `signer` on `session`. Transports are tried in the
+// signer's order; the first one that can carry the draft wins.
+pub fn tailor_request(
+    session: &SigningSession,
+    signer: &PublicKey,
+    capabilities: Option<&SignerCapabilities>,
+) -> Result<SigningRequest, String> {
+    let tx: &Transaction = &session.transaction;
+    let Some(capabilities) = capabilities else {
+        return Ok(SigningRequest {
+            signer: signer.clone(),
+            transport: Transport::Json,
+            aggregate_digest: None,
+            max_fragment_len: None,
+            body: RequestBody::Session {
+                session: session.clone(),
+            },
+        });
+    };
+    if !capabilities.hash_algorithms.contains(&tx.hash_algorithm) {
+        return Err(format!(
+            "{} cannot recompute {} hashes",
+            signer.0,
+            tx.hash_algorithm.name()
+        ));
+    }
+
+    let aggregate_digest = match tx.aggregate_signers() {
+        Ok(signers)
+            if capabilities.aggregate
+                && tx.spends.len() > 1
+                && signers.pubkeys.contains(signer) =>
+        {
+            Some(tx.aggregate_digest()?)
+        }
+        _ => None,
+    };
+    let indices: Vec<usize> = (0..tx.spends.len()).collect();
+    let bundle = RequestBody::Bundle {
+        bundle: export_bundle(tx, &indices, signer)?,
+    };
+    let full = RequestBody::Session {
+        session: session.clone(),
+    };
+    let max = capabilities.max_payload;
+
+    for &transport in &capabilities.transports {
+        let (body, max_fragment_len) = match transport {
+            Transport::Json if fits(&full, max)? => (full.clone(), None),
+            Transport::Json | Transport::File | Transport::Nfc if fits(&bundle, max)? => {
+                (bundle.clone(), None)
+            }
+            // QR splits any payload into fragments.
+            Transport::Qr => (bundle.clone(), Some(max.unwrap_or(DEFAULT_QR_FRAGMENT))),
+            _ => continue,
+        };
+        return Ok(SigningRequest {
+            signer: signer.clone(),
+            transport,
+            aggregate_digest,
+            max_fragment_len,
+            body,
+        });
+    }
+    Err(format!(
+        "No transport {} offers can carry this draft",
+        signer.0
+    ))
+}
+
+impl<S: Storage> Tenant<'_, S> {
+    pub fn signer_capabilities(&self) -> Result<BTreeMap<String, SignerCapabilities>, String> {
+        Ok(self.load("signers")?.unwrap_or_default())
+    }
+
+    // Keeps the newest advertisement per key.
+    pub fn advertise_capabilities(
+        &mut self,
+        advertisement: &CapabilityAdvertisement,
+        verifier: &dyn SignatureVerifier,
+    ) -> Result<(), String> {
+        advertisement.verify(verifier)?;
+        let capabilities = &advertisement.capabilities;
+        let mut signers = self.signer_capabilities()?;
+        if let Some(known) = signers.get(&capabilities.pubkey.0) {
+            if known.at >= capabilities.at {
+                return Err("A newer advertisement is already on file".into());
+            }
+        }
+        signers.insert(capabilities.pubkey.0.clone(), capabilities.clone());
+        self.store("signers", &signers)
+    }
+
+    pub fn signing_request(
+        &self,
+        wallet_id: &str,
+        session_index: usize,
+        signer: &PublicKey,
+    ) -> Result<SigningRequest, String> {
+        let wallet = self.wallet(wallet_id)?;
+        let session = wallet
+            .sessions
+            .get(session_index)
+            .ok_or_else(|| format!("Session index {} out of bounds", session_index))?;
+        if !wallet.descriptor.participants().contains(signer) {
+            return Err(format!(
+                "{} is not a signer on wallet {}",
+                signer.0, wallet_id
+            ));
+        }
+        let signers = self.signer_capabilities()?;
+        tailor_request(session, signer, signers.get(&signer.0))
+    }
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+#[wasm_bindgen]
+pub fn get_capabilities_message(capabilities_json: &str) -> Result<String, String> {
+    let capabilities: SignerCapabilities =
+        serde_json::from_str(capabilities_json).map_err(|e| e.to_string())?;
+    capabilities.validate()?;
+    Ok(capabilities.message())
+}
+
+// `capabilities_json` may be empty for a signer that never advertised.
+#[wasm_bindgen]
+pub fn tailor_signing_request(
+    session_json: &str,
+    signer: &str,
+    capabilities_json: &str,
+) -> Result<String, String> {
+    let session = SigningSession::from_json(session_json)?;
+    let capabilities: Option<SignerCapabilities> = if capabilities_json.is_empty() {
+        None
+    } else {
+        Some(serde_json::from_str(capabilities_json).map_err(|e| e.to_string())?)
+    };
+    let request = tailor_request(
+        &session,
+        &PublicKey(signer.to_string()),
+        capabilities.as_ref(),
+    )?;
+    serde_json::to_string(&request).map_err(|e| e.to_string())
+}
@@ -0,0 +1,427 @@
+use crate::hashing::{digest_hex, HashAlgorithm};
+use crate::session::SigningSession;
+use crate::{Lock, Note, Output, PublicKey, Transaction};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Payment Channels (2-of-2)
+// ============================================================================
+
+// Each channel state is a commitment transaction spending the funding note
+// into the two current balances, exchanged and signed through a normal
+// `SigningSession`. Moving to a new state requires both parties to reveal the
+// revocation secret of the previous one. Locks have no timelock primitive
+// yet, so a broadcast revoked state cannot be penalised on-chain; instead
+// `detect_revoked` produces the evidence (state + revealed secret).
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelParty {
+    A,
+    B,
+}
+
+impl ChannelParty {
+    pub fn parse(party: &str) -> Result<Self, String> {
+        match party {
+            "a" => Ok(ChannelParty::A),
+            "b" => Ok(ChannelParty::B),
+            other => Err(format!("Unknown channel party: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelStatus {
+    Funding,
+    Open,
+    Closing,
+    Closed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChannelState {
+    pub sequence: u64,
+    pub balance_a: u64,
+    pub balance_b: u64,
+    pub revocation_hash_a: String,
+    pub revocation_hash_b: String,
+    #[serde(default)]
+    pub revocation_secret_a: Option<String>,
+    #[serde(default)]
+    pub revocation_secret_b: Option<String>,
+    pub commitment: SigningSession,
+}
+
+impl ChannelState {
+    pub fn is_revoked(&self) -> bool {
+        self.revocation_secret_a.is_some() && self.revocation_secret_b.is_some()
+    }
+
+    pub fn is_signed(&self) -> bool {
+        self.commitment.transaction.validate_signatures().is_ok()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Channel {
+    pub party_a: PublicKey,
+    pub party_b: PublicKey,
+    pub capacity: u64,
+    pub status: ChannelStatus,
+    #[serde(default)]
+    pub funding_note: Option<Note>,
+    #[serde(default)]
+    pub states: Vec<ChannelState>,
+    #[serde(default)]
+    pub close: Option<SigningSession>,
+}
+
+pub fn revocation_hash(secret: &str) -> String {
+    digest_hex(HashAlgorithm::Sha256, secret.as_bytes()).expect("sha256 is always available")
+}
+
+impl Channel {
+    pub fn new(party_a: PublicKey, party_b: PublicKey, capacity: u64) -> Result<Self, String> {
+        if capacity == 0 {
+            return Err("Channel capacity must be positive".into());
+        }
+        let channel = Self {
+            party_a,
+            party_b,
+            capacity,
+            status: ChannelStatus::Funding,
+            funding_note: None,
+            states: Vec::new(),
+            close: None,
+        };
+        channel.lock().validate()?;
+        Ok(channel)
+    }
+
+    pub fn lock(&self) -> Lock {
+        Lock::multisig(2, vec![self.party_a.clone(), self.party_b.clone()])
+    }
+
+    // Funding spends each party's notes into the 2-of-2 lock; any surplus
+    // goes back to its owner as change.
+    pub fn build_funding(
+        &self,
+        notes: Vec<Note>,
+        contribution_a: u64,
+        contribution_b: u64,
+    ) -> Result<SigningSession, String> {
+        if contribution_a + contribution_b != self.capacity {
+            return Err("Contributions must add up to the channel capacity".into());
+        }
+
+        let mut input_a = 0;
+        let mut input_b = 0;
+        for note in &notes {
+            if note.lock == Lock::single(&self.party_a) {
+                input_a += note.value;
+            } else if note.lock == Lock::single(&self.party_b) {
+                input_b += note.value;
+            } else {
+                return Err("Funding note is not owned by either party".into());
+            }
+        }
+        if input_a < contribution_a || input_b < contribution_b {
+            return Err("Insufficient funding notes".into());
+        }
+
+        let mut outputs = vec![Output {
+            recipient: self.lock().fingerprint(),
+            value: self.capacity,
+            lock: self.lock(),
+        }];
+        for (party, change) in [
+            (&self.party_a, input_a - contribution_a),
+            (&self.party_b, input_b - contribution_b),
+        ] {
+            if change > 0 {
+                outputs.push(Output {
+                    recipient: party.0.clone(),
+                    value: change,
+                    lock: Lock::single(party),
+                });
+            }
+        }
+
+        let tx = Transaction::build(notes, outputs, HashAlgorithm::default())?;
+        Ok(SigningSession::new(tx))
+    }
+
+    pub fn record_funding_note(&mut self, note: Note) -> Result<(), String> {
+        if self.status != ChannelStatus::Funding {
+            return Err("Channel is already funded".into());
+        }
+        if note.lock != self.lock() || note.value != self.capacity {
+            return Err("Note does not match the channel funding output".into());
+        }
+        self.funding_note = Some(note);
+        self.status = ChannelStatus::Open;
+        Ok(())
+    }
+
+    pub fn latest_state(&self) -> Option<&ChannelState> {
+        self.states.last()
+    }
+
+    fn settlement_tx(&self, balance_a: u64, balance_b: u64) -> Result<Transaction, String> {
+        let note = self.funding_note.clone().ok_or("Channel is not funded")?;
+        if balance_a + balance_b != self.capacity {
+            return Err("Balances must add up to the channel capacity".into());
+        }
+
+        let mut outputs = Vec::new();
+        for (party, value) in [(&self.party_a, balance_a), (&self.party_b, balance_b)] {
+            if value > 0 {
+                outputs.push(Output {
+                    recipient: party.0.clone(),
+                    value,
+                    lock: Lock::single(party),
+                });
+            }
+        }
+        Transaction::build(vec![note], outputs, HashAlgorithm::default())
+    }
+
+    // Proposes the next state. The previous state must be fully signed, and
+    // its revocation secrets are handed over before this one is signed.
+    pub fn propose_state(
+        &mut self,
+        balance_a: u64,
+        balance_b: u64,
+        revocation_hash_a: String,
+        revocation_hash_b: String,
+    ) -> Result<&ChannelState, String> {
+        if self.status != ChannelStatus::Open {
+            return Err("Channel is not open".into());
+        }
+        if let Some(prev) = self.latest_state() {
+            if !prev.is_signed() {
+                return Err("Previous state is not fully signed".into());
+            }
+        }
+
+        let sequence = self.latest_state().map_or(0, |s| s.sequence + 1);
+        let commitment = SigningSession::new(self.settlement_tx(balance_a, balance_b)?);
+        self.states.push(ChannelState {
+            sequence,
+            balance_a,
+            balance_b,
+            revocation_hash_a,
+            revocation_hash_b,
+            revocation_secret_a: None,
+            revocation_secret_b: None,
+            commitment,
+        });
+        Ok(self.states.last().expect("state was just pushed"))
+    }
+
+    pub fn sign_state(&mut self, sequence: u64, signed: &SigningSession) -> Result<usize, String> {
+        let state = self
+            .states
+            .iter_mut()
+            .find(|s| s.sequence == sequence)
+            .ok_or("Unknown channel state")?;
+        state.commitment.merge(signed)
+    }
+
+    pub fn revoke(
+        &mut self,
+        sequence: u64,
+        party: ChannelParty,
+        secret: &str,
+    ) -> Result<(), String> {
+        let latest = self.latest_state().map(|s| s.sequence);
+        if Some(sequence) == latest {
+            return Err("The latest state cannot be revoked".into());
+        }
+        let state = self
+            .states
+            .iter_mut()
+            .find(|s| s.sequence == sequence)
+            .ok_or("Unknown channel state")?;
+
+        let (expected, slot) = match party {
+            ChannelParty::A => (&state.revocation_hash_a, &mut state.revocation_secret_a),
+            ChannelParty::B => (&state.revocation_hash_b, &mut state.revocation_secret_b),
+        };
+        if revocation_hash(secret) != *expected {
+            return Err("Revocation secret does not match".into());
+        }
+        *slot = Some(secret.to_string());
+        Ok(())
+    }
+
+    // Returns the revoked state a broadcast transaction corresponds to, if any.
+    pub fn detect_revoked(&self, broadcast: &Transaction) -> Option<&ChannelState> {
+        let hashes: Vec<&str> = broadcast
+            .spends
+            .iter()
+            .map(|s| s.seeds.message_hash.as_str())
+            .collect();
+
+        self.states.iter().find(|state| {
+            let revoked =
+                state.revocation_secret_a.is_some() || state.revocation_secret_b.is_some();
+            let committed: Vec<&str> = state
+                .commitment
+                .transaction
+                .spends
+                .iter()
+                .map(|s| s.seeds.message_hash.as_str())
+                .collect();
+            revoked && committed == hashes
+        })
+    }
+
+    pub fn build_cooperative_close(&mut self) -> Result<&SigningSession, String> {
+        if self.status != ChannelStatus::Open {
+            return Err("Channel is not open".into());
+        }
+        let (a, b) = self
+            .latest_state()
+            .map_or((self.capacity, 0), |s| (s.balance_a, s.balance_b));
+        let tx = self.settlement_tx(a, b)?;
+        self.status = ChannelStatus::Closing;
+        Ok(self.close.insert(SigningSession::new(tx)))
+    }
+
+    // Unilateral close broadcasts the latest commitment both parties signed.
+    pub fn unilateral_close(&mut self) -> Result<&SigningSession, String> {
+        if self.status != ChannelStatus::Open && self.status != ChannelStatus::Closing {
+            return Err("Channel is not open".into());
+        }
+        let state = self
+            .states
+            .iter()
+            .rev()
+            .find(|s| s.is_signed() && !s.is_revoked())
+            .ok_or("No signed channel state to close with")?;
+        let session = state.commitment.clone();
+        self.status = ChannelStatus::Closing;
+        Ok(self.close.insert(session))
+    }
+
+    pub fn mark_closed(&mut self) -> Result<(), String> {
+        let close = self.close.as_ref().ok_or("No closing transaction")?;
+        close.transaction.validate_signatures()?;
+        self.status = ChannelStatus::Closed;
+        Ok(())
+    }
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+fn parse_channel(channel_json: &str) -> Result<Channel, String> {
+    serde_json::from_str(channel_json).map_err(|e| e.to_string())
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, String> {
+    serde_json::to_string(value).map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+pub fn channel_create(party_a: &str, party_b: &str, capacity: u64) -> Result<String, String> {
+    to_json(&Channel::new(
+        PublicKey(party_a.to_string()),
+        PublicKey(party_b.to_string()),
+        capacity,
+    )?)
+}
+
+#[wasm_bindgen]
+pub fn channel_build_funding(
+    channel_json: &str,
+    notes_json: &str,
+    contribution_a: u64,
+    contribution_b: u64,
+) -> Result<String, String> {
+    let channel = parse_channel(channel_json)?;
+    let notes: Vec<Note> = serde_json::from_str(notes_json).map_err(|e| e.to_string())?;
+    to_json(&channel.build_funding(notes, contribution_a, contribution_b)?)
+}
+
+#[wasm_bindgen]
+pub fn channel_record_funding(channel_json: &str, note_json: &str) -> Result<String, String> {
+    let mut channel = parse_channel(channel_json)?;
+    let note: Note = serde_json::from_str(note_json).map_err(|e| e.to_string())?;
+    channel.record_funding_note(note)?;
+    to_json(&channel)
+}
+
+#[wasm_bindgen]
+pub fn channel_propose_state(
+    channel_json: &str,
+    balance_a: u64,
+    balance_b: u64,
+    revocation_hash_a: &str,
+    revocation_hash_b: &str,
+) -> Result<String, String> {
+    let mut channel = parse_channel(channel_json)?;
+    channel.propose_state(
+        balance_a,
+        balance_b,
+        revocation_hash_a.to_string(),
+        revocation_hash_b.to_string(),
+    )?;
+    to_json(&channel)
+}
+
+#[wasm_bindgen]
+pub fn channel_sign_state(
+    channel_json: &str,
+    sequence: u64,
+    session_json: &str,
+) -> Result<String, String> {
+    let mut channel = parse_channel(channel_json)?;
+    channel.sign_state(sequence, &SigningSession::from_json(session_json)?)?;
+    to_json(&channel)
+}
+
+#[wasm_bindgen]
+pub fn channel_revoke(
+    channel_json: &str,
+    sequence: u64,
+    party: &str,
+    secret: &str,
+) -> Result<String, String> {
+    let mut channel = parse_channel(channel_json)?;
+    channel.revoke(sequence, ChannelParty::parse(party)?, secret)?;
+    to_json(&channel)
+}
+
+#[wasm_bindgen]
+pub fn channel_revocation_hash(secret: &str) -> String {
+    revocation_hash(secret)
+}
+
+#[wasm_bindgen]
+pub fn channel_cooperative_close(channel_json: &str) -> Result<String, String> {
+    let mut channel = parse_channel(channel_json)?;
+    channel.build_cooperative_close()?;
+    to_json(&channel)
+}
+
+#[wasm_bindgen]
+pub fn channel_unilateral_close(channel_json: &str) -> Result<String, String> {
+    let mut channel = parse_channel(channel_json)?;
+    channel.unilateral_close()?;
+    to_json(&channel)
+}
+
+#[wasm_bindgen]
+pub fn channel_detect_revoked(channel_json: &str, tx_json: &str) -> Result<String, String> {
+    let channel = parse_channel(channel_json)?;
+    let tx: Transaction = serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+    to_json(&channel.detect_revoked(&tx))
+}
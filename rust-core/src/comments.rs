@@ -0,0 +1,205 @@
+use crate::hashing::{digest_hex, HashAlgorithm};
+use crate::session::SigningSession;
+use crate::verify::VerificationItem;
+use crate::{PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Draft Comments
+// ============================================================================
+
+// Discussion about a draft ("why is this payout 3x normal?") kept with the
+// draft itself. Comments live in the session next to the signatures but
+// outside every spend hash, so posting one never disturbs approvals. Each is
+// signed by its author over the draft it was written about, so a comment
+// stays attributable after the draft is edited and cannot be replayed onto
+// another draft. Replies name their parent by id; clients build the thread.
+
+const COMMENT_DOMAIN: &[u8] = b"nockchain-multisig/comment/v1";
+pub const MAX_COMMENT_LENGTH: usize = 4000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Comment {
+    // Filled in when posted: the comment's message hash.
+    #[serde(default)]
+    pub id: String,
+    // Draft hash the comment was written against; the current draft if empty.
+    #[serde(default)]
+    pub draft: String,
+    pub author: PublicKey,
+    pub body: String,
+    pub at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<String>,
+    pub signature: Signature,
+}
+
+#[derive(Serialize)]
+struct CommentTerms<'a> {
+    draft: &'a str,
+    author: &'a PublicKey,
+    body: &'a str,
+    at: u64,
+    reply_to: &'a Option<String>,
+}
+
+impl Comment {
+    // What the author signs.
+    pub fn message(&self) -> String {
+        let terms = CommentTerms {
+            draft: &self.draft,
+            author: &self.author,
+            body: &self.body,
+            at: self.at,
+            reply_to: &self.reply_to,
+        };
+        let bytes = serde_json::to_vec(&terms).expect("Serialization failed");
+        digest_hex(HashAlgorithm::Sha256, &[COMMENT_DOMAIN, &bytes].concat())
+            .expect("sha256 is always available")
+    }
+
+    pub fn verification_item(&self) -> VerificationItem {
+        VerificationItem {
+            pubkey: self.author.clone(),
+            message_hash: self.message(),
+            signature: self.signature.clone(),
+        }
+    }
+}
+
+impl SigningSession {
+    // Anyone who can sign on any branch of any spend may comment.
+    pub fn is_participant(&self, pubkey: &PublicKey) -> bool {
+        self.transaction.spends.iter().any(|spend| {
+            spend
+                .note
+                .lock
+                .conditions()
+                .any(|c| c.pkh.pubkeys.contains(pubkey))
+        })
+    }
+
+    // Fills in the draft and id. Posting the same comment twice is a no-op.
+    pub fn add_comment(&mut self, mut comment: Comment) -> Result<Comment, String> {
+        if comment.draft.is_empty() {
+            comment.draft = self.draft_hash();
+        }
+        let id = comment.message();
+        if !comment.id.is_empty() && comment.id != id {
+            return Err("Comment id does not match its contents".into());
+        }
+        comment.id = id;
+        if self.comments.iter().any(|c| c.id == comment.id) {
+            return Ok(comment);
+        }
+
+        let body = comment.body.trim();
+        if body.is_empty() {
+            return Err("Comment is empty".into());
+        }
+        if comment.body.chars().count() > MAX_COMMENT_LENGTH {
+            return Err(format!(
+                "Comment is longer than {} characters",
+                MAX_COMMENT_LENGTH
+            ));
+        }
+        if comment.signature.0.is_empty() {
+            return Err("Comment is not signed".into());
+        }
+        if !self.is_participant(&comment.author) {
+            return Err(format!(
+                "{} is not a signer on this draft",
+                comment.author.0
+            ));
+        }
+        if let Some(parent) = &comment.reply_to {
+            if !self.comments.iter().any(|c| &c.id == parent) {
+                return Err(format!("Unknown comment {}", parent));
+            }
+        }
+
+        self.comments.push(comment.clone());
+        Ok(comment)
+    }
+
+    // Oldest first, so parents precede their replies.
+    pub fn comment_thread(&self) -> Vec<&Comment> {
+        let mut comments: Vec<&Comment> = self.comments.iter().collect();
+        comments.sort_by(|a, b| (a.at, &a.id).cmp(&(b.at, &b.id)));
+        comments
+    }
+
+    // Adds their comments we have not seen; returns how many.
+    pub fn merge_comments(&mut self, other: &SigningSession) -> usize {
+        let mut pending: Vec<&Comment> = other
+            .comment_thread()
+            .into_iter()
+            .filter(|c| !self.comments.iter().any(|ours| ours.id == c.id))
+            .collect();
+        let mut added = 0;
+        // Replies may arrive before their parents; retry until nothing moves.
+        loop {
+            let before = pending.len();
+            pending.retain(|c| self.add_comment((*c).clone()).is_err());
+            added += before - pending.len();
+            if pending.len() == before {
+                return added;
+            }
+        }
+    }
+
+    pub fn comment_verification_items(&self) -> Vec<VerificationItem> {
+        self.comments
+            .iter()
+            .map(Comment::verification_item)
+            .collect()
+    }
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+#[wasm_bindgen]
+pub fn get_comment_message(
+    session_json: &str,
+    author: &str,
+    body: &str,
+    at: u64,
+    reply_to: Option<String>,
+) -> Result<String, String> {
+    let session = SigningSession::from_json(session_json)?;
+    let comment = Comment {
+        id: String::new(),
+        draft: session.draft_hash(),
+        author: PublicKey(author.to_string()),
+        body: body.to_string(),
+        at,
+        reply_to,
+        signature: Signature(String::new()),
+    };
+    Ok(comment.message())
+}
+
+// Takes `{author, body, at, reply_to?, signature}` and returns the session.
+#[wasm_bindgen]
+pub fn session_post_comment(session_json: &str, comment_json: &str) -> Result<String, String> {
+    let mut session = SigningSession::from_json(session_json)?;
+    let comment: Comment = serde_json::from_str(comment_json).map_err(|e| e.to_string())?;
+    session.add_comment(comment)?;
+    session.to_json()
+}
+
+#[wasm_bindgen]
+pub fn list_comments(session_json: &str) -> Result<String, String> {
+    let session = SigningSession::from_json(session_json)?;
+    serde_json::to_string(&session.comment_thread()).map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+pub fn get_comment_verification_items(session_json: &str) -> Result<String, String> {
+    let session = SigningSession::from_json(session_json)?;
+    serde_json::to_string(&session.comment_verification_items()).map_err(|e| e.to_string())
+}
@@ -0,0 +1,51 @@
+use crate::bindings::exports::nockchain::multisig::api::Guest;
+
+// ============================================================================
+// WASI Component Interface
+// ============================================================================
+
+// Backs `wit/multisig.wit`. Build with `cargo component build --release
+// --features component`; `src/bindings.rs` is generated by cargo-component.
+
+struct Component;
+
+impl Guest for Component {
+    fn build_transaction(notes_json: String, outputs_json: String) -> Result<String, String> {
+        crate::build_transaction(&notes_json, &outputs_json)
+    }
+
+    fn build_transaction_with_hash(
+        notes_json: String,
+        outputs_json: String,
+        algorithm: String,
+    ) -> Result<String, String> {
+        crate::build_transaction_with_hash(&notes_json, &outputs_json, &algorithm)
+    }
+
+    fn get_spend_hash(tx_json: String, spend_index: u32) -> Result<String, String> {
+        crate::get_spend_hash(&tx_json, spend_index as usize)
+    }
+
+    fn add_signature(
+        tx_json: String,
+        spend_index: u32,
+        pubkey: String,
+        signature: String,
+    ) -> Result<String, String> {
+        crate::add_signature(&tx_json, spend_index as usize, &pubkey, &signature)
+    }
+
+    fn get_spend_signing_status(tx_json: String, spend_index: u32) -> Result<String, String> {
+        crate::get_spend_signing_status(&tx_json, spend_index as usize)
+    }
+
+    fn validate_transaction(tx_json: String) -> Result<String, String> {
+        crate::validate_transaction(&tx_json)
+    }
+
+    fn worker_dispatch(request_json: String) -> String {
+        crate::worker::worker_dispatch(&request_json)
+    }
+}
+
+crate::bindings::export!(Component with_types_in crate::bindings);
@@ -0,0 +1,292 @@
+use crate::encoding::{base64url_decode, base64url_encode};
+use crate::hashing::{constant_time_eq, hmac_sha256};
+use crate::rng::RandomSource;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Symmetric Encryption
+// ============================================================================
+
+// ChaCha20 (RFC 8439) with an HMAC-SHA256 tag, encrypt-then-MAC. The nonce
+// is synthetic (an HMAC of the plaintext), so nothing here needs a random
+// number generator and reusing a key can only reveal that two plaintexts
+// were identical.
+
+pub const SEALED_FORMAT_VERSION: u32 = 1;
+const CIPHER_NAME: &str = "chacha20-hmac-sha256";
+
+fn quarter_round(s: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(16);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(12);
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(8);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(7);
+}
+
+fn chacha20_block(key: &[u8; 32], counter: u32, nonce: &[u8; 12]) -> [u8; 64] {
+    let word = |b: &[u8]| u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+    let mut state = [0u32; 16];
+    state[..4].copy_from_slice(&[0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574]);
+    for i in 0..8 {
+        state[4 + i] = word(&key[4 * i..]);
+    }
+    state[12] = counter;
+    for i in 0..3 {
+        state[13 + i] = word(&nonce[4 * i..]);
+    }
+
+    let mut working = state;
+    for _ in 0..10 {
+        quarter_round(&mut working, 0, 4, 8, 12);
+        quarter_round(&mut working, 1, 5, 9, 13);
+        quarter_round(&mut working, 2, 6, 10, 14);
+        quarter_round(&mut working, 3, 7, 11, 15);
+        quarter_round(&mut working, 0, 5, 10, 15);
+        quarter_round(&mut working, 1, 6, 11, 12);
+        quarter_round(&mut working, 2, 7, 8, 13);
+        quarter_round(&mut working, 3, 4, 9, 14);
+    }
+
+    let mut out = [0u8; 64];
+    for i in 0..16 {
+        out[4 * i..4 * i + 4].copy_from_slice(&working[i].wrapping_add(state[i]).to_le_bytes());
+    }
+    out
+}
+
+pub fn chacha20_xor(key: &[u8; 32], nonce: &[u8; 12], counter: u32, data: &mut [u8]) {
+    for (i, chunk) in data.chunks_mut(64).enumerate() {
+        let block = chacha20_block(key, counter.wrapping_add(i as u32), nonce);
+        for (byte, k) in chunk.iter_mut().zip(block) {
+            *byte ^= k;
+        }
+    }
+}
+
+pub fn pbkdf2_sha256(password: &[u8], salt: &[u8], iterations: u32) -> [u8; 32] {
+    let mut u = hmac_sha256(password, &[salt, &1u32.to_be_bytes()].concat());
+    let mut out = u;
+    for _ in 1..iterations {
+        u = hmac_sha256(password, &u);
+        for (o, b) in out.iter_mut().zip(u) {
+            *o ^= b;
+        }
+    }
+    out
+}
+
+#[derive(Clone)]
+pub struct SecretKey([u8; 32]);
+
+impl std::fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SecretKey({})", self.id())
+    }
+}
+
+impl SecretKey {
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn from_hex(key_hex: &str) -> Result<Self, String> {
+        let bytes = hex::decode(key_hex.trim()).map_err(|e| e.to_string())?;
+        let bytes: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| "Encryption keys must be 32 bytes (64 hex characters)")?;
+        Ok(Self(bytes))
+    }
+
+    pub fn generate(rng: &mut dyn RandomSource) -> Result<Self, String> {
+        let mut bytes = [0u8; 32];
+        rng.fill_bytes(&mut bytes)?;
+        Ok(Self(bytes))
+    }
+
+    pub fn from_passphrase(passphrase: &str, salt: &[u8], iterations: u32) -> Self {
+        Self(pbkdf2_sha256(passphrase.as_bytes(), salt, iterations))
+    }
+
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+
+    fn subkey(&self, label: &[u8]) -> [u8; 32] {
+        hmac_sha256(&self.0, label)
+    }
+
+    // Short public identifier, so stored data can name the key it needs
+    // without revealing it.
+    pub fn id(&self) -> String {
+        hex::encode(&self.subkey(b"id")[..8])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Sealed {
+    pub format: u32,
+    pub cipher: String,
+    pub key_id: String,
+    pub nonce: String,
+    pub ciphertext: String,
+    pub tag: String,
+}
+
+fn tag(mac_key: &[u8; 32], nonce: &[u8], associated: &[u8], ciphertext: &[u8]) -> [u8; 32] {
+    let len = (associated.len() as u64).to_be_bytes();
+    hmac_sha256(mac_key, &[nonce, &len, associated, ciphertext].concat())
+}
+
+// `associated` is authenticated but not encrypted; pass the storage key or
+// similar so sealed values cannot be swapped between slots.
+pub fn seal(key: &SecretKey, plaintext: &[u8], associated: &[u8]) -> Sealed {
+    let enc_key = key.subkey(b"enc");
+    let mac_key = key.subkey(b"mac");
+
+    let len = (associated.len() as u64).to_be_bytes();
+    let siv = hmac_sha256(
+        &mac_key,
+        &[b"siv", &len[..], associated, plaintext].concat(),
+    );
+    let nonce: [u8; 12] = siv[..12].try_into().expect("12 bytes");
+
+    let mut ciphertext = plaintext.to_vec();
+    chacha20_xor(&enc_key, &nonce, 1, &mut ciphertext);
+
+    Sealed {
+        format: SEALED_FORMAT_VERSION,
+        cipher: CIPHER_NAME.to_string(),
+        key_id: key.id(),
+        nonce: hex::encode(nonce),
+        tag: hex::encode(tag(&mac_key, &nonce, associated, &ciphertext)),
+        ciphertext: base64url_encode(&ciphertext),
+    }
+}
+
+pub fn open(key: &SecretKey, sealed: &Sealed, associated: &[u8]) -> Result<Vec<u8>, String> {
+    if sealed.format > SEALED_FORMAT_VERSION || sealed.cipher != CIPHER_NAME {
+        return Err(format!(
+            "Unsupported encryption {} v{}",
+            sealed.cipher, sealed.format
+        ));
+    }
+    if sealed.key_id != key.id() {
+        return Err(format!("Data was encrypted with key {}", sealed.key_id));
+    }
+
+    let nonce: [u8; 12] = hex::decode(&sealed.nonce)
+        .map_err(|e| e.to_string())?
+        .try_into()
+        .map_err(|_| "Malformed nonce")?;
+    let mut data = base64url_decode(&sealed.ciphertext)?;
+    let expected = hex::decode(&sealed.tag).map_err(|e| e.to_string())?;
+
+    let mac_key = key.subkey(b"mac");
+    if !constant_time_eq(&expected, &tag(&mac_key, &nonce, associated, &data)) {
+        return Err("Wrong key or the data has been tampered with".into());
+    }
+    chacha20_xor(&key.subkey(b"enc"), &nonce, 1, &mut data);
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chacha20_rfc8439_encryption() {
+        // RFC 8439 section 2.4.2.
+        let key: [u8; 32] = core::array::from_fn(|i| i as u8);
+        let nonce = [0, 0, 0, 0, 0, 0, 0, 0x4a, 0, 0, 0, 0];
+        let mut data = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.".to_vec();
+        chacha20_xor(&key, &nonce, 1, &mut data);
+        assert_eq!(
+            hex::encode(&data),
+            "6e2e359a2568f98041ba0728dd0d6981e97e7aec1d4360c20a27afccfd9fae0b\
+             f91b65c5524733ab8f593dabcd62b3571639d624e65152ab8f530c359f0861d8\
+             07ca0dbf500d6a6156a38e088a22b65e52bc514d16ccf806818ce91ab7793736\
+             5af90bbf74a35be6b40b8eedf2785e42874d"
+        );
+    }
+
+    #[test]
+    fn pbkdf2_sha256_vectors() {
+        // The RFC 6070 inputs with their HMAC-SHA256 outputs (the RFC lists
+        // SHA-1), plus the PBKDF2 vector of RFC 7914 section 11.
+        let cases: [(&[u8], &[u8], u32, &str); 5] = [
+            (
+                b"password",
+                b"salt",
+                1,
+                "120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b",
+            ),
+            (
+                b"password",
+                b"salt",
+                2,
+                "ae4d0c95af6b46d32d0adff928f06dd02a303f8ef3c251dfd6e2d85a95474c43",
+            ),
+            (
+                b"password",
+                b"salt",
+                4096,
+                "c5e478d59288c841aa530db6845c4c8d962893a001ce4e11a4963873aa98134a",
+            ),
+            (
+                b"passwordPASSWORDpassword",
+                b"saltSALTsaltSALTsaltSALTsaltSALTsalt",
+                4096,
+                "348c89dbcbd32b2f32d814b8116e84cf2b17347ebc1800181c4e2a1fb8dd53e1",
+            ),
+            (
+                b"passwd",
+                b"salt",
+                1,
+                "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc",
+            ),
+        ];
+        for (password, salt, iterations, expected) in cases {
+            assert_eq!(
+                hex::encode(pbkdf2_sha256(password, salt, iterations)),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn seal_open_round_trip() {
+        let key = SecretKey::from_bytes([7; 32]);
+        let plaintext = b"wallet state spanning more than one sixty-four byte chacha block";
+        let sealed = seal(&key, plaintext, b"wallets/w1");
+        assert_eq!(open(&key, &sealed, b"wallets/w1").unwrap(), plaintext);
+        assert_eq!(seal(&key, plaintext, b"wallets/w1"), sealed);
+        assert!(open(&key, &sealed, b"wallets/w2").is_err());
+        assert!(open(&SecretKey::from_bytes([8; 32]), &sealed, b"wallets/w1").is_err());
+    }
+
+    #[test]
+    fn open_rejects_tampering() {
+        let key = SecretKey::from_bytes([7; 32]);
+        let sealed = seal(&key, b"secret", b"slot");
+
+        let mut tag = hex::decode(&sealed.tag).unwrap();
+        tag[0] ^= 1;
+        let forged = Sealed {
+            tag: hex::encode(tag),
+            ..sealed.clone()
+        };
+        assert!(open(&key, &forged, b"slot").is_err());
+
+        let mut ciphertext = base64url_decode(&sealed.ciphertext).unwrap();
+        ciphertext[0] ^= 1;
+        let forged = Sealed {
+            ciphertext: base64url_encode(&ciphertext),
+            ..sealed
+        };
+        assert!(open(&key, &forged, b"slot").is_err());
+    }
+}
@@ -0,0 +1,274 @@
+use crate::hashing::{digest_hex, hmac_sha256, HashAlgorithm};
+use crate::session::SigningSession;
+use crate::{NoteName, PublicKey};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Selective Disclosure
+// ============================================================================
+
+// An audit bundle describes a signing session for someone outside the
+// wallet: what it spent, where the outputs went, and who approved it. Any
+// of those fields can be redacted. A redacted field is replaced by a salted
+// hash commitment to its value, e.g. an auditor sees amounts and approvals
+// but only commitments to the recipients. Full disclosure later means handing
+// over the unredacted bundle, and `verify_disclosure` checks that every
+// commitment in the redacted one opens to the value disclosed, so the first
+// bundle cannot have been made to fit after the fact.
+//
+// Salts are derived from a secret seed the exporter keeps, so the same seed
+// always yields the same commitments, and a low-entropy value (an amount)
+// cannot be guessed from its commitment. Revealed fields carry their salt.
+
+pub const DISCLOSURE_FORMAT_VERSION: u32 = 1;
+
+const DISCLOSURE_DOMAIN: &[u8] = b"nockchain-multisig/disclosure/v1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisclosureField {
+    // Output recipients and locks.
+    Recipients,
+    // Spent notes and output values.
+    Amounts,
+    // Who signed each spend.
+    Approvals,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum Disclosed<T> {
+    Revealed { value: T, salt: String },
+    Committed { commitment: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Payee {
+    pub recipient: String,
+    // Lock fingerprint.
+    pub lock: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpentNote {
+    pub name: NoteName,
+    pub value: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpendDisclosure {
+    pub note: Disclosed<SpentNote>,
+    pub signers: Disclosed<Vec<PublicKey>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutputDisclosure {
+    pub payee: Disclosed<Payee>,
+    pub value: Disclosed<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuditBundle {
+    pub format: u32,
+    pub draft: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redacted: Vec<DisclosureField>,
+    pub spends: Vec<SpendDisclosure>,
+    pub outputs: Vec<OutputDisclosure>,
+    // Head of the session's verified audit log and its length.
+    pub audit_head: String,
+    pub audit_entries: usize,
+}
+
+fn commitment<T: Serialize>(value: &T, salt: &str) -> String {
+    let value = serde_json::to_vec(value).expect("Serialization failed");
+    let preimage = [DISCLOSURE_DOMAIN, salt.as_bytes(), b"\n", &value].concat();
+    digest_hex(HashAlgorithm::Sha256, &preimage).expect("sha256 is always available")
+}
+
+fn disclose<T: Serialize>(value: T, path: &str, seed: &[u8], redact: bool) -> Disclosed<T> {
+    let salt = hex::encode(hmac_sha256(seed, path.as_bytes()));
+    if redact {
+        Disclosed::Committed {
+            commitment: commitment(&value, &salt),
+        }
+    } else {
+        Disclosed::Revealed { value, salt }
+    }
+}
+
+impl<T: Serialize + PartialEq> Disclosed<T> {
+    pub fn value(&self) -> Option<&T> {
+        match self {
+            Self::Revealed { value, .. } => Some(value),
+            Self::Committed { .. } => None,
+        }
+    }
+
+    // `full` must be revealed; a revealed field here must match it, a
+    // committed one must open to it.
+    fn check(&self, full: &Disclosed<T>, path: &str) -> Result<(), String> {
+        let Disclosed::Revealed { value, salt } = full else {
+            return Err(format!("{} is not disclosed in the full bundle", path));
+        };
+        let matches = match self {
+            Self::Revealed { value: ours, .. } => ours == value,
+            Self::Committed { commitment: ours } => *ours == commitment(value, salt),
+        };
+        if !matches {
+            return Err(format!("{} does not match the full disclosure", path));
+        }
+        Ok(())
+    }
+}
+
+impl AuditBundle {
+    // `seed` must be 16+ secret bytes, the same for every export of the
+    // session that should be comparable.
+    pub fn from_session(
+        session: &SigningSession,
+        redact: &[DisclosureField],
+        seed: &[u8],
+    ) -> Result<Self, String> {
+        if seed.len() < 16 {
+            return Err("Disclosure seed must be at least 16 bytes".into());
+        }
+        session.audit.verify()?;
+        let redacts = |field| redact.contains(&field);
+        let tx = &session.transaction;
+        let spends = tx
+            .spends
+            .iter()
+            .enumerate()
+            .map(|(i, spend)| SpendDisclosure {
+                note: disclose(
+                    SpentNote {
+                        name: spend.note.name.clone(),
+                        value: spend.note.value,
+                    },
+                    &format!("spends/{}/note", i),
+                    seed,
+                    redacts(DisclosureField::Amounts),
+                ),
+                signers: disclose(
+                    spend
+                        .seeds
+                        .signatures
+                        .iter()
+                        .map(|(pk, _)| pk.clone())
+                        .collect(),
+                    &format!("spends/{}/signers", i),
+                    seed,
+                    redacts(DisclosureField::Approvals),
+                ),
+            })
+            .collect();
+        let outputs = tx
+            .outputs
+            .iter()
+            .enumerate()
+            .map(|(i, output)| OutputDisclosure {
+                payee: disclose(
+                    Payee {
+                        recipient: output.recipient.clone(),
+                        lock: output.lock.fingerprint(),
+                    },
+                    &format!("outputs/{}/payee", i),
+                    seed,
+                    redacts(DisclosureField::Recipients),
+                ),
+                value: disclose(
+                    output.value,
+                    &format!("outputs/{}/value", i),
+                    seed,
+                    redacts(DisclosureField::Amounts),
+                ),
+            })
+            .collect();
+        let mut redacted = redact.to_vec();
+        redacted.sort_by_key(|f| *f as u8);
+        redacted.dedup();
+        Ok(Self {
+            format: DISCLOSURE_FORMAT_VERSION,
+            draft: session.draft_hash(),
+            redacted,
+            spends,
+            outputs,
+            audit_head: session.audit.head().to_string(),
+            audit_entries: session.audit.entries.len(),
+        })
+    }
+
+    // Checks `full`, an unredacted bundle of the same session, against this
+    // one.
+    pub fn verify_disclosure(&self, full: &AuditBundle) -> Result<(), String> {
+        if full.format != DISCLOSURE_FORMAT_VERSION || self.format != DISCLOSURE_FORMAT_VERSION {
+            return Err(format!(
+                "Unsupported disclosure format (expected {})",
+                DISCLOSURE_FORMAT_VERSION
+            ));
+        }
+        if !full.redacted.is_empty() {
+            return Err("Full disclosure still has redacted fields".into());
+        }
+        if full.draft != self.draft
+            || full.audit_head != self.audit_head
+            || full.audit_entries != self.audit_entries
+        {
+            return Err("Bundles describe different drafts or audit logs".into());
+        }
+        if full.spends.len() != self.spends.len() || full.outputs.len() != self.outputs.len() {
+            return Err("Bundles have different spends or outputs".into());
+        }
+        for (i, (ours, theirs)) in self.spends.iter().zip(&full.spends).enumerate() {
+            ours.note
+                .check(&theirs.note, &format!("spends/{}/note", i))?;
+            ours.signers
+                .check(&theirs.signers, &format!("spends/{}/signers", i))?;
+        }
+        for (i, (ours, theirs)) in self.outputs.iter().zip(&full.outputs).enumerate() {
+            ours.payee
+                .check(&theirs.payee, &format!("outputs/{}/payee", i))?;
+            ours.value
+                .check(&theirs.value, &format!("outputs/{}/value", i))?;
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+// `redact_json` lists the fields to withhold, e.g. `["recipients"]`; an
+// empty list gives the full disclosure.
+#[wasm_bindgen]
+pub fn export_audit_bundle(
+    session_json: &str,
+    redact_json: &str,
+    seed_hex: &str,
+) -> Result<String, String> {
+    let session = SigningSession::from_json(session_json)?;
+    let redact: Vec<DisclosureField> =
+        serde_json::from_str(redact_json).map_err(|e| e.to_string())?;
+    let seed = hex::decode(seed_hex).map_err(|_| "Seed must be hex")?;
+    let bundle = AuditBundle::from_session(&session, &redact, &seed)?;
+    serde_json::to_string(&bundle).map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+pub fn verify_audit_disclosure(redacted_json: &str, full_json: &str) -> Result<String, String> {
+    let redacted: AuditBundle = serde_json::from_str(redacted_json).map_err(|e| e.to_string())?;
+    let full: AuditBundle = serde_json::from_str(full_json).map_err(|e| e.to_string())?;
+    redacted.verify_disclosure(&full)?;
+    Ok(format!(
+        "Disclosure matches the redacted bundle for draft {}",
+        full.draft
+    ))
+}
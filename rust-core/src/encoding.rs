@@ -0,0 +1,448 @@
+// ============================================================================
+// Compact Encodings
+// ============================================================================
+
+// URL-safe base64 without padding (RFC 4648 §5), for strings users paste or
+// put in links.
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+pub fn base64url_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, &b)| acc | (b as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            out.push(ALPHABET[(n >> (18 - 6 * i)) as usize & 63] as char);
+        }
+    }
+    out
+}
+
+pub fn base64url_decode(text: &str) -> Result<Vec<u8>, String> {
+    let text = text.trim_end_matches('=');
+    if text.len() % 4 == 1 {
+        return Err("Invalid base64 length".into());
+    }
+
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    for chunk in text.as_bytes().chunks(4) {
+        let mut n = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            let v = ALPHABET
+                .iter()
+                .position(|&a| a == c)
+                .ok_or("Invalid base64 character")?;
+            n |= (v as u32) << (18 - 6 * i);
+        }
+        for i in 0..chunk.len() - 1 {
+            out.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Ok(out)
+}
+
+// ============================================================================
+// Compression
+// ============================================================================
+
+// A small LZ77 for archived JSON, which is mostly repeated keys, hashes and
+// locks. The stream is the decompressed length as a LEB128 varint, then
+// tokens: a byte below 0x80 is a run of that many plus one literals, any
+// other byte is a match of `(b & 0x7f) + 4` bytes copied from a two-byte
+// little-endian distance back.
+
+const MIN_MATCH: usize = 4;
+const MAX_MATCH: usize = 0x7f + MIN_MATCH;
+const MAX_LITERALS: usize = 0x80;
+const WINDOW: usize = u16::MAX as usize;
+
+fn flush_literals(out: &mut Vec<u8>, literals: &[u8]) {
+    for run in literals.chunks(MAX_LITERALS) {
+        out.push((run.len() - 1) as u8);
+        out.extend_from_slice(run);
+    }
+}
+
+pub fn compress(input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len() / 2 + 8);
+    let mut len = input.len();
+    while len >= 0x80 {
+        out.push(len as u8 | 0x80);
+        len >>= 7;
+    }
+    out.push(len as u8);
+
+    let mut table = vec![usize::MAX; 1 << 14];
+    let hash = |at: usize| {
+        let word = u32::from_le_bytes([input[at], input[at + 1], input[at + 2], input[at + 3]]);
+        (word.wrapping_mul(2654435761) >> 18) as usize
+    };
+    let mut literal_start = 0;
+    let mut i = 0;
+    while i + MIN_MATCH <= input.len() {
+        let h = hash(i);
+        let candidate = table[h];
+        table[h] = i;
+        let found = candidate != usize::MAX
+            && i - candidate <= WINDOW
+            && input[candidate..candidate + MIN_MATCH] == input[i..i + MIN_MATCH];
+        if !found {
+            i += 1;
+            continue;
+        }
+        let mut length = MIN_MATCH;
+        while length < MAX_MATCH
+            && i + length < input.len()
+            && input[candidate + length] == input[i + length]
+        {
+            length += 1;
+        }
+        flush_literals(&mut out, &input[literal_start..i]);
+        out.push(0x80 | (length - MIN_MATCH) as u8);
+        out.extend_from_slice(&((i - candidate) as u16).to_le_bytes());
+        i += length;
+        literal_start = i;
+    }
+    flush_literals(&mut out, &input[literal_start..]);
+    out
+}
+
+pub fn decompress(input: &[u8]) -> Result<Vec<u8>, String> {
+    let mut len = 0usize;
+    let mut shift = 0;
+    let mut pos = 0;
+    loop {
+        let byte = *input.get(pos).ok_or("Truncated compressed data")?;
+        pos += 1;
+        if shift > 56 {
+            return Err("Invalid compressed length".into());
+        }
+        len |= ((byte & 0x7f) as usize) << shift;
+        if byte < 0x80 {
+            break;
+        }
+        shift += 7;
+    }
+
+    let mut out: Vec<u8> = Vec::with_capacity(len.min(input.len() * MAX_MATCH));
+    while pos < input.len() {
+        let token = input[pos];
+        pos += 1;
+        if token < 0x80 {
+            let run = input
+                .get(pos..pos + token as usize + 1)
+                .ok_or("Truncated compressed data")?;
+            out.extend_from_slice(run);
+            pos += run.len();
+        } else {
+            let distance = input
+                .get(pos..pos + 2)
+                .map(|d| u16::from_le_bytes([d[0], d[1]]) as usize)
+                .ok_or("Truncated compressed data")?;
+            pos += 2;
+            if distance == 0 || distance > out.len() {
+                return Err("Invalid match distance".into());
+            }
+            let start = out.len() - distance;
+            for k in 0..(token & 0x7f) as usize + MIN_MATCH {
+                out.push(out[start + k]);
+            }
+        }
+        if out.len() > len {
+            return Err("Compressed data longer than declared".into());
+        }
+    }
+    if out.len() != len {
+        return Err("Compressed data shorter than declared".into());
+    }
+    Ok(out)
+}
+
+// ============================================================================
+// Binary Encoding
+// ============================================================================
+
+// A self-describing binary form of any JSON document, for hosts that call
+// into WASM often enough that building and parsing JSON text shows up in
+// profiles. Values go through `serde_json::Value`, so every type encodes
+// exactly as its JSON does and decodes back to the same value. Map keys are
+// sorted, so a value has one encoding; the decoder rejects keys that are out
+// of order or repeated rather than let a later entry replace an earlier
+// one. Lowercase hex strings, which most
+// hashes and signatures are, are stored as their bytes.
+//
+//   "nmb1" value
+//   value = 0x00 null | 0x01 false | 0x02 true
+//         | 0x03 varint           (unsigned integer)
+//         | 0x04 varint           (negative integer n, as -(n + 1))
+//         | 0x05 f64 (8 bytes LE)
+//         | 0x06 varint utf8      (string)
+//         | 0x07 varint bytes     (lowercase hex string)
+//         | 0x08 varint value*    (array)
+//         | 0x09 varint (varint utf8 value)*   (map)
+
+const BINARY_MAGIC: &[u8] = b"nmb1";
+const MAX_BINARY_DEPTH: usize = 64;
+
+fn write_varint(out: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        out.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+fn is_lower_hex(s: &str) -> bool {
+    !s.is_empty()
+        && s.len().is_multiple_of(2)
+        && s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+fn write_value(out: &mut Vec<u8>, value: &serde_json::Value) {
+    use serde_json::Value;
+    match value {
+        Value::Null => out.push(0x00),
+        Value::Bool(false) => out.push(0x01),
+        Value::Bool(true) => out.push(0x02),
+        Value::Number(n) => {
+            if let Some(u) = n.as_u64() {
+                out.push(0x03);
+                write_varint(out, u);
+            } else if let Some(i) = n.as_i64() {
+                out.push(0x04);
+                write_varint(out, !(i as u64));
+            } else {
+                out.push(0x05);
+                out.extend_from_slice(&n.as_f64().unwrap_or_default().to_le_bytes());
+            }
+        }
+        Value::String(s) if is_lower_hex(s) => {
+            out.push(0x07);
+            write_varint(out, s.len() as u64 / 2);
+            out.extend(hex::decode(s).expect("checked hex"));
+        }
+        Value::String(s) => {
+            out.push(0x06);
+            write_varint(out, s.len() as u64);
+            out.extend_from_slice(s.as_bytes());
+        }
+        Value::Array(items) => {
+            out.push(0x08);
+            write_varint(out, items.len() as u64);
+            for item in items {
+                write_value(out, item);
+            }
+        }
+        Value::Object(map) => {
+            out.push(0x09);
+            write_varint(out, map.len() as u64);
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            for (key, item) in entries {
+                write_varint(out, key.len() as u64);
+                out.extend_from_slice(key.as_bytes());
+                write_value(out, item);
+            }
+        }
+    }
+}
+
+struct BinaryReader<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BinaryReader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|&end| end <= self.input.len());
+        let bytes = &self.input[self.pos..end.ok_or("Truncated binary value")?];
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn varint(&mut self) -> Result<u64, String> {
+        let mut n = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            n |= ((byte & 0x7f) as u64) << shift;
+            if byte < 0x80 {
+                return Ok(n);
+            }
+        }
+        Err("Invalid varint".into())
+    }
+
+    // Lengths can never exceed what is left of the input.
+    fn length(&mut self) -> Result<usize, String> {
+        let n = self.varint()?;
+        if n > (self.input.len() - self.pos) as u64 {
+            return Err("Truncated binary value".into());
+        }
+        Ok(n as usize)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let len = self.length()?;
+        String::from_utf8(self.take(len)?.to_vec())
+            .map_err(|_| "Invalid UTF-8 in binary value".into())
+    }
+
+    fn value(&mut self, depth: usize) -> Result<serde_json::Value, String> {
+        use serde_json::Value;
+        if depth > MAX_BINARY_DEPTH {
+            return Err("Binary value nested too deeply".into());
+        }
+        Ok(match self.take(1)?[0] {
+            0x00 => Value::Null,
+            0x01 => Value::Bool(false),
+            0x02 => Value::Bool(true),
+            0x03 => Value::from(self.varint()?),
+            0x04 => Value::from(!self.varint()? as i64),
+            0x05 => {
+                let bytes: [u8; 8] = self.take(8)?.try_into().expect("8 bytes");
+                serde_json::Number::from_f64(f64::from_le_bytes(bytes))
+                    .map(Value::Number)
+                    .ok_or("Invalid number in binary value")?
+            }
+            0x06 => Value::String(self.string()?),
+            0x07 => {
+                let len = self.length()?;
+                Value::String(hex::encode(self.take(len)?))
+            }
+            0x08 => {
+                let len = self.length()?;
+                let mut items = Vec::with_capacity(len);
+                for _ in 0..len {
+                    items.push(self.value(depth + 1)?);
+                }
+                Value::Array(items)
+            }
+            0x09 => {
+                let len = self.length()?;
+                let mut map = serde_json::Map::new();
+                let mut previous: Option<String> = None;
+                for _ in 0..len {
+                    let key = self.string()?;
+                    if previous.as_ref().is_some_and(|p| *p >= key) {
+                        return Err(format!(
+                            "Binary map key {:?} is repeated or out of order",
+                            key
+                        ));
+                    }
+                    let value = self.value(depth + 1)?;
+                    map.insert(key.clone(), value);
+                    previous = Some(key);
+                }
+                Value::Object(map)
+            }
+            tag => return Err(format!("Unknown binary tag {:#04x}", tag)),
+        })
+    }
+}
+
+pub fn to_binary<T: serde::Serialize>(value: &T) -> Result<Vec<u8>, String> {
+    let value = serde_json::to_value(value).map_err(|e| e.to_string())?;
+    let mut out = BINARY_MAGIC.to_vec();
+    write_value(&mut out, &value);
+    Ok(out)
+}
+
+pub fn from_binary<T: serde::de::DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+    let body = bytes
+        .strip_prefix(BINARY_MAGIC)
+        .ok_or("Not a binary-encoded value")?;
+    let mut reader = BinaryReader {
+        input: body,
+        pos: 0,
+    };
+    let value = reader.value(0)?;
+    if reader.pos != body.len() {
+        return Err("Trailing bytes after binary value".into());
+    }
+    serde_json::from_value(value).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{draft, MULTISIG_LOCK};
+    use crate::Transaction;
+
+    // Encodes a map of integers in the given order, bypassing the sorting in
+    // `write_value`.
+    fn raw_map(entries: &[(&str, u64)]) -> Vec<u8> {
+        let mut out = BINARY_MAGIC.to_vec();
+        out.push(0x09);
+        write_varint(&mut out, entries.len() as u64);
+        for (key, value) in entries {
+            write_varint(&mut out, key.len() as u64);
+            out.extend_from_slice(key.as_bytes());
+            out.push(0x03);
+            write_varint(&mut out, *value);
+        }
+        out
+    }
+
+    #[test]
+    fn round_trips_transactions() {
+        let tx = draft(MULTISIG_LOCK);
+        let bytes = to_binary(&tx).unwrap();
+        let decoded: Transaction = from_binary(&bytes).unwrap();
+        assert_eq!(
+            serde_json::to_value(&decoded).unwrap(),
+            serde_json::to_value(&tx).unwrap()
+        );
+        assert_eq!(to_binary(&decoded).unwrap(), bytes);
+    }
+
+    #[test]
+    fn round_trips_every_value_kind() {
+        let value = serde_json::json!({
+            "null": null,
+            "bools": [true, false],
+            "numbers": [0, 1, u64::MAX, -1, i64::MIN, 1.5],
+            "text": "Not hex",
+            "hex": "00ff",
+            "nested": {"b": [], "a": {}},
+        });
+        let decoded: serde_json::Value = from_binary(&to_binary(&value).unwrap()).unwrap();
+        assert_eq!(decoded, value);
+    }
+
+    #[test]
+    fn rejects_duplicate_map_keys() {
+        let err = from_binary::<serde_json::Value>(&raw_map(&[("a", 1), ("a", 2)])).unwrap_err();
+        assert!(err.contains("repeated or out of order"), "{err}");
+    }
+
+    #[test]
+    fn rejects_unsorted_map_keys() {
+        assert!(from_binary::<serde_json::Value>(&raw_map(&[("b", 1), ("a", 2)])).is_err());
+        from_binary::<serde_json::Value>(&raw_map(&[("a", 1), ("b", 2)])).unwrap();
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        let bytes = to_binary(&draft(MULTISIG_LOCK)).unwrap();
+        assert!(from_binary::<Transaction>(&bytes[..bytes.len() - 1]).is_err());
+        assert!(from_binary::<Transaction>(&[bytes.as_slice(), &[0x00]].concat()).is_err());
+        assert!(from_binary::<Transaction>(&bytes[4..]).is_err());
+        assert!(from_binary::<serde_json::Value>(b"nmb1\x0a").is_err());
+        // A length far beyond the input.
+        assert!(from_binary::<serde_json::Value>(b"nmb1\x08\xff\xff\x7f").is_err());
+        let deep = [
+            BINARY_MAGIC,
+            &[0x08, 0x01].repeat(MAX_BINARY_DEPTH + 2),
+            &[0x00],
+        ]
+        .concat();
+        let err = from_binary::<serde_json::Value>(&deep).unwrap_err();
+        assert!(err.contains("nested too deeply"), "{err}");
+    }
+}
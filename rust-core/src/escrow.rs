@@ -0,0 +1,278 @@
+use crate::hashing::HashAlgorithm;
+use crate::session::SigningSession;
+use crate::{Lock, Note, Output, PublicKey, Transaction};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Escrow (2-of-3 buyer / seller / arbiter)
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EscrowRole {
+    Buyer,
+    Seller,
+    Arbiter,
+}
+
+impl EscrowRole {
+    pub fn parse(role: &str) -> Result<Self, String> {
+        match role {
+            "buyer" => Ok(EscrowRole::Buyer),
+            "seller" => Ok(EscrowRole::Seller),
+            "arbiter" => Ok(EscrowRole::Arbiter),
+            other => Err(format!("Unknown escrow role: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SettlementKind {
+    Release,
+    Refund,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case", deny_unknown_fields)]
+pub enum EscrowState {
+    AwaitingFunding,
+    Funded,
+    Disputed,
+    Settling { kind: SettlementKind },
+    Released,
+    Refunded,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Dispute {
+    pub raised_by: EscrowRole,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Escrow {
+    pub buyer: PublicKey,
+    pub seller: PublicKey,
+    pub arbiter: PublicKey,
+    pub amount: u64,
+    pub state: EscrowState,
+    #[serde(default)]
+    pub dispute: Option<Dispute>,
+    #[serde(default)]
+    pub funding_notes: Vec<Note>,
+    #[serde(default)]
+    pub settlement: Option<SigningSession>,
+}
+
+impl Escrow {
+    pub fn new(
+        buyer: PublicKey,
+        seller: PublicKey,
+        arbiter: PublicKey,
+        amount: u64,
+    ) -> Result<Self, String> {
+        if amount == 0 {
+            return Err("Escrow amount must be positive".into());
+        }
+        let escrow = Self {
+            buyer,
+            seller,
+            arbiter,
+            amount,
+            state: EscrowState::AwaitingFunding,
+            dispute: None,
+            funding_notes: Vec::new(),
+            settlement: None,
+        };
+        escrow.lock().validate()?;
+        Ok(escrow)
+    }
+
+    pub fn lock(&self) -> Lock {
+        Lock::multisig(
+            2,
+            vec![self.buyer.clone(), self.seller.clone(), self.arbiter.clone()],
+        )
+    }
+
+    pub fn funding_address(&self) -> String {
+        self.lock().fingerprint()
+    }
+
+    pub fn funded_value(&self) -> u64 {
+        self.funding_notes.iter().map(|n| n.value).sum()
+    }
+
+    pub fn pubkey(&self, role: EscrowRole) -> &PublicKey {
+        match role {
+            EscrowRole::Buyer => &self.buyer,
+            EscrowRole::Seller => &self.seller,
+            EscrowRole::Arbiter => &self.arbiter,
+        }
+    }
+
+    pub fn record_funding(&mut self, note: Note) -> Result<(), String> {
+        if self.state != EscrowState::AwaitingFunding {
+            return Err("Escrow is not awaiting funding".into());
+        }
+        if note.lock != self.lock() {
+            return Err("Note is not locked to the escrow address".into());
+        }
+        if self.funding_notes.iter().any(|n| n.name == note.name) {
+            return Err("Funding note already recorded".into());
+        }
+        self.funding_notes.push(note);
+        if self.funded_value() >= self.amount {
+            self.state = EscrowState::Funded;
+        }
+        Ok(())
+    }
+
+    pub fn raise_dispute(&mut self, raised_by: EscrowRole, reason: String) -> Result<(), String> {
+        match self.state {
+            EscrowState::Funded | EscrowState::Settling { .. } => {}
+            _ => return Err("Only a funded, unsettled escrow can be disputed".into()),
+        }
+        if self.dispute.is_some() {
+            return Err("Escrow is already disputed".into());
+        }
+        if raised_by == EscrowRole::Arbiter {
+            return Err("The arbiter cannot raise a dispute".into());
+        }
+        // Any draft prepared before the dispute is void; the arbiter decides.
+        self.settlement = None;
+        self.dispute = Some(Dispute { raised_by, reason });
+        self.state = EscrowState::Disputed;
+        Ok(())
+    }
+
+    // Release pays the seller, refund returns funds to the buyer. Without a
+    // dispute, buyer and seller co-sign; with one, the arbiter joins whichever
+    // side they rule for.
+    pub fn build_settlement(&mut self, kind: SettlementKind) -> Result<&SigningSession, String> {
+        match self.state {
+            EscrowState::Funded | EscrowState::Disputed | EscrowState::Settling { .. } => {}
+            _ => return Err("Escrow is not funded".into()),
+        }
+
+        let payee = match kind {
+            SettlementKind::Release => &self.seller,
+            SettlementKind::Refund => &self.buyer,
+        };
+        let output = Output {
+            recipient: payee.0.clone(),
+            value: self.funded_value(),
+            lock: Lock::single(payee),
+        };
+
+        let tx = Transaction::build(
+            self.funding_notes.clone(),
+            vec![output],
+            HashAlgorithm::default(),
+        )?;
+        self.state = EscrowState::Settling { kind };
+        Ok(self.settlement.insert(SigningSession::new(tx)))
+    }
+
+    // Called once the settlement draft has collected its signatures.
+    pub fn complete_settlement(&mut self) -> Result<(), String> {
+        let kind = match self.state {
+            EscrowState::Settling { kind } => kind,
+            _ => return Err("No settlement in progress".into()),
+        };
+        let session = self.settlement.as_ref().ok_or("No settlement draft")?;
+        session.transaction.validate_signatures()?;
+
+        self.state = match kind {
+            SettlementKind::Release => EscrowState::Released,
+            SettlementKind::Refund => EscrowState::Refunded,
+        };
+        Ok(())
+    }
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+fn parse_escrow(escrow_json: &str) -> Result<Escrow, String> {
+    serde_json::from_str(escrow_json).map_err(|e| e.to_string())
+}
+
+fn to_json(escrow: &Escrow) -> Result<String, String> {
+    serde_json::to_string(escrow).map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+pub fn escrow_create(
+    buyer: &str,
+    seller: &str,
+    arbiter: &str,
+    amount: u64,
+) -> Result<String, String> {
+    let escrow = Escrow::new(
+        PublicKey(buyer.to_string()),
+        PublicKey(seller.to_string()),
+        PublicKey(arbiter.to_string()),
+        amount,
+    )?;
+    to_json(&escrow)
+}
+
+#[wasm_bindgen]
+pub fn escrow_funding_address(escrow_json: &str) -> Result<String, String> {
+    Ok(parse_escrow(escrow_json)?.funding_address())
+}
+
+#[wasm_bindgen]
+pub fn escrow_lock(escrow_json: &str) -> Result<String, String> {
+    serde_json::to_string(&parse_escrow(escrow_json)?.lock()).map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+pub fn escrow_record_funding(escrow_json: &str, note_json: &str) -> Result<String, String> {
+    let mut escrow = parse_escrow(escrow_json)?;
+    let note: Note = serde_json::from_str(note_json).map_err(|e| e.to_string())?;
+    escrow.record_funding(note)?;
+    to_json(&escrow)
+}
+
+#[wasm_bindgen]
+pub fn escrow_raise_dispute(
+    escrow_json: &str,
+    raised_by: &str,
+    reason: &str,
+) -> Result<String, String> {
+    let mut escrow = parse_escrow(escrow_json)?;
+    escrow.raise_dispute(EscrowRole::parse(raised_by)?, reason.to_string())?;
+    to_json(&escrow)
+}
+
+#[wasm_bindgen]
+pub fn escrow_build_release(escrow_json: &str) -> Result<String, String> {
+    let mut escrow = parse_escrow(escrow_json)?;
+    escrow.build_settlement(SettlementKind::Release)?;
+    to_json(&escrow)
+}
+
+#[wasm_bindgen]
+pub fn escrow_build_refund(escrow_json: &str) -> Result<String, String> {
+    let mut escrow = parse_escrow(escrow_json)?;
+    escrow.build_settlement(SettlementKind::Refund)?;
+    to_json(&escrow)
+}
+
+// `session_json` is the settlement session after signatures were collected.
+#[wasm_bindgen]
+pub fn escrow_complete(escrow_json: &str, session_json: &str) -> Result<String, String> {
+    let mut escrow = parse_escrow(escrow_json)?;
+    let signed = SigningSession::from_json(session_json)?;
+    let draft = escrow.settlement.as_mut().ok_or("No settlement draft")?;
+    draft.merge(&signed)?;
+    escrow.complete_settlement()?;
+    to_json(&escrow)
+}
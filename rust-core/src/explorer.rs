@@ -0,0 +1,210 @@
+use crate::network::Network;
+use crate::outputs::output_hash;
+use crate::{NoteName, PublicKey, Transaction};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Explorer Export
+// ============================================================================
+
+// Explorers and indexers differ in what they ingest and where they show a
+// transaction, so each one is described by a profile rather than built in:
+// the payload format it takes and deep-link templates with `{txid}`,
+// `{address}` and `{network}` placeholders. Exports are made from the
+// finalized transaction, so the txid in the link is the one the node saw.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExplorerFormat {
+    // The transaction exactly as `finalize_transaction` returns it.
+    Canonical,
+    // A flat record: txid, inputs and outputs with their addresses, totals
+    // and the keys that signed.
+    Indexer,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExplorerProfile {
+    pub name: String,
+    pub format: ExplorerFormat,
+    pub tx_url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address_url: Option<String>,
+    // Networks the explorer serves; empty means all.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub networks: Vec<Network>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IndexedInput {
+    pub note: NoteName,
+    pub value: u64,
+    pub address: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IndexedOutput {
+    pub index: usize,
+    pub hash: String,
+    pub recipient: String,
+    pub value: u64,
+    pub address: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IndexedTransaction {
+    pub txid: String,
+    pub network: Network,
+    pub inputs: Vec<IndexedInput>,
+    pub outputs: Vec<IndexedOutput>,
+    pub total: u64,
+    pub signers: Vec<PublicKey>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExplorerExport {
+    pub explorer: String,
+    pub txid: String,
+    pub network: Network,
+    pub payload: serde_json::Value,
+    pub link: String,
+    // Output address -> its page, when the explorer has address pages.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub address_links: Vec<(String, String)>,
+}
+
+fn fill(template: &str, network: Network, txid: &str, address: &str) -> String {
+    template
+        .replace("{network}", network.name())
+        .replace("{txid}", txid)
+        .replace("{address}", address)
+}
+
+impl ExplorerProfile {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Explorer name must not be empty".into());
+        }
+        if !self.tx_url.starts_with("https://") || !self.tx_url.contains("{txid}") {
+            return Err("Transaction link must be an https:// URL containing {txid}".into());
+        }
+        if let Some(url) = &self.address_url {
+            if !url.starts_with("https://") || !url.contains("{address}") {
+                return Err("Address link must be an https:// URL containing {address}".into());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn serves(&self, network: Network) -> bool {
+        self.networks.is_empty() || self.networks.contains(&network)
+    }
+
+    pub fn tx_link(&self, txid: &str, network: Network) -> Result<String, String> {
+        self.validate()?;
+        if !self.serves(network) {
+            return Err(format!("{} does not serve {}", self.name, network.name()));
+        }
+        if txid.is_empty() || !txid.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err("Transaction id must be hex".into());
+        }
+        Ok(fill(&self.tx_url, network, txid, ""))
+    }
+
+    pub fn address_link(&self, address: &str, network: Network) -> Option<String> {
+        let url = self.address_url.as_ref()?;
+        Some(fill(url, network, "", address))
+    }
+
+    pub fn export(&self, tx: &Transaction) -> Result<ExplorerExport, String> {
+        let tx = tx.finalize()?;
+        let txid = tx.txid()?;
+        let network = tx.network;
+        let link = self.tx_link(&txid, network)?;
+        let indexed = index_transaction(&tx, &txid);
+        let payload = match self.format {
+            ExplorerFormat::Canonical => serde_json::to_value(&tx),
+            ExplorerFormat::Indexer => serde_json::to_value(&indexed),
+        }
+        .map_err(|e| e.to_string())?;
+        let address_links = indexed
+            .outputs
+            .iter()
+            .filter_map(|o| {
+                self.address_link(&o.address, network)
+                    .map(|url| (o.address.clone(), url))
+            })
+            .collect();
+        Ok(ExplorerExport {
+            explorer: self.name.clone(),
+            txid,
+            network,
+            payload,
+            link,
+            address_links,
+        })
+    }
+}
+
+pub fn index_transaction(tx: &Transaction, txid: &str) -> IndexedTransaction {
+    let network = tx.network;
+    let mut signers: Vec<PublicKey> = Vec::new();
+    for (pk, _) in tx.spends.iter().flat_map(|s| &s.seeds.signatures) {
+        if !signers.contains(pk) {
+            signers.push(pk.clone());
+        }
+    }
+    IndexedTransaction {
+        txid: txid.to_string(),
+        network,
+        inputs: tx
+            .spends
+            .iter()
+            .map(|s| IndexedInput {
+                note: s.note.name.clone(),
+                value: s.note.value,
+                address: s.note.lock.address(network),
+            })
+            .collect(),
+        outputs: tx
+            .outputs
+            .iter()
+            .enumerate()
+            .map(|(index, o)| IndexedOutput {
+                index,
+                hash: output_hash(o),
+                recipient: o.recipient.clone(),
+                value: o.value,
+                address: o.lock.address(network),
+            })
+            .collect(),
+        total: tx.total_output(),
+        signers,
+    }
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+// Finalizes `tx_json` and formats it for the explorer in `profile_json`.
+// Returns `{explorer, txid, network, payload, link, address_links}`.
+#[wasm_bindgen]
+pub fn export_to_explorer(tx_json: &str, profile_json: &str) -> Result<String, String> {
+    let tx: Transaction = serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+    let profile: ExplorerProfile = serde_json::from_str(profile_json).map_err(|e| e.to_string())?;
+    serde_json::to_string(&profile.export(&tx)?).map_err(|e| e.to_string())
+}
+
+// A "view on explorer" link for a txid already broadcast.
+#[wasm_bindgen]
+pub fn get_explorer_link(txid: &str, network: &str, profile_json: &str) -> Result<String, String> {
+    let profile: ExplorerProfile = serde_json::from_str(profile_json).map_err(|e| e.to_string())?;
+    profile.tx_link(txid, Network::parse(network)?)
+}
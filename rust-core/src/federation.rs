@@ -0,0 +1,513 @@
+use crate::hashing::{Digester, HashAlgorithm};
+use crate::session::{SignatureConflict, SigningSession};
+use crate::storage::{validate_segment, Storage, Tenant};
+use crate::verify::SignatureVerifier;
+use crate::{PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// ============================================================================
+// Coordinator Federation
+// ============================================================================
+
+// Two coordinators (say, run by different departments) can serve the same
+// wallet so neither is a single point of failure. Each names the other as a
+// peer, with the key the peer signs with and the wallets they share, and
+// they swap signed deltas: the sessions of those wallets that changed since
+// the last exchange. Received sessions are folded in with the usual
+// signature merge, so it does not matter who saw a signature first or in
+// what order deltas arrive; drafts new to the receiver go through its own
+// policies like any other. The crate does no I/O and holds no keys: the host
+// signs `SessionDelta::message_hash` with its coordinator key and carries
+// the result to the peer.
+
+const FEDERATION_DOMAIN: &[u8] = b"nockchain-multisig/federation/v1";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FederationPeer {
+    pub id: String,
+    // Key the peer coordinator signs its deltas with.
+    pub key: PublicKey,
+    // Wallet ids shared with the peer.
+    pub wallets: Vec<String>,
+    // Sequence of the last delta sent, and the highest one accepted.
+    #[serde(default)]
+    pub sent_sequence: u64,
+    #[serde(default)]
+    pub received_sequence: u64,
+    // Draft hash -> session state last sent to, and last received from, the
+    // peer. A session is sent again only when it differs from both.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sent: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub seen: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SessionDelta {
+    pub from: PublicKey,
+    pub to: PublicKey,
+    pub sequence: u64,
+    // Wallet id -> its changed sessions.
+    pub wallets: BTreeMap<String, Vec<SigningSession>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SignedSessionDelta {
+    pub delta: SessionDelta,
+    pub signature: Signature,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FederatedConflict {
+    pub wallet: String,
+    pub draft: String,
+    pub conflict: SignatureConflict,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RejectedSession {
+    pub wallet: String,
+    pub draft: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FederationReport {
+    pub peer: String,
+    pub sequence: u64,
+    // Signatures learned from sessions both sides already had.
+    pub merged: usize,
+    // Draft hashes of sessions new to this coordinator.
+    pub added: Vec<String>,
+    pub conflicts: Vec<FederatedConflict>,
+    // Sessions the peer has that this coordinator refused, e.g. by policy.
+    pub rejected: Vec<RejectedSession>,
+}
+
+impl SessionDelta {
+    // What the sending coordinator signs.
+    pub fn message_hash(&self) -> String {
+        let bytes = serde_json::to_vec(self).expect("Serialization failed");
+        let mut digester =
+            Digester::new(HashAlgorithm::Sha256).expect("sha256 is always available");
+        digester.update(FEDERATION_DOMAIN);
+        digester.update(&bytes);
+        digester.finalize_hex()
+    }
+}
+
+// The part of a session that merging converges: signatures and preimages on
+// each spend, and comments. Audit logs stay local to each coordinator.
+pub fn session_state(session: &SigningSession) -> String {
+    let mut digester = Digester::new(HashAlgorithm::Sha256).expect("sha256 is always available");
+    for spend in &session.transaction.spends {
+        let mut signatures: Vec<String> = spend
+            .seeds
+            .signatures
+            .iter()
+            .map(|(pk, sig)| format!("{}={}", pk.0, sig.0))
+            .collect();
+        signatures.sort();
+        let mut preimages = spend.seeds.preimages.clone();
+        preimages.sort();
+        for item in signatures.iter().chain(&preimages) {
+            digester.update(item.as_bytes());
+            digester.update(b"\n");
+        }
+        digester.update(b"--\n");
+    }
+    let mut comments: Vec<&str> = session.comments.iter().map(|c| c.id.as_str()).collect();
+    comments.sort();
+    for id in comments {
+        digester.update(id.as_bytes());
+        digester.update(b"\n");
+    }
+    digester.finalize_hex()
+}
+
+fn peer_key(id: &str) -> Result<String, String> {
+    validate_segment("peer", id)?;
+    Ok(format!("federation/{}", id))
+}
+
+impl<S: Storage> Tenant<'_, S> {
+    // The key this coordinator signs its deltas with.
+    pub fn federation_key(&self) -> Result<Option<PublicKey>, String> {
+        self.load("federation_key")
+    }
+
+    pub fn set_federation_key(&mut self, key: &PublicKey) -> Result<(), String> {
+        self.store("federation_key", key)
+    }
+
+    // Adding a peer again replaces its wallets and starts the exchange over.
+    pub fn add_federation_peer(
+        &mut self,
+        id: &str,
+        key: PublicKey,
+        wallets: Vec<String>,
+    ) -> Result<FederationPeer, String> {
+        let own = self
+            .federation_key()?
+            .ok_or("Set this coordinator's federation key first")?;
+        if key == own {
+            return Err("A peer cannot use this coordinator's own key".into());
+        }
+        if wallets.is_empty() {
+            return Err("A peer must share at least one wallet".into());
+        }
+        for wallet in &wallets {
+            self.wallet(wallet)?;
+        }
+        if let Some(other) = self
+            .federation_peers()?
+            .into_iter()
+            .find(|p| p.id != id && p.key == key)
+        {
+            return Err(format!("Peer {} already uses that key", other.id));
+        }
+        let peer = FederationPeer {
+            id: id.to_string(),
+            key,
+            wallets,
+            sent_sequence: 0,
+            received_sequence: 0,
+            sent: BTreeMap::new(),
+            seen: BTreeMap::new(),
+        };
+        self.put_federation_peer(&peer)?;
+        Ok(peer)
+    }
+
+    pub fn federation_peer(&self, id: &str) -> Result<FederationPeer, String> {
+        self.load(&peer_key(id)?)?
+            .ok_or_else(|| format!("Unknown federation peer {}", id))
+    }
+
+    pub fn federation_peers(&self) -> Result<Vec<FederationPeer>, String> {
+        self.list("federation")?
+            .iter()
+            .map(|id| self.federation_peer(id))
+            .collect()
+    }
+
+    pub fn put_federation_peer(&mut self, peer: &FederationPeer) -> Result<(), String> {
+        self.store(&peer_key(&peer.id)?, peer)
+    }
+
+    pub fn remove_federation_peer(&mut self, id: &str) -> Result<bool, String> {
+        self.remove(&peer_key(id)?)
+    }
+
+    // The sessions the peer has not seen in their current state, or `None`
+    // when it is up to date. The delta counts as sent once built; since
+    // merging is idempotent, a lost delta is recovered by `resync_peer`.
+    pub fn federation_delta(&mut self, peer_id: &str) -> Result<Option<SessionDelta>, String> {
+        let from = self
+            .federation_key()?
+            .ok_or("Set this coordinator's federation key first")?;
+        let mut peer = self.federation_peer(peer_id)?;
+        let existing = self.wallet_ids()?;
+
+        let mut wallets = BTreeMap::new();
+        let mut drafts = Vec::new();
+        for wallet_id in peer.wallets.iter().filter(|w| existing.contains(w)) {
+            let mut changed = Vec::new();
+            for session in self.wallet(wallet_id)?.sessions {
+                let draft = session.draft_hash();
+                let state = session_state(&session);
+                let known = |m: &BTreeMap<String, String>| m.get(&draft) == Some(&state);
+                if !known(&peer.sent) && !known(&peer.seen) {
+                    peer.sent.insert(draft.clone(), state);
+                    changed.push(session);
+                }
+                drafts.push(draft);
+            }
+            if !changed.is_empty() {
+                wallets.insert(wallet_id.clone(), changed);
+            }
+        }
+        peer.sent.retain(|d, _| drafts.contains(d));
+        peer.seen.retain(|d, _| drafts.contains(d));
+        if wallets.is_empty() {
+            self.put_federation_peer(&peer)?;
+            return Ok(None);
+        }
+
+        peer.sent_sequence += 1;
+        let delta = SessionDelta {
+            from,
+            to: peer.key.clone(),
+            sequence: peer.sent_sequence,
+            wallets,
+        };
+        self.put_federation_peer(&peer)?;
+        Ok(Some(delta))
+    }
+
+    // Forgets what the peer was sent, so the next delta carries every shared
+    // session again.
+    pub fn resync_peer(&mut self, peer_id: &str) -> Result<(), String> {
+        let mut peer = self.federation_peer(peer_id)?;
+        peer.sent.clear();
+        peer.seen.clear();
+        self.put_federation_peer(&peer)
+    }
+
+    // Checks the delta is from a peer, for this coordinator, newer than the
+    // last one, signed, and only touches wallets shared with that peer, then
+    // folds its sessions in. Signatures inside sessions are checked with
+    // `verifier` where the two sides disagree.
+    pub fn apply_federation_delta(
+        &mut self,
+        signed: &SignedSessionDelta,
+        verifier: &dyn SignatureVerifier,
+        now: u64,
+    ) -> Result<FederationReport, String> {
+        let delta = &signed.delta;
+        let own = self
+            .federation_key()?
+            .ok_or("Set this coordinator's federation key first")?;
+        if delta.to != own {
+            return Err("Delta is addressed to another coordinator".into());
+        }
+        let mut peer = self
+            .federation_peers()?
+            .into_iter()
+            .find(|p| p.key == delta.from)
+            .ok_or_else(|| format!("{} is not a federation peer", delta.from.0))?;
+        if !verifier.verify(&delta.from, &delta.message_hash(), &signed.signature) {
+            return Err(format!("Invalid signature on delta from {}", peer.id));
+        }
+        if delta.sequence <= peer.received_sequence {
+            return Err(format!(
+                "Delta {} from {} is a replay (last accepted {})",
+                delta.sequence, peer.id, peer.received_sequence
+            ));
+        }
+        if let Some(wallet) = delta.wallets.keys().find(|w| !peer.wallets.contains(w)) {
+            return Err(format!("Wallet {} is not shared with {}", wallet, peer.id));
+        }
+
+        let mut report = FederationReport {
+            peer: peer.id.clone(),
+            sequence: delta.sequence,
+            ..FederationReport::default()
+        };
+        for (wallet_id, sessions) in &delta.wallets {
+            for theirs in sessions {
+                let draft = theirs.draft_hash();
+                peer.seen.insert(draft.clone(), session_state(theirs));
+                let mut wallet = self.wallet(wallet_id)?;
+                match wallet.sessions.iter_mut().find(|s| s.draft_hash() == draft) {
+                    Some(ours) => {
+                        let merged = ours.merge_verified(theirs, verifier)?;
+                        report.merged += merged.added + merged.replaced;
+                        report
+                            .conflicts
+                            .extend(merged.conflicts.into_iter().map(|conflict| {
+                                FederatedConflict {
+                                    wallet: wallet_id.clone(),
+                                    draft: draft.clone(),
+                                    conflict,
+                                }
+                            }));
+                        self.put_wallet(&wallet)?;
+                    }
+                    None => match self.add_session(wallet_id, theirs.clone(), now) {
+                        Ok(_) => report.added.push(draft),
+                        Err(error) => report.rejected.push(RejectedSession {
+                            wallet: wallet_id.clone(),
+                            draft,
+                            error,
+                        }),
+                    },
+                }
+            }
+        }
+        peer.received_sequence = delta.sequence;
+        self.put_federation_peer(&peer)?;
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{Coordinator, MemoryStorage};
+    use crate::testing::{draft, HashVerifier, MULTISIG_LOCK};
+    use crate::wallets::{Wallet, WalletDescriptor};
+    use crate::Lock;
+
+    fn key(name: &str) -> PublicKey {
+        PublicKey(name.into())
+    }
+
+    // A coordinator signing as `own`, sharing the multisig wallet with `peer`.
+    fn coordinator(own: &str, peer: &str) -> (Coordinator<MemoryStorage>, String) {
+        let mut coordinator = Coordinator::with_plaintext_storage(MemoryStorage::default());
+        let wallet = Wallet::new(WalletDescriptor {
+            label: "treasury".into(),
+            lock: serde_json::from_str::<Lock>(MULTISIG_LOCK).unwrap(),
+        })
+        .unwrap();
+        let mut tenant = coordinator.tenant("acme").unwrap();
+        tenant.put_wallet(&wallet).unwrap();
+        tenant.set_federation_key(&key(own)).unwrap();
+        tenant
+            .add_federation_peer(peer, key(peer), vec![wallet.id.clone()])
+            .unwrap();
+        (coordinator, wallet.id)
+    }
+
+    fn signed_session(signers: &[&str]) -> SigningSession {
+        let mut session = SigningSession::new(draft(MULTISIG_LOCK));
+        for signer in signers {
+            let pubkey = key(signer);
+            let hash = session.transaction.spends[0].seeds.message_hash.clone();
+            let signature = HashVerifier::sign(&pubkey, &hash);
+            session.add_signature(0, pubkey, signature, None).unwrap();
+        }
+        session
+    }
+
+    fn sign(delta: SessionDelta) -> SignedSessionDelta {
+        let signature = HashVerifier::sign(&delta.from, &delta.message_hash());
+        SignedSessionDelta { delta, signature }
+    }
+
+    // "alice" holds a session signed by "a"; returns it and her signed delta
+    // for "bob".
+    fn alice_delta() -> (Coordinator<MemoryStorage>, String, SignedSessionDelta) {
+        let (mut alice, wallet) = coordinator("alice", "bob");
+        let mut tenant = alice.tenant("acme").unwrap();
+        tenant
+            .add_session(&wallet, signed_session(&["a"]), 1)
+            .unwrap();
+        let delta = tenant.federation_delta("bob").unwrap().unwrap();
+        (alice, wallet, sign(delta))
+    }
+
+    #[test]
+    fn peers_exchange_sessions_and_go_quiet() {
+        let (mut alice, wallet, delta) = alice_delta();
+        let (mut bob, _) = coordinator("bob", "alice");
+        let mut bob_tenant = bob.tenant("acme").unwrap();
+
+        let report = bob_tenant
+            .apply_federation_delta(&delta, &HashVerifier, 2)
+            .unwrap();
+        assert_eq!(report.added.len(), 1);
+        // Bob holds exactly what Alice sent, so he has nothing to send back.
+        assert!(bob_tenant.federation_delta("alice").unwrap().is_none());
+
+        // Bob's cosigner signs; the delta carries it back to Alice.
+        let mut session = bob_tenant.wallet(&wallet).unwrap().sessions[0].clone();
+        let b = key("b");
+        let hash = session.transaction.spends[0].seeds.message_hash.clone();
+        session
+            .add_signature(0, b.clone(), HashVerifier::sign(&b, &hash), None)
+            .unwrap();
+        bob_tenant.add_session(&wallet, session, 3).unwrap();
+        let back = sign(bob_tenant.federation_delta("alice").unwrap().unwrap());
+
+        let mut alice_tenant = alice.tenant("acme").unwrap();
+        let report = alice_tenant
+            .apply_federation_delta(&back, &HashVerifier, 4)
+            .unwrap();
+        assert_eq!(report.merged, 1);
+        assert!(alice_tenant.federation_delta("bob").unwrap().is_none());
+    }
+
+    #[test]
+    fn rejects_forged_and_replayed_deltas() {
+        let (_, _, delta) = alice_delta();
+        let (mut bob, _) = coordinator("bob", "alice");
+        let mut tenant = bob.tenant("acme").unwrap();
+
+        let mut forged = delta.clone();
+        forged.signature = Signature("00".repeat(32));
+        let err = tenant
+            .apply_federation_delta(&forged, &HashVerifier, 2)
+            .unwrap_err();
+        assert!(err.contains("Invalid signature"), "{err}");
+
+        tenant
+            .apply_federation_delta(&delta, &HashVerifier, 2)
+            .unwrap();
+        let err = tenant
+            .apply_federation_delta(&delta, &HashVerifier, 3)
+            .unwrap_err();
+        assert!(err.contains("replay"), "{err}");
+    }
+
+    #[test]
+    fn rejects_deltas_from_strangers_or_for_others() {
+        let (_, _, delta) = alice_delta();
+        let (mut carol, _) = coordinator("carol", "dave");
+        let err = carol
+            .tenant("acme")
+            .unwrap()
+            .apply_federation_delta(&delta, &HashVerifier, 2)
+            .unwrap_err();
+        assert!(err.contains("another coordinator"), "{err}");
+
+        let mut stranger = delta.delta.clone();
+        stranger.from = key("mallory");
+        let (mut bob, _) = coordinator("bob", "alice");
+        let err = bob
+            .tenant("acme")
+            .unwrap()
+            .apply_federation_delta(&sign(stranger), &HashVerifier, 2)
+            .unwrap_err();
+        assert!(err.contains("not a federation peer"), "{err}");
+    }
+
+    #[test]
+    fn rejects_unshared_wallets() {
+        let (_, wallet, delta) = alice_delta();
+        let mut inner = delta.delta.clone();
+        let sessions = inner.wallets.remove(&wallet).unwrap();
+        inner.wallets.insert("elsewhere".into(), sessions);
+        let (mut bob, _) = coordinator("bob", "alice");
+        let mut tenant = bob.tenant("acme").unwrap();
+        let err = tenant
+            .apply_federation_delta(&sign(inner), &HashVerifier, 2)
+            .unwrap_err();
+        assert!(err.contains("not shared"), "{err}");
+        assert_eq!(
+            tenant.federation_peer("alice").unwrap().received_sequence,
+            0
+        );
+    }
+
+    #[test]
+    fn add_federation_peer_checks_its_key_and_wallets() {
+        let (mut coordinator, wallet) = coordinator("alice", "bob");
+        let mut tenant = coordinator.tenant("acme").unwrap();
+        let shared = vec![wallet.clone()];
+        assert!(tenant
+            .add_federation_peer("self", key("alice"), shared.clone())
+            .is_err());
+        assert!(tenant
+            .add_federation_peer("none", key("x"), vec![])
+            .is_err());
+        assert!(tenant
+            .add_federation_peer("ghost", key("x"), vec!["missing".into()])
+            .is_err());
+        let err = tenant
+            .add_federation_peer("twin", key("bob"), shared)
+            .unwrap_err();
+        assert!(err.contains("already uses that key"), "{err}");
+        assert!(tenant
+            .add_federation_peer("../bob", key("y"), vec![wallet])
+            .is_err());
+    }
+}
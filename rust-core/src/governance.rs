@@ -0,0 +1,257 @@
+use crate::hashing::{digest_hex, HashAlgorithm};
+use crate::verify::{SignatureVerifier, UnverifiedSignatures};
+use crate::{PkhCondition, PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Governance Votes
+// ============================================================================
+
+// Votes are signatures by members of a multisig set over a per-choice message
+// derived from the proposal hash. Approve and reject sign different messages,
+// so a vote cannot be replayed as its opposite.
+
+const PROPOSAL_DOMAIN: &[u8] = b"nockchain-multisig/proposal/v1";
+const VOTE_DOMAIN: &[u8] = b"nockchain-multisig/vote/v1";
+
+fn sha256_hex(parts: &[&[u8]]) -> String {
+    digest_hex(HashAlgorithm::Sha256, &parts.concat()).expect("sha256 is always available")
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Proposal {
+    pub title: String,
+    pub payload: serde_json::Value,
+    pub electorate: PkhCondition,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at_height: Option<u64>,
+}
+
+impl Proposal {
+    pub fn hash(&self) -> String {
+        let bytes = serde_json::to_vec(self).expect("Serialization failed");
+        sha256_hex(&[PROPOSAL_DOMAIN, &bytes])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VoteChoice {
+    Approve,
+    Reject,
+}
+
+impl VoteChoice {
+    pub fn parse(choice: &str) -> Result<Self, String> {
+        match choice {
+            "approve" => Ok(VoteChoice::Approve),
+            "reject" => Ok(VoteChoice::Reject),
+            other => Err(format!("Unknown vote choice: {}", other)),
+        }
+    }
+
+    fn tag(self) -> &'static [u8] {
+        match self {
+            VoteChoice::Approve => b"approve",
+            VoteChoice::Reject => b"reject",
+        }
+    }
+}
+
+pub fn vote_message(proposal_hash: &str, choice: VoteChoice) -> String {
+    sha256_hex(&[VOTE_DOMAIN, proposal_hash.as_bytes(), b":", choice.tag()])
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Vote {
+    pub voter: PublicKey,
+    pub choice: VoteChoice,
+    pub signature: Signature,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Pending,
+    Passed,
+    Rejected,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Tally {
+    pub approvals: usize,
+    pub rejections: usize,
+    pub threshold: usize,
+    pub electorate: usize,
+    pub outcome: Outcome,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Ballot {
+    pub proposal: Proposal,
+    pub proposal_hash: String,
+    #[serde(default)]
+    pub votes: Vec<Vote>,
+}
+
+impl Ballot {
+    pub fn new(proposal: Proposal) -> Result<Self, String> {
+        proposal.electorate.validate()?;
+        Ok(Self {
+            proposal_hash: proposal.hash(),
+            proposal,
+            votes: Vec::new(),
+        })
+    }
+
+    pub fn cast(&mut self, vote: Vote, verifier: &dyn SignatureVerifier) -> Result<(), String> {
+        if !self.proposal.electorate.pubkeys.contains(&vote.voter) {
+            return Err("Voter is not part of the electorate".into());
+        }
+        if self.votes.iter().any(|v| v.voter == vote.voter) {
+            return Err("Voter has already voted".into());
+        }
+        let message = vote_message(&self.proposal_hash, vote.choice);
+        if !verifier.verify(&vote.voter, &message, &vote.signature) {
+            return Err("Vote signature is invalid".into());
+        }
+        self.votes.push(vote);
+        Ok(())
+    }
+
+    // Passed once approvals reach the threshold; rejected as soon as enough
+    // members voted no that the threshold is out of reach.
+    pub fn tally(&self) -> Tally {
+        let approvals = self
+            .votes
+            .iter()
+            .filter(|v| v.choice == VoteChoice::Approve)
+            .count();
+        let rejections = self.votes.len() - approvals;
+        let threshold = self.proposal.electorate.threshold;
+        let electorate = self.proposal.electorate.pubkeys.len();
+
+        let outcome = if approvals >= threshold {
+            Outcome::Passed
+        } else if electorate - rejections < threshold {
+            Outcome::Rejected
+        } else {
+            Outcome::Pending
+        };
+
+        Tally {
+            approvals,
+            rejections,
+            threshold,
+            electorate,
+            outcome,
+        }
+    }
+
+    pub fn attestation(&self) -> Result<Attestation, String> {
+        let tally = self.tally();
+        if tally.outcome == Outcome::Pending {
+            return Err("Vote is still pending".into());
+        }
+        Ok(Attestation {
+            proposal: self.proposal.clone(),
+            proposal_hash: self.proposal_hash.clone(),
+            votes: self.votes.clone(),
+            outcome: tally.outcome,
+        })
+    }
+}
+
+// Self-contained proof of a decision: anyone holding it can recompute the
+// proposal hash, check every vote against the electorate, and re-tally.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Attestation {
+    pub proposal: Proposal,
+    pub proposal_hash: String,
+    pub votes: Vec<Vote>,
+    pub outcome: Outcome,
+}
+
+impl Attestation {
+    pub fn verify(&self, verifier: &dyn SignatureVerifier) -> Result<Tally, String> {
+        if self.proposal.hash() != self.proposal_hash {
+            return Err("Proposal hash does not match proposal".into());
+        }
+        let mut ballot = Ballot::new(self.proposal.clone())?;
+        for vote in &self.votes {
+            ballot.cast(vote.clone(), verifier)?;
+        }
+        let tally = ballot.tally();
+        if tally.outcome != self.outcome {
+            return Err("Recorded outcome does not match the votes".into());
+        }
+        Ok(tally)
+    }
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, String> {
+    serde_json::to_string(value).map_err(|e| e.to_string())
+}
+
+fn parse_ballot(ballot_json: &str) -> Result<Ballot, String> {
+    serde_json::from_str(ballot_json).map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+pub fn governance_create_ballot(proposal_json: &str) -> Result<String, String> {
+    let proposal: Proposal = serde_json::from_str(proposal_json).map_err(|e| e.to_string())?;
+    to_json(&Ballot::new(proposal)?)
+}
+
+#[wasm_bindgen]
+pub fn governance_vote_message(ballot_json: &str, choice: &str) -> Result<String, String> {
+    let ballot = parse_ballot(ballot_json)?;
+    Ok(vote_message(
+        &ballot.proposal_hash,
+        VoteChoice::parse(choice)?,
+    ))
+}
+
+#[wasm_bindgen]
+pub fn governance_cast_vote(
+    ballot_json: &str,
+    voter: &str,
+    choice: &str,
+    signature: &str,
+) -> Result<String, String> {
+    let mut ballot = parse_ballot(ballot_json)?;
+    let vote = Vote {
+        voter: PublicKey(voter.to_string()),
+        choice: VoteChoice::parse(choice)?,
+        signature: Signature(signature.to_string()),
+    };
+    ballot.cast(vote, &UnverifiedSignatures)?;
+    to_json(&ballot)
+}
+
+#[wasm_bindgen]
+pub fn governance_tally(ballot_json: &str) -> Result<String, String> {
+    to_json(&parse_ballot(ballot_json)?.tally())
+}
+
+#[wasm_bindgen]
+pub fn governance_export_attestation(ballot_json: &str) -> Result<String, String> {
+    to_json(&parse_ballot(ballot_json)?.attestation()?)
+}
+
+#[wasm_bindgen]
+pub fn governance_verify_attestation(attestation_json: &str) -> Result<String, String> {
+    let attestation: Attestation =
+        serde_json::from_str(attestation_json).map_err(|e| e.to_string())?;
+    to_json(&attestation.verify(&UnverifiedSignatures)?)
+}
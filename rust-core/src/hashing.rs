@@ -0,0 +1,159 @@
+use crate::Transaction;
+use nockchain_multisig_core::network::Network;
+use nockchain_multisig_core::{canonical_preimage, SpendHasher};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+pub use nockchain_multisig_core::hashing::{digest_hex, Digester, HashAlgorithm};
+
+// ============================================================================
+// Hash Backends
+// ============================================================================
+
+// `sha2` picks its SHA-256 compression function at runtime: SHA-NI on x86,
+// the crypto extensions on aarch64, portable code everywhere else (including
+// wasm32, which has no SHA instructions). The `hash-soft` feature pins the
+// portable path so the two can be benchmarked against each other.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HashBackend {
+    Sha256Soft,
+    Sha256ShaNi,
+    Sha256ArmCrypto,
+}
+
+impl HashBackend {
+    pub fn name(self) -> &'static str {
+        match self {
+            HashBackend::Sha256Soft => "sha256-soft",
+            HashBackend::Sha256ShaNi => "sha256-sha-ni",
+            HashBackend::Sha256ArmCrypto => "sha256-arm-crypto",
+        }
+    }
+}
+
+#[cfg(feature = "hash-soft")]
+pub fn active_backend() -> HashBackend {
+    HashBackend::Sha256Soft
+}
+
+#[cfg(all(not(feature = "hash-soft"), any(target_arch = "x86", target_arch = "x86_64")))]
+pub fn active_backend() -> HashBackend {
+    let accelerated = std::arch::is_x86_feature_detected!("sha")
+        && std::arch::is_x86_feature_detected!("sse2")
+        && std::arch::is_x86_feature_detected!("ssse3")
+        && std::arch::is_x86_feature_detected!("sse4.1");
+    if accelerated {
+        HashBackend::Sha256ShaNi
+    } else {
+        HashBackend::Sha256Soft
+    }
+}
+
+#[cfg(all(not(feature = "hash-soft"), target_arch = "aarch64"))]
+pub fn active_backend() -> HashBackend {
+    if std::arch::is_aarch64_feature_detected!("sha2") {
+        HashBackend::Sha256ArmCrypto
+    } else {
+        HashBackend::Sha256Soft
+    }
+}
+
+#[cfg(all(
+    not(feature = "hash-soft"),
+    not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))
+))]
+pub fn active_backend() -> HashBackend {
+    HashBackend::Sha256Soft
+}
+
+#[wasm_bindgen]
+pub fn hash_backend() -> String {
+    active_backend().name().to_string()
+}
+
+#[wasm_bindgen]
+pub fn supported_hash_algorithms() -> String {
+    let names: Vec<&str> = HashAlgorithm::supported().iter().map(|a| a.name()).collect();
+    serde_json::to_string(&names).expect("Serialization failed")
+}
+
+// ============================================================================
+// Streaming Spend Hashes
+// ============================================================================
+
+// For hardware signers with little RAM: the host sends the canonical
+// preimage (`get_canonical_preimage`) in chunks and the device hashes as it
+// goes. The result equals the spend's `message_hash`.
+
+#[wasm_bindgen]
+pub struct StreamingHasher(Option<SpendHasher>);
+
+#[wasm_bindgen]
+pub fn hasher_new(
+    algorithm: &str,
+    network: &str,
+    spend_index: usize,
+) -> Result<StreamingHasher, String> {
+    let algorithm = HashAlgorithm::parse(algorithm)?;
+    let network = Network::parse(network)?;
+    Ok(StreamingHasher(Some(SpendHasher::new(algorithm, network, spend_index)?)))
+}
+
+#[wasm_bindgen]
+pub fn hasher_update(hasher: &mut StreamingHasher, bytes: &[u8]) -> Result<(), String> {
+    hasher
+        .0
+        .as_mut()
+        .ok_or("Hasher already finalized")?
+        .update(bytes);
+    Ok(())
+}
+
+#[wasm_bindgen]
+pub fn hasher_finalize(hasher: &mut StreamingHasher) -> Result<String, String> {
+    Ok(hasher
+        .0
+        .take()
+        .ok_or("Hasher already finalized")?
+        .finalize_hex())
+}
+
+#[wasm_bindgen]
+pub fn get_canonical_preimage(tx_json: &str) -> Result<Vec<u8>, String> {
+    let tx: Transaction = serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+    Ok(canonical_preimage(&tx.outputs, tx.lineage.as_ref()))
+}
+
+// ============================================================================
+// Message Authentication
+// ============================================================================
+
+const HMAC_BLOCK: usize = 64;
+
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    use sha2::{Digest, Sha256};
+
+    let mut block = [0u8; HMAC_BLOCK];
+    if key.len() > HMAC_BLOCK {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let ipad: Vec<u8> = block.iter().map(|b| b ^ 0x36).collect();
+    let opad: Vec<u8> = block.iter().map(|b| b ^ 0x5c).collect();
+
+    let inner = Sha256::new().chain_update(&ipad).chain_update(message).finalize();
+    Sha256::new()
+        .chain_update(&opad)
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
+
+// Comparison whose running time does not depend on where the inputs differ.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,207 @@
+use crate::hashing::{digest_hex, HashAlgorithm};
+use crate::verify::{PrecomputedVerifier, SignatureVerifier, VerificationItem};
+use crate::{PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Identity Attestations
+// ============================================================================
+
+// Binds a participant key to who holds it. The key signs the claim itself,
+// so nobody can pin an identity on a key without its holder. On top of that
+// the claim can point at a DID whose document lists the key, and an issuer
+// (e.g. the company's key) can countersign it. Fetching and trusting the
+// DID document is the host's job; here it is only checked to list the key.
+// Attestations travel with wallet backups and are verified again on import.
+
+const IDENTITY_DOMAIN: &[u8] = b"nockchain-multisig/identity/v1";
+
+// Key fields a DID verification method may carry the key in.
+const DID_KEY_FIELDS: [&str; 3] = ["publicKeyMultibase", "publicKeyHex", "publicKeyBase58"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IdentityClaim {
+    pub pubkey: PublicKey,
+    // A DID (`did:web:acme.com#cfo`) or a plain name.
+    pub subject: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    pub issued_at: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Countersignature {
+    pub issuer: PublicKey,
+    pub signature: Signature,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IdentityAttestation {
+    pub claim: IdentityClaim,
+    pub key_signature: Signature,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub did_document: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub countersignature: Option<Countersignature>,
+}
+
+// What a verified attestation establishes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IdentityCheck {
+    pub pubkey: PublicKey,
+    pub subject: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    pub did_bound: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<PublicKey>,
+}
+
+impl IdentityClaim {
+    // What both the key and any issuer sign.
+    pub fn message(&self) -> String {
+        let bytes = serde_json::to_vec(self).expect("Serialization failed");
+        digest_hex(HashAlgorithm::Sha256, &[IDENTITY_DOMAIN, &bytes].concat())
+            .expect("sha256 is always available")
+    }
+}
+
+impl IdentityAttestation {
+    pub fn verification_items(&self) -> Vec<VerificationItem> {
+        let message = self.claim.message();
+        let mut items = vec![VerificationItem {
+            pubkey: self.claim.pubkey.clone(),
+            message_hash: message.clone(),
+            signature: self.key_signature.clone(),
+        }];
+        if let Some(c) = &self.countersignature {
+            items.push(VerificationItem {
+                pubkey: c.issuer.clone(),
+                message_hash: message,
+                signature: c.signature.clone(),
+            });
+        }
+        items
+    }
+
+    pub fn verify(&self, verifier: &dyn SignatureVerifier) -> Result<IdentityCheck, String> {
+        let claim = &self.claim;
+        if claim.subject.trim().is_empty() {
+            return Err("Identity subject must not be empty".into());
+        }
+        for item in self.verification_items() {
+            if !verifier.verify(&item.pubkey, &item.message_hash, &item.signature) {
+                return Err(format!(
+                    "Invalid identity signature from {} for {}",
+                    item.pubkey.0, claim.pubkey.0
+                ));
+            }
+        }
+        if let Some(document) = &self.did_document {
+            check_did_document(document, &claim.subject, &claim.pubkey)?;
+        }
+        Ok(IdentityCheck {
+            pubkey: claim.pubkey.clone(),
+            subject: claim.subject.clone(),
+            role: claim.role.clone(),
+            did_bound: self.did_document.is_some(),
+            issuer: self.countersignature.as_ref().map(|c| c.issuer.clone()),
+        })
+    }
+}
+
+// The document must be the subject's and list the key under a verification
+// method (the one the subject's fragment names, if it has one).
+fn check_did_document(
+    document: &serde_json::Value,
+    subject: &str,
+    pubkey: &PublicKey,
+) -> Result<(), String> {
+    if !subject.starts_with("did:") {
+        return Err(format!("{} is not a DID", subject));
+    }
+    let did = subject.split('#').next().unwrap_or(subject);
+    if document.get("id").and_then(|v| v.as_str()) != Some(did) {
+        return Err(format!("DID document is not for {}", did));
+    }
+    let methods = document
+        .get("verificationMethod")
+        .and_then(|v| v.as_array())
+        .map_or(&[][..], Vec::as_slice);
+    let listed = methods.iter().any(|method| {
+        let names_key = DID_KEY_FIELDS
+            .iter()
+            .any(|f| method.get(*f).and_then(|v| v.as_str()) == Some(pubkey.0.as_str()));
+        let id = method.get("id").and_then(|v| v.as_str()).unwrap_or("");
+        let fragment_ok = !subject.contains('#')
+            || id == subject
+            || (id.starts_with('#') && subject.ends_with(id));
+        names_key && fragment_ok
+    });
+    if !listed {
+        return Err(format!(
+            "DID document for {} does not list {}",
+            did, pubkey.0
+        ));
+    }
+    Ok(())
+}
+
+// Verifies each attestation and checks it is about one of `participants`.
+pub fn verify_identities(
+    attestations: &[IdentityAttestation],
+    participants: &[PublicKey],
+    verifier: &dyn SignatureVerifier,
+) -> Result<Vec<IdentityCheck>, String> {
+    attestations
+        .iter()
+        .map(|attestation| {
+            let pubkey = &attestation.claim.pubkey;
+            if !participants.contains(pubkey) {
+                return Err(format!("{} is not a participant of this wallet", pubkey.0));
+            }
+            attestation.verify(verifier)
+        })
+        .collect()
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+#[wasm_bindgen]
+pub fn get_identity_message(claim_json: &str) -> Result<String, String> {
+    let claim: IdentityClaim = serde_json::from_str(claim_json).map_err(|e| e.to_string())?;
+    Ok(claim.message())
+}
+
+// Items to check with WebCrypto before `attach_identity`; accepts one
+// attestation or a list (e.g. the `identities` of a backup).
+#[wasm_bindgen]
+pub fn get_identity_verification_items(attestations_json: &str) -> Result<String, String> {
+    let attestations: Vec<IdentityAttestation> =
+        match serde_json::from_str::<IdentityAttestation>(attestations_json) {
+            Ok(one) => vec![one],
+            Err(_) => serde_json::from_str(attestations_json).map_err(|e| e.to_string())?,
+        };
+    let items: Vec<VerificationItem> = attestations
+        .iter()
+        .flat_map(IdentityAttestation::verification_items)
+        .collect();
+    serde_json::to_string(&items).map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+pub fn verify_identity(attestation_json: &str, verified_json: &str) -> Result<String, String> {
+    let attestation: IdentityAttestation =
+        serde_json::from_str(attestation_json).map_err(|e| e.to_string())?;
+    let verified: Vec<VerificationItem> =
+        serde_json::from_str(verified_json).map_err(|e| e.to_string())?;
+    let check = attestation.verify(&PrecomputedVerifier::new(verified))?;
+    serde_json::to_string(&check).map_err(|e| e.to_string())
+}
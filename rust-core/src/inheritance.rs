@@ -0,0 +1,298 @@
+use crate::hashing::{digest_hex, HashAlgorithm};
+use crate::session::SigningSession;
+use crate::storage::{validate_segment, Notification, NotificationEvent, Storage, Tenant};
+use crate::verify::SignatureVerifier;
+use crate::{
+    Lock, Output, PkhCondition, PublicKey, Signature, SpendCondition, TimCondition, Transaction,
+};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Inheritance Locks
+// ============================================================================
+
+// Branch 0 is the owners' multisig; branch 1 lets the heirs spend alone once
+// the chain passes `unlock_height`. The height only keeps heirs from moving
+// funds early on chain; owners who are alive re-lock to a later height from
+// time to time. The coordinator's dead-man's switch below decides when the
+// heirs are actually asked to act.
+pub fn inheritance_lock(
+    owners: PkhCondition,
+    heirs: PkhCondition,
+    unlock_height: u64,
+) -> Result<Lock, String> {
+    let lock = Lock::from_conditions(vec![
+        SpendCondition {
+            pkh: owners,
+            hax: None,
+            tim: None,
+            emergency: false,
+        },
+        SpendCondition {
+            pkh: heirs,
+            hax: None,
+            tim: Some(TimCondition {
+                min_height: Some(unlock_height),
+                max_height: None,
+            }),
+            emergency: false,
+        },
+    ])?;
+    lock.validate()?;
+    Ok(lock)
+}
+
+// ============================================================================
+// Dead-Man's Switch
+// ============================================================================
+
+// Owners check in with a signed proof of life. If none arrives for `silence`
+// seconds, `run_dead_man_switches(now)` prepares a draft sweeping the
+// wallet's free notes to the beneficiary over the heirs' time-locked branch,
+// puts it into the signing flow, and notifies the heirs. A later check-in
+// disarms the switch again; drafts already prepared stay for the owners to
+// discard.
+
+const CHECK_IN_DOMAIN: &[u8] = b"nockchain-multisig/check-in/v1";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Beneficiary {
+    pub recipient: String,
+    pub lock: Lock,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InheritancePlan {
+    pub id: String,
+    pub wallet: String,
+    // Keys whose check-ins count as proof of life.
+    pub owners: Vec<PublicKey>,
+    // The wallet lock's time-locked heir branch.
+    pub heir_branch: usize,
+    pub beneficiary: Beneficiary,
+    // Seconds without a check-in before the switch fires.
+    pub silence: u64,
+    #[serde(default)]
+    pub last_check_in: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub triggered_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CheckIn {
+    pub pubkey: PublicKey,
+    pub at: u64,
+    pub signature: Signature,
+}
+
+#[derive(Serialize)]
+struct CheckInTerms<'a> {
+    plan: &'a str,
+    pubkey: &'a PublicKey,
+    at: u64,
+}
+
+// What an owner signs to check in.
+pub fn check_in_message(plan: &str, pubkey: &PublicKey, at: u64) -> String {
+    let terms = CheckInTerms { plan, pubkey, at };
+    let bytes = serde_json::to_vec(&terms).expect("Serialization failed");
+    digest_hex(HashAlgorithm::Sha256, &[CHECK_IN_DOMAIN, &bytes].concat())
+        .expect("sha256 is always available")
+}
+
+impl InheritancePlan {
+    pub fn is_due(&self, now: u64) -> bool {
+        self.triggered_at.is_none() && now.saturating_sub(self.last_check_in) > self.silence
+    }
+
+    fn validate(&self, lock: &Lock) -> Result<(), String> {
+        if self.silence == 0 {
+            return Err("Silence period must be positive".into());
+        }
+        if self.owners.is_empty() {
+            return Err("Inheritance plan needs at least one owner key".into());
+        }
+        if let Some(pk) = self.owners.iter().find(|pk| !lock.pkh.pubkeys.contains(pk)) {
+            return Err(format!("{} is not an owner key of the wallet", pk.0));
+        }
+        let branch = lock
+            .condition(self.heir_branch)
+            .filter(|_| self.heir_branch > 0)
+            .ok_or_else(|| format!("Wallet lock has no heir branch {}", self.heir_branch))?;
+        if branch.tim.and_then(|t| t.min_height).is_none() {
+            return Err("Heir branch must be time-locked".into());
+        }
+        self.beneficiary.lock.validate()
+    }
+
+    pub fn heirs<'a>(&self, lock: &'a Lock) -> &'a [PublicKey] {
+        lock.condition(self.heir_branch)
+            .map_or(&[], |c| c.pkh.pubkeys.as_slice())
+    }
+}
+
+fn plan_key(id: &str) -> Result<String, String> {
+    validate_segment("inheritance plan", id)?;
+    Ok(format!("inheritance/{}", id))
+}
+
+impl<S: Storage> Tenant<'_, S> {
+    // Arms a plan; `now` counts as the first check-in.
+    pub fn create_inheritance_plan(
+        &mut self,
+        mut plan: InheritancePlan,
+        now: u64,
+    ) -> Result<InheritancePlan, String> {
+        let wallet = self.wallet(&plan.wallet)?;
+        plan.validate(&wallet.descriptor.lock)?;
+        plan.last_check_in = now;
+        plan.triggered_at = None;
+        self.put_inheritance_plan(&plan)?;
+        Ok(plan)
+    }
+
+    pub fn inheritance_plan(&self, id: &str) -> Result<InheritancePlan, String> {
+        self.load(&plan_key(id)?)?
+            .ok_or_else(|| format!("Unknown inheritance plan {}", id))
+    }
+
+    pub fn inheritance_plans(&self) -> Result<Vec<InheritancePlan>, String> {
+        self.list("inheritance")?
+            .iter()
+            .map(|id| self.inheritance_plan(id))
+            .collect()
+    }
+
+    pub fn put_inheritance_plan(&mut self, plan: &InheritancePlan) -> Result<(), String> {
+        self.store(&plan_key(&plan.id)?, plan)
+    }
+
+    pub fn delete_inheritance_plan(&mut self, id: &str) -> Result<bool, String> {
+        self.remove(&plan_key(id)?)
+    }
+
+    pub fn check_in(
+        &mut self,
+        plan_id: &str,
+        check_in: &CheckIn,
+        verifier: &dyn SignatureVerifier,
+        now: u64,
+    ) -> Result<InheritancePlan, String> {
+        let mut plan = self.inheritance_plan(plan_id)?;
+        if !plan.owners.contains(&check_in.pubkey) {
+            return Err(format!(
+                "{} is not an owner on this plan",
+                check_in.pubkey.0
+            ));
+        }
+        if check_in.at <= plan.last_check_in || check_in.at > now {
+            return Err("Check-in is stale or from the future".into());
+        }
+        let message = check_in_message(plan_id, &check_in.pubkey, check_in.at);
+        if !verifier.verify(&check_in.pubkey, &message, &check_in.signature) {
+            return Err("Invalid check-in signature".into());
+        }
+
+        plan.last_check_in = check_in.at;
+        if plan.triggered_at.take().is_some() {
+            let lock = self.wallet(&plan.wallet)?.descriptor.lock;
+            self.notify(&[Notification {
+                at: now,
+                wallet: plan.wallet.clone(),
+                notify: plan.heirs(&lock).to_vec(),
+                event: NotificationEvent::RecoveryCancelled {
+                    plan: plan.id.clone(),
+                },
+            }])?;
+        }
+        self.put_inheritance_plan(&plan)?;
+        Ok(plan)
+    }
+
+    fn prepare_recovery(
+        &mut self,
+        plan: &InheritancePlan,
+        now: u64,
+    ) -> Result<NotificationEvent, String> {
+        let wallet = self.wallet(&plan.wallet)?;
+        if wallet.archived {
+            return Err(format!("Wallet {} is archived", wallet.id));
+        }
+        let notes = wallet.free_notes();
+        if notes.is_empty() {
+            return Err("No notes free to spend".into());
+        }
+        let value = notes
+            .iter()
+            .try_fold(0u64, |sum, n| sum.checked_add(n.value));
+        let output = Output {
+            recipient: plan.beneficiary.recipient.clone(),
+            value: value.ok_or("Note values overflow")?,
+            lock: plan.beneficiary.lock.clone(),
+        };
+        let mut tx = Transaction::build(notes, vec![output], HashAlgorithm::default())?;
+        for spend in &mut tx.spends {
+            spend.switch_branch(plan.heir_branch)?;
+        }
+        let session = SigningSession::new(tx);
+        let draft = session.draft_hash();
+        let session = self.add_session(&plan.wallet, session, now)?;
+        Ok(NotificationEvent::RecoveryPrepared {
+            plan: plan.id.clone(),
+            session,
+            draft,
+        })
+    }
+
+    // Fires every plan whose owners have been silent too long.
+    pub fn run_dead_man_switches(&mut self, now: u64) -> Result<Vec<Notification>, String> {
+        let mut notifications = Vec::new();
+        for mut plan in self.inheritance_plans()? {
+            if !plan.is_due(now) {
+                continue;
+            }
+            let event = self.prepare_recovery(&plan, now).unwrap_or_else(|error| {
+                NotificationEvent::RecoveryFailed {
+                    plan: plan.id.clone(),
+                    error,
+                }
+            });
+            plan.triggered_at = Some(now);
+            self.put_inheritance_plan(&plan)?;
+            let lock = self.wallet(&plan.wallet)?.descriptor.lock;
+            notifications.push(Notification {
+                at: now,
+                wallet: plan.wallet.clone(),
+                notify: plan.heirs(&lock).to_vec(),
+                event,
+            });
+        }
+        self.notify(&notifications)?;
+        Ok(notifications)
+    }
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+#[wasm_bindgen]
+pub fn create_inheritance_lock(
+    owners_json: &str,
+    heirs_json: &str,
+    unlock_height: u64,
+) -> Result<String, String> {
+    let owners: PkhCondition = serde_json::from_str(owners_json).map_err(|e| e.to_string())?;
+    let heirs: PkhCondition = serde_json::from_str(heirs_json).map_err(|e| e.to_string())?;
+    let lock = inheritance_lock(owners, heirs, unlock_height)?;
+    serde_json::to_string(&lock).map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+pub fn get_check_in_message(plan_id: &str, pubkey: &str, at: u64) -> String {
+    check_in_message(plan_id, &PublicKey(pubkey.to_string()), at)
+}
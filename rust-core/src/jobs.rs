@@ -0,0 +1,307 @@
+use crate::hashing::HashAlgorithm;
+use crate::network::Network;
+#[cfg(feature = "view-keys")]
+use crate::viewkey::{ScanReport, ViewKey, ViewRecord};
+use crate::{Note, Output, Transaction};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Long-Running Jobs
+// ============================================================================
+
+// WASM runs on the caller's thread, so a big operation blocks the page until
+// it returns. A job instead does a bounded amount of work per
+// `poll_progress(budget)` call; the UI calls it from a timer or
+// requestIdleCallback loop, renders progress in between, and can `cancel()`
+// at any point. Nothing runs unless the caller polls.
+//
+//   const job = start_job('build', JSON.stringify({ notes, outputs }));
+//   while (JSON.parse(job.poll_progress(200)).status === 'running') {
+//     await new Promise(r => setTimeout(r));
+//   }
+//   const tx = job.result();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobProgress {
+    pub kind: String,
+    pub status: JobStatus,
+    pub done: usize,
+    pub total: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BuildInput {
+    notes: Vec<Note>,
+    outputs: Vec<Output>,
+    #[serde(default)]
+    hash_algorithm: HashAlgorithm,
+    #[serde(default)]
+    network: Network,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ValidateInput {
+    transactions: Vec<Transaction>,
+}
+
+#[cfg(feature = "view-keys")]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScanInput {
+    view_key: String,
+    records: Vec<ViewRecord>,
+}
+
+enum Work {
+    // Hashes one spend per step.
+    Build(Transaction),
+    // Validates one draft per step.
+    Validate {
+        transactions: Vec<Transaction>,
+        results: Vec<Result<(), String>>,
+    },
+    // Opens one record per step.
+    #[cfg(feature = "view-keys")]
+    Scan {
+        view_key: ViewKey,
+        records: Vec<ViewRecord>,
+        report: ScanReport,
+    },
+}
+
+#[derive(Serialize)]
+struct ValidationResult {
+    valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl Work {
+    fn total(&self) -> usize {
+        match self {
+            Work::Build(tx) => tx.spends.len(),
+            Work::Validate { transactions, .. } => transactions.len(),
+            #[cfg(feature = "view-keys")]
+            Work::Scan { records, .. } => records.len(),
+        }
+    }
+
+    fn step(&mut self, index: usize) -> Result<(), String> {
+        match self {
+            Work::Build(tx) => {
+                tx.spends[index].note.lock.validate()?;
+                let hash = tx.spend_hash(index)?;
+                tx.spends[index].seeds.message_hash = hash;
+            }
+            Work::Validate {
+                transactions,
+                results,
+            } => {
+                let tx = &transactions[index];
+                results.push(
+                    tx.validate_hash_algorithm()
+                        .and_then(|_| tx.validate_network())
+                        .and_then(|_| tx.validate_balance())
+                        .and_then(|_| tx.validate_signatures()),
+                );
+            }
+            #[cfg(feature = "view-keys")]
+            Work::Scan {
+                view_key,
+                records,
+                report,
+            } => {
+                let part = view_key.scan(std::slice::from_ref(&records[index]))?;
+                report.skipped += part.skipped;
+                for note in part.notes {
+                    if !report.notes.iter().any(|n| n.name == note.name) {
+                        report.balance += note.value;
+                        report.notes.push(note);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<String, String> {
+        match self {
+            Work::Build(tx) => {
+                tx.validate_network()?;
+                tx.validate_balance()?;
+                serde_json::to_string(&tx).map_err(|e| e.to_string())
+            }
+            Work::Validate { results, .. } => {
+                let results: Vec<ValidationResult> = results
+                    .into_iter()
+                    .map(|r| ValidationResult {
+                        valid: r.is_ok(),
+                        error: r.err(),
+                    })
+                    .collect();
+                serde_json::to_string(&results).map_err(|e| e.to_string())
+            }
+            #[cfg(feature = "view-keys")]
+            Work::Scan { report, .. } => serde_json::to_string(&report).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub struct Job {
+    kind: String,
+    work: Option<Work>,
+    done: usize,
+    total: usize,
+    status: JobStatus,
+    outcome: Option<Result<String, String>>,
+}
+
+impl Job {
+    fn new(kind: &str, input_json: &str) -> Result<Job, String> {
+        let work = match kind {
+            "build" => {
+                let input: BuildInput =
+                    serde_json::from_str(input_json).map_err(|e| e.to_string())?;
+                let mut tx = Transaction::new(
+                    input
+                        .notes
+                        .into_iter()
+                        .map(|note| crate::Spend {
+                            note,
+                            seeds: crate::Seeds::new(String::new()),
+                        })
+                        .collect(),
+                    input.outputs,
+                );
+                tx.set_hash_algorithm(input.hash_algorithm)?;
+                tx.set_network(input.network);
+                Work::Build(tx)
+            }
+            "validate" => {
+                let input: ValidateInput =
+                    serde_json::from_str(input_json).map_err(|e| e.to_string())?;
+                Work::Validate {
+                    results: Vec::with_capacity(input.transactions.len()),
+                    transactions: input.transactions,
+                }
+            }
+            #[cfg(feature = "view-keys")]
+            "scan" => {
+                let input: ScanInput =
+                    serde_json::from_str(input_json).map_err(|e| e.to_string())?;
+                let view_key = ViewKey::decode(&input.view_key)?;
+                Work::Scan {
+                    report: view_key.scan(&[])?,
+                    view_key,
+                    records: input.records,
+                }
+            }
+            other => return Err(format!("Unknown job kind '{}'", other)),
+        };
+
+        Ok(Job {
+            kind: kind.to_string(),
+            total: work.total(),
+            work: Some(work),
+            done: 0,
+            status: JobStatus::Running,
+            outcome: None,
+        })
+    }
+
+    pub fn progress(&self) -> JobProgress {
+        JobProgress {
+            kind: self.kind.clone(),
+            status: self.status,
+            done: self.done,
+            total: self.total,
+            error: match &self.outcome {
+                Some(Err(e)) => Some(e.clone()),
+                _ => None,
+            },
+        }
+    }
+
+    fn fail(&mut self, error: String) {
+        self.work = None;
+        self.status = JobStatus::Failed;
+        self.outcome = Some(Err(error));
+    }
+}
+
+#[wasm_bindgen]
+impl Job {
+    // Runs up to `budget` steps (at least one) and reports progress.
+    pub fn poll_progress(&mut self, budget: usize) -> String {
+        if self.status == JobStatus::Running {
+            let end = self.total.min(self.done + budget.max(1));
+            while self.done < end {
+                let step = self
+                    .work
+                    .as_mut()
+                    .expect("running jobs have work")
+                    .step(self.done);
+                if let Err(e) = step {
+                    self.fail(e);
+                    break;
+                }
+                self.done += 1;
+            }
+
+            if self.status == JobStatus::Running && self.done == self.total {
+                let outcome = self.work.take().expect("running jobs have work").finish();
+                self.status = if outcome.is_ok() {
+                    JobStatus::Done
+                } else {
+                    JobStatus::Failed
+                };
+                self.outcome = Some(outcome);
+            }
+        }
+
+        serde_json::to_string(&self.progress()).expect("Serialization failed")
+    }
+
+    // Drops the remaining work; a finished job keeps its result.
+    pub fn cancel(&mut self) {
+        if self.status == JobStatus::Running {
+            self.work = None;
+            self.status = JobStatus::Cancelled;
+        }
+    }
+
+    pub fn result(&self) -> Result<String, String> {
+        match (&self.outcome, self.status) {
+            (Some(outcome), _) => outcome.clone(),
+            (None, JobStatus::Cancelled) => Err("Job was cancelled".into()),
+            (None, _) => Err("Job is still running".into()),
+        }
+    }
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+// Kinds: "build" ({notes, outputs, hash_algorithm?}), "validate"
+// ({transactions}), and "scan" ({view_key, records}) with view-keys.
+#[wasm_bindgen]
+pub fn start_job(kind: &str, input_json: &str) -> Result<Job, String> {
+    Job::new(kind, input_json)
+}
@@ -0,0 +1,203 @@
+use crate::wallets::Wallet;
+use crate::PublicKey;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// ============================================================================
+// Keyset Health
+// ============================================================================
+
+// Audits the keys of one wallet against everything else the manager knows.
+// Nothing here is fatal on its own: findings carry a severity and the report
+// lists the keys worth rotating, for the UI to turn into recommendations.
+// Keys are opaque strings to this crate, so "weak" only catches material that
+// is obviously not a real key (placeholders, repeated characters, test
+// values); it does not replace checking keys on the device that made them.
+
+// Shortest key string taken for real key material.
+pub const MIN_KEY_LENGTH: usize = 32;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeysetAuditOptions {
+    pub now: u64,
+    // Keys older than this (seconds) should be rotated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_key_age: Option<u64>,
+    // When each key was created, as far as the host knows. Keys with an
+    // identity attestation fall back to its issue time.
+    #[serde(default)]
+    pub created_at: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "issue", rename_all = "snake_case", deny_unknown_fields)]
+pub enum KeyIssue {
+    // The same key guards other wallets, so one compromise spreads.
+    ReusedAcrossWallets { wallets: Vec<String> },
+    // No signature from this key in any draft the wallet has seen; the
+    // holder may have lost it without anyone noticing.
+    NeverSigned,
+    PastMaxAge { age: u64, max_age: u64 },
+    // Listed twice in one condition, so it counts twice toward the threshold.
+    RepeatedInCondition { branch: usize },
+    // Another key in the wallet is the same material written differently.
+    DuplicateMaterial { other: PublicKey },
+    WeakMaterial { reason: String },
+}
+
+impl KeyIssue {
+    pub fn severity(&self) -> Severity {
+        match self {
+            KeyIssue::NeverSigned => Severity::Info,
+            KeyIssue::ReusedAcrossWallets { .. } | KeyIssue::PastMaxAge { .. } => Severity::Warning,
+            KeyIssue::RepeatedInCondition { .. }
+            | KeyIssue::DuplicateMaterial { .. }
+            | KeyIssue::WeakMaterial { .. } => Severity::Critical,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyFinding {
+    pub pubkey: PublicKey,
+    pub severity: Severity,
+    #[serde(flatten)]
+    pub issue: KeyIssue,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeysetReport {
+    pub wallet: String,
+    pub keys: usize,
+    pub findings: Vec<KeyFinding>,
+    // Keys with a warning or worse, most severe first.
+    pub rotate: Vec<PublicKey>,
+}
+
+fn normalized(pubkey: &PublicKey) -> String {
+    pubkey.0.trim().to_ascii_lowercase()
+}
+
+fn weakness(pubkey: &PublicKey) -> Option<String> {
+    let key = pubkey.0.trim();
+    let mut chars = key.chars();
+    let first = chars.next()?;
+    if chars.all(|c| c == first) {
+        return Some("Key is one repeated character".into());
+    }
+    if key.len() < MIN_KEY_LENGTH {
+        return Some(format!(
+            "Key is {} characters, below the {} expected of real key material",
+            key.len(),
+            MIN_KEY_LENGTH
+        ));
+    }
+    None
+}
+
+fn has_signed(wallet: &Wallet, pubkey: &PublicKey) -> bool {
+    wallet
+        .sessions
+        .iter()
+        .chain(&wallet.invalidated)
+        .flat_map(|s| &s.transaction.spends)
+        .any(|spend| spend.seeds.signatures.iter().any(|(pk, _)| pk == pubkey))
+}
+
+// `others` is every other wallet to check for reuse; archived ones count,
+// since their keys may still guard notes.
+pub fn audit_keyset(
+    wallet: &Wallet,
+    others: &[Wallet],
+    options: &KeysetAuditOptions,
+) -> KeysetReport {
+    let participants = wallet.descriptor.participants();
+    let mut findings = Vec::new();
+    let mut found = |pubkey: &PublicKey, issue: KeyIssue| {
+        findings.push(KeyFinding {
+            pubkey: pubkey.clone(),
+            severity: issue.severity(),
+            issue,
+        });
+    };
+
+    for (branch, condition) in wallet.descriptor.lock.conditions().enumerate() {
+        let keys = &condition.pkh.pubkeys;
+        for (i, pubkey) in keys.iter().enumerate() {
+            if keys[..i].contains(pubkey) {
+                found(pubkey, KeyIssue::RepeatedInCondition { branch });
+            }
+        }
+    }
+
+    for (i, pubkey) in participants.iter().enumerate() {
+        if let Some(reason) = weakness(pubkey) {
+            found(pubkey, KeyIssue::WeakMaterial { reason });
+        }
+        let duplicate = participants[..i]
+            .iter()
+            .find(|other| normalized(other) == normalized(pubkey));
+        if let Some(other) = duplicate {
+            found(
+                pubkey,
+                KeyIssue::DuplicateMaterial {
+                    other: other.clone(),
+                },
+            );
+        }
+
+        let wallets: Vec<String> = others
+            .iter()
+            .filter(|w| w.id != wallet.id && w.descriptor.participants().contains(pubkey))
+            .map(|w| w.id.clone())
+            .collect();
+        if !wallets.is_empty() {
+            found(pubkey, KeyIssue::ReusedAcrossWallets { wallets });
+        }
+
+        if !has_signed(wallet, pubkey) {
+            found(pubkey, KeyIssue::NeverSigned);
+        }
+
+        let created_at = options.created_at.get(&pubkey.0).copied().or_else(|| {
+            wallet
+                .identities
+                .iter()
+                .find(|a| &a.claim.pubkey == pubkey)
+                .map(|a| a.claim.issued_at)
+        });
+        if let (Some(max_age), Some(created_at)) = (options.max_key_age, created_at) {
+            let age = options.now.saturating_sub(created_at);
+            if age > max_age {
+                found(pubkey, KeyIssue::PastMaxAge { age, max_age });
+            }
+        }
+    }
+
+    let mut worst: Vec<(Severity, &PublicKey)> = Vec::new();
+    for finding in findings.iter().filter(|f| f.severity >= Severity::Warning) {
+        match worst.iter_mut().find(|(_, pk)| *pk == &finding.pubkey) {
+            Some(entry) => entry.0 = entry.0.max(finding.severity),
+            None => worst.push((finding.severity, &finding.pubkey)),
+        }
+    }
+    worst.sort_by_key(|(severity, _)| std::cmp::Reverse(*severity));
+    let rotate = worst.into_iter().map(|(_, pk)| pk.clone()).collect();
+
+    KeysetReport {
+        wallet: wallet.id.clone(),
+        keys: participants.len(),
+        findings,
+        rotate,
+    }
+}
@@ -1,253 +1,422 @@
+use hashing::HashAlgorithm;
+use limits::TransactionLimits;
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
 use wasm_bindgen::prelude::*;
-use std::collections::HashSet;
+
+pub use nockchain_multisig_core::{
+    arena, blake3, build, checkpoint, delegation, diff, emergency, filter, limits, lineage, mast,
+    maturity, merkle, network, outputs, paths, preapproval, preimage_hash, rng, schema, shuffle,
+    spv, summary, template, threshold, verify, ConditionRef, HaxCondition, Lock, Note, NoteName,
+    Output, PkhCondition, PublicKey, Seeds, Signature, Spend, SpendCondition, SpendHashCache,
+    TimCondition, Transaction,
+};
+
+#[cfg(feature = "coordinator")]
+pub mod alerts;
+#[cfg(feature = "coordinator")]
+pub mod api_tokens;
+pub mod attestation;
+pub mod audit;
+#[cfg(feature = "coordinator")]
+pub mod backup;
+pub mod backup_signers;
+#[cfg(all(feature = "component", target_os = "wasi"))]
+#[allow(warnings)]
+mod bindings;
+#[cfg(feature = "browser-store")]
+pub mod browser_store;
+#[cfg(feature = "coordinator")]
+pub mod capabilities;
+#[cfg(feature = "channel")]
+pub mod channel;
+pub mod comments;
+#[cfg(all(feature = "component", target_os = "wasi"))]
+mod component;
+pub mod crypto;
+pub mod disclosure;
+pub mod encoding;
+#[cfg(feature = "escrow")]
+pub mod escrow;
+#[cfg(feature = "explorer")]
+pub mod explorer;
+#[cfg(feature = "coordinator")]
+pub mod federation;
+#[cfg(feature = "governance")]
+pub mod governance;
+pub mod hashing;
+#[cfg(feature = "wallets")]
+pub mod identity;
+#[cfg(feature = "coordinator")]
+pub mod inheritance;
+#[cfg(feature = "jobs")]
+pub mod jobs;
+#[cfg(feature = "wallets")]
+pub mod keyset;
+pub mod messages;
+#[cfg(feature = "wallets")]
+pub mod metadata;
+#[cfg(feature = "mockchain")]
+pub mod mockchain;
+#[cfg(feature = "nfc")]
+pub mod nfc;
+#[cfg(feature = "offline")]
+pub mod offline;
+#[cfg(feature = "native")]
+pub mod parallel;
+#[cfg(feature = "policy")]
+pub mod policy;
+#[cfg(feature = "psbt")]
+pub mod psbt;
+pub mod session;
+pub mod session_pool;
+pub mod snapshot;
+#[cfg(feature = "qr")]
+pub mod qr;
+#[cfg(any(all(feature = "native", unix), target_arch = "wasm32"))]
+pub mod random;
+#[cfg(feature = "wallets")]
+pub mod reconcile;
+#[cfg(feature = "wallets")]
+pub mod recovery;
+#[cfg(feature = "wallets")]
+pub mod relock;
+pub mod reminders;
+#[cfg(feature = "resume")]
+pub mod resume;
+#[cfg(feature = "coordinator")]
+pub mod retention;
+#[cfg(feature = "sandbox")]
+pub mod sandbox;
+#[cfg(feature = "scenarios")]
+pub mod scenarios;
+#[cfg(feature = "coordinator")]
+pub mod schedule;
+#[cfg(feature = "wallets")]
+pub mod statedump;
+#[cfg(feature = "coordinator")]
+pub mod storage;
+#[cfg(feature = "swap")]
+pub mod swap;
+#[cfg(feature = "wallets")]
+pub mod tracking;
+#[cfg(test)]
+mod testing;
+#[cfg(feature = "peer")]
+pub mod transport;
+#[cfg(feature = "coordinator")]
+pub mod treasury;
+#[cfg(feature = "view-keys")]
+pub mod viewkey;
+#[cfg(feature = "wallets")]
+pub mod wallet_backup;
+#[cfg(feature = "wallets")]
+pub mod wallets;
+#[cfg(feature = "worker")]
+pub mod worker;
 
 // ============================================================================
-// Core Types
+// Signing Status
 // ============================================================================
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
-pub struct PublicKey(pub String);
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Signature(pub String);
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct NoteName {
-    pub first: String,
-    pub last: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PkhCondition {
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SigningStatus {
+    pub spend_index: usize,
     pub threshold: usize,
-    pub pubkeys: Vec<PublicKey>,
+    pub signed: Vec<PublicKey>,
+    pub pending: Vec<PublicKey>,
+    pub complete: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub incident: Option<emergency::IncidentAttestation>,
+    // Keys of the spend's branch that are backups, not primary signers.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub backups: Vec<PublicKey>,
+    // The other branches admitting backup keys, for a spend that is stuck.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub backup_routes: Vec<backup_signers::BackupEligibility>,
 }
 
-impl PkhCondition {
-    pub fn validate(&self) -> Result<(), String> {
-        if self.threshold == 0 {
-            return Err("Threshold must be >= 1".into());
-        }
-        if self.threshold > self.pubkeys.len() {
-            return Err("Threshold exceeds number of pubkeys".into());
-        }
+pub(crate) fn signing_status(spend_index: usize, tx: &Transaction) -> Result<SigningStatus, String> {
+    let spend = &tx.spends[spend_index];
+    let condition = spend.condition()?;
+    let pkh = condition.pkh;
 
-        let mut set = HashSet::new();
-        for pk in &self.pubkeys {
-            if !set.insert(pk) {
-                return Err("Duplicate public key in multisig set".into());
-            }
-        }
+    let mut signed = Vec::new();
+    let mut pending = Vec::new();
 
-        Ok(())
+    for pk in &pkh.pubkeys {
+        if spend.seeds.has_signature(pk) {
+            signed.push(pk.clone());
+        } else {
+            pending.push(pk.clone());
+        }
     }
-}
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Lock {
-    pub pkh: PkhCondition,
-}
+    let preimages_ok = condition
+        .hax
+        .is_none_or(|hax| hax.is_satisfied_by(&spend.seeds.preimages));
+    let incident_ok = !condition.emergency || spend.seeds.incident.is_some();
+    let complete = signed.len() >= pkh.threshold && preimages_ok && incident_ok;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Note {
-    pub name: NoteName,
-    pub value: u64,
-    pub lock: Lock,
+    Ok(SigningStatus {
+        spend_index,
+        threshold: pkh.threshold,
+        signed,
+        pending,
+        complete,
+        incident: spend.seeds.incident.clone(),
+        backups: backup_signers::backup_keys(&spend.note.lock, spend.seeds.branch),
+        backup_routes: backup_signers::backup_routes(&spend.note.lock)
+            .into_iter()
+            .filter(|route| route.branch != spend.seeds.branch)
+            .collect(),
+    })
 }
 
 // ============================================================================
-// Seeds and Signing
+// WASM Interface
 // ============================================================================
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Seeds {
-    pub message_hash: String,
-    pub signatures: Vec<(PublicKey, Signature)>,
+// Limit violations fail with a JSON error, `{"limit", "max", "actual"}`.
+fn build_within(
+    notes: Vec<Note>,
+    outputs: Vec<Output>,
+    algorithm: HashAlgorithm,
+    limits: &TransactionLimits,
+) -> Result<Transaction, String> {
+    let options = build::BuildOptions {
+        limits: *limits,
+        ..Default::default()
+    };
+    Ok(Transaction::build_with(notes, outputs, algorithm, &options)?.transaction)
 }
 
-impl Seeds {
-    pub fn new(message_hash: String) -> Self {
-        Self {
-            message_hash,
-            signatures: Vec::new(),
-        }
-    }
-
-    pub fn add_signature(&mut self, pubkey: PublicKey, signature: Signature) {
-        self.signatures.retain(|(pk, _)| pk != &pubkey);
-        self.signatures.push((pubkey, signature));
-    }
-
-    pub fn signature_count(&self) -> usize {
-        self.signatures.len()
-    }
-
-    pub fn has_signature(&self, pubkey: &PublicKey) -> bool {
-        self.signatures.iter().any(|(pk, _)| pk == pubkey)
-    }
+fn serialize_within(tx: &Transaction, limits: &TransactionLimits) -> Result<String, String> {
+    limits.check(tx).map_err(|e| e.to_json())?;
+    serde_json::to_string(tx).map_err(|e| e.to_string())
 }
 
-// ============================================================================
-// Transaction Model
-// ============================================================================
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Spend {
-    pub note: Note,
-    pub seeds: Seeds,
+#[wasm_bindgen]
+pub fn build_transaction(notes_json: &str, outputs_json: &str) -> Result<String, String> {
+    build_transaction_with_hash(notes_json, outputs_json, HashAlgorithm::default().name())
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Output {
-    pub recipient: String,
-    pub value: u64,
-    pub lock: Lock,
-}
+#[wasm_bindgen]
+pub fn build_transaction_with_hash(
+    notes_json: &str,
+    outputs_json: &str,
+    algorithm: &str,
+) -> Result<String, String> {
+    let algorithm = HashAlgorithm::parse(algorithm)?;
+    let notes: Vec<Note> =
+        serde_json::from_str(notes_json).map_err(|e| e.to_string())?;
+    let outputs: Vec<Output> =
+        serde_json::from_str(outputs_json).map_err(|e| e.to_string())?;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Transaction {
-    pub spends: Vec<Spend>,
-    pub outputs: Vec<Output>,
+    let limits = TransactionLimits::default();
+    let tx = build_within(notes, outputs, algorithm, &limits)?;
+    tx.validate_network()?;
+
+    serialize_within(&tx, &limits)
 }
 
-impl Transaction {
-    pub fn total_input(&self) -> u64 {
-        self.spends.iter().map(|s| s.note.value).sum()
-    }
+// As `build_transaction`, with caps from `limits_json` (any of `max_spends`,
+// `max_outputs`, `max_serialized_size`, `max_value`; the rest default).
+#[wasm_bindgen]
+pub fn build_transaction_with_limits(
+    notes_json: &str,
+    outputs_json: &str,
+    limits_json: &str,
+) -> Result<String, String> {
+    let limits: TransactionLimits =
+        serde_json::from_str(limits_json).map_err(|e| e.to_string())?;
+    let notes: Vec<Note> =
+        serde_json::from_str(notes_json).map_err(|e| e.to_string())?;
+    let outputs: Vec<Output> =
+        serde_json::from_str(outputs_json).map_err(|e| e.to_string())?;
 
-    pub fn total_output(&self) -> u64 {
-        self.outputs.iter().map(|o| o.value).sum()
-    }
+    let tx = build_within(notes, outputs, HashAlgorithm::default(), &limits)?;
+    tx.validate_network()?;
 
-    pub fn validate_balance(&self) -> Result<(), String> {
-        if self.total_input() != self.total_output() {
-            return Err("Input value does not equal output value".into());
-        }
-        Ok(())
-    }
+    serialize_within(&tx, &limits)
+}
 
-    pub fn validate_signatures(&self) -> Result<(), String> {
-        for (i, spend) in self.spends.iter().enumerate() {
-            let pkh = &spend.note.lock.pkh;
-            pkh.validate()?;
+// Builds with `options_json`: `limits`; `duplicates`, "keep", "merge" or
+// "warn" for outputs repeating a recipient and lock; `max_output_value` to
+// split larger outputs; `change` to pay the remainder back; `shuffle`; and
+// `seed` (hex) for the randomizing passes. Returns `{transaction, warnings, splits,
+// change}`.
+#[wasm_bindgen]
+pub fn build_transaction_with_options(
+    notes_json: &str,
+    outputs_json: &str,
+    options_json: &str,
+) -> Result<String, String> {
+    let options: build::BuildOptions =
+        serde_json::from_str(options_json).map_err(|e| e.to_string())?;
+    let notes: Vec<Note> =
+        serde_json::from_str(notes_json).map_err(|e| e.to_string())?;
+    let outputs: Vec<Output> =
+        serde_json::from_str(outputs_json).map_err(|e| e.to_string())?;
 
-            if spend.seeds.signature_count() < pkh.threshold {
-                return Err(format!("Spend {} has insufficient signatures", i));
-            }
+    let report = Transaction::build_with(notes, outputs, HashAlgorithm::default(), &options)?;
+    report.transaction.validate_network()?;
 
-            for (pk, _) in &spend.seeds.signatures {
-                if !pkh.pubkeys.contains(pk) {
-                    return Err(format!("Spend {} has invalid signer", i));
-                }
-            }
-        }
-        Ok(())
-    }
+    serde_json::to_string(&report).map_err(|e| e.to_string())
 }
 
-// ============================================================================
-// Deterministic Hashing
-// ============================================================================
-
-#[derive(Serialize)]
-struct SigningPayload<'a> {
-    spend_index: usize,
-    transaction: &'a Transaction,
+// Reorders an unsigned draft's spends and outputs from `seed` (hex). Every
+// cosigner holding the same notes, outputs and seed gets the same draft,
+// whatever order they were listed in.
+#[wasm_bindgen]
+pub fn shuffle_transaction(tx_json: &str, seed: &str) -> Result<String, String> {
+    let mut tx: Transaction =
+        serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+    let seed = hex::decode(seed).map_err(|_| "Seed must be hex")?;
+    tx.shuffle(&seed)?;
+    serde_json::to_string(&tx).map_err(|e| e.to_string())
 }
 
-fn compute_spend_hash(spend_index: usize, tx: &Transaction) -> String {
-    let mut tx_clone = tx.clone();
+// Splits one payment into outputs of at most `max_value` each.
+#[wasm_bindgen]
+pub fn split_payment(output_json: &str, max_value: u64) -> Result<String, String> {
+    let output: Output = serde_json::from_str(output_json).map_err(|e| e.to_string())?;
+    let parts = build::split_output(&output, max_value)?;
+    serde_json::to_string(&parts).map_err(|e| e.to_string())
+}
 
-    for spend in tx_clone.spends.iter_mut() {
-        spend.seeds.signatures.clear();
-    }
+// Previews the outputs a template resolves to for these notes.
+#[wasm_bindgen]
+pub fn resolve_template(template_json: &str, notes_json: &str) -> Result<String, String> {
+    let template: template::DraftTemplate =
+        serde_json::from_str(template_json).map_err(|e| e.to_string())?;
+    let notes: Vec<Note> = serde_json::from_str(notes_json).map_err(|e| e.to_string())?;
+    let outputs = template.resolve(&notes)?;
+    serde_json::to_string(&outputs).map_err(|e| e.to_string())
+}
 
-    let payload = SigningPayload {
-        spend_index,
-        transaction: &tx_clone,
-    };
+// Builds a draft from a template with the template's own build options.
+// Returns the same report as `build_transaction_with_options`.
+#[wasm_bindgen]
+pub fn instantiate_template(template_json: &str, notes_json: &str) -> Result<String, String> {
+    let template: template::DraftTemplate =
+        serde_json::from_str(template_json).map_err(|e| e.to_string())?;
+    let notes: Vec<Note> = serde_json::from_str(notes_json).map_err(|e| e.to_string())?;
 
-    let bytes = serde_json::to_vec(&payload).expect("Serialization failed");
+    let report = template.instantiate(notes, HashAlgorithm::default())?;
+    report.transaction.validate_network()?;
 
-    let mut hasher = Sha256::new();
-    hasher.update(bytes);
-    hex::encode(hasher.finalize())
+    serde_json::to_string(&report).map_err(|e| e.to_string())
 }
 
-// ============================================================================
-// Signing Status
-// ============================================================================
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SigningStatus {
-    pub spend_index: usize,
-    pub threshold: usize,
-    pub signed: Vec<PublicKey>,
-    pub pending: Vec<PublicKey>,
-    pub complete: bool,
+#[wasm_bindgen]
+pub fn get_default_limits() -> String {
+    serde_json::to_string(&TransactionLimits::default()).expect("Serialization failed")
 }
 
-fn signing_status(spend_index: usize, tx: &Transaction) -> SigningStatus {
-    let spend = &tx.spends[spend_index];
-    let pkh = &spend.note.lock.pkh;
-
-    let mut signed = Vec::new();
-    let mut pending = Vec::new();
-
-    for pk in &pkh.pubkeys {
-        if spend.seeds.has_signature(pk) {
-            signed.push(pk.clone());
-        } else {
-            pending.push(pk.clone());
-        }
-    }
+// Drafts for testnet or regtest get network-tagged spend hashes, so their
+// signatures are worthless on mainnet. Notes must be on the same network.
+#[wasm_bindgen]
+pub fn build_transaction_for_network(
+    notes_json: &str,
+    outputs_json: &str,
+    network: &str,
+) -> Result<String, String> {
+    let network = network::Network::parse(network)?;
+    let notes: Vec<Note> =
+        serde_json::from_str(notes_json).map_err(|e| e.to_string())?;
+    let outputs: Vec<Output> =
+        serde_json::from_str(outputs_json).map_err(|e| e.to_string())?;
 
-    let complete = signed.len() >= pkh.threshold;
+    let limits = TransactionLimits::default();
+    let mut tx = build_within(notes, outputs, HashAlgorithm::default(), &limits)?;
+    tx.set_network(network);
+    tx.refresh_spend_hashes()?;
+    tx.validate_network()?;
 
-    SigningStatus {
-        spend_index,
-        threshold: pkh.threshold,
-        signed,
-        pending,
-        complete,
-    }
+    serialize_within(&tx, &limits)
 }
 
-// ============================================================================
-// WASM Interface
-// ============================================================================
-
+// Refuses unconfirmed notes and immature coinbase notes at `height`, which
+// the node would reject anyway; `allow_immature` skips the check for drafts
+// prepared ahead of time.
 #[wasm_bindgen]
-pub fn build_transaction(notes_json: &str, outputs_json: &str) -> Result<String, String> {
+pub fn build_transaction_at(
+    notes_json: &str,
+    outputs_json: &str,
+    height: u64,
+    allow_immature: bool,
+) -> Result<String, String> {
     let notes: Vec<Note> =
         serde_json::from_str(notes_json).map_err(|e| e.to_string())?;
     let outputs: Vec<Output> =
         serde_json::from_str(outputs_json).map_err(|e| e.to_string())?;
 
-    let mut spends = Vec::new();
+    // The draft takes the notes' network; mixed notes fail validation.
+    let network = notes.first().map(|n| n.network).unwrap_or_default();
+    let limits = TransactionLimits::default();
+    let mut tx = build_within(notes, outputs, HashAlgorithm::default(), &limits)?;
+    if !network.is_default() {
+        tx.set_network(network);
+        tx.refresh_spend_hashes()?;
+    }
+    tx.validate_network()?;
+    if !allow_immature {
+        tx.validate_spendability(height)?;
+    }
 
-    for (i, note) in notes.into_iter().enumerate() {
-        note.lock.pkh.validate()?;
+    serialize_within(&tx, &limits)
+}
 
-        let dummy_tx = Transaction {
-            spends: Vec::new(),
-            outputs: outputs.clone(),
-        };
+// What changed from `a_json` (e.g. the draft a signer last approved) to
+// `b_json`: outputs added, removed or re-priced, lock changes, and the fee
+// and total deltas.
+#[wasm_bindgen]
+pub fn diff_transactions(a_json: &str, b_json: &str) -> Result<String, String> {
+    let a: Transaction =
+        serde_json::from_str(a_json).map_err(|e| e.to_string())?;
+    let b: Transaction =
+        serde_json::from_str(b_json).map_err(|e| e.to_string())?;
 
-        let hash = compute_spend_hash(i, &dummy_tx);
+    serde_json::to_string(&a.diff(&b)).map_err(|e| e.to_string())
+}
 
-        spends.push(Spend {
-            note,
-            seeds: Seeds::new(hash),
-        });
-    }
+// Turns `next_json` into the next version of `previous_json` (same lineage,
+// next sequence) and refreshes its spend hashes.
+#[wasm_bindgen]
+pub fn supersede_transaction(previous_json: &str, next_json: &str) -> Result<String, String> {
+    let previous: Transaction =
+        serde_json::from_str(previous_json).map_err(|e| e.to_string())?;
+    let mut next: Transaction =
+        serde_json::from_str(next_json).map_err(|e| e.to_string())?;
+
+    next.supersede(&previous)?;
+    serde_json::to_string(&next).map_err(|e| e.to_string())
+}
 
-    let tx = Transaction { spends, outputs };
-    tx.validate_balance()?;
+// Indices of the drafts to keep: the newest version in each lineage plus
+// every draft without one.
+#[wasm_bindgen]
+pub fn select_newest_versions(txs_json: &str) -> Result<String, String> {
+    let txs: Vec<Transaction> =
+        serde_json::from_str(txs_json).map_err(|e| e.to_string())?;
 
-    serde_json::to_string(&tx).map_err(|e| e.to_string())
+    serde_json::to_string(&lineage::newest_versions(&txs)?).map_err(|e| e.to_string())
+}
+
+// Address prefix, dust limit and default node RPC URL for a network.
+#[wasm_bindgen]
+pub fn get_network_profile(network: &str) -> Result<String, String> {
+    let network = network::Network::parse(network)?;
+    serde_json::to_string(&network.profile()).map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+pub fn get_lock_address(lock_json: &str, network: &str) -> Result<String, String> {
+    let lock: Lock =
+        serde_json::from_str(lock_json).map_err(|e| e.to_string())?;
+
+    Ok(lock.address(network::Network::parse(network)?))
 }
 
 #[wasm_bindgen]
@@ -262,6 +431,54 @@ pub fn get_spend_hash(tx_json: &str, spend_index: usize) -> Result<String, Strin
     Ok(tx.spends[spend_index].seeds.message_hash.clone())
 }
 
+// Recomputes every spend hash from the draft and returns the spends whose
+// stored hash differs, as `[{spend_index, stored, expected}]`. Empty when
+// the draft is intact.
+#[wasm_bindgen]
+pub fn verify_spend_hashes(tx_json: &str) -> Result<String, String> {
+    let tx: Transaction =
+        serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+
+    serde_json::to_string(&tx.spend_hash_mismatches()?).map_err(|e| e.to_string())
+}
+
+// `[{index, hash}]` for every output, for tooling that refers to outputs
+// of a draft.
+#[wasm_bindgen]
+pub fn get_output_ids(tx_json: &str) -> Result<String, String> {
+    let tx: Transaction =
+        serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+
+    serde_json::to_string(&tx.output_ids()).map_err(|e| e.to_string())
+}
+
+// The current index of the output an id names, even if outputs were
+// reordered since it was taken.
+#[wasm_bindgen]
+pub fn resolve_output_id(tx_json: &str, output_id_json: &str) -> Result<usize, String> {
+    let tx: Transaction =
+        serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+    let id: outputs::OutputId =
+        serde_json::from_str(output_id_json).map_err(|e| e.to_string())?;
+
+    tx.resolve_output(&id)
+}
+
+// What a wallet should show a signer before they sign: network, each
+// output's recipient, amount and lock fingerprint, and the total.
+#[wasm_bindgen]
+pub fn get_transaction_summary(tx_json: &str) -> Result<String, String> {
+    let tx: Transaction = serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+    serde_json::to_string(&tx.summary()).map_err(|e| e.to_string())
+}
+
+// Recorded alongside a signature to bind it to the summary shown.
+#[wasm_bindgen]
+pub fn get_summary_hash(tx_json: &str) -> Result<String, String> {
+    let tx: Transaction = serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+    Ok(tx.summary_hash())
+}
+
 #[wasm_bindgen]
 pub fn add_signature(
     tx_json: &str,
@@ -272,6 +489,17 @@ pub fn add_signature(
     let mut tx: Transaction =
         serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
 
+    add_signature_to(&mut tx, spend_index, pubkey, signature)?;
+
+    serde_json::to_string(&tx).map_err(|e| e.to_string())
+}
+
+fn add_signature_to(
+    tx: &mut Transaction,
+    spend_index: usize,
+    pubkey: &str,
+    signature: &str,
+) -> Result<(), String> {
     let pk = PublicKey(pubkey.to_string());
 
     let spend = tx
@@ -279,17 +507,283 @@ pub fn add_signature(
         .get_mut(spend_index)
         .ok_or("Invalid spend index")?;
 
-    if !spend.note.lock.pkh.pubkeys.contains(&pk) {
+    if !spend.signers()?.pubkeys.contains(&pk) {
         return Err("Public key not allowed for this spend".into());
     }
 
     spend
         .seeds
         .add_signature(pk, Signature(signature.to_string()));
+    Ok(())
+}
+
+// ============================================================================
+// Binary Variants
+// ============================================================================
+
+// The same calls over `Uint8Array`s in the binary encoding (see
+// `encoding::to_binary`), for hosts making thousands of calls in a batch.
+// `transaction_to_bytes` / `transaction_from_bytes` convert at the edges.
+
+#[wasm_bindgen]
+pub fn transaction_to_bytes(tx_json: &str) -> Result<Vec<u8>, String> {
+    let tx: Transaction =
+        serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+
+    encoding::to_binary(&tx)
+}
+
+#[wasm_bindgen]
+pub fn transaction_from_bytes(tx_bytes: &[u8]) -> Result<String, String> {
+    let tx: Transaction = encoding::from_binary(tx_bytes)?;
 
     serde_json::to_string(&tx).map_err(|e| e.to_string())
 }
 
+// `notes` and `outputs` are binary-encoded arrays.
+#[wasm_bindgen]
+pub fn build_transaction_bytes(notes: &[u8], outputs: &[u8]) -> Result<Vec<u8>, String> {
+    let notes: Vec<Note> = encoding::from_binary(notes)?;
+    let outputs: Vec<Output> = encoding::from_binary(outputs)?;
+
+    let limits = TransactionLimits::default();
+    let tx = build_within(notes, outputs, HashAlgorithm::default(), &limits)?;
+    tx.validate_network()?;
+    limits.check(&tx).map_err(|e| e.to_json())?;
+
+    encoding::to_binary(&tx)
+}
+
+#[wasm_bindgen]
+pub fn add_signature_bytes(
+    tx_bytes: &[u8],
+    spend_index: usize,
+    pubkey: &str,
+    signature: &str,
+) -> Result<Vec<u8>, String> {
+    let mut tx: Transaction = encoding::from_binary(tx_bytes)?;
+
+    add_signature_to(&mut tx, spend_index, pubkey, signature)?;
+
+    encoding::to_binary(&tx)
+}
+
+// Merkle root of all spend hashes. Signing it once covers every spend, for
+// transactions whose spends all share one signer set.
+#[wasm_bindgen]
+pub fn get_aggregate_digest(tx_json: &str) -> Result<String, String> {
+    let tx: Transaction =
+        serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+
+    tx.aggregate_digest()
+}
+
+#[wasm_bindgen]
+pub fn get_spend_inclusion_proof(tx_json: &str, spend_index: usize) -> Result<String, String> {
+    let tx: Transaction =
+        serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+
+    let proof = tx.spend_inclusion_proof(spend_index)?;
+    serde_json::to_string(&proof).map_err(|e| e.to_string())
+}
+
+// Checks the proof is internally consistent and, when `root` is non-empty,
+// that it ends at that aggregate digest.
+#[wasm_bindgen]
+pub fn verify_spend_inclusion_proof(proof_json: &str, root: &str) -> Result<String, String> {
+    let proof: merkle::MerkleProof =
+        serde_json::from_str(proof_json).map_err(|e| e.to_string())?;
+
+    proof.verify()?;
+    if !root.is_empty() && proof.root != root {
+        return Err("Proof is for a different aggregate digest".into());
+    }
+
+    Ok(format!("Spend hash {} is covered by {}", proof.spend_hash, proof.root))
+}
+
+// Checks that a note exists on the chain ending at `trusted_tip` (a header
+// hash from a checkpoint or an independent source) and reports its depth.
+#[wasm_bindgen]
+pub fn verify_note_inclusion(proof_json: &str, trusted_tip: &str) -> Result<String, String> {
+    let proof: spv::NoteInclusionProof =
+        serde_json::from_str(proof_json).map_err(|e| e.to_string())?;
+
+    let report = proof.verify(trusted_tip)?;
+    serde_json::to_string(&report).map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+pub fn get_note_commitment(note_json: &str, algorithm: &str) -> Result<String, String> {
+    let note: Note =
+        serde_json::from_str(note_json).map_err(|e| e.to_string())?;
+
+    spv::note_commitment(HashAlgorithm::parse(algorithm)?, &note)
+}
+
+// Matches watched locks against compact block filters locally and returns
+// the hashes of the blocks worth fetching, in filter order.
+#[wasm_bindgen]
+pub fn match_block_filters(filters_json: &str, locks_json: &str) -> Result<String, String> {
+    let filters: Vec<filter::BlockFilter> =
+        serde_json::from_str(filters_json).map_err(|e| e.to_string())?;
+    let locks: Vec<Lock> =
+        serde_json::from_str(locks_json).map_err(|e| e.to_string())?;
+
+    let items: Vec<Vec<u8>> = locks.iter().map(filter::lock_filter_item).collect();
+    let mut matched = Vec::new();
+    for f in &filters {
+        if f.matches_any(&items)? {
+            matched.push(f.block_hash.clone());
+        }
+    }
+    serde_json::to_string(&matched).map_err(|e| e.to_string())
+}
+
+// The checks a checkpoint signer set's signatures need, for an external
+// verifier such as WebCrypto.
+#[wasm_bindgen]
+pub fn get_checkpoint_verification_items(signed_json: &str) -> Result<String, String> {
+    let signed: checkpoint::SignedCheckpoint =
+        serde_json::from_str(signed_json).map_err(|e| e.to_string())?;
+
+    serde_json::to_string(&signed.verification_items()).map_err(|e| e.to_string())
+}
+
+// For checkpoint signers auditing a checkpoint against their own note set.
+#[wasm_bindgen]
+pub fn get_checkpoint_state_hash(notes_json: &str) -> Result<String, String> {
+    let notes: Vec<Note> =
+        serde_json::from_str(notes_json).map_err(|e| e.to_string())?;
+
+    checkpoint::state_hash(&notes)
+}
+
+#[wasm_bindgen]
+pub fn add_aggregate_signature(
+    tx_json: &str,
+    pubkey: &str,
+    signature: &str,
+) -> Result<String, String> {
+    let mut tx: Transaction =
+        serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+
+    tx.add_aggregate_signature(
+        PublicKey(pubkey.to_string()),
+        Signature(signature.to_string()),
+    )?;
+
+    serde_json::to_string(&tx).map_err(|e| e.to_string())
+}
+
+// The hash the emergency key signs to attest to an incident on this spend.
+#[wasm_bindgen]
+pub fn get_incident_message(
+    tx_json: &str,
+    spend_index: usize,
+    reporter: &str,
+    reason: &str,
+    timestamp: u64,
+) -> Result<String, String> {
+    let tx: Transaction =
+        serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+
+    let spend = tx.spends.get(spend_index).ok_or("Invalid spend index")?;
+
+    Ok(emergency::incident_message(
+        &PublicKey(reporter.to_string()),
+        reason,
+        timestamp,
+        &spend.seeds.message_hash,
+    ))
+}
+
+#[wasm_bindgen]
+pub fn add_emergency_signature(
+    tx_json: &str,
+    spend_index: usize,
+    signature: &str,
+    incident_json: &str,
+) -> Result<String, String> {
+    let mut tx: Transaction =
+        serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+    let incident: emergency::IncidentAttestation =
+        serde_json::from_str(incident_json).map_err(|e| e.to_string())?;
+
+    tx.spends
+        .get_mut(spend_index)
+        .ok_or("Invalid spend index")?
+        .add_emergency_signature(Signature(signature.to_string()), incident)?;
+
+    serde_json::to_string(&tx).map_err(|e| e.to_string())
+}
+
+// Which lock paths the collected signatures satisfy or are closest to.
+// Time locks are only judged when `height` is given.
+#[wasm_bindgen]
+pub fn analyze_spend_paths(
+    tx_json: &str,
+    spend_index: usize,
+    height: Option<u64>,
+) -> Result<String, String> {
+    let tx: Transaction =
+        serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+
+    let spend = tx.spends.get(spend_index).ok_or("Invalid spend index")?;
+
+    serde_json::to_string(&spend.analyze_paths(height)).map_err(|e| e.to_string())
+}
+
+// Per spend, the minimal sets of outstanding keys that would still complete
+// it once the keys in `declined_json` (a list of pubkeys) are ruled out.
+#[wasm_bindgen]
+pub fn analyze_signer_substitutions(
+    tx_json: &str,
+    declined_json: &str,
+    height: Option<u64>,
+) -> Result<String, String> {
+    let tx: Transaction =
+        serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+    let declined: Vec<PublicKey> =
+        serde_json::from_str(declined_json).map_err(|e| e.to_string())?;
+
+    let analyses: Vec<_> = tx
+        .spends
+        .iter()
+        .map(|spend| spend.signer_substitutions(&declined, height))
+        .collect();
+
+    serde_json::to_string(&analyses).map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+pub fn switch_spend_path(
+    tx_json: &str,
+    spend_index: usize,
+    branch: usize,
+) -> Result<String, String> {
+    let mut tx: Transaction =
+        serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+
+    tx.spends
+        .get_mut(spend_index)
+        .ok_or("Invalid spend index")?
+        .switch_branch(branch)?;
+
+    serde_json::to_string(&tx).map_err(|e| e.to_string())
+}
+
+// Public input for an external threshold prover.
+#[wasm_bindgen]
+pub fn get_threshold_statement(tx_json: &str, spend_index: usize) -> Result<String, String> {
+    let tx: Transaction =
+        serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+
+    let spend = tx.spends.get(spend_index).ok_or("Invalid spend index")?;
+
+    serde_json::to_string(&spend.threshold_statement()?).map_err(|e| e.to_string())
+}
+
 #[wasm_bindgen]
 pub fn get_spend_signing_status(
     tx_json: &str,
@@ -302,7 +796,7 @@ pub fn get_spend_signing_status(
         return Err("Spend index out of bounds".into());
     }
 
-    let status = signing_status(spend_index, &tx);
+    let status = signing_status(spend_index, &tx)?;
     serde_json::to_string(&status).map_err(|e| e.to_string())
 }
 
@@ -311,8 +805,249 @@ pub fn validate_transaction(tx_json: &str) -> Result<String, String> {
     let tx: Transaction =
         serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
 
-    tx.validate_balance()?;
+    tx.validate_structure()?;
     tx.validate_signatures()?;
 
     Ok("Transaction is valid and ready for broadcast".into())
 }
+
+// Checks one spend up to `level` ("lock", "hash" or "signatures"), for a
+// signer screen about to sign it. Signatures are taken as valid, as in
+// `validate_transaction`.
+#[wasm_bindgen]
+pub fn validate_spend(tx_json: &str, spend_index: usize, level: &str) -> Result<String, String> {
+    let tx: Transaction =
+        serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+    let level = verify::SpendCheckLevel::parse(level)?;
+
+    tx.validate_spend(spend_index, level, &verify::UnverifiedSignatures)?;
+
+    Ok(format!("Spend {} is valid", spend_index))
+}
+
+// As `validate_spend` at the "signatures" level, with signatures checked
+// externally (`get_verification_items`).
+#[wasm_bindgen]
+pub fn validate_spend_with_results(
+    tx_json: &str,
+    spend_index: usize,
+    verified_json: &str,
+) -> Result<String, String> {
+    let tx: Transaction =
+        serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+    let verified: Vec<verify::VerificationItem> =
+        serde_json::from_str(verified_json).map_err(|e| e.to_string())?;
+
+    tx.validate_spend(
+        spend_index,
+        verify::SpendCheckLevel::Signatures,
+        &verify::PrecomputedVerifier::new(verified),
+    )?;
+
+    Ok(format!("Spend {} is valid", spend_index))
+}
+
+// Compact interned form for very large drafts: each distinct lock and pubkey
+// is stored once. `unpack_transaction` restores the original exactly.
+#[wasm_bindgen]
+pub fn pack_transaction(tx_json: &str) -> Result<String, String> {
+    let tx: Transaction =
+        serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+
+    serde_json::to_string(&arena::TransactionArena::from_transaction(&tx))
+        .map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+pub fn unpack_transaction(packed_json: &str) -> Result<String, String> {
+    let packed: arena::TransactionArena =
+        serde_json::from_str(packed_json).map_err(|e| e.to_string())?;
+
+    serde_json::to_string(&packed.to_transaction()?).map_err(|e| e.to_string())
+}
+
+// Signature checks for an external verifier (WebCrypto in the browser); pass
+// the ones that passed to `validate_transaction_with_results`.
+#[wasm_bindgen]
+pub fn get_verification_items(tx_json: &str) -> Result<String, String> {
+    let tx: Transaction =
+        serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+
+    serde_json::to_string(&tx.verification_items()?).map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+pub fn validate_transaction_with_results(
+    tx_json: &str,
+    verified_json: &str,
+) -> Result<String, String> {
+    let tx: Transaction =
+        serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+    let verified: Vec<verify::VerificationItem> =
+        serde_json::from_str(verified_json).map_err(|e| e.to_string())?;
+
+    tx.validate_structure()?;
+    tx.verify_signatures(&verify::PrecomputedVerifier::new(verified))?;
+
+    Ok("Transaction is valid and ready for broadcast".into())
+}
+
+// The newest transaction schema this build reads, for cosigner apps to
+// exchange before passing drafts around.
+#[wasm_bindgen]
+pub fn get_schema_version() -> u32 {
+    schema::TRANSACTION_SCHEMA_VERSION
+}
+
+// The schema to write for a cosigner that reads up to `theirs`.
+#[wasm_bindgen]
+pub fn negotiate_schema_version(theirs: u32) -> Result<u32, String> {
+    schema::negotiate(theirs)
+}
+
+// Brings transaction JSON written under an older schema up to the current
+// one. Newer schemas are rejected with the versions involved.
+#[wasm_bindgen]
+pub fn upgrade_transaction(old_json: &str) -> Result<String, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(old_json).map_err(|e| e.to_string())?;
+
+    let tx = schema::upgrade_transaction(value)?;
+
+    serde_json::to_string(&tx).map_err(|e| e.to_string())
+}
+
+// Validates and returns the canonical wire form: the same signature set
+// always yields the same bytes.
+#[wasm_bindgen]
+pub fn finalize_transaction(tx_json: &str) -> Result<String, String> {
+    let tx: Transaction =
+        serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+
+    let tx = tx.finalize()?;
+
+    serde_json::to_string(&tx).map_err(|e| e.to_string())
+}
+
+// Finalized form that reveals only the exercised branch of each lock.
+#[wasm_bindgen]
+pub fn reveal_transaction(tx_json: &str) -> Result<String, String> {
+    let tx: Transaction =
+        serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+
+    let revealed = tx.reveal()?;
+
+    serde_json::to_string(&revealed).map_err(|e| e.to_string())
+}
+
+// `lock_roots_json` lists the `get_lock_commitment` root of each spent note,
+// in spend order.
+#[wasm_bindgen]
+pub fn validate_revealed_transaction(
+    revealed_json: &str,
+    lock_roots_json: &str,
+) -> Result<bool, String> {
+    let revealed: mast::RevealedTransaction =
+        serde_json::from_str(revealed_json).map_err(|e| e.to_string())?;
+    let lock_roots: Vec<String> =
+        serde_json::from_str(lock_roots_json).map_err(|e| e.to_string())?;
+
+    revealed.validate(&lock_roots)?;
+
+    Ok(true)
+}
+
+#[wasm_bindgen]
+pub fn get_lock_commitment(lock_json: &str, algorithm: &str) -> Result<String, String> {
+    let lock: Lock =
+        serde_json::from_str(lock_json).map_err(|e| e.to_string())?;
+
+    lock.branch_root(HashAlgorithm::parse(algorithm)?)
+}
+
+#[wasm_bindgen]
+pub fn get_transaction_id(tx_json: &str) -> Result<String, String> {
+    let tx: Transaction =
+        serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+
+    tx.txid()
+}
+
+// Stable id of the unsigned draft; the same payment built anywhere gets the
+// same id, signed or not.
+#[wasm_bindgen]
+pub fn get_draft_id(tx_json: &str) -> Result<String, String> {
+    let tx: Transaction =
+        serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+
+    tx.draft_id()
+}
+
+// Like `validate_transaction`, additionally checking time locks against the
+// given chain height.
+#[wasm_bindgen]
+pub fn validate_transaction_at(tx_json: &str, height: u64) -> Result<String, String> {
+    let tx: Transaction =
+        serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+
+    tx.validate_structure()?;
+    tx.validate_signatures_at(Some(height))?;
+    tx.validate_timelocks(height)?;
+
+    Ok("Transaction is valid and ready for broadcast".into())
+}
+
+#[wasm_bindgen]
+pub fn enabled_features() -> String {
+    let features: &[&str] = &[
+        #[cfg(feature = "blake3")]
+        "blake3",
+        #[cfg(feature = "worker")]
+        "worker",
+        #[cfg(feature = "policy")]
+        "policy",
+        #[cfg(feature = "wallets")]
+        "wallets",
+        #[cfg(feature = "escrow")]
+        "escrow",
+        #[cfg(feature = "channel")]
+        "channel",
+        #[cfg(feature = "swap")]
+        "swap",
+        #[cfg(feature = "governance")]
+        "governance",
+        #[cfg(feature = "resume")]
+        "resume",
+        #[cfg(feature = "offline")]
+        "offline",
+        #[cfg(feature = "qr")]
+        "qr",
+        #[cfg(feature = "nfc")]
+        "nfc",
+        #[cfg(feature = "peer")]
+        "peer",
+        #[cfg(feature = "coordinator")]
+        "coordinator",
+        #[cfg(feature = "browser-store")]
+        "browser-store",
+        #[cfg(feature = "view-keys")]
+        "view-keys",
+        #[cfg(feature = "jobs")]
+        "jobs",
+        #[cfg(feature = "psbt")]
+        "psbt",
+        #[cfg(feature = "explorer")]
+        "explorer",
+        #[cfg(feature = "mockchain")]
+        "mockchain",
+        #[cfg(feature = "native")]
+        "native",
+        #[cfg(feature = "hash-soft")]
+        "hash-soft",
+        #[cfg(feature = "sandbox")]
+        "sandbox",
+        #[cfg(feature = "scenarios")]
+        "scenarios",
+    ];
+    serde_json::to_string(features).expect("Serialization failed")
+}
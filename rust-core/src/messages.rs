@@ -0,0 +1,238 @@
+use crate::limits::LimitExceeded;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Error Messages
+// ============================================================================
+
+// Errors cross the WASM boundary as English strings. Rather than have every
+// frontend pattern-match them, the table below gives each validation error a
+// stable code and names its parameters; `describe_error` turns a message
+// back into `{code, params}`. A catalog maps codes to templates in another
+// language, with the same `{name}` placeholders, and `render` fills them in.
+// The English catalog is the table itself, so the two cannot drift apart.
+// Messages not in the table get the code `unknown` and are shown as-is.
+
+const MESSAGES: &[(&str, &str)] = &[
+    // Locks
+    ("threshold_zero", "Threshold must be >= 1"),
+    ("threshold_exceeds_keys", "Threshold exceeds number of pubkeys"),
+    ("duplicate_pubkey", "Duplicate public key in multisig set"),
+    ("hash_lock_empty", "Hash lock must list at least one hash"),
+    ("time_lock_empty", "Time lock window is empty"),
+    ("emergency_branch_keys", "Emergency branch must be spendable by a single key"),
+    ("lock_without_conditions", "Lock needs at least one spend condition"),
+    // Spends and signatures
+    ("missing_preimage", "Spend {spend} is missing a hash lock preimage"),
+    ("insufficient_signatures", "Spend {spend} has insufficient signatures"),
+    ("invalid_signer", "Spend {spend} has invalid signer"),
+    ("non_signer_signature", "Spend {spend} has a signature from {pubkey}, not a signer"),
+    ("invalid_signature", "Spend {spend} has invalid signature from {pubkey}"),
+    ("time_lock_not_satisfied", "Spend {spend} time lock not satisfied at height {height}"),
+    ("spend_hash_malformed", "Spend {spend} hash is not a {algorithm} digest"),
+    ("spend_hash_mismatch", "Spend {spend} hash does not match the draft's outputs"),
+    ("summary_mismatch", "Spend {spend} was signed by {pubkey} over a different summary"),
+    ("spend_index_out_of_bounds", "Spend index out of bounds"),
+    ("batch_verification_failed", "Signature batch verification failed"),
+    // Delegations and pre-approvals
+    ("delegation_signature_invalid", "Delegation grant from {pubkey} has an invalid signature"),
+    ("delegation_value_exceeded", "Delegation from {pubkey} only covers transactions under {value}"),
+    ("delegation_expired", "Delegation from {pubkey} expired at height {height}"),
+    ("delegation_unsigned", "Spend {spend} carries a delegation without a signature"),
+    ("preapproval_signature_invalid", "Pre-approval from {pubkey} has an invalid signature"),
+    ("preapproval_other_lock", "Pre-approval from {pubkey} is for another lock"),
+    ("preapproval_output_not_covered", "Output to {recipient} is not covered by the pre-approval from {pubkey}"),
+    ("preapproval_outputs_missing", "Draft is missing outputs pre-approved by {pubkey}"),
+    ("preapproval_expired", "Pre-approval from {pubkey} expired at height {height}"),
+    ("preapproval_slot_mismatch", "Spend {spend} carries a pre-approval that does not fill its slot"),
+    // Emergency spends
+    ("incident_missing", "Spend {spend} uses an emergency key without an incident attestation"),
+    ("incident_outside_emergency", "Spend {spend} carries an incident attestation outside an emergency branch"),
+    ("incident_reporter_unsigned", "Spend {spend} incident reporter has not signed the spend"),
+    ("incident_signature_invalid", "Spend {spend} incident attestation has an invalid signature"),
+    ("incident_reason_missing", "Spend {spend} incident has no reason"),
+    // Outputs, value and networks
+    ("unbalanced", "Input value does not equal output value"),
+    ("spend_network_mismatch", "Spend {spend} note is on {note_network} but the draft is for {network}"),
+    ("output_below_dust", "Output {output} is below the {network} dust limit of {limit}"),
+    ("output_network_mismatch", "Output {output} pays a {address_network} address from a {network} draft"),
+    ("unknown_network", "Unknown network: {network}"),
+    // Formats
+    ("hash_algorithm_unsupported", "Hash algorithm {algorithm} is not supported by this build"),
+    ("unknown_hash_algorithm", "Unknown hash algorithm: {algorithm}"),
+    ("schema_too_new", "Transaction schema version {version} is newer than this app supports ({supported}); update the app"),
+    ("unknown_field", "unknown field `{field}`, {expected}"),
+    ("missing_field", "missing field `{field}`{position}"),
+    // `LimitExceeded`, which arrives as JSON.
+    ("limit_exceeded", "Transaction exceeds the limit on {limit}: {actual} (max {max})"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DescribedError {
+    pub code: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, String>,
+    // The original message, for logs and as a fallback.
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MessageCatalog {
+    pub locale: String,
+    // Code -> template. Codes missing here fall back to the English message.
+    pub messages: BTreeMap<String, String>,
+}
+
+fn placeholders(template: &str) -> Vec<&str> {
+    template
+        .split('{')
+        .skip(1)
+        .filter_map(|rest| rest.split_once('}').map(|(name, _)| name))
+        .collect()
+}
+
+// Literal text and placeholder names, alternating, starting with a literal.
+fn parts(template: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut rest = template;
+    while let Some((literal, tail)) = rest.split_once('{') {
+        let (name, tail) = tail.split_once('}').unwrap_or((tail, ""));
+        parts.push(literal);
+        parts.push(name);
+        rest = tail;
+    }
+    parts.push(rest);
+    parts
+}
+
+// Each placeholder takes the shortest text up to the next literal, or the
+// rest of the message if it is last.
+fn match_template(template: &str, message: &str) -> Option<BTreeMap<String, String>> {
+    let parts = parts(template);
+    let mut rest = message.strip_prefix(parts[0])?;
+    let mut params = BTreeMap::new();
+    for pair in parts[1..].chunks(2) {
+        let (name, literal) = (pair[0], pair[1]);
+        let end = if literal.is_empty() {
+            rest.len()
+        } else {
+            rest.find(literal)?
+        };
+        params.insert(name.to_string(), rest[..end].to_string());
+        rest = &rest[end + literal.len()..];
+    }
+    rest.is_empty().then_some(params)
+}
+
+pub fn describe_error(message: &str) -> DescribedError {
+    // Limit errors carry their fields as JSON already; `limit` is given as
+    // the machine name (`serialized_size`) rather than English.
+    if let Ok(exceeded) = serde_json::from_str::<LimitExceeded>(message) {
+        let kind = serde_json::to_value(exceeded.limit).expect("Serialization failed");
+        let params = [
+            ("limit", kind.as_str().unwrap_or_default().to_string()),
+            ("actual", exceeded.actual.to_string()),
+            ("max", exceeded.max.to_string()),
+        ];
+        return DescribedError {
+            code: "limit_exceeded".into(),
+            params: params.map(|(k, v)| (k.to_string(), v)).into(),
+            message: exceeded.to_string(),
+        };
+    }
+    let message = message.to_string();
+    for (code, template) in MESSAGES {
+        if let Some(params) = match_template(template, &message) {
+            return DescribedError {
+                code: code.to_string(),
+                params,
+                message,
+            };
+        }
+    }
+    DescribedError {
+        code: "unknown".into(),
+        params: BTreeMap::new(),
+        message,
+    }
+}
+
+impl MessageCatalog {
+    pub fn english() -> Self {
+        Self {
+            locale: "en".into(),
+            messages: MESSAGES
+                .iter()
+                .map(|(code, template)| (code.to_string(), template.to_string()))
+                .collect(),
+        }
+    }
+
+    // Every code must exist and every template must use exactly the
+    // placeholders of the English one, so a translation cannot drop or
+    // invent a parameter.
+    pub fn validate(&self) -> Result<(), String> {
+        for (code, template) in &self.messages {
+            let (_, english) = MESSAGES
+                .iter()
+                .find(|(c, _)| c == code)
+                .ok_or_else(|| format!("Unknown message code {}", code))?;
+            let mut expected = placeholders(english);
+            let mut found = placeholders(template);
+            expected.sort_unstable();
+            found.sort_unstable();
+            if expected != found {
+                return Err(format!(
+                    "Message {} must use the placeholders {{{}}}",
+                    code,
+                    expected.join("}, {")
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn render(&self, error: &DescribedError) -> String {
+        let Some(template) = self.messages.get(&error.code) else {
+            return error.message.clone();
+        };
+        let mut out = String::new();
+        for (i, part) in parts(template).into_iter().enumerate() {
+            if i % 2 == 0 {
+                out.push_str(part);
+            } else {
+                out.push_str(error.params.get(part).map_or("", String::as_str));
+            }
+        }
+        out
+    }
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+// Returns `{code, params, message}` for an error returned by any call.
+#[wasm_bindgen]
+pub fn describe_error_message(message: &str) -> Result<String, String> {
+    serde_json::to_string(&describe_error(message)).map_err(|e| e.to_string())
+}
+
+// The English catalog: every code with its template, as a starting point
+// for translations.
+#[wasm_bindgen]
+pub fn get_message_catalog() -> Result<String, String> {
+    serde_json::to_string(&MessageCatalog::english()).map_err(|e| e.to_string())
+}
+
+// Renders an error message in the catalog's language.
+#[wasm_bindgen]
+pub fn render_error_message(message: &str, catalog_json: &str) -> Result<String, String> {
+    let catalog: MessageCatalog = serde_json::from_str(catalog_json).map_err(|e| e.to_string())?;
+    catalog.validate()?;
+    Ok(catalog.render(&describe_error(message)))
+}
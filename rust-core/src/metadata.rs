@@ -0,0 +1,233 @@
+use crate::policy::note_key;
+use crate::{Lock, NoteName};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// ============================================================================
+// Wallet Metadata
+// ============================================================================
+
+// Labels, contacts, note tags and the wallet's display name, edited
+// independently on every device that holds the wallet. Each entry is a
+// last-writer-wins register stamped with `(at, device)`, and removals leave
+// a tombstone, so merging two copies in any order, any number of times,
+// gives the same result on every device and never loses an edit to an entry
+// the other side did not touch. A new local edit is always stamped after whatever the
+// device has already seen, so a slow clock cannot make it lose to history.
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Stamp {
+    pub at: u64,
+    pub device: String,
+}
+
+// `value` is None once removed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Entry<T> {
+    pub value: Option<T>,
+    pub stamp: Stamp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Contact {
+    pub name: String,
+    pub lock: Lock,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub memo: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SupersededEdit {
+    pub key: String,
+    pub kept: Stamp,
+    pub dropped: Stamp,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MetadataMergeReport {
+    // Keys whose value changed here.
+    pub updated: Vec<String>,
+    // Incoming edits that lost to a later edit made here.
+    pub superseded: Vec<SupersededEdit>,
+}
+
+// Keys are free-form for labels (a note as `first/last`, a draft hash, a
+// txid), lock fingerprints for contacts, and `first/last` for note tags.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WalletMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<Entry<String>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, Entry<String>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub contacts: BTreeMap<String, Entry<Contact>>,
+    // Tags that set a note's funds apart ("grant funds"), for coin
+    // selection and `restricted_tag` policies.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, Entry<Vec<String>>>,
+}
+
+fn next_stamp<T>(current: Option<&Entry<T>>, device: &str, at: u64) -> Stamp {
+    let at = match current {
+        Some(entry) if entry.stamp.at >= at => entry.stamp.at + 1,
+        _ => at,
+    };
+    Stamp {
+        at,
+        device: device.to_string(),
+    }
+}
+
+// Keeps whichever entry is later.
+fn merge_entry<T: Clone + PartialEq>(
+    key: &str,
+    ours: &mut Option<Entry<T>>,
+    theirs: &Entry<T>,
+    report: &mut MetadataMergeReport,
+) {
+    match ours {
+        Some(mine) if mine.stamp >= theirs.stamp => {
+            if mine.stamp != theirs.stamp && mine.value != theirs.value {
+                report.superseded.push(SupersededEdit {
+                    key: key.to_string(),
+                    kept: mine.stamp.clone(),
+                    dropped: theirs.stamp.clone(),
+                });
+            }
+        }
+        Some(mine) => {
+            if mine.value != theirs.value {
+                report.updated.push(key.to_string());
+            }
+            *mine = theirs.clone();
+        }
+        None => {
+            *ours = Some(theirs.clone());
+            report.updated.push(key.to_string());
+        }
+    }
+}
+
+fn merge_map<T: Clone + PartialEq>(
+    prefix: &str,
+    ours: &mut BTreeMap<String, Entry<T>>,
+    theirs: &BTreeMap<String, Entry<T>>,
+    report: &mut MetadataMergeReport,
+) {
+    for (key, entry) in theirs {
+        let mut slot = ours.remove(key);
+        merge_entry(&format!("{}{}", prefix, key), &mut slot, entry, report);
+        ours.insert(key.clone(), slot.expect("merge always fills the slot"));
+    }
+}
+
+impl WalletMetadata {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn set_name(&mut self, name: String, device: &str, at: u64) {
+        self.name = Some(Entry {
+            stamp: next_stamp(self.name.as_ref(), device, at),
+            value: Some(name),
+        });
+    }
+
+    // An empty label removes it.
+    pub fn set_label(&mut self, key: &str, label: String, device: &str, at: u64) {
+        let stamp = next_stamp(self.labels.get(key), device, at);
+        let value = (!label.is_empty()).then_some(label);
+        self.labels.insert(key.to_string(), Entry { value, stamp });
+    }
+
+    pub fn label(&self, key: &str) -> Option<&str> {
+        self.labels.get(key)?.value.as_deref()
+    }
+
+    pub fn set_contact(&mut self, contact: Contact, device: &str, at: u64) -> String {
+        let key = contact.lock.fingerprint();
+        let stamp = next_stamp(self.contacts.get(&key), device, at);
+        self.contacts.insert(
+            key.clone(),
+            Entry {
+                value: Some(contact),
+                stamp,
+            },
+        );
+        key
+    }
+
+    pub fn remove_contact(&mut self, key: &str, device: &str, at: u64) -> Result<(), String> {
+        let entry = self
+            .contacts
+            .get(key)
+            .filter(|e| e.value.is_some())
+            .ok_or_else(|| format!("Unknown contact {}", key))?;
+        let stamp = next_stamp(Some(entry), device, at);
+        self.contacts
+            .insert(key.to_string(), Entry { value: None, stamp });
+        Ok(())
+    }
+
+    pub fn contacts(&self) -> impl Iterator<Item = (&String, &Contact)> {
+        self.contacts
+            .iter()
+            .filter_map(|(k, e)| e.value.as_ref().map(|c| (k, c)))
+    }
+
+    // Replaces the note's tags; an empty list untags it. Tags are trimmed,
+    // sorted and deduplicated.
+    pub fn set_note_tags(
+        &mut self,
+        name: &NoteName,
+        tags: Vec<String>,
+        device: &str,
+        at: u64,
+    ) -> Result<(), String> {
+        let mut tags: Vec<String> = tags.iter().map(|t| t.trim().to_string()).collect();
+        if tags.iter().any(String::is_empty) {
+            return Err("Note tags must not be empty".into());
+        }
+        tags.sort();
+        tags.dedup();
+        let key = note_key(name);
+        let stamp = next_stamp(self.tags.get(&key), device, at);
+        let value = (!tags.is_empty()).then_some(tags);
+        self.tags.insert(key, Entry { value, stamp });
+        Ok(())
+    }
+
+    pub fn tags_of(&self, name: &NoteName) -> &[String] {
+        self.tags
+            .get(&note_key(name))
+            .and_then(|e| e.value.as_deref())
+            .unwrap_or_default()
+    }
+
+    // Every tagged note's tags, keyed by `note_key`.
+    pub fn note_tags(&self) -> BTreeMap<String, Vec<String>> {
+        self.tags
+            .iter()
+            .filter_map(|(k, e)| e.value.clone().map(|tags| (k.clone(), tags)))
+            .collect()
+    }
+
+    // Reported keys are `name`, `label:<key>`, `contact:<key>` or
+    // `tags:<key>`.
+    pub fn merge(&mut self, other: &WalletMetadata) -> MetadataMergeReport {
+        let mut report = MetadataMergeReport::default();
+        if let Some(theirs) = &other.name {
+            merge_entry("name", &mut self.name, theirs, &mut report);
+        }
+        merge_map("label:", &mut self.labels, &other.labels, &mut report);
+        merge_map("contact:", &mut self.contacts, &other.contacts, &mut report);
+        merge_map("tags:", &mut self.tags, &other.tags, &mut report);
+        report
+    }
+}
@@ -0,0 +1,447 @@
+use crate::checkpoint::{state_hash, Checkpoint};
+use crate::filter::{lock_filter_item, BlockFilter};
+use crate::hashing::HashAlgorithm;
+use crate::merkle::{merkle_root, MerkleProof};
+use crate::network::Network;
+use crate::spv::{note_commitment, BlockHeader, NoteInclusionProof, EMPTY_ROOT};
+use crate::verify::{SignatureVerifier, UnverifiedSignatures};
+use crate::{Lock, Note, NoteName, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Mock Chain
+// ============================================================================
+
+// An in-memory chain for end-to-end tests: credit notes to a lock, build and
+// sign against them, broadcast, mine, and reorg, all without a node.
+// Broadcasts get the same checks as `finalize_transaction` plus double-spend
+// and time-lock checks against the chain. Chain state is recomputed from the
+// blocks, so a reorg is just dropping blocks.
+//
+// Notes created by a transaction are named `<txid>/<output index>`; credited
+// notes are named `credit/<n>`. Unspent notes carry the height they were
+// confirmed at, and broadcasts are held to the network's coinbase maturity.
+// Each block commits to the notes it creates (credits, then outputs in
+// transaction order), so tests can check light-client inclusion proofs.
+//
+// A broadcast that continues a pending draft's lineage with a higher
+// sequence replaces it in the mempool, as a node honoring replacement would.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Block {
+    pub height: u64,
+    pub credits: Vec<Note>,
+    pub transactions: Vec<Transaction>,
+}
+
+pub struct MockChain {
+    network: Network,
+    blocks: Vec<Block>,
+    pending_credits: Vec<Note>,
+    mempool: Vec<Transaction>,
+    credits_issued: u64,
+    verifier: Box<dyn SignatureVerifier>,
+}
+
+#[derive(Default)]
+struct ChainState {
+    unspent: BTreeMap<(String, String), Note>,
+    confirmed: BTreeMap<String, u64>,
+}
+
+fn note_key(name: &NoteName) -> (String, String) {
+    (name.first.clone(), name.last.clone())
+}
+
+fn output_notes(tx: &Transaction, txid: &str) -> Vec<Note> {
+    tx.outputs
+        .iter()
+        .enumerate()
+        .map(|(i, output)| Note {
+            name: NoteName {
+                first: txid.to_string(),
+                last: i.to_string(),
+            },
+            value: output.value,
+            lock: output.lock.clone(),
+            network: tx.network,
+            coinbase: false,
+            confirmed_at: None,
+        })
+        .collect()
+}
+
+fn created_notes(block: &Block) -> Result<Vec<Note>, String> {
+    let mut notes = block.credits.clone();
+    for tx in &block.transactions {
+        notes.extend(output_notes(tx, &tx.txid()?));
+    }
+    Ok(notes)
+}
+
+fn note_root(notes: &[Note]) -> Result<String, String> {
+    if notes.is_empty() {
+        return Ok(EMPTY_ROOT.into());
+    }
+    let commitments = notes
+        .iter()
+        .map(|n| note_commitment(HashAlgorithm::Sha256, n))
+        .collect::<Result<Vec<_>, _>>()?;
+    merkle_root(HashAlgorithm::Sha256, &commitments)
+}
+
+impl MockChain {
+    pub fn new(network: Network) -> Self {
+        Self {
+            network,
+            blocks: Vec::new(),
+            pending_credits: Vec::new(),
+            mempool: Vec::new(),
+            credits_issued: 0,
+            verifier: Box::new(UnverifiedSignatures),
+        }
+    }
+
+    pub fn with_verifier(mut self, verifier: impl SignatureVerifier + 'static) -> Self {
+        self.verifier = Box::new(verifier);
+        self
+    }
+
+    pub fn height(&self) -> u64 {
+        self.blocks.len() as u64
+    }
+
+    fn state(&self) -> Result<ChainState, String> {
+        let mut state = ChainState::default();
+        for block in &self.blocks {
+            for note in &block.credits {
+                let note = Note {
+                    confirmed_at: Some(block.height),
+                    ..note.clone()
+                };
+                state.unspent.insert(note_key(&note.name), note);
+            }
+            for tx in &block.transactions {
+                let txid = tx.txid()?;
+                for spend in &tx.spends {
+                    state.unspent.remove(&note_key(&spend.note.name));
+                }
+                for mut note in output_notes(tx, &txid) {
+                    note.confirmed_at = Some(block.height);
+                    state.unspent.insert(note_key(&note.name), note);
+                }
+                state.confirmed.insert(txid, block.height);
+            }
+        }
+        Ok(state)
+    }
+
+    // The note confirms in the next block.
+    pub fn credit(&mut self, lock: &Lock, value: u64) -> Note {
+        self.issue(lock, value, false)
+    }
+
+    // Like `credit`, but subject to coinbase maturity.
+    pub fn credit_coinbase(&mut self, lock: &Lock, value: u64) -> Note {
+        self.issue(lock, value, true)
+    }
+
+    fn issue(&mut self, lock: &Lock, value: u64, coinbase: bool) -> Note {
+        self.credits_issued += 1;
+        let note = Note {
+            name: NoteName {
+                first: "credit".into(),
+                last: self.credits_issued.to_string(),
+            },
+            value,
+            lock: lock.clone(),
+            network: self.network,
+            coinbase,
+            confirmed_at: None,
+        };
+        self.pending_credits.push(note.clone());
+        note
+    }
+
+    pub fn broadcast(&mut self, tx: &Transaction) -> Result<String, String> {
+        let height = self.height() + 1;
+        let tx = tx.finalize_at(Some(height))?;
+        if tx.network != self.network {
+            return Err(format!(
+                "Transaction is for {} but this chain is {}",
+                tx.network.name(),
+                self.network.name()
+            ));
+        }
+        tx.verify_signatures_at(self.verifier.as_ref(), Some(height))?;
+        tx.validate_timelocks(height)?;
+
+        let txid = tx.txid()?;
+        let state = self.state()?;
+        if state.confirmed.contains_key(&txid) {
+            return Err("Transaction is already confirmed".into());
+        }
+        if self
+            .mempool
+            .iter()
+            .any(|t| t.txid().as_deref() == Ok(txid.as_str()))
+        {
+            return Ok(txid);
+        }
+
+        // A newer version of a pending draft replaces it; an older one is
+        // refused.
+        let replaced = match &tx.lineage {
+            Some(lineage) => self
+                .mempool
+                .iter()
+                .position(|t| t.effective_lineage().id == lineage.id),
+            None => None,
+        };
+        if let Some(j) = replaced {
+            let pending = self.mempool[j].effective_lineage().sequence;
+            if pending >= tx.effective_lineage().sequence {
+                return Err(format!(
+                    "Sequence {} of this draft is already pending",
+                    pending
+                ));
+            }
+        }
+
+        for (i, spend) in tx.spends.iter().enumerate() {
+            // Compared without provenance, which the draft may not carry.
+            let note = match state.unspent.get(&note_key(&spend.note.name)) {
+                Some(n) if n.value == spend.note.value && n.lock == spend.note.lock => n,
+                _ => return Err(format!("Spend {} note is not unspent on this chain", i)),
+            };
+            note.spendable_at(self.height() + 1)
+                .map_err(|e| format!("Spend {}: {}", i, e))?;
+            let pending = self
+                .mempool
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| Some(*j) != replaced)
+                .flat_map(|(_, t)| &t.spends)
+                .any(|s| s.note.name == spend.note.name);
+            if pending {
+                return Err(format!("Spend {} note is already spent in the mempool", i));
+            }
+        }
+
+        if let Some(j) = replaced {
+            self.mempool.remove(j);
+        }
+        self.mempool.push(tx);
+        Ok(txid)
+    }
+
+    pub fn mine(&mut self) -> &Block {
+        let block = Block {
+            height: self.height() + 1,
+            credits: std::mem::take(&mut self.pending_credits),
+            transactions: std::mem::take(&mut self.mempool),
+        };
+        self.blocks.push(block);
+        self.blocks.last().expect("just pushed")
+    }
+
+    // Drops the last `depth` blocks; their credits and transactions go back
+    // to pending, as a node would return them to its mempool.
+    pub fn reorg(&mut self, depth: u64) -> Result<(), String> {
+        if depth > self.height() {
+            return Err(format!("Chain is only {} blocks high", self.height()));
+        }
+        let keep = self.blocks.len() - depth as usize;
+        let mut credits = Vec::new();
+        let mut transactions = Vec::new();
+        for block in self.blocks.drain(keep..) {
+            credits.extend(block.credits);
+            transactions.extend(block.transactions);
+        }
+        credits.append(&mut self.pending_credits);
+        transactions.append(&mut self.mempool);
+        self.pending_credits = credits;
+        self.mempool = transactions;
+        Ok(())
+    }
+
+    // Discards everything not yet mined, e.g. to simulate a reorg that
+    // evicts a transaction.
+    pub fn clear_mempool(&mut self) {
+        self.mempool.clear();
+    }
+
+    pub fn unspent_notes(&self, lock: &Lock) -> Result<Vec<Note>, String> {
+        Ok(self
+            .state()?
+            .unspent
+            .into_values()
+            .filter(|n| &n.lock == lock)
+            .collect())
+    }
+
+    pub fn balance(&self, lock: &Lock) -> Result<u64, String> {
+        Ok(self.unspent_notes(lock)?.iter().map(|n| n.value).sum())
+    }
+
+    pub fn headers(&self) -> Result<Vec<BlockHeader>, String> {
+        let mut headers: Vec<BlockHeader> = Vec::with_capacity(self.blocks.len());
+        for block in &self.blocks {
+            let parent = match headers.last() {
+                Some(h) => h.hash(HashAlgorithm::Sha256)?,
+                None => EMPTY_ROOT.into(),
+            };
+            headers.push(BlockHeader {
+                height: block.height,
+                parent,
+                note_root: note_root(&created_notes(block)?)?,
+            });
+        }
+        Ok(headers)
+    }
+
+    pub fn tip_hash(&self) -> Result<String, String> {
+        match self.headers()?.last() {
+            Some(header) => header.hash(HashAlgorithm::Sha256),
+            None => Ok(EMPTY_ROOT.into()),
+        }
+    }
+
+    // Unsigned; tests sign `message_hash` with whatever scheme they use.
+    pub fn checkpoint(&self) -> Result<Checkpoint, String> {
+        let notes: Vec<Note> = self.state()?.unspent.into_values().collect();
+        Ok(Checkpoint {
+            network: self.network,
+            height: self.height(),
+            block_hash: self.tip_hash()?,
+            state_hash: state_hash(&notes)?,
+        })
+    }
+
+    // Over the locks of every note the block creates or spends.
+    pub fn block_filter(&self, height: u64) -> Result<BlockFilter, String> {
+        let index = height
+            .checked_sub(1)
+            .filter(|i| *i < self.height())
+            .ok_or_else(|| format!("No block at height {}", height))?;
+        let block = &self.blocks[index as usize];
+        let mut items: Vec<Vec<u8>> = created_notes(block)?
+            .iter()
+            .map(|n| lock_filter_item(&n.lock))
+            .collect();
+        for tx in &block.transactions {
+            items.extend(tx.spends.iter().map(|s| lock_filter_item(&s.note.lock)));
+        }
+        let header = &self.headers()?[index as usize];
+        BlockFilter::build(&header.hash(HashAlgorithm::Sha256)?, &items)
+    }
+
+    // What an honest node would serve for a confirmed note, spent or not.
+    pub fn prove_note(&self, name: &NoteName) -> Result<NoteInclusionProof, String> {
+        for (i, block) in self.blocks.iter().enumerate() {
+            let notes = created_notes(block)?;
+            if let Some(index) = notes.iter().position(|n| &n.name == name) {
+                let commitments = notes
+                    .iter()
+                    .map(|n| note_commitment(HashAlgorithm::Sha256, n))
+                    .collect::<Result<Vec<_>, _>>()?;
+                return Ok(NoteInclusionProof {
+                    note: Note {
+                        confirmed_at: Some(block.height),
+                        ..notes[index].clone()
+                    },
+                    path: MerkleProof::generate(HashAlgorithm::Sha256, &commitments, index)?,
+                    headers: self.headers()?.split_off(i),
+                });
+            }
+        }
+        Err(format!(
+            "Note {}/{} is not on this chain",
+            name.first, name.last
+        ))
+    }
+
+    // 0 while in the mempool, None if unknown.
+    pub fn confirmations(&self, txid: &str) -> Result<Option<u64>, String> {
+        if let Some(height) = self.state()?.confirmed.get(txid) {
+            return Ok(Some(self.height() - height + 1));
+        }
+        let pending = self.mempool.iter().any(|t| t.txid().as_deref() == Ok(txid));
+        Ok(pending.then_some(0))
+    }
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+#[wasm_bindgen(js_name = MockChain)]
+pub struct WasmMockChain(MockChain);
+
+#[wasm_bindgen(js_class = MockChain)]
+impl WasmMockChain {
+    #[wasm_bindgen(constructor)]
+    pub fn new(network: &str) -> Result<WasmMockChain, String> {
+        Ok(Self(MockChain::new(Network::parse(network)?)))
+    }
+
+    pub fn height(&self) -> u64 {
+        self.0.height()
+    }
+
+    pub fn credit(&mut self, lock_json: &str, value: u64) -> Result<String, String> {
+        let lock: Lock = serde_json::from_str(lock_json).map_err(|e| e.to_string())?;
+        serde_json::to_string(&self.0.credit(&lock, value)).map_err(|e| e.to_string())
+    }
+
+    pub fn broadcast(&mut self, tx_json: &str) -> Result<String, String> {
+        let tx: Transaction = serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+        self.0.broadcast(&tx)
+    }
+
+    pub fn credit_coinbase(&mut self, lock_json: &str, value: u64) -> Result<String, String> {
+        let lock: Lock = serde_json::from_str(lock_json).map_err(|e| e.to_string())?;
+        serde_json::to_string(&self.0.credit_coinbase(&lock, value)).map_err(|e| e.to_string())
+    }
+
+    pub fn mine(&mut self) -> u64 {
+        self.0.mine().height
+    }
+
+    pub fn reorg(&mut self, depth: u64) -> Result<(), String> {
+        self.0.reorg(depth)
+    }
+
+    pub fn clear_mempool(&mut self) {
+        self.0.clear_mempool();
+    }
+
+    pub fn unspent_notes(&self, lock_json: &str) -> Result<String, String> {
+        let lock: Lock = serde_json::from_str(lock_json).map_err(|e| e.to_string())?;
+        serde_json::to_string(&self.0.unspent_notes(&lock)?).map_err(|e| e.to_string())
+    }
+
+    pub fn confirmations(&self, txid: &str) -> Result<Option<u64>, String> {
+        self.0.confirmations(txid)
+    }
+
+    pub fn tip_hash(&self) -> Result<String, String> {
+        self.0.tip_hash()
+    }
+
+    pub fn checkpoint(&self) -> Result<String, String> {
+        serde_json::to_string(&self.0.checkpoint()?).map_err(|e| e.to_string())
+    }
+
+    pub fn block_filter(&self, height: u64) -> Result<String, String> {
+        serde_json::to_string(&self.0.block_filter(height)?).map_err(|e| e.to_string())
+    }
+
+    pub fn prove_note(&self, name_json: &str) -> Result<String, String> {
+        let name: NoteName = serde_json::from_str(name_json).map_err(|e| e.to_string())?;
+        serde_json::to_string(&self.0.prove_note(&name)?).map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,311 @@
+use crate::offline::{BundleSignature, SignatureBundle, SigningBundle, BUNDLE_FORMAT_VERSION};
+use crate::{PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// NFC Payloads
+// ============================================================================
+
+// Tap-to-sign carries the same exchange as offline bundles, reduced to what
+// fits on a tag: one NDEF external-type record whose payload is binary.
+//
+//   u8  version           (1)
+//   u8  kind              (1 = request, 2 = response)
+//   32  draft hash
+//   u8  signer length, then signer bytes
+//   u8  entry count, then per entry:
+//         u16 spend index
+//         32  message hash
+//         u16 signature length, then signature bytes   (responses only)
+//
+// Integers are big-endian. Hashes are the raw 32 bytes of the hex digests.
+
+pub const NDEF_RECORD_TYPE: &str = "nockchain.org:msig";
+const PAYLOAD_VERSION: u8 = 1;
+const KIND_REQUEST: u8 = 1;
+const KIND_RESPONSE: u8 = 2;
+
+// Usable NDEF bytes on common NTAG21x tags.
+pub const NTAG213_CAPACITY: usize = 137;
+pub const NTAG215_CAPACITY: usize = 496;
+pub const NTAG216_CAPACITY: usize = 868;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NfcRequestEntry {
+    pub spend_index: usize,
+    pub message_hash: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NfcRequest {
+    pub draft: String,
+    pub signer: PublicKey,
+    pub spends: Vec<NfcRequestEntry>,
+}
+
+impl From<&SigningBundle> for NfcRequest {
+    fn from(bundle: &SigningBundle) -> Self {
+        Self {
+            draft: bundle.draft.clone(),
+            signer: bundle.signer.clone(),
+            spends: bundle
+                .spends
+                .iter()
+                .map(|s| NfcRequestEntry {
+                    spend_index: s.spend_index,
+                    message_hash: s.message_hash.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, n: usize, what: &str) -> Result<(), String> {
+        let n = u8::try_from(n).map_err(|_| format!("Too many {} for an NFC payload", what))?;
+        self.0.push(n);
+        Ok(())
+    }
+
+    fn u16(&mut self, n: usize, what: &str) -> Result<(), String> {
+        let n = u16::try_from(n).map_err(|_| format!("{} too large for an NFC payload", what))?;
+        self.0.extend_from_slice(&n.to_be_bytes());
+        Ok(())
+    }
+
+    fn hash(&mut self, hex_digest: &str) -> Result<(), String> {
+        let bytes = hex::decode(hex_digest).map_err(|e| e.to_string())?;
+        if bytes.len() != 32 {
+            return Err("NFC payloads carry 32-byte hashes only".into());
+        }
+        self.0.extend_from_slice(&bytes);
+        Ok(())
+    }
+
+    fn bytes8(&mut self, bytes: &[u8], what: &str) -> Result<(), String> {
+        self.u8(bytes.len(), what)?;
+        self.0.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    fn bytes16(&mut self, bytes: &[u8], what: &str) -> Result<(), String> {
+        self.u16(bytes.len(), what)?;
+        self.0.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        if self.0.len() < n {
+            return Err("NFC payload is truncated".into());
+        }
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<usize, String> {
+        Ok(self.take(1)?[0] as usize)
+    }
+
+    fn u16(&mut self) -> Result<usize, String> {
+        let b = self.take(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]) as usize)
+    }
+
+    fn hash(&mut self) -> Result<String, String> {
+        Ok(hex::encode(self.take(32)?))
+    }
+
+    fn string8(&mut self) -> Result<String, String> {
+        let n = self.u8()?;
+        String::from_utf8(self.take(n)?.to_vec()).map_err(|e| e.to_string())
+    }
+
+    fn string16(&mut self) -> Result<String, String> {
+        let n = self.u16()?;
+        String::from_utf8(self.take(n)?.to_vec()).map_err(|e| e.to_string())
+    }
+}
+
+fn header(w: &mut Writer, kind: u8, draft: &str, signer: &PublicKey) -> Result<(), String> {
+    w.0.push(PAYLOAD_VERSION);
+    w.0.push(kind);
+    w.hash(draft)?;
+    w.bytes8(signer.0.as_bytes(), "signer bytes")
+}
+
+fn read_header(r: &mut Reader<'_>, kind: u8) -> Result<(String, PublicKey), String> {
+    let version = r.u8()? as u8;
+    if version != PAYLOAD_VERSION {
+        return Err(format!("Unsupported NFC payload version {}", version));
+    }
+    if r.u8()? as u8 != kind {
+        return Err("Unexpected NFC payload kind".into());
+    }
+    Ok((r.hash()?, PublicKey(r.string8()?)))
+}
+
+pub fn encode_request(request: &NfcRequest) -> Result<Vec<u8>, String> {
+    let mut w = Writer(Vec::new());
+    header(&mut w, KIND_REQUEST, &request.draft, &request.signer)?;
+    w.u8(request.spends.len(), "spends")?;
+    for entry in &request.spends {
+        w.u16(entry.spend_index, "Spend index")?;
+        w.hash(&entry.message_hash)?;
+    }
+    Ok(w.0)
+}
+
+pub fn decode_request(payload: &[u8]) -> Result<NfcRequest, String> {
+    let mut r = Reader(payload);
+    let (draft, signer) = read_header(&mut r, KIND_REQUEST)?;
+    let spends = (0..r.u8()?)
+        .map(|_| {
+            Ok(NfcRequestEntry {
+                spend_index: r.u16()?,
+                message_hash: r.hash()?,
+            })
+        })
+        .collect::<Result<_, String>>()?;
+    Ok(NfcRequest {
+        draft,
+        signer,
+        spends,
+    })
+}
+
+pub fn encode_response(bundle: &SignatureBundle) -> Result<Vec<u8>, String> {
+    let mut w = Writer(Vec::new());
+    header(&mut w, KIND_RESPONSE, &bundle.draft, &bundle.signer)?;
+    w.u8(bundle.signatures.len(), "signatures")?;
+    for item in &bundle.signatures {
+        w.u16(item.spend_index, "Spend index")?;
+        w.hash(&item.message_hash)?;
+        w.bytes16(item.signature.0.as_bytes(), "Signature")?;
+    }
+    Ok(w.0)
+}
+
+pub fn decode_response(payload: &[u8]) -> Result<SignatureBundle, String> {
+    let mut r = Reader(payload);
+    let (draft, signer) = read_header(&mut r, KIND_RESPONSE)?;
+    let signatures = (0..r.u8()?)
+        .map(|_| {
+            Ok(BundleSignature {
+                spend_index: r.u16()?,
+                message_hash: r.hash()?,
+                signature: Signature(r.string16()?),
+            })
+        })
+        .collect::<Result<_, String>>()?;
+    Ok(SignatureBundle {
+        format: BUNDLE_FORMAT_VERSION,
+        draft,
+        signer,
+        signatures,
+    })
+}
+
+// ============================================================================
+// NDEF Framing
+// ============================================================================
+
+// A single-record NDEF message (MB and ME set) with an NFC Forum external
+// type. Short-record form is used whenever the payload allows it.
+pub fn ndef_message(payload: &[u8], capacity: usize) -> Result<Vec<u8>, String> {
+    const MB_ME: u8 = 0xC0;
+    const SR: u8 = 0x10;
+    const TNF_EXTERNAL: u8 = 0x04;
+
+    let record_type = NDEF_RECORD_TYPE.as_bytes();
+    let mut out = Vec::with_capacity(payload.len() + record_type.len() + 6);
+    if payload.len() <= u8::MAX as usize {
+        out.push(MB_ME | SR | TNF_EXTERNAL);
+        out.push(record_type.len() as u8);
+        out.push(payload.len() as u8);
+    } else {
+        out.push(MB_ME | TNF_EXTERNAL);
+        out.push(record_type.len() as u8);
+        out.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    }
+    out.extend_from_slice(record_type);
+    out.extend_from_slice(payload);
+
+    if out.len() > capacity {
+        return Err(format!(
+            "NFC message is {} bytes but the tag holds {}; sign fewer spends per tap",
+            out.len(),
+            capacity
+        ));
+    }
+    Ok(out)
+}
+
+// Finds our record in an NDEF message and returns its payload.
+pub fn ndef_payload(message: &[u8]) -> Result<&[u8], String> {
+    let mut r = Reader(message);
+    while !r.0.is_empty() {
+        let flags = r.u8()? as u8;
+        let type_len = r.u8()?;
+        let payload_len = if flags & 0x10 != 0 {
+            r.u8()?
+        } else {
+            let b = r.take(4)?;
+            u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize
+        };
+        let id_len = if flags & 0x08 != 0 { r.u8()? } else { 0 };
+        let record_type = r.take(type_len)?;
+        r.take(id_len)?;
+        let payload = r.take(payload_len)?;
+        if flags & 0x07 == 0x04 && record_type == NDEF_RECORD_TYPE.as_bytes() {
+            return Ok(payload);
+        }
+        if flags & 0x40 != 0 {
+            break;
+        }
+    }
+    Err("No multisig record in NFC message".into())
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+// Takes a bundle from `export_signing_bundle` and returns NDEF message bytes.
+#[wasm_bindgen]
+pub fn encode_nfc_request(bundle_json: &str, capacity: usize) -> Result<Vec<u8>, String> {
+    let bundle: SigningBundle = serde_json::from_str(bundle_json).map_err(|e| e.to_string())?;
+    ndef_message(&encode_request(&NfcRequest::from(&bundle))?, capacity)
+}
+
+#[wasm_bindgen]
+pub fn decode_nfc_request(message: &[u8]) -> Result<String, String> {
+    let request = decode_request(ndef_payload(message)?)?;
+    serde_json::to_string(&request).map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+pub fn encode_nfc_response(
+    signature_bundle_json: &str,
+    capacity: usize,
+) -> Result<Vec<u8>, String> {
+    let bundle: SignatureBundle =
+        serde_json::from_str(signature_bundle_json).map_err(|e| e.to_string())?;
+    ndef_message(&encode_response(&bundle)?, capacity)
+}
+
+// Returns a signature bundle ready for `import_signature_bundle`.
+#[wasm_bindgen]
+pub fn decode_nfc_response(message: &[u8]) -> Result<String, String> {
+    let bundle = decode_response(ndef_payload(message)?)?;
+    serde_json::to_string(&bundle).map_err(|e| e.to_string())
+}
@@ -0,0 +1,336 @@
+use crate::hashing::{digest_hex, HashAlgorithm};
+use crate::session::SigningSession;
+use crate::{PublicKey, Signature, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Offline Signing Bundles
+// ============================================================================
+
+// An air-gapped signer needs far less than the full transaction: the hashes
+// it must sign plus enough context to decide whether to sign them. Outputs
+// are summarized once (locks reduced to their fingerprint), and only the
+// requested spends the signer can actually sign are included.
+
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutputSummary {
+    pub recipient: String,
+    pub value: u64,
+    pub lock: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpendSummary {
+    pub spend_index: usize,
+    pub message_hash: String,
+    pub value: u64,
+    pub threshold: usize,
+    pub signed: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SigningBundle {
+    pub format: u32,
+    pub draft: String,
+    pub signer: PublicKey,
+    pub spends: Vec<SpendSummary>,
+    pub outputs: Vec<OutputSummary>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BundleSignature {
+    pub spend_index: usize,
+    pub message_hash: String,
+    pub signature: Signature,
+}
+
+// What comes back across the gap.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SignatureBundle {
+    pub format: u32,
+    pub draft: String,
+    pub signer: PublicKey,
+    pub signatures: Vec<BundleSignature>,
+}
+
+pub fn export_bundle(
+    tx: &Transaction,
+    spend_indices: &[usize],
+    signer: &PublicKey,
+) -> Result<SigningBundle, String> {
+    let mut spends = Vec::new();
+    for &i in spend_indices {
+        let spend = tx
+            .spends
+            .get(i)
+            .ok_or_else(|| format!("Spend index {} out of bounds", i))?;
+        let pkh = spend.signers()?;
+        if !pkh.pubkeys.contains(signer) || spend.seeds.has_signature(signer) {
+            continue;
+        }
+        spends.push(SpendSummary {
+            spend_index: i,
+            message_hash: spend.seeds.message_hash.clone(),
+            value: spend.note.value,
+            threshold: pkh.threshold,
+            signed: spend.seeds.signature_count(),
+        });
+    }
+    if spends.is_empty() {
+        return Err("Nothing for this signer to sign".into());
+    }
+
+    Ok(SigningBundle {
+        format: BUNDLE_FORMAT_VERSION,
+        draft: SigningSession::new(tx.clone()).draft_hash(),
+        signer: signer.clone(),
+        spends,
+        outputs: tx
+            .outputs
+            .iter()
+            .map(|o| OutputSummary {
+                recipient: o.recipient.clone(),
+                value: o.value,
+                lock: o.lock.fingerprint(),
+            })
+            .collect(),
+    })
+}
+
+// Applies signatures from an offline signer. Every signature must name the
+// message hash it was made over, and that hash must still be the spend's.
+pub fn import_bundle(tx: &mut Transaction, bundle: &SignatureBundle) -> Result<usize, String> {
+    if bundle.format > BUNDLE_FORMAT_VERSION {
+        return Err(format!("Unsupported bundle format {}", bundle.format));
+    }
+    if bundle.draft != SigningSession::new(tx.clone()).draft_hash() {
+        return Err("Signature bundle was made for a different draft".into());
+    }
+
+    for item in &bundle.signatures {
+        let spend = tx
+            .spends
+            .get(item.spend_index)
+            .ok_or_else(|| format!("Spend index {} out of bounds", item.spend_index))?;
+        if spend.seeds.message_hash != item.message_hash {
+            return Err(format!(
+                "Spend {} message hash does not match the bundle",
+                item.spend_index
+            ));
+        }
+        if !spend.signers()?.pubkeys.contains(&bundle.signer) {
+            return Err(format!(
+                "{} is not allowed to sign spend {}",
+                bundle.signer.0, item.spend_index
+            ));
+        }
+    }
+
+    for item in &bundle.signatures {
+        tx.spends[item.spend_index]
+            .seeds
+            .add_signature(bundle.signer.clone(), item.signature.clone());
+    }
+    Ok(bundle.signatures.len())
+}
+
+// ============================================================================
+// File Transport
+// ============================================================================
+
+// Bundles moved by USB stick or SD card travel as a directory of files plus
+// a MANIFEST.json listing each file's size and BLAKE3 digest. File names
+// carry the draft and signer so several round trips can share one card:
+//
+//   <draft[..16]>-<signer[..16]>-request.json     SigningBundle
+//   <draft[..16]>-<signer[..16]>-signatures.json  SignatureBundle
+
+pub const MANIFEST_FILE_NAME: &str = "MANIFEST.json";
+const MANIFEST_ALGORITHM: HashAlgorithm = HashAlgorithm::Blake3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BundleFileKind {
+    Request,
+    Signatures,
+}
+
+impl BundleFileKind {
+    fn suffix(self) -> &'static str {
+        match self {
+            BundleFileKind::Request => "request",
+            BundleFileKind::Signatures => "signatures",
+        }
+    }
+}
+
+fn short(id: &str) -> String {
+    id.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .take(16)
+        .collect()
+}
+
+pub fn bundle_file_name(kind: BundleFileKind, draft: &str, signer: &PublicKey) -> String {
+    format!(
+        "{}-{}-{}.json",
+        short(draft),
+        short(&signer.0),
+        kind.suffix()
+    )
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestEntry {
+    pub name: String,
+    pub size: usize,
+    pub digest: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BundleManifest {
+    pub format: u32,
+    pub algorithm: HashAlgorithm,
+    pub files: Vec<ManifestEntry>,
+}
+
+impl BundleManifest {
+    // `files` maps file name to contents.
+    pub fn build(files: &BTreeMap<String, String>) -> Result<Self, String> {
+        let files = files
+            .iter()
+            .filter(|(name, _)| name.as_str() != MANIFEST_FILE_NAME)
+            .map(|(name, contents)| {
+                Ok(ManifestEntry {
+                    name: name.clone(),
+                    size: contents.len(),
+                    digest: digest_hex(MANIFEST_ALGORITHM, contents.as_bytes())?,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self {
+            format: BUNDLE_FORMAT_VERSION,
+            algorithm: MANIFEST_ALGORITHM,
+            files,
+        })
+    }
+
+    // Every listed file must be present and unmodified; unlisted files are
+    // rejected too, since nothing vouches for them.
+    pub fn verify(&self, files: &BTreeMap<String, String>) -> Result<(), String> {
+        if self.format > BUNDLE_FORMAT_VERSION {
+            return Err(format!("Unsupported manifest format {}", self.format));
+        }
+        for entry in &self.files {
+            let contents = files
+                .get(&entry.name)
+                .ok_or_else(|| format!("{} is listed in the manifest but missing", entry.name))?;
+            if contents.len() != entry.size
+                || digest_hex(self.algorithm, contents.as_bytes())? != entry.digest
+            {
+                return Err(format!("{} is corrupted or has been modified", entry.name));
+            }
+        }
+        for name in files.keys() {
+            if name != MANIFEST_FILE_NAME && !self.files.iter().any(|e| &e.name == name) {
+                return Err(format!("{} is not listed in the manifest", name));
+            }
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+#[wasm_bindgen]
+pub fn get_bundle_file_name(kind: &str, draft: &str, signer: &str) -> Result<String, String> {
+    let kind: BundleFileKind = serde_json::from_value(serde_json::Value::String(kind.to_string()))
+        .map_err(|e| e.to_string())?;
+    Ok(bundle_file_name(
+        kind,
+        draft,
+        &PublicKey(signer.to_string()),
+    ))
+}
+
+// `files_json` is an object mapping file name to contents.
+#[wasm_bindgen]
+pub fn create_bundle_manifest(files_json: &str) -> Result<String, String> {
+    let files: BTreeMap<String, String> =
+        serde_json::from_str(files_json).map_err(|e| e.to_string())?;
+    let manifest = BundleManifest::build(&files)?;
+    serde_json::to_string_pretty(&manifest).map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+pub fn verify_bundle_integrity(manifest_json: &str, files_json: &str) -> Result<String, String> {
+    let manifest: BundleManifest =
+        serde_json::from_str(manifest_json).map_err(|e| e.to_string())?;
+    let files: BTreeMap<String, String> =
+        serde_json::from_str(files_json).map_err(|e| e.to_string())?;
+    manifest.verify(&files)?;
+    Ok(format!("{} files verified", manifest.files.len()))
+}
+
+// Verifies the card's manifest, then applies the signature bundle found on
+// it. Nothing is applied unless every file checks out.
+#[wasm_bindgen]
+pub fn import_signature_bundle_files(
+    tx_json: &str,
+    manifest_json: &str,
+    files_json: &str,
+) -> Result<String, String> {
+    let manifest: BundleManifest =
+        serde_json::from_str(manifest_json).map_err(|e| e.to_string())?;
+    let files: BTreeMap<String, String> =
+        serde_json::from_str(files_json).map_err(|e| e.to_string())?;
+    manifest.verify(&files)?;
+
+    let mut tx: Transaction = serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+    let suffix = format!("-{}.json", BundleFileKind::Signatures.suffix());
+    let mut applied = 0;
+    for (_, contents) in files.iter().filter(|(name, _)| name.ends_with(&suffix)) {
+        let bundle: SignatureBundle = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+        applied += import_bundle(&mut tx, &bundle)?;
+    }
+    if applied == 0 {
+        return Err("No signature bundles found".into());
+    }
+    serde_json::to_string(&tx).map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+pub fn export_signing_bundle(
+    tx_json: &str,
+    spend_indices_json: &str,
+    for_pubkey: &str,
+) -> Result<String, String> {
+    let tx: Transaction = serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+    let indices: Vec<usize> =
+        serde_json::from_str(spend_indices_json).map_err(|e| e.to_string())?;
+
+    let bundle = export_bundle(&tx, &indices, &PublicKey(for_pubkey.to_string()))?;
+    serde_json::to_string(&bundle).map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+pub fn import_signature_bundle(tx_json: &str, bundle_json: &str) -> Result<String, String> {
+    let mut tx: Transaction = serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+    let bundle: SignatureBundle = serde_json::from_str(bundle_json).map_err(|e| e.to_string())?;
+
+    import_bundle(&mut tx, &bundle)?;
+    serde_json::to_string(&tx).map_err(|e| e.to_string())
+}
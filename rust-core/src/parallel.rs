@@ -0,0 +1,203 @@
+use crate::verify::{SignatureVerifier, SignedMessage, VerificationItem};
+use crate::{Spend, Transaction};
+use std::thread;
+
+// ============================================================================
+// Parallel Validation (native only)
+// ============================================================================
+
+// Work is split into one chunk per core and run on scoped std threads. There
+// is no rayon: the crate builds offline from its existing dependencies, and
+// wasm32-unknown-unknown, the browser target, has no threads to pool anyway.
+
+fn worker_count(jobs: usize) -> usize {
+    let cores = thread::available_parallelism().map_or(1, |n| n.get());
+    cores.min(jobs).max(1)
+}
+
+fn chunk_size(jobs: usize) -> usize {
+    jobs.div_ceil(worker_count(jobs)).max(1)
+}
+
+fn validate_spend_chunk(
+    tx: &Transaction,
+    offset: usize,
+    spends: &[Spend],
+    aggregate_root: Option<&str>,
+    verifier: &dyn SignatureVerifier,
+    height: Option<u64>,
+) -> Result<(), String> {
+    for (i, spend) in spends.iter().enumerate() {
+        spend.validate_signers(offset + i)?;
+        tx.validate_spend_delegations(offset + i, height)?;
+        tx.validate_spend_preapprovals(offset + i, height)?;
+    }
+
+    let items: Vec<VerificationItem> = spends
+        .iter()
+        .flat_map(|s| s.verification_items(aggregate_root))
+        .collect();
+    let batch: Vec<SignedMessage<'_>> = items.iter().map(|item| item.as_signed()).collect();
+    if verifier.verify_batch(&batch) {
+        return Ok(());
+    }
+
+    for (i, spend) in spends.iter().enumerate() {
+        spend.verify_all_signatures(offset + i, aggregate_root, verifier)?;
+    }
+    Err("Signature batch verification failed".into())
+}
+
+// Same checks as `validate_structure` + `verify_signatures_at`, with spends
+// split across worker threads. Each worker validates its spends' delegations and
+// pre-approvals and verifies their grants, terms and incidents along with
+// the slot signatures. Errors report the lowest failing spend.
+pub fn validate_transaction(
+    tx: &Transaction,
+    verifier: &dyn SignatureVerifier,
+    height: Option<u64>,
+) -> Result<(), String> {
+    tx.validate_structure()?;
+    tx.validate_aggregates()?;
+    let root = tx.signing_root()?;
+    let root = root.as_deref();
+
+    let size = chunk_size(tx.spends.len());
+    let results: Vec<Result<(), String>> = thread::scope(|scope| {
+        let handles: Vec<_> = tx
+            .spends
+            .chunks(size)
+            .enumerate()
+            .map(|(n, chunk)| {
+                scope.spawn(move || validate_spend_chunk(tx, n * size, chunk, root, verifier, height))
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| {
+                h.join()
+                    .unwrap_or_else(|_| Err("Validation worker panicked".into()))
+            })
+            .collect()
+    });
+
+    results.into_iter().collect()
+}
+
+// Validates many independent drafts at once, e.g. a coordinator re-checking
+// its pending queue. Results are returned in input order.
+pub fn validate_transactions(
+    txs: &[Transaction],
+    verifier: &dyn SignatureVerifier,
+    height: Option<u64>,
+) -> Vec<Result<(), String>> {
+    let size = chunk_size(txs.len());
+    thread::scope(|scope| {
+        let handles: Vec<_> = txs
+            .chunks(size)
+            .map(|chunk| {
+                let handle = scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|tx| {
+                            tx.validate_structure()?;
+                            tx.verify_signatures_at(verifier, height)
+                        })
+                        .collect::<Vec<_>>()
+                });
+                (chunk.len(), handle)
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|(len, h)| {
+                h.join()
+                    .unwrap_or_else(|_| vec![Err("Validation worker panicked".into()); len])
+            })
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preapproval::PreApproval;
+    use crate::testing::HashVerifier;
+    use crate::{build_transaction, PublicKey, Signature};
+
+    // Two spends from a 2-of-2 lock: "a" signs each spend, "b" pre-approves.
+    fn preapproved_draft(forge: bool) -> Transaction {
+        let lock = r#"{"pkh":{"threshold":2,"pubkeys":["a","b"]}}"#;
+        let notes = format!(
+            r#"[{{"name":{{"first":"n0","last":"l"}},"value":3,"lock":{lock}}},{{"name":{{"first":"n1","last":"l"}},"value":4,"lock":{lock}}}]"#
+        );
+        let outputs =
+            r#"[{"recipient":"r","value":7,"lock":{"pkh":{"threshold":1,"pubkeys":["r"]}}}]"#;
+        let json = build_transaction(&notes, outputs).expect("build failed");
+        let mut tx: Transaction = serde_json::from_str(&json).expect("parse failed");
+
+        let b = PublicKey("b".into());
+        let mut preapproval = PreApproval {
+            signer: b.clone(),
+            lock: tx.spends[0].note.lock.fingerprint(),
+            outputs: tx.outputs.clone(),
+            valid_until_height: 100,
+            signature: Signature(String::new()),
+        };
+        preapproval.signature = if forge {
+            Signature("00".repeat(32))
+        } else {
+            HashVerifier::sign(&b, &preapproval.message())
+        };
+        assert_eq!(
+            tx.apply_preapproval(&preapproval, Some(50)).unwrap(),
+            vec![0, 1]
+        );
+
+        let a = PublicKey("a".into());
+        for spend in &mut tx.spends {
+            let signature = HashVerifier::sign(&a, &spend.seeds.message_hash);
+            spend.seeds.add_signature(a.clone(), signature);
+        }
+        tx
+    }
+
+    #[test]
+    fn accepts_valid_preapproval() {
+        let tx = preapproved_draft(false);
+        tx.verify_signatures_at(&HashVerifier, Some(60)).unwrap();
+        validate_transaction(&tx, &HashVerifier, Some(60)).unwrap();
+    }
+
+    #[test]
+    fn rejects_forged_preapproval() {
+        let tx = preapproved_draft(true);
+        // The slot signatures alone pass, since pre-approved slots are
+        // checked against their terms.
+        for (i, spend) in tx.spends.iter().enumerate() {
+            spend.verify_signatures(i, None, &HashVerifier).unwrap();
+        }
+        let sequential = tx.verify_signatures_at(&HashVerifier, Some(60)).unwrap_err();
+        let parallel = validate_transaction(&tx, &HashVerifier, Some(60)).unwrap_err();
+        assert_eq!(parallel, sequential);
+        assert_eq!(parallel, "Pre-approval from b has an invalid signature");
+    }
+
+    #[test]
+    fn rejects_preapproval_without_height_or_expired() {
+        let tx = preapproved_draft(false);
+        assert!(tx.verify_signatures(&HashVerifier).is_err());
+        assert!(validate_transaction(&tx, &HashVerifier, None).is_err());
+        assert!(validate_transaction(&tx, &HashVerifier, Some(101)).is_err());
+    }
+
+    #[test]
+    fn rejects_outputs_changed_after_signing() {
+        let mut tx = preapproved_draft(false);
+        tx.outputs[0].recipient = "mallory".into();
+        let err = validate_transaction(&tx, &HashVerifier, Some(60)).unwrap_err();
+        assert_eq!(err, "Spend 0 hash does not match the draft's outputs");
+        let batch = validate_transactions(&[tx], &HashVerifier, Some(60));
+        assert_eq!(batch[0].as_ref().unwrap_err(), &err);
+    }
+}
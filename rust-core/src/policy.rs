@@ -0,0 +1,285 @@
+use crate::{NoteName, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Spending Policies
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case", deny_unknown_fields)]
+pub enum PolicyRule {
+    MaxOutputValue { limit: u64 },
+    MaxTotalOutput { limit: u64 },
+    AllowedRecipients { recipients: Vec<String> },
+    MaxOutputs { limit: usize },
+    // Value leaving the wallet over any `period` seconds, counting this
+    // transaction and the finalized ones in the history.
+    MaxSpentInPeriod { limit: u64, period: u64 },
+    // Notes tagged `tag` may only be spent alongside other such notes, and
+    // only pay the `allowed` recipients (change aside); an empty list allows
+    // anyone.
+    RestrictedTag { tag: String, allowed: Vec<String> },
+}
+
+// Each tagged note's tags, keyed by `note_key` (see `WalletMetadata`).
+pub type NoteTags = BTreeMap<String, Vec<String>>;
+
+pub fn note_key(name: &NoteName) -> String {
+    format!("{}/{}", name.first, name.last)
+}
+
+// Output value not returning to a lock the transaction spends from, i.e.
+// everything but change.
+pub fn outgoing_value(tx: &Transaction) -> u64 {
+    tx.outputs
+        .iter()
+        .filter(|o| !tx.spends.iter().any(|s| s.note.lock == o.lock))
+        .map(|o| o.value)
+        .sum()
+}
+
+// ============================================================================
+// Spending History
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpendRecord {
+    pub at: u64,
+    pub txid: String,
+    pub value: u64,
+}
+
+// Finalized transactions of one wallet, oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpendingHistory {
+    pub records: Vec<SpendRecord>,
+}
+
+impl SpendingHistory {
+    // Recording the same txid again is a no-op; returns whether it was new.
+    pub fn record(&mut self, record: SpendRecord) -> bool {
+        if self.records.iter().any(|r| r.txid == record.txid) {
+            return false;
+        }
+        let at = self.records.partition_point(|r| r.at <= record.at);
+        self.records.insert(at, record);
+        true
+    }
+
+    // Total over `(now - period, now]`.
+    pub fn spent_in(&self, period: u64, now: u64) -> u64 {
+        let from = now.saturating_sub(period);
+        self.records
+            .iter()
+            .filter(|r| r.at > from && r.at <= now)
+            .map(|r| r.value)
+            .sum()
+    }
+
+    // Drops records too old for any window of at most `keep` seconds.
+    pub fn prune(&mut self, keep: u64, now: u64) {
+        let from = now.saturating_sub(keep);
+        self.records.retain(|r| r.at > from);
+    }
+}
+
+impl PolicyRule {
+    pub fn check(&self, tx: &Transaction) -> Result<(), String> {
+        self.check_with(tx, &SpendingHistory::default(), 0)
+    }
+
+    pub fn check_with(
+        &self,
+        tx: &Transaction,
+        history: &SpendingHistory,
+        now: u64,
+    ) -> Result<(), String> {
+        self.check_with_tags(tx, history, now, &NoteTags::new())
+    }
+
+    // As `check_with`; `restricted_tag` rules only see notes tagged in `tags`.
+    pub fn check_with_tags(
+        &self,
+        tx: &Transaction,
+        history: &SpendingHistory,
+        now: u64,
+        tags: &NoteTags,
+    ) -> Result<(), String> {
+        match self {
+            PolicyRule::MaxOutputValue { limit } => {
+                for (i, output) in tx.outputs.iter().enumerate() {
+                    if output.value > *limit {
+                        return Err(format!(
+                            "Output {} value {} exceeds policy limit {}",
+                            i, output.value, limit
+                        ));
+                    }
+                }
+            }
+            PolicyRule::MaxTotalOutput { limit } => {
+                if tx.total_output() > *limit {
+                    return Err(format!(
+                        "Total output {} exceeds policy limit {}",
+                        tx.total_output(),
+                        limit
+                    ));
+                }
+            }
+            PolicyRule::AllowedRecipients { recipients } => {
+                for (i, output) in tx.outputs.iter().enumerate() {
+                    if !recipients.contains(&output.recipient) {
+                        return Err(format!(
+                            "Output {} recipient {} is not on the allow list",
+                            i, output.recipient
+                        ));
+                    }
+                }
+            }
+            PolicyRule::MaxOutputs { limit } => {
+                if tx.outputs.len() > *limit {
+                    return Err(format!(
+                        "Transaction has {} outputs, policy allows {}",
+                        tx.outputs.len(),
+                        limit
+                    ));
+                }
+            }
+            PolicyRule::MaxSpentInPeriod { limit, period } => {
+                let spent = history.spent_in(*period, now);
+                let total = spent.saturating_add(outgoing_value(tx));
+                if total > *limit {
+                    return Err(format!(
+                        "Spending {} in {}s ({} already spent) exceeds policy limit {}",
+                        total, period, spent, limit
+                    ));
+                }
+            }
+            PolicyRule::RestrictedTag { tag, allowed } => {
+                let tagged: Vec<bool> = tx
+                    .spends
+                    .iter()
+                    .map(|s| {
+                        tags.get(&note_key(&s.note.name))
+                            .is_some_and(|t| t.contains(tag))
+                    })
+                    .collect();
+                if !tagged.contains(&true) {
+                    return Ok(());
+                }
+                if let Some(i) = tagged.iter().position(|t| !t) {
+                    return Err(format!(
+                        "Spend {} is not tagged '{}' but is spent with notes that are",
+                        i, tag
+                    ));
+                }
+                for (i, output) in tx.outputs.iter().enumerate() {
+                    let change = tx.spends.iter().any(|s| s.note.lock == output.lock);
+                    if !change && !allowed.is_empty() && !allowed.contains(&output.recipient) {
+                        return Err(format!(
+                            "Output {} pays {}, which may not receive '{}' funds",
+                            i, output.recipient, tag
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicySet {
+    pub rules: Vec<PolicyRule>,
+}
+
+impl PolicySet {
+    pub fn check(&self, tx: &Transaction) -> Result<(), String> {
+        self.check_with(tx, &SpendingHistory::default(), 0)
+    }
+
+    pub fn check_with(
+        &self,
+        tx: &Transaction,
+        history: &SpendingHistory,
+        now: u64,
+    ) -> Result<(), String> {
+        self.check_with_tags(tx, history, now, &NoteTags::new())
+    }
+
+    pub fn check_with_tags(
+        &self,
+        tx: &Transaction,
+        history: &SpendingHistory,
+        now: u64,
+        tags: &NoteTags,
+    ) -> Result<(), String> {
+        for rule in &self.rules {
+            rule.check_with_tags(tx, history, now, tags)?;
+        }
+        Ok(())
+    }
+
+    // The tightest `MaxOutputValue`, for splitting payments to fit it.
+    pub fn max_output_value(&self) -> Option<u64> {
+        self.rules
+            .iter()
+            .filter_map(|rule| match rule {
+                PolicyRule::MaxOutputValue { limit } => Some(*limit),
+                _ => None,
+            })
+            .min()
+    }
+}
+
+#[wasm_bindgen]
+pub fn check_policies(policies_json: &str, tx_json: &str) -> Result<String, String> {
+    let policies: PolicySet = serde_json::from_str(policies_json).map_err(|e| e.to_string())?;
+    let tx: Transaction = serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+
+    policies.check(&tx)?;
+
+    Ok("Transaction satisfies all policies".into())
+}
+
+// As `check_policies`, with the wallet's finalized spending for velocity
+// rules.
+#[wasm_bindgen]
+pub fn check_policies_with_history(
+    policies_json: &str,
+    tx_json: &str,
+    history_json: &str,
+    now: u64,
+) -> Result<String, String> {
+    let policies: PolicySet = serde_json::from_str(policies_json).map_err(|e| e.to_string())?;
+    let tx: Transaction = serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+    let history: SpendingHistory = serde_json::from_str(history_json).map_err(|e| e.to_string())?;
+
+    policies.check_with(&tx, &history, now)?;
+
+    Ok("Transaction satisfies all policies".into())
+}
+
+// As `check_policies_with_history`, with the wallet's note tags (as from
+// `WalletManager::get_note_tags`) for `restricted_tag` rules.
+#[wasm_bindgen]
+pub fn check_policies_with_tags(
+    policies_json: &str,
+    tx_json: &str,
+    history_json: &str,
+    tags_json: &str,
+    now: u64,
+) -> Result<String, String> {
+    let policies: PolicySet = serde_json::from_str(policies_json).map_err(|e| e.to_string())?;
+    let tx: Transaction = serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+    let history: SpendingHistory = serde_json::from_str(history_json).map_err(|e| e.to_string())?;
+    let tags: NoteTags = serde_json::from_str(tags_json).map_err(|e| e.to_string())?;
+
+    policies.check_with_tags(&tx, &history, now, &tags)?;
+
+    Ok("Transaction satisfies all policies".into())
+}
@@ -0,0 +1,526 @@
+use crate::encoding::base64url_decode;
+use crate::session::SigningSession;
+use crate::wallets::WalletDescriptor;
+use crate::{Lock, NoteName, PkhCondition, PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// PSBT Bridge
+// ============================================================================
+
+// For teams moving over from Bitcoin multisig tooling. Import reads a BIP 174
+// PSBT (v0 or v2, base64 or hex) and keeps only what maps onto our model:
+// the multisig script's threshold and keys, global xpubs, BIP 32 key origins
+// and partial signatures. Everything else is counted and skipped. Bitcoin
+// signatures cannot sign our drafts; they only show who had signed. Export
+// goes the other way as JSON laid out like `bitcoin-cli decodepsbt`, so
+// existing review tooling and habits carry over.
+
+const PSBT_MAGIC: &[u8] = b"psbt\xff";
+
+const GLOBAL_UNSIGNED_TX: u8 = 0x00;
+const GLOBAL_XPUB: u8 = 0x01;
+const GLOBAL_INPUT_COUNT: u8 = 0x04;
+const GLOBAL_OUTPUT_COUNT: u8 = 0x05;
+const GLOBAL_VERSION: u8 = 0xfb;
+const IN_WITNESS_UTXO: u8 = 0x01;
+const IN_PARTIAL_SIG: u8 = 0x02;
+const IN_REDEEM_SCRIPT: u8 = 0x04;
+const IN_WITNESS_SCRIPT: u8 = 0x05;
+const IN_BIP32_DERIVATION: u8 = 0x06;
+const OUT_BIP32_DERIVATION: u8 = 0x02;
+const OUT_AMOUNT: u8 = 0x03;
+
+const OP_CHECKMULTISIG: u8 = 0xae;
+const HARDENED: u32 = 0x8000_0000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeyOrigin {
+    pub fingerprint: String,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PsbtXpub {
+    pub xpub: String,
+    #[serde(flatten)]
+    pub origin: KeyOrigin,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PsbtKey {
+    pub pubkey: PublicKey,
+    #[serde(flatten)]
+    pub origin: KeyOrigin,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PsbtInput {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multisig: Option<PkhCondition>,
+    #[serde(default)]
+    pub partial_sigs: Vec<(PublicKey, Signature)>,
+    #[serde(default)]
+    pub derivations: Vec<PsbtKey>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PsbtOutput {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<u64>,
+    #[serde(default)]
+    pub derivations: Vec<PsbtKey>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PsbtImport {
+    pub version: u32,
+    pub xpubs: Vec<PsbtXpub>,
+    pub inputs: Vec<PsbtInput>,
+    pub outputs: Vec<PsbtOutput>,
+    // Fields with no counterpart here.
+    pub skipped: usize,
+}
+
+type Pairs<'a> = Vec<(&'a [u8], &'a [u8])>;
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|&end| end <= self.bytes.len());
+        let end = end.ok_or("PSBT is truncated")?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn uint(&mut self, n: usize) -> Result<u64, String> {
+        let bytes = self.take(n)?;
+        Ok(bytes
+            .iter()
+            .rev()
+            .fold(0u64, |acc, &b| (acc << 8) | u64::from(b)))
+    }
+
+    fn compact_size(&mut self) -> Result<u64, String> {
+        match self.take(1)?[0] {
+            0xfd => self.uint(2),
+            0xfe => self.uint(4),
+            0xff => self.uint(8),
+            n => Ok(u64::from(n)),
+        }
+    }
+
+    fn length(&mut self) -> Result<usize, String> {
+        let n = self.compact_size()?;
+        usize::try_from(n)
+            .ok()
+            .filter(|&n| n <= self.bytes.len())
+            .ok_or_else(|| "PSBT length out of range".into())
+    }
+
+    fn map(&mut self) -> Result<Pairs<'a>, String> {
+        let mut pairs = Vec::new();
+        loop {
+            let key_len = self.length()?;
+            if key_len == 0 {
+                return Ok(pairs);
+            }
+            let key = self.take(key_len)?;
+            let value_len = self.length()?;
+            pairs.push((key, self.take(value_len)?));
+        }
+    }
+}
+
+// (inputs, output values) of an unsigned v0 transaction.
+fn tx_counts(tx: &[u8]) -> Result<(usize, Vec<u64>), String> {
+    let mut r = Reader { bytes: tx, pos: 0 };
+    r.take(4)?;
+    let inputs = r.length()?;
+    for _ in 0..inputs {
+        r.take(36)?;
+        let script = r.length()?;
+        r.take(script)?;
+        r.take(4)?;
+    }
+    let outputs = r.length()?;
+    let mut values = Vec::with_capacity(outputs);
+    for _ in 0..outputs {
+        values.push(r.uint(8)?);
+        let script = r.length()?;
+        r.take(script)?;
+    }
+    Ok((inputs, values))
+}
+
+fn key_origin(value: &[u8]) -> Result<KeyOrigin, String> {
+    if value.len() < 4 || !value.len().is_multiple_of(4) {
+        return Err("Malformed BIP 32 key origin".into());
+    }
+    let mut path = String::from("m");
+    for step in value[4..].chunks(4) {
+        let index = u32::from_le_bytes([step[0], step[1], step[2], step[3]]);
+        path.push_str(&format!("/{}", index & !HARDENED));
+        if index & HARDENED != 0 {
+            path.push('\'');
+        }
+    }
+    Ok(KeyOrigin {
+        fingerprint: hex::encode(&value[..4]),
+        path,
+    })
+}
+
+// `OP_m <key>... OP_n OP_CHECKMULTISIG`, the script every Bitcoin multisig
+// wallet uses.
+fn parse_multisig(script: &[u8]) -> Option<PkhCondition> {
+    let (&first, rest) = script.split_first()?;
+    let (&last, rest) = rest.split_last()?;
+    let (&n_op, mut keys) = rest.split_last()?;
+    if last != OP_CHECKMULTISIG || !(0x51..=0x60).contains(&first) {
+        return None;
+    }
+    let mut pubkeys = Vec::new();
+    while let Some((&len, tail)) = keys.split_first() {
+        let len = usize::from(len);
+        if (len != 33 && len != 65) || tail.len() < len {
+            return None;
+        }
+        pubkeys.push(PublicKey(hex::encode(&tail[..len])));
+        keys = &tail[len..];
+    }
+    let condition = PkhCondition {
+        threshold: usize::from(first - 0x50),
+        pubkeys,
+    };
+    (usize::from(n_op.wrapping_sub(0x50)) == condition.pubkeys.len()).then_some(condition)
+}
+
+fn base58check(payload: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    const ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+    let checksum = Sha256::digest(Sha256::digest(payload));
+    let bytes = [payload, &checksum[..4]].concat();
+    let mut digits: Vec<u8> = Vec::new();
+    for &byte in &bytes {
+        let mut carry = u32::from(byte);
+        for digit in digits.iter_mut() {
+            carry += u32::from(*digit) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    let zeros = bytes.iter().take_while(|&&b| b == 0).count();
+    std::iter::repeat_n('1', zeros)
+        .chain(
+            digits
+                .iter()
+                .rev()
+                .map(|&d| ALPHABET[usize::from(d)] as char),
+        )
+        .collect()
+}
+
+fn decode(text: &str) -> Result<Vec<u8>, String> {
+    let text = text.trim();
+    if text.len().is_multiple_of(2) && text.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return hex::decode(text).map_err(|e| e.to_string());
+    }
+    let url_safe: String = text
+        .chars()
+        .map(|c| match c {
+            '+' => '-',
+            '/' => '_',
+            c => c,
+        })
+        .collect();
+    base64url_decode(&url_safe)
+}
+
+impl PsbtImport {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let bytes = decode(text)?;
+        let mut r = Reader {
+            bytes: &bytes,
+            pos: 0,
+        };
+        if r.take(PSBT_MAGIC.len()).ok() != Some(PSBT_MAGIC) {
+            return Err("Not a PSBT".into());
+        }
+
+        let mut import = PsbtImport::default();
+        let mut counts = (None, None);
+        let mut output_values = Vec::new();
+        for (key, value) in r.map()? {
+            match key[0] {
+                GLOBAL_UNSIGNED_TX => {
+                    let (inputs, values) = tx_counts(value)?;
+                    counts = (Some(inputs), Some(values.len()));
+                    output_values = values;
+                }
+                GLOBAL_XPUB if key.len() == 79 => import.xpubs.push(PsbtXpub {
+                    xpub: base58check(&key[1..]),
+                    origin: key_origin(value)?,
+                }),
+                GLOBAL_INPUT_COUNT => {
+                    counts.0 = Some(
+                        Reader {
+                            bytes: value,
+                            pos: 0,
+                        }
+                        .length()?,
+                    )
+                }
+                GLOBAL_OUTPUT_COUNT => {
+                    counts.1 = Some(
+                        Reader {
+                            bytes: value,
+                            pos: 0,
+                        }
+                        .length()?,
+                    )
+                }
+                GLOBAL_VERSION if value.len() == 4 => {
+                    import.version = u32::from_le_bytes([value[0], value[1], value[2], value[3]]);
+                }
+                _ => import.skipped += 1,
+            }
+        }
+        let (Some(inputs), Some(outputs)) = counts else {
+            return Err("PSBT has no transaction or input/output counts".into());
+        };
+
+        for _ in 0..inputs {
+            let mut input = PsbtInput::default();
+            let mut redeem = None;
+            for (key, value) in r.map()? {
+                match key[0] {
+                    IN_WITNESS_UTXO if value.len() >= 8 => {
+                        input.value = Some(
+                            Reader {
+                                bytes: value,
+                                pos: 0,
+                            }
+                            .uint(8)?,
+                        );
+                    }
+                    IN_PARTIAL_SIG => input.partial_sigs.push((
+                        PublicKey(hex::encode(&key[1..])),
+                        Signature(hex::encode(value)),
+                    )),
+                    IN_REDEEM_SCRIPT => redeem = parse_multisig(value),
+                    IN_WITNESS_SCRIPT => input.multisig = parse_multisig(value),
+                    IN_BIP32_DERIVATION => input.derivations.push(PsbtKey {
+                        pubkey: PublicKey(hex::encode(&key[1..])),
+                        origin: key_origin(value)?,
+                    }),
+                    _ => import.skipped += 1,
+                }
+            }
+            // Legacy P2SH multisig has only a redeem script.
+            input.multisig = input.multisig.or(redeem);
+            import.inputs.push(input);
+        }
+
+        for i in 0..outputs {
+            let mut output = PsbtOutput {
+                value: output_values.get(i).copied(),
+                derivations: Vec::new(),
+            };
+            for (key, value) in r.map()? {
+                match key[0] {
+                    OUT_BIP32_DERIVATION => output.derivations.push(PsbtKey {
+                        pubkey: PublicKey(hex::encode(&key[1..])),
+                        origin: key_origin(value)?,
+                    }),
+                    OUT_AMOUNT if value.len() == 8 => {
+                        output.value = Some(
+                            Reader {
+                                bytes: value,
+                                pos: 0,
+                            }
+                            .uint(8)?,
+                        );
+                    }
+                    _ => import.skipped += 1,
+                }
+            }
+            import.outputs.push(output);
+        }
+        Ok(import)
+    }
+
+    // Every key the PSBT names as a cosigner, in script order.
+    pub fn participants(&self) -> Vec<PublicKey> {
+        let mut participants: Vec<PublicKey> = Vec::new();
+        let keys = self.inputs.iter().flat_map(|input| {
+            let script = input.multisig.iter().flat_map(|m| &m.pubkeys);
+            script.chain(input.derivations.iter().map(|d| &d.pubkey))
+        });
+        for pubkey in keys {
+            if !participants.contains(pubkey) {
+                participants.push(pubkey.clone());
+            }
+        }
+        participants
+    }
+
+    // The wallet the PSBT spends from, if every input agrees on its script.
+    pub fn descriptor(&self, label: &str) -> Result<WalletDescriptor, String> {
+        let mut scripts = self.inputs.iter().filter_map(|i| i.multisig.as_ref());
+        let multisig = scripts.next().ok_or("PSBT has no multisig script")?;
+        if scripts.any(|m| m != multisig) {
+            return Err("PSBT inputs come from different multisig wallets".into());
+        }
+        let lock = Lock::new(multisig.clone());
+        lock.validate()?;
+        Ok(WalletDescriptor {
+            label: label.to_string(),
+            lock,
+        })
+    }
+}
+
+// ============================================================================
+// decodepsbt-style Export
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PsbtUtxo {
+    pub amount: u64,
+    // The lock fingerprint, our funding address.
+    pub address: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PsbtWitnessScript {
+    pub threshold: usize,
+    pub pubkeys: Vec<PublicKey>,
+    #[serde(default)]
+    pub branch: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PsbtExportInput {
+    pub note: NoteName,
+    pub witness_utxo: PsbtUtxo,
+    pub witness_script: PsbtWitnessScript,
+    // What each signer signs for this input.
+    pub sighash: String,
+    pub partial_signatures: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PsbtExportOutput {
+    pub recipient: String,
+    pub amount: u64,
+    pub address: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PsbtExport {
+    pub draft: String,
+    pub inputs: Vec<PsbtExportInput>,
+    pub outputs: Vec<PsbtExportOutput>,
+}
+
+impl PsbtExport {
+    pub fn from_session(session: &SigningSession) -> Result<Self, String> {
+        let tx = &session.transaction;
+        let inputs = tx
+            .spends
+            .iter()
+            .enumerate()
+            .map(|(i, spend)| {
+                let condition = spend
+                    .note
+                    .lock
+                    .condition(spend.seeds.branch)
+                    .ok_or_else(|| format!("Spend {} uses a branch its lock lacks", i))?;
+                Ok(PsbtExportInput {
+                    note: spend.note.name.clone(),
+                    witness_utxo: PsbtUtxo {
+                        amount: spend.note.value,
+                        address: spend.note.lock.fingerprint(),
+                    },
+                    witness_script: PsbtWitnessScript {
+                        threshold: condition.pkh.threshold,
+                        pubkeys: condition.pkh.pubkeys.clone(),
+                        branch: spend.seeds.branch,
+                    },
+                    sighash: spend.seeds.message_hash.clone(),
+                    partial_signatures: spend
+                        .seeds
+                        .signatures
+                        .iter()
+                        .map(|(pk, sig)| (pk.0.clone(), sig.0.clone()))
+                        .collect(),
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let outputs = tx
+            .outputs
+            .iter()
+            .map(|o| PsbtExportOutput {
+                recipient: o.recipient.clone(),
+                amount: o.value,
+                address: o.lock.fingerprint(),
+            })
+            .collect();
+        Ok(Self {
+            draft: session.draft_hash(),
+            inputs,
+            outputs,
+        })
+    }
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+#[wasm_bindgen]
+pub fn import_psbt(psbt: &str) -> Result<String, String> {
+    let import = PsbtImport::parse(psbt)?;
+    serde_json::to_string(&import).map_err(|e| e.to_string())
+}
+
+// A wallet descriptor for `WalletManager.create_wallet`.
+#[wasm_bindgen]
+pub fn psbt_wallet_descriptor(psbt: &str, label: &str) -> Result<String, String> {
+    let descriptor = PsbtImport::parse(psbt)?.descriptor(label)?;
+    serde_json::to_string(&descriptor).map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+pub fn export_psbt_json(session_json: &str) -> Result<String, String> {
+    let session = SigningSession::from_json(session_json)?;
+    let export = PsbtExport::from_session(&session)?;
+    serde_json::to_string(&export).map_err(|e| e.to_string())
+}
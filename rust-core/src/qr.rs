@@ -0,0 +1,430 @@
+use crate::encoding::{base64url_decode, base64url_encode};
+use crate::hashing::{digest_hex, HashAlgorithm};
+use std::collections::{BTreeMap, BTreeSet};
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Animated QR Framing
+// ============================================================================
+
+// Payloads too large for one QR code are split into fragments and shown as
+// an endless loop of parts. Parts 1..=total carry one fragment each; later
+// parts XOR a pseudo-random subset of fragments together (a fountain code),
+// so a camera that missed some frames still finishes without waiting for the
+// loop to come round again. Each part is one text QR code:
+//
+//   nmq1/<seq>-<total>/<length>/<checksum>/<base64url(fragment)>
+//
+// `checksum` is the first 8 hex digits of the payload's SHA-256; it ties the
+// parts of one payload together and seeds the fragment mixing.
+
+const PART_PREFIX: &str = "nmq1";
+
+// Bounds on what a scanned part may claim, so a crafted frame cannot make
+// the decoder loop over billions of fragments. Far beyond any animated QR
+// a camera will sit through.
+pub const MAX_QR_PARTS: usize = 4096;
+pub const MAX_QR_PAYLOAD_LEN: usize = 1 << 20;
+
+fn checksum(payload: &[u8]) -> u32 {
+    let hex = digest_hex(HashAlgorithm::Sha256, payload).expect("sha256 is always available");
+    u32::from_str_radix(&hex[..8], 16).expect("digest is hex")
+}
+
+// Fragments mixed into part `seq`. Encoder and decoder must agree exactly.
+fn mixed_fragments(seq: u32, total: usize, checksum: u32) -> BTreeSet<usize> {
+    if (seq as usize) <= total {
+        return BTreeSet::from([seq as usize - 1]);
+    }
+
+    // splitmix64, seeded per part
+    let mut state = u64::from(seq) << 32 | u64::from(checksum);
+    let mut next = || {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    };
+    let mut indexes: BTreeSet<usize> = (0..total).filter(|_| next() & 1 == 1).collect();
+    if indexes.is_empty() {
+        indexes.insert(next() as usize % total);
+    }
+    indexes
+}
+
+fn xor_into(target: &mut [u8], other: &[u8]) {
+    for (t, o) in target.iter_mut().zip(other) {
+        *t ^= o;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Part {
+    seq: u32,
+    total: usize,
+    length: usize,
+    checksum: u32,
+    data: Vec<u8>,
+}
+
+impl Part {
+    fn parse(text: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = text.trim().split('/').collect();
+        let [prefix, position, length, checksum, data] = fields[..] else {
+            return Err("Not a multi-part QR payload".into());
+        };
+        if !prefix.eq_ignore_ascii_case(PART_PREFIX) {
+            return Err("Not a multi-part QR payload".into());
+        }
+        let (seq, total) = position
+            .split_once('-')
+            .ok_or("Malformed QR part position")?;
+        let part = Part {
+            seq: seq.parse().map_err(|_| "Malformed QR part sequence")?,
+            total: total.parse().map_err(|_| "Malformed QR part total")?,
+            length: length.parse().map_err(|_| "Malformed QR payload length")?,
+            checksum: u32::from_str_radix(checksum, 16).map_err(|_| "Malformed QR checksum")?,
+            data: base64url_decode(data)?,
+        };
+        if part.seq == 0 || part.total == 0 || part.total > MAX_QR_PARTS {
+            return Err("Malformed QR part position".into());
+        }
+        if part.length < part.total || part.length > MAX_QR_PAYLOAD_LEN {
+            return Err("Malformed QR payload length".into());
+        }
+        if part.data.len() != part.length.div_ceil(part.total) {
+            return Err("QR part has the wrong fragment size".into());
+        }
+        Ok(part)
+    }
+
+    fn format(&self) -> String {
+        format!(
+            "{}/{}-{}/{}/{:08x}/{}",
+            PART_PREFIX,
+            self.seq,
+            self.total,
+            self.length,
+            self.checksum,
+            base64url_encode(&self.data)
+        )
+    }
+}
+
+// ============================================================================
+// Encoder
+// ============================================================================
+
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct QrEncoder {
+    fragments: Vec<Vec<u8>>,
+    length: usize,
+    checksum: u32,
+    seq: u32,
+}
+
+impl QrEncoder {
+    pub fn from_bytes(payload: &[u8], max_fragment_len: usize) -> Result<Self, String> {
+        if payload.is_empty() {
+            return Err("Nothing to encode".into());
+        }
+        if max_fragment_len == 0 {
+            return Err("Fragment length must be positive".into());
+        }
+        if payload.len() > MAX_QR_PAYLOAD_LEN {
+            return Err(format!(
+                "Payload is larger than {} bytes",
+                MAX_QR_PAYLOAD_LEN
+            ));
+        }
+        let total = payload.len().div_ceil(max_fragment_len);
+        if total > MAX_QR_PARTS {
+            return Err(format!(
+                "Payload needs more than {} parts; use a larger fragment length",
+                MAX_QR_PARTS
+            ));
+        }
+        // Spread bytes evenly so the last fragment is not mostly padding.
+        let fragment_len = payload.len().div_ceil(total);
+        let fragments = payload
+            .chunks(fragment_len)
+            .map(|c| {
+                let mut f = c.to_vec();
+                f.resize(fragment_len, 0);
+                f
+            })
+            .collect();
+        Ok(Self {
+            fragments,
+            length: payload.len(),
+            checksum: checksum(payload),
+            seq: 0,
+        })
+    }
+
+    fn part(&self, seq: u32) -> Part {
+        let mut data = vec![0u8; self.fragments[0].len()];
+        for i in mixed_fragments(seq, self.fragments.len(), self.checksum) {
+            xor_into(&mut data, &self.fragments[i]);
+        }
+        Part {
+            seq,
+            total: self.fragments.len(),
+            length: self.length,
+            checksum: self.checksum,
+            data,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl QrEncoder {
+    #[wasm_bindgen(constructor)]
+    pub fn new(payload: &str, max_fragment_len: usize) -> Result<QrEncoder, String> {
+        Self::from_bytes(payload.as_bytes(), max_fragment_len)
+    }
+
+    pub fn part_count(&self) -> usize {
+        self.fragments.len()
+    }
+
+    // Parts never run out; display them in a loop until the scanner is done.
+    pub fn next_part(&mut self) -> String {
+        self.seq = self.seq.wrapping_add(1).max(1);
+        self.part(self.seq).format()
+    }
+}
+
+// ============================================================================
+// Decoder
+// ============================================================================
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default)]
+pub struct QrDecoder {
+    total: usize,
+    length: usize,
+    checksum: u32,
+    // Received parts as equations over GF(2), kept fully reduced and keyed by
+    // pivot fragment. Once there are `total` of them each one names a single
+    // fragment.
+    rows: BTreeMap<usize, (BTreeSet<usize>, Vec<u8>)>,
+    seen: BTreeSet<u32>,
+    result: Option<Vec<u8>>,
+}
+
+impl QrDecoder {
+    pub fn result_bytes(&self) -> Option<&[u8]> {
+        self.result.as_deref()
+    }
+
+    fn start(&mut self, part: &Part) {
+        self.total = part.total;
+        self.length = part.length;
+        self.checksum = part.checksum;
+    }
+
+    fn absorb(&mut self, mut indexes: BTreeSet<usize>, mut data: Vec<u8>) {
+        for (pivot, (row, row_data)) in &self.rows {
+            if indexes.contains(pivot) {
+                indexes = &indexes ^ row;
+                xor_into(&mut data, row_data);
+            }
+        }
+        let Some(&pivot) = indexes.first() else {
+            return; // nothing new
+        };
+        for (row, row_data) in self.rows.values_mut() {
+            if row.contains(&pivot) {
+                *row = &*row ^ &indexes;
+                xor_into(row_data, &data);
+            }
+        }
+        self.rows.insert(pivot, (indexes, data));
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        let mut payload: Vec<u8> = self
+            .rows
+            .values()
+            .flat_map(|(_, data)| data)
+            .copied()
+            .collect();
+        payload.truncate(self.length);
+        if checksum(&payload) != self.checksum {
+            *self = Self::default();
+            return Err("Reassembled QR payload failed its checksum; scan again".into());
+        }
+        self.result = Some(payload);
+        Ok(())
+    }
+}
+
+#[wasm_bindgen]
+impl QrDecoder {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Returns true once the payload is complete. Duplicate parts and parts
+    // of an already finished payload are ignored.
+    pub fn receive_part(&mut self, part: &str) -> Result<bool, String> {
+        if self.result.is_some() {
+            return Ok(true);
+        }
+        let part = Part::parse(part)?;
+        if self.total == 0 {
+            self.start(&part);
+        } else if (part.total, part.length, part.checksum)
+            != (self.total, self.length, self.checksum)
+        {
+            return Err("QR part belongs to a different payload".into());
+        }
+        if !self.seen.insert(part.seq) {
+            return Ok(false);
+        }
+
+        self.absorb(
+            mixed_fragments(part.seq, self.total, self.checksum),
+            part.data,
+        );
+        if self.rows.len() == self.total {
+            self.finish()?;
+        }
+        Ok(self.result.is_some())
+    }
+
+    // Fraction of the independent parts needed that have arrived, 0.0 to 1.0.
+    pub fn progress(&self) -> f64 {
+        if self.result.is_some() {
+            return 1.0;
+        }
+        if self.total == 0 {
+            return 0.0;
+        }
+        self.rows.len() as f64 / self.total as f64
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.result.is_some()
+    }
+
+    pub fn expected_parts(&self) -> usize {
+        self.total
+    }
+
+    pub fn result(&self) -> Option<String> {
+        self.result
+            .as_ref()
+            .map(|bytes| String::from_utf8_lossy(bytes).into_owned())
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAYLOAD: &str = "a payload long enough to need several animated QR parts to carry it";
+
+    fn parts(n: usize) -> Vec<String> {
+        let mut encoder = QrEncoder::new(PAYLOAD, 8).unwrap();
+        (0..n).map(|_| encoder.next_part()).collect()
+    }
+
+    fn decode(parts: &[String]) -> QrDecoder {
+        let mut decoder = QrDecoder::new();
+        for part in parts {
+            if decoder.receive_part(part).unwrap() {
+                break;
+            }
+        }
+        decoder
+    }
+
+    #[test]
+    fn round_trips_in_order() {
+        let total = QrEncoder::new(PAYLOAD, 8).unwrap().part_count();
+        let decoder = decode(&parts(total));
+        assert_eq!(decoder.result().as_deref(), Some(PAYLOAD));
+        assert_eq!(decoder.progress(), 1.0);
+    }
+
+    #[test]
+    fn recovers_dropped_frames_from_mixed_parts() {
+        let total = QrEncoder::new(PAYLOAD, 8).unwrap().part_count();
+        // Miss every other plain part; the mixed ones fill the gaps.
+        let seen: Vec<String> = parts(total * 8)
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| *i >= total || i % 2 == 0)
+            .map(|(_, p)| p)
+            .collect();
+        let decoder = decode(&seen);
+        assert_eq!(decoder.result().as_deref(), Some(PAYLOAD));
+    }
+
+    #[test]
+    fn ignores_duplicate_parts() {
+        let first = &parts(1)[0];
+        let mut decoder = QrDecoder::new();
+        assert!(!decoder.receive_part(first).unwrap());
+        let progress = decoder.progress();
+        assert!(!decoder.receive_part(first).unwrap());
+        assert_eq!(decoder.progress(), progress);
+    }
+
+    #[test]
+    fn rejects_crafted_frames() {
+        for frame in [
+            "",
+            "nmq1/1-2/4/00000000",
+            "xyz1/1-1/2/00000000/AAA",
+            "nmq1/0-1/2/00000000/AAA",
+            "nmq1/1-0/2/00000000/AAA",
+            "nmq1/1-4097/4097/00000000/AA",
+            "nmq1/1-2/1/00000000/AA",
+            "nmq1/1-1/2000000/00000000/AA",
+            "nmq1/1-1/3/00000000/AA",
+            "nmq1/1-1/2/zzzzzzzz/AAA",
+            "nmq1/x-1/2/00000000/AAA",
+        ] {
+            assert!(QrDecoder::new().receive_part(frame).is_err(), "{frame}");
+        }
+    }
+
+    #[test]
+    fn rejects_parts_of_another_payload() {
+        let mut decoder = QrDecoder::new();
+        decoder.receive_part(&parts(1)[0]).unwrap();
+        let other = QrEncoder::new("something else entirely", 8)
+            .unwrap()
+            .next_part();
+        let err = decoder.receive_part(&other).unwrap_err();
+        assert!(err.contains("different payload"), "{err}");
+    }
+
+    #[test]
+    fn rejects_corrupted_fragments_and_resets() {
+        let total = QrEncoder::new(PAYLOAD, 8).unwrap().part_count();
+        let mut frames = parts(total);
+        let mut part = Part::parse(&frames[0]).unwrap();
+        part.data[0] ^= 1;
+        frames[0] = part.format();
+
+        let mut decoder = QrDecoder::new();
+        let mut result = Ok(false);
+        for frame in &frames {
+            result = decoder.receive_part(frame);
+        }
+        assert!(result.unwrap_err().contains("checksum"));
+        assert_eq!(decoder.expected_parts(), 0);
+        assert!(!decoder.is_complete());
+    }
+}
@@ -0,0 +1,49 @@
+use crate::rng::RandomSource;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Entropy Sources
+// ============================================================================
+
+// `RandomSource` implementations backed by the platform. Anything else (an
+// HSM, a hardware RNG, a recorded stream for a test) implements the trait
+// itself; `SeededRng` is the deterministic one.
+
+// `/dev/urandom`, for native hosts such as a coordinator.
+#[cfg(all(feature = "native", unix))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OsRandom;
+
+#[cfg(all(feature = "native", unix))]
+impl RandomSource for OsRandom {
+    fn fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), String> {
+        use std::io::Read;
+        std::fs::File::open("/dev/urandom")
+            .and_then(|mut f| f.read_exact(dest))
+            .map_err(|e| format!("OS randomness unavailable: {}", e))
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = crypto, js_name = getRandomValues, catch)]
+    fn get_random_values(buf: &mut [u8]) -> Result<(), JsValue>;
+}
+
+// `crypto.getRandomValues`, in browsers, workers and Node.
+#[cfg(target_arch = "wasm32")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WebCryptoRandom;
+
+#[cfg(target_arch = "wasm32")]
+impl RandomSource for WebCryptoRandom {
+    fn fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), String> {
+        // The API fills at most 65536 bytes per call.
+        for chunk in dest.chunks_mut(65536) {
+            get_random_values(chunk).map_err(|_| "crypto.getRandomValues is unavailable")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+use crate::wallets::Wallet;
+use crate::{Note, NoteName};
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Reconciliation
+// ============================================================================
+
+// With several coordinators watching one wallet, any of them may broadcast
+// a spend or see a deposit the others missed. Reconciliation compares what
+// the wallet holds against a snapshot of the node's view of its lock and
+// reports where they disagree. The crate does no I/O: the host queries the
+// node (at whatever URL it trusts) and passes the result in. Nothing is
+// changed; acting on the report is left to the caller, e.g. by feeding the
+// node's events through `apply_chain_event`.
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NodeNote {
+    pub note: Note,
+    // Txid of the transaction that spent the note, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spent_by: Option<String>,
+}
+
+// Every note the node knows at the wallet's lock, spent or not.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NodeSnapshot {
+    // Where the snapshot came from (e.g. the node URL), echoed in the report.
+    pub node: String,
+    pub tip: u64,
+    pub notes: Vec<NodeNote>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExternalSpend {
+    pub note: NoteName,
+    pub txid: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StaleDraft {
+    pub draft: String,
+    // The notes it spends that went elsewhere.
+    pub notes: Vec<NoteName>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReconciliationReport {
+    pub node: String,
+    pub tip: u64,
+    // Held notes the node says were spent by a transaction that is not one
+    // of the wallet's drafts.
+    pub spent_elsewhere: Vec<ExternalSpend>,
+    // Unspent notes on the node the wallet does not hold.
+    pub unknown_incoming: Vec<Note>,
+    // Held notes the node has never seen.
+    pub unknown_to_node: Vec<NoteName>,
+    // Open drafts that spend a note already spent by another transaction.
+    pub invalidated_drafts: Vec<StaleDraft>,
+    // Open drafts whose notes were all spent by the draft itself, i.e.
+    // broadcast by another coordinator.
+    pub broadcast_drafts: Vec<String>,
+}
+
+impl ReconciliationReport {
+    pub fn in_sync(&self) -> bool {
+        self.spent_elsewhere.is_empty()
+            && self.unknown_incoming.is_empty()
+            && self.unknown_to_node.is_empty()
+            && self.invalidated_drafts.is_empty()
+    }
+}
+
+impl Wallet {
+    pub fn reconcile(&self, snapshot: &NodeSnapshot) -> Result<ReconciliationReport, String> {
+        if let Some(n) = snapshot
+            .notes
+            .iter()
+            .find(|n| n.note.lock != self.descriptor.lock)
+        {
+            return Err(format!(
+                "Node note {}/{} is not locked to this wallet",
+                n.note.name.first, n.note.name.last
+            ));
+        }
+        let on_node = |name: &NoteName| snapshot.notes.iter().find(|n| &n.note.name == name);
+        let draft_txids: Vec<Option<String>> = self
+            .sessions
+            .iter()
+            .map(|s| s.transaction.txid().ok())
+            .collect();
+        let is_draft = |txid: &String| draft_txids.iter().flatten().any(|t| t == txid);
+
+        let mut report = ReconciliationReport {
+            node: snapshot.node.clone(),
+            tip: snapshot.tip,
+            ..ReconciliationReport::default()
+        };
+        for note in &self.notes {
+            match on_node(&note.name) {
+                None => report.unknown_to_node.push(note.name.clone()),
+                Some(NodeNote {
+                    spent_by: Some(txid),
+                    ..
+                }) if !is_draft(txid) => report.spent_elsewhere.push(ExternalSpend {
+                    note: note.name.clone(),
+                    txid: txid.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+        report.unknown_incoming = snapshot
+            .notes
+            .iter()
+            .filter(|n| n.spent_by.is_none())
+            .filter(|n| !self.notes.iter().any(|held| held.name == n.note.name))
+            .map(|n| n.note.clone())
+            .collect();
+
+        for (session, txid) in self.sessions.iter().zip(&draft_txids) {
+            let mut stale = Vec::new();
+            let mut landed = !session.transaction.spends.is_empty();
+            for spend in &session.transaction.spends {
+                let spent_by = on_node(&spend.note.name).and_then(|n| n.spent_by.as_ref());
+                match spent_by {
+                    Some(by) if Some(by) == txid.as_ref() => {}
+                    Some(_) => {
+                        stale.push(spend.note.name.clone());
+                        landed = false;
+                    }
+                    None => landed = false,
+                }
+            }
+            if !stale.is_empty() {
+                report.invalidated_drafts.push(StaleDraft {
+                    draft: session.draft_hash(),
+                    notes: stale,
+                });
+            } else if landed {
+                report.broadcast_drafts.push(session.draft_hash());
+            }
+        }
+        Ok(report)
+    }
+}
@@ -0,0 +1,302 @@
+use crate::hashing::{digest_hex, HashAlgorithm};
+use crate::session::SigningSession;
+use crate::verify::SignatureVerifier;
+use crate::wallets::{Wallet, WalletDescriptor};
+use crate::{Lock, Output, PublicKey, Signature, Transaction};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Cosigner Replacement (social recovery)
+// ============================================================================
+
+// Replacing a lost key means moving the funds: the wallet id is derived from
+// its lock, so the new key set is a new wallet. One `KeyReplacement` tracks
+// the whole operation. The remaining keys approve the new lock by signing the
+// operation hash, sweep drafts move every note from the old lock to the new
+// one through the normal signing flow, and once every note is covered by a
+// fully signed sweep the old wallet is archived and the new one takes over
+// its policies and metadata. The lost key can never approve its own
+// replacement, so the primary branch must still reach its threshold without
+// it.
+
+const KEY_REPLACEMENT_DOMAIN: &[u8] = b"nockchain-multisig/key-replacement/v1";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ReplacementState {
+    CollectingApprovals,
+    Sweeping,
+    Completed { new_wallet: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReplacementApproval {
+    pub signer: PublicKey,
+    pub signature: Signature,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeyReplacement {
+    pub wallet: String,
+    pub lost: PublicKey,
+    pub replacement: PublicKey,
+    pub new_lock: Lock,
+    pub state: ReplacementState,
+    #[serde(default)]
+    pub approvals: Vec<ReplacementApproval>,
+    // Draft hashes of the sweep sessions added to the old wallet.
+    #[serde(default)]
+    pub sweeps: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct ReplacementTerms<'a> {
+    wallet: &'a str,
+    lost: &'a PublicKey,
+    replacement: &'a PublicKey,
+    new_lock: &'a Lock,
+}
+
+// Swaps `lost` for `replacement` on every branch that names it.
+pub fn replace_key(lock: &Lock, lost: &PublicKey, replacement: &PublicKey) -> Lock {
+    let mut lock = lock.clone();
+    let swap = |pubkeys: &mut Vec<PublicKey>| {
+        for pk in pubkeys.iter_mut().filter(|pk| *pk == lost) {
+            *pk = replacement.clone();
+        }
+    };
+    swap(&mut lock.pkh.pubkeys);
+    for alternative in &mut lock.alternatives {
+        swap(&mut alternative.pkh.pubkeys);
+    }
+    lock
+}
+
+impl KeyReplacement {
+    pub fn new(wallet: &Wallet, lost: PublicKey, replacement: PublicKey) -> Result<Self, String> {
+        let participants = wallet.descriptor.participants();
+        if !participants.contains(&lost) {
+            return Err(format!("{} is not a signer on this wallet", lost.0));
+        }
+        if participants.contains(&replacement) {
+            return Err(format!(
+                "{} is already a signer on this wallet",
+                replacement.0
+            ));
+        }
+        let primary = &wallet.descriptor.lock.pkh;
+        let remaining = primary.pubkeys.iter().filter(|pk| **pk != lost).count();
+        if remaining < primary.threshold {
+            return Err(format!(
+                "Only {} keys remain for a {}-of-{} primary branch",
+                remaining,
+                primary.threshold,
+                primary.pubkeys.len()
+            ));
+        }
+        let new_lock = replace_key(&wallet.descriptor.lock, &lost, &replacement);
+        new_lock.validate()?;
+        Ok(Self {
+            wallet: wallet.id.clone(),
+            lost,
+            replacement,
+            new_lock,
+            state: ReplacementState::CollectingApprovals,
+            approvals: Vec::new(),
+            sweeps: Vec::new(),
+        })
+    }
+
+    // What each remaining key signs to approve the replacement.
+    pub fn approval_message(&self) -> String {
+        let terms = ReplacementTerms {
+            wallet: &self.wallet,
+            lost: &self.lost,
+            replacement: &self.replacement,
+            new_lock: &self.new_lock,
+        };
+        let bytes = serde_json::to_vec(&terms).expect("Serialization failed");
+        digest_hex(
+            HashAlgorithm::Sha256,
+            &[KEY_REPLACEMENT_DOMAIN, &bytes].concat(),
+        )
+        .expect("sha256 is always available")
+    }
+
+    pub fn new_wallet_id(&self) -> String {
+        self.new_lock.fingerprint()[..16].to_string()
+    }
+
+    pub fn approve(
+        &mut self,
+        wallet: &Wallet,
+        approval: ReplacementApproval,
+        verifier: &dyn SignatureVerifier,
+    ) -> Result<(), String> {
+        self.check_wallet(wallet)?;
+        if self.state != ReplacementState::CollectingApprovals {
+            return Err("Replacement is no longer collecting approvals".into());
+        }
+        if approval.signer == self.lost {
+            return Err("The lost key cannot approve its own replacement".into());
+        }
+        if !wallet
+            .descriptor
+            .lock
+            .pkh
+            .pubkeys
+            .contains(&approval.signer)
+        {
+            return Err(format!(
+                "{} is not a key on the primary branch",
+                approval.signer.0
+            ));
+        }
+        if self.approvals.iter().any(|a| a.signer == approval.signer) {
+            return Err(format!("{} has already approved", approval.signer.0));
+        }
+        if !verifier.verify(
+            &approval.signer,
+            &self.approval_message(),
+            &approval.signature,
+        ) {
+            return Err("Invalid approval signature".into());
+        }
+        self.approvals.push(approval);
+        if self.approvals.len() >= wallet.descriptor.lock.pkh.threshold {
+            self.state = ReplacementState::Sweeping;
+        }
+        Ok(())
+    }
+
+    // Adds a draft moving every free note to the new lock to the old
+    // wallet's sessions. Call again for notes that arrive later.
+    pub fn build_sweep(&mut self, wallet: &mut Wallet) -> Result<usize, String> {
+        self.check_wallet(wallet)?;
+        if self.state != ReplacementState::Sweeping {
+            return Err("Replacement has not been approved".into());
+        }
+        let (index, draft) = add_sweep(wallet, &self.new_lock)?;
+        self.sweeps.push(draft);
+        Ok(index)
+    }
+
+    // Every note still held by the old wallet must be spent by a fully
+    // signed sweep paying only the new lock.
+    pub fn verify_complete(&self, wallet: &Wallet) -> Result<(), String> {
+        self.check_wallet(wallet)?;
+        if self.state != ReplacementState::Sweeping {
+            return Err("Replacement is not sweeping".into());
+        }
+        check_sweeps(wallet, &self.sweeps, &self.new_lock)
+    }
+
+    // Archives the old wallet and returns its successor, carrying over
+    // policies and metadata. The swept notes reach the new wallet through
+    // chain tracking once the sweeps confirm.
+    pub fn complete(&mut self, wallet: &mut Wallet) -> Result<Wallet, String> {
+        self.verify_complete(wallet)?;
+        let successor = hand_over(wallet, &self.new_lock)?;
+        self.state = ReplacementState::Completed {
+            new_wallet: successor.id.clone(),
+        };
+        Ok(successor)
+    }
+
+    fn check_wallet(&self, wallet: &Wallet) -> Result<(), String> {
+        if wallet.id != self.wallet {
+            return Err(format!("Replacement is for wallet {}", self.wallet));
+        }
+        Ok(())
+    }
+}
+
+// Adds a draft moving every free note of `wallet` to `new_lock` and returns
+// its session index and draft hash.
+pub(crate) fn add_sweep(wallet: &mut Wallet, new_lock: &Lock) -> Result<(usize, String), String> {
+    let notes = wallet.free_notes();
+    if notes.is_empty() {
+        return Err("No notes left to sweep".into());
+    }
+    let value = notes
+        .iter()
+        .try_fold(0u64, |sum, n| sum.checked_add(n.value))
+        .ok_or("Note values overflow")?;
+    let output = Output {
+        recipient: new_lock.fingerprint()[..16].to_string(),
+        value,
+        lock: new_lock.clone(),
+    };
+    let tx = Transaction::build(notes, vec![output], HashAlgorithm::default())?;
+    let session = SigningSession::new(tx);
+    let draft = session.draft_hash();
+    let index = wallet.add_session(session)?;
+    Ok((index, draft))
+}
+
+// Every note still held by `wallet` must be spent by a fully signed sweep
+// (one of `sweeps`, by draft hash) paying only `new_lock`.
+pub(crate) fn check_sweeps(
+    wallet: &Wallet,
+    sweeps: &[String],
+    new_lock: &Lock,
+) -> Result<(), String> {
+    let sweeps: Vec<&SigningSession> = wallet
+        .sessions
+        .iter()
+        .filter(|s| sweeps.contains(&s.draft_hash()))
+        .collect();
+    for session in &sweeps {
+        if session
+            .transaction
+            .outputs
+            .iter()
+            .any(|o| &o.lock != new_lock)
+        {
+            return Err("A sweep pays outside the new lock".into());
+        }
+        session.transaction.validate_signatures()?;
+    }
+    for note in &wallet.notes {
+        let swept = sweeps.iter().any(|s| {
+            s.transaction
+                .spends
+                .iter()
+                .any(|spend| spend.note.name == note.name)
+        });
+        if !swept {
+            return Err(format!(
+                "Note {}/{} is not covered by a sweep",
+                note.name.first, note.name.last
+            ));
+        }
+    }
+    Ok(())
+}
+
+// Archives `wallet` and returns its successor under `new_lock`, carrying
+// over policies and metadata.
+pub(crate) fn hand_over(wallet: &mut Wallet, new_lock: &Lock) -> Result<Wallet, String> {
+    let mut successor = Wallet::new(WalletDescriptor {
+        label: wallet.descriptor.label.clone(),
+        lock: new_lock.clone(),
+    })?;
+    successor.policies = wallet.policies.clone();
+    successor.metadata = wallet.metadata.clone();
+    wallet.archived = true;
+    Ok(successor)
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+#[wasm_bindgen]
+pub fn get_key_replacement_message(replacement_json: &str) -> Result<String, String> {
+    let replacement: KeyReplacement =
+        serde_json::from_str(replacement_json).map_err(|e| e.to_string())?;
+    Ok(replacement.approval_message())
+}
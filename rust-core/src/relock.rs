@@ -0,0 +1,206 @@
+use crate::hashing::{digest_hex, HashAlgorithm};
+use crate::recovery::{add_sweep, check_sweeps, hand_over, ReplacementApproval, ReplacementState};
+use crate::verify::SignatureVerifier;
+use crate::wallets::Wallet;
+use crate::{Lock, PublicKey};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Threshold and Membership Changes
+// ============================================================================
+
+// Changing a wallet's threshold or key set changes its lock, and so its id:
+// like a key replacement, the change is a move of funds to a new wallet. A
+// `LockChange` proposes the new lock; the current quorum (the primary
+// branch at its current threshold) approves it by signing the proposal
+// hash, and the same sweep, check and hand-over steps as a key replacement
+// follow. The new lock is checked for sanity before anyone is asked to sign.
+
+const LOCK_CHANGE_DOMAIN: &[u8] = b"nockchain-multisig/lock-change/v1";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LockChange {
+    pub wallet: String,
+    pub new_lock: Lock,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub state: ReplacementState,
+    #[serde(default)]
+    pub approvals: Vec<ReplacementApproval>,
+    // Draft hashes of the sweep sessions added to the old wallet.
+    #[serde(default)]
+    pub sweeps: Vec<String>,
+}
+
+// What approvers are shown, for the primary branch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LockChangeSummary {
+    pub threshold: (usize, usize),
+    pub keys: (usize, usize),
+    pub added: Vec<PublicKey>,
+    pub removed: Vec<PublicKey>,
+}
+
+#[derive(Serialize)]
+struct LockChangeTerms<'a> {
+    wallet: &'a str,
+    new_lock: &'a Lock,
+    reason: Option<&'a str>,
+}
+
+// Structural validity plus the checks a governed change needs: the lock must
+// actually change, and a multi-key primary branch may not drop to a single
+// signature.
+pub fn check_new_lock(old: &Lock, new: &Lock) -> Result<(), String> {
+    new.validate()?;
+    if new == old {
+        return Err("New lock is the same as the current one".into());
+    }
+    let primary = &new.pkh;
+    if primary.threshold == 1 && primary.pubkeys.len() > 1 && old.pkh.threshold > 1 {
+        return Err(format!(
+            "A 1-of-{} primary branch would let any one key spend alone",
+            primary.pubkeys.len()
+        ));
+    }
+    Ok(())
+}
+
+impl LockChange {
+    pub fn new(wallet: &Wallet, new_lock: Lock, reason: Option<String>) -> Result<Self, String> {
+        check_new_lock(&wallet.descriptor.lock, &new_lock)?;
+        Ok(Self {
+            wallet: wallet.id.clone(),
+            new_lock,
+            reason,
+            state: ReplacementState::CollectingApprovals,
+            approvals: Vec::new(),
+            sweeps: Vec::new(),
+        })
+    }
+
+    pub fn summary(&self, wallet: &Wallet) -> Result<LockChangeSummary, String> {
+        self.check_wallet(wallet)?;
+        let old = &wallet.descriptor.lock.pkh;
+        let new = &self.new_lock.pkh;
+        Ok(LockChangeSummary {
+            threshold: (old.threshold, new.threshold),
+            keys: (old.pubkeys.len(), new.pubkeys.len()),
+            added: new
+                .pubkeys
+                .iter()
+                .filter(|pk| !old.pubkeys.contains(pk))
+                .cloned()
+                .collect(),
+            removed: old
+                .pubkeys
+                .iter()
+                .filter(|pk| !new.pubkeys.contains(pk))
+                .cloned()
+                .collect(),
+        })
+    }
+
+    // What each approving key signs.
+    pub fn approval_message(&self) -> String {
+        let terms = LockChangeTerms {
+            wallet: &self.wallet,
+            new_lock: &self.new_lock,
+            reason: self.reason.as_deref(),
+        };
+        let bytes = serde_json::to_vec(&terms).expect("Serialization failed");
+        digest_hex(
+            HashAlgorithm::Sha256,
+            &[LOCK_CHANGE_DOMAIN, &bytes].concat(),
+        )
+        .expect("sha256 is always available")
+    }
+
+    pub fn new_wallet_id(&self) -> String {
+        self.new_lock.fingerprint()[..16].to_string()
+    }
+
+    pub fn approve(
+        &mut self,
+        wallet: &Wallet,
+        approval: ReplacementApproval,
+        verifier: &dyn SignatureVerifier,
+    ) -> Result<(), String> {
+        self.check_wallet(wallet)?;
+        if self.state != ReplacementState::CollectingApprovals {
+            return Err("Lock change is no longer collecting approvals".into());
+        }
+        let quorum = &wallet.descriptor.lock.pkh;
+        if !quorum.pubkeys.contains(&approval.signer) {
+            return Err(format!(
+                "{} is not a key on the primary branch",
+                approval.signer.0
+            ));
+        }
+        if self.approvals.iter().any(|a| a.signer == approval.signer) {
+            return Err(format!("{} has already approved", approval.signer.0));
+        }
+        if !verifier.verify(
+            &approval.signer,
+            &self.approval_message(),
+            &approval.signature,
+        ) {
+            return Err("Invalid approval signature".into());
+        }
+        self.approvals.push(approval);
+        if self.approvals.len() >= quorum.threshold {
+            self.state = ReplacementState::Sweeping;
+        }
+        Ok(())
+    }
+
+    // Adds a draft moving every free note to the new lock to the old
+    // wallet's sessions. Call again for notes that arrive later.
+    pub fn build_sweep(&mut self, wallet: &mut Wallet) -> Result<usize, String> {
+        self.check_wallet(wallet)?;
+        if self.state != ReplacementState::Sweeping {
+            return Err("Lock change has not been approved".into());
+        }
+        let (index, draft) = add_sweep(wallet, &self.new_lock)?;
+        self.sweeps.push(draft);
+        Ok(index)
+    }
+
+    pub fn verify_complete(&self, wallet: &Wallet) -> Result<(), String> {
+        self.check_wallet(wallet)?;
+        if self.state != ReplacementState::Sweeping {
+            return Err("Lock change is not sweeping".into());
+        }
+        check_sweeps(wallet, &self.sweeps, &self.new_lock)
+    }
+
+    // Archives the old wallet and returns its successor.
+    pub fn complete(&mut self, wallet: &mut Wallet) -> Result<Wallet, String> {
+        self.verify_complete(wallet)?;
+        let successor = hand_over(wallet, &self.new_lock)?;
+        self.state = ReplacementState::Completed {
+            new_wallet: successor.id.clone(),
+        };
+        Ok(successor)
+    }
+
+    fn check_wallet(&self, wallet: &Wallet) -> Result<(), String> {
+        if wallet.id != self.wallet {
+            return Err(format!("Lock change is for wallet {}", self.wallet));
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+#[wasm_bindgen]
+pub fn get_lock_change_message(change_json: &str) -> Result<String, String> {
+    let change: LockChange = serde_json::from_str(change_json).map_err(|e| e.to_string())?;
+    Ok(change.approval_message())
+}
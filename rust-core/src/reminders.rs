@@ -0,0 +1,199 @@
+#[cfg(feature = "coordinator")]
+use crate::audit::AuditEvent;
+use crate::session::SigningSession;
+#[cfg(feature = "coordinator")]
+use crate::storage::{Notification, NotificationEvent, Storage, Tenant};
+use crate::PublicKey;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Signing Reminders
+// ============================================================================
+
+// A session can carry a reminder schedule: steps that fire a set time after
+// the coordinator accepted it, while signatures are still missing. A
+// `remind` step nudges the signers who have not signed yet; an `escalate`
+// step tells other keys (backup signers, a manager) that the draft is
+// stalled and who it waits on. Each step fires once, from the coordinator's
+// scheduler, and is recorded in the session's audit log, so the record shows
+// who was chased and when.
+
+pub const REMIND_AFTER: u64 = 12 * 60 * 60;
+pub const ESCALATE_AFTER: u64 = 48 * 60 * 60;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ReminderAction {
+    Remind,
+    Escalate { to: Vec<PublicKey> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReminderStep {
+    // Seconds after the session was accepted.
+    pub after: u64,
+    #[serde(flatten)]
+    pub action: ReminderAction,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReminderSchedule {
+    pub steps: Vec<ReminderStep>,
+    // Steps already fired.
+    #[serde(default)]
+    pub fired: usize,
+}
+
+impl ReminderSchedule {
+    // Remind pending signers after 12 hours, escalate to `backup` after 48.
+    pub fn standard(backup: Vec<PublicKey>) -> Self {
+        Self {
+            steps: vec![
+                ReminderStep {
+                    after: REMIND_AFTER,
+                    action: ReminderAction::Remind,
+                },
+                ReminderStep {
+                    after: ESCALATE_AFTER,
+                    action: ReminderAction::Escalate { to: backup },
+                },
+            ],
+            fired: 0,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.steps.is_empty() {
+            return Err("A reminder schedule needs at least one step".into());
+        }
+        if self.steps.windows(2).any(|w| w[0].after >= w[1].after) {
+            return Err("Reminder steps must be in increasing order of delay".into());
+        }
+        for step in &self.steps {
+            if let ReminderAction::Escalate { to } = &step.action {
+                if to.is_empty() {
+                    return Err("An escalation step must name who to escalate to".into());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl SigningSession {
+    // Keys that still have to sign for the draft to be complete: the
+    // unsigned keys of every spend short of its threshold.
+    pub fn pending_signers(&self) -> Result<Vec<PublicKey>, String> {
+        let mut pending: Vec<PublicKey> = Vec::new();
+        for i in 0..self.transaction.spends.len() {
+            let status = crate::signing_status(i, &self.transaction)?;
+            if status.complete {
+                continue;
+            }
+            for pubkey in status.pending {
+                if !pending.contains(&pubkey) {
+                    pending.push(pubkey);
+                }
+            }
+        }
+        Ok(pending)
+    }
+}
+
+#[cfg(feature = "coordinator")]
+impl<S: Storage> Tenant<'_, S> {
+    // Replaces the session's schedule; steps count from when the session was
+    // accepted, so steps already past fire on the next run.
+    pub fn set_session_reminders(
+        &mut self,
+        wallet_id: &str,
+        session_index: usize,
+        schedule: Option<ReminderSchedule>,
+    ) -> Result<(), String> {
+        if let Some(schedule) = &schedule {
+            schedule.validate()?;
+        }
+        let mut wallet = self.wallet(wallet_id)?;
+        let session = wallet
+            .sessions
+            .get_mut(session_index)
+            .ok_or("Invalid session index")?;
+        session.reminders = schedule.map(|s| ReminderSchedule { fired: 0, ..s });
+        self.put_wallet(&wallet)
+    }
+
+    // Fires every step that has come due on a session still missing
+    // signatures, queues a notification for each, and records it in the
+    // session's audit log. Run from a timer; `run_due_schedules` calls it.
+    pub fn run_reminders(&mut self, now: u64) -> Result<Vec<Notification>, String> {
+        let mut notifications = Vec::new();
+        for wallet_id in self.wallet_ids()? {
+            let mut wallet = self.wallet(&wallet_id)?;
+            let mut changed = false;
+            for (index, session) in wallet.sessions.iter_mut().enumerate() {
+                let Some(created_at) = session.created_at else {
+                    continue;
+                };
+                let Some(mut schedule) = session.reminders.clone() else {
+                    continue;
+                };
+                // Fully signed and waiting on broadcast needs no chasing.
+                if session.transaction.validate_signatures().is_ok() {
+                    continue;
+                }
+                let pending = session.pending_signers()?;
+                let draft = session.draft_hash();
+                while let Some(step) = schedule.steps.get(schedule.fired) {
+                    if created_at.saturating_add(step.after) > now {
+                        break;
+                    }
+                    let (notify, event, audit) = match &step.action {
+                        ReminderAction::Remind => (
+                            pending.clone(),
+                            NotificationEvent::SignatureReminder {
+                                session: index,
+                                draft: draft.clone(),
+                                pending: pending.clone(),
+                            },
+                            AuditEvent::ReminderSent {
+                                step: schedule.fired,
+                                notified: pending.clone(),
+                            },
+                        ),
+                        ReminderAction::Escalate { to } => (
+                            to.clone(),
+                            NotificationEvent::SigningEscalated {
+                                session: index,
+                                draft: draft.clone(),
+                                pending: pending.clone(),
+                            },
+                            AuditEvent::SigningEscalated {
+                                step: schedule.fired,
+                                notified: to.clone(),
+                                pending: pending.clone(),
+                            },
+                        ),
+                    };
+                    session.audit.record(audit, Some(now));
+                    notifications.push(Notification {
+                        at: now,
+                        wallet: wallet_id.clone(),
+                        notify,
+                        event,
+                    });
+                    schedule.fired += 1;
+                }
+                if session.reminders.as_ref() != Some(&schedule) {
+                    session.reminders = Some(schedule);
+                    changed = true;
+                }
+            }
+            if changed {
+                self.put_wallet(&wallet)?;
+            }
+        }
+        self.notify(&notifications)?;
+        Ok(notifications)
+    }
+}
@@ -0,0 +1,102 @@
+use crate::encoding::{base64url_decode, base64url_encode};
+use crate::hashing::{constant_time_eq, hmac_sha256};
+use crate::session::SigningSession;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Resumption Tokens
+// ============================================================================
+
+// A token is one pasteable string naming a draft: which session, which
+// server holds it, and the draft hash the fetched session must match. It is
+// MACed with a secret shared by the cosigners (or the relay), so an edited
+// token is rejected before anything is fetched.
+//
+//   nmr1.<base64url(payload json)>.<base64url(hmac-sha256)>
+
+const TOKEN_PREFIX: &str = "nmr1";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ResumptionToken {
+    pub session: String,
+    pub server: String,
+    pub integrity: String,
+}
+
+impl ResumptionToken {
+    pub fn for_session(session: &SigningSession, session_id: &str, server: &str) -> Self {
+        Self {
+            session: session_id.to_string(),
+            server: server.to_string(),
+            integrity: session.draft_hash(),
+        }
+    }
+
+    pub fn encode(&self, secret: &[u8]) -> String {
+        let payload = serde_json::to_vec(self).expect("Serialization failed");
+        let body = format!("{}.{}", TOKEN_PREFIX, base64url_encode(&payload));
+        let mac = hmac_sha256(secret, body.as_bytes());
+        format!("{}.{}", body, base64url_encode(&mac))
+    }
+
+    pub fn decode(token: &str, secret: &[u8]) -> Result<Self, String> {
+        let token = token.trim();
+        let (body, mac) = token.rsplit_once('.').ok_or("Malformed resumption token")?;
+        let (prefix, payload) = body.split_once('.').ok_or("Malformed resumption token")?;
+        if prefix != TOKEN_PREFIX {
+            return Err(format!("Unsupported resumption token version '{}'", prefix));
+        }
+
+        let mac = base64url_decode(mac)?;
+        if !constant_time_eq(&mac, &hmac_sha256(secret, body.as_bytes())) {
+            return Err("Resumption token has been tampered with".into());
+        }
+
+        let payload = base64url_decode(payload)?;
+        serde_json::from_slice(&payload).map_err(|e| e.to_string())
+    }
+
+    // Checks a session fetched from `server` is the draft the token names.
+    pub fn check(&self, session: &SigningSession) -> Result<(), String> {
+        if session.draft_hash() != self.integrity {
+            return Err("Fetched session does not match the resumption token".into());
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+#[wasm_bindgen]
+pub fn create_resumption_token(
+    session_json: &str,
+    session_id: &str,
+    server: &str,
+    secret: &str,
+) -> Result<String, String> {
+    let session = SigningSession::from_json(session_json)?;
+    Ok(ResumptionToken::for_session(&session, session_id, server).encode(secret.as_bytes()))
+}
+
+// Returns the token payload as JSON: `{session, server, integrity}`.
+#[wasm_bindgen]
+pub fn redeem_resumption_token(token: &str, secret: &str) -> Result<String, String> {
+    let token = ResumptionToken::decode(token, secret.as_bytes())?;
+    serde_json::to_string(&token).map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+pub fn verify_resumed_session(
+    token: &str,
+    secret: &str,
+    session_json: &str,
+) -> Result<String, String> {
+    let token = ResumptionToken::decode(token, secret.as_bytes())?;
+    let session = SigningSession::from_json(session_json)?;
+    token.check(&session)?;
+    Ok(session_json.to_string())
+}
@@ -0,0 +1,202 @@
+use crate::encoding::{base64url_decode, base64url_encode, compress, decompress};
+use crate::session::SigningSession;
+use crate::storage::{validate_segment, Storage, Tenant};
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Session Retention
+// ============================================================================
+
+// Wallet documents grow with every draft, so a long-running coordinator
+// moves sessions it no longer needs out of them. Completed sessions (the
+// txid was recorded with `record_finalized`, or the draft was invalidated
+// or replaced) are archived `archive_after` seconds after completion, which
+// for invalidated drafts is taken to be their creation; open drafts older
+// than `draft_ttl` are pruned from the signing flow and archived as expired.
+// Archived sessions are stored compressed, one list per wallet, and can
+// still be listed and opened for audits.
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RetentionPolicy {
+    // Seconds after completion before a session is archived.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive_after: Option<u64>,
+    // Seconds after the coordinator accepted a draft before it expires.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub draft_ttl: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveReason {
+    Broadcast,
+    Invalidated,
+    Expired,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ArchivedSession {
+    pub draft: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub txid: Option<String>,
+    pub reason: ArchiveReason,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+    pub archived_at: u64,
+    // Compressed session JSON, base64url.
+    pub data: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RetentionReport {
+    pub archived: usize,
+    // How many of the archived sessions were expired drafts.
+    pub pruned: usize,
+    // Uncompressed and stored bytes of what was archived.
+    pub bytes_in: usize,
+    pub bytes_out: usize,
+}
+
+impl ArchivedSession {
+    fn new(
+        session: &SigningSession,
+        txid: Option<String>,
+        reason: ArchiveReason,
+        now: u64,
+    ) -> Result<(Self, usize), String> {
+        let json = serde_json::to_vec(session).map_err(|e| e.to_string())?;
+        let archived = Self {
+            draft: session.draft_hash(),
+            txid,
+            reason,
+            created_at: session.created_at,
+            archived_at: now,
+            data: base64url_encode(&compress(&json)),
+        };
+        Ok((archived, json.len()))
+    }
+
+    pub fn session(&self) -> Result<SigningSession, String> {
+        let json = decompress(&base64url_decode(&self.data)?)?;
+        let session: SigningSession = serde_json::from_slice(&json).map_err(|e| e.to_string())?;
+        if session.draft_hash() != self.draft {
+            return Err(format!("Archived session {} is corrupt", self.draft));
+        }
+        Ok(session)
+    }
+}
+
+fn archive_key(wallet_id: &str) -> Result<String, String> {
+    validate_segment("wallet", wallet_id)?;
+    Ok(format!("session_archive/{}", wallet_id))
+}
+
+impl<S: Storage> Tenant<'_, S> {
+    pub fn retention(&self) -> Result<RetentionPolicy, String> {
+        Ok(self.load("retention")?.unwrap_or_default())
+    }
+
+    pub fn set_retention(&mut self, policy: &RetentionPolicy) -> Result<(), String> {
+        self.store("retention", policy)
+    }
+
+    pub fn archived_sessions(&self, wallet_id: &str) -> Result<Vec<ArchivedSession>, String> {
+        Ok(self.load(&archive_key(wallet_id)?)?.unwrap_or_default())
+    }
+
+    pub fn set_archived_sessions(
+        &mut self,
+        wallet_id: &str,
+        archived: &[ArchivedSession],
+    ) -> Result<(), String> {
+        self.store(&archive_key(wallet_id)?, &archived)
+    }
+
+    // Opens one archived session, with its audit log, by draft hash.
+    pub fn archived_session(&self, wallet_id: &str, draft: &str) -> Result<SigningSession, String> {
+        self.archived_sessions(wallet_id)?
+            .iter()
+            .find(|a| a.draft == draft)
+            .ok_or_else(|| format!("No archived session {}", draft))?
+            .session()
+    }
+
+    // Applies the tenant's retention policy to every wallet.
+    pub fn apply_retention(&mut self, now: u64) -> Result<RetentionReport, String> {
+        let policy = self.retention()?;
+        let mut report = RetentionReport::default();
+        if policy == RetentionPolicy::default() {
+            return Ok(report);
+        }
+        let due = |after: Option<u64>, since: Option<u64>| match (after, since) {
+            (Some(after), Some(since)) => now >= since.saturating_add(after),
+            _ => false,
+        };
+
+        for wallet_id in self.wallet_ids()? {
+            let mut wallet = self.wallet(&wallet_id)?;
+            let history = self.spending(&wallet_id)?;
+            let mut archived = Vec::new();
+
+            let mut open = Vec::new();
+            for session in std::mem::take(&mut wallet.sessions) {
+                let txid = session.transaction.txid().ok();
+                let broadcast = history
+                    .records
+                    .iter()
+                    .find(|r| Some(&r.txid) == txid.as_ref());
+                let reason = match broadcast {
+                    Some(r) if due(policy.archive_after, Some(r.at)) => {
+                        Some(ArchiveReason::Broadcast)
+                    }
+                    Some(_) => None,
+                    None if due(policy.draft_ttl, session.created_at) => {
+                        report.pruned += 1;
+                        Some(ArchiveReason::Expired)
+                    }
+                    None => None,
+                };
+                match reason {
+                    Some(reason) => {
+                        archived.push(ArchivedSession::new(&session, txid, reason, now)?)
+                    }
+                    None => open.push(session),
+                }
+            }
+            wallet.sessions = open;
+
+            let mut kept = Vec::new();
+            for session in std::mem::take(&mut wallet.invalidated) {
+                if due(policy.archive_after, session.created_at) {
+                    let txid = session.transaction.txid().ok();
+                    archived.push(ArchivedSession::new(
+                        &session,
+                        txid,
+                        ArchiveReason::Invalidated,
+                        now,
+                    )?);
+                } else {
+                    kept.push(session);
+                }
+            }
+            wallet.invalidated = kept;
+
+            if archived.is_empty() {
+                continue;
+            }
+            let mut stored = self.archived_sessions(&wallet_id)?;
+            for (entry, bytes) in archived {
+                report.archived += 1;
+                report.bytes_in += bytes;
+                report.bytes_out += entry.data.len();
+                stored.push(entry);
+            }
+            self.set_archived_sessions(&wallet_id, &stored)?;
+            self.put_wallet(&wallet)?;
+        }
+        Ok(report)
+    }
+}
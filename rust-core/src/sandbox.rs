@@ -0,0 +1,135 @@
+use crate::hashing::{digest_hex, HashAlgorithm};
+use crate::verify::{SignatureVerifier, VerificationItem};
+use crate::{PublicKey, Signature, Transaction};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Sandbox
+// ============================================================================
+
+// Fake keys and signatures so a frontend can run the whole signing flow
+// without a wallet. A sandbox key is derived from a name ("alice"), and its
+// signature over a message is a hash of the key and the message: anyone can
+// compute it, so it proves nothing. Both carry a `sandbox-` prefix so they
+// stand out in any JSON they end up in. `SandboxVerifier` accepts exactly
+// these signatures, which lets the UI show a wrong-key or tampered-draft
+// error the way real verification would.
+//
+// A real verifier never accepts them. Still, so that no shipped bundle
+// hands out fake signatures, the feature refuses to build with release
+// optimizations. Use `wasm-pack build --dev -- --features sandbox`.
+
+#[cfg(not(debug_assertions))]
+compile_error!(
+    "The `sandbox` feature fakes keys and signatures and is for development builds only"
+);
+
+const SANDBOX_DOMAIN: &[u8] = b"nockchain-multisig/sandbox/v1";
+
+pub const SANDBOX_PREFIX: &str = "sandbox-";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SandboxKey {
+    pub name: String,
+    pub pubkey: PublicKey,
+}
+
+fn sandbox_digest(parts: &[&str]) -> String {
+    let mut bytes = SANDBOX_DOMAIN.to_vec();
+    for part in parts {
+        bytes.extend_from_slice(part.as_bytes());
+        bytes.push(0);
+    }
+    digest_hex(HashAlgorithm::Sha256, &bytes).expect("sha256 is always available")
+}
+
+pub fn sandbox_key(name: &str) -> SandboxKey {
+    SandboxKey {
+        name: name.to_string(),
+        pubkey: PublicKey(format!("{}pk-{}", SANDBOX_PREFIX, sandbox_digest(&[name]))),
+    }
+}
+
+pub fn sandbox_signature(pubkey: &PublicKey, message_hash: &str) -> Signature {
+    Signature(format!(
+        "{}sig-{}",
+        SANDBOX_PREFIX,
+        sandbox_digest(&[&pubkey.0, message_hash])
+    ))
+}
+
+pub fn is_sandbox_key(pubkey: &PublicKey) -> bool {
+    pubkey.0.starts_with(SANDBOX_PREFIX)
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SandboxVerifier;
+
+impl SignatureVerifier for SandboxVerifier {
+    fn verify(&self, pubkey: &PublicKey, message_hash: &str, signature: &Signature) -> bool {
+        is_sandbox_key(pubkey) && sandbox_signature(pubkey, message_hash) == *signature
+    }
+}
+
+// Signs every spend `pubkey` can sign with a plain signature over the spend
+// hash. Returns how many spends were signed.
+pub fn sign_with_sandbox_key(tx: &mut Transaction, pubkey: &PublicKey) -> Result<usize, String> {
+    if !is_sandbox_key(pubkey) {
+        return Err(format!("{} is not a sandbox key", pubkey.0));
+    }
+    let mut signed = 0;
+    for spend in &mut tx.spends {
+        if spend.signers()?.pubkeys.contains(pubkey) {
+            let signature = sandbox_signature(pubkey, &spend.seeds.message_hash);
+            spend.seeds.add_signature(pubkey.clone(), signature);
+            signed += 1;
+        }
+    }
+    if signed == 0 {
+        return Err(format!("{} cannot sign any spend in this draft", pubkey.0));
+    }
+    Ok(signed)
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+// `names_json` is a list of names, e.g. `["alice", "bob", "carol"]`.
+#[wasm_bindgen]
+pub fn sandbox_keys(names_json: &str) -> Result<String, String> {
+    let names: Vec<String> = serde_json::from_str(names_json).map_err(|e| e.to_string())?;
+    let keys: Vec<SandboxKey> = names.iter().map(|name| sandbox_key(name)).collect();
+    serde_json::to_string(&keys).map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+pub fn sandbox_sign(pubkey: &str, message_hash: &str) -> Result<String, String> {
+    let pubkey = PublicKey(pubkey.to_string());
+    if !is_sandbox_key(&pubkey) {
+        return Err(format!("{} is not a sandbox key", pubkey.0));
+    }
+    Ok(sandbox_signature(&pubkey, message_hash).0)
+}
+
+#[wasm_bindgen]
+pub fn sandbox_sign_transaction(tx_json: &str, pubkey: &str) -> Result<String, String> {
+    let mut tx: Transaction = serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+    sign_with_sandbox_key(&mut tx, &PublicKey(pubkey.to_string()))?;
+    serde_json::to_string(&tx).map_err(|e| e.to_string())
+}
+
+// Stands in for WebCrypto: takes the items from any `get_*_verification_items`
+// call and returns the ones that pass, for the matching `*_with_results` call.
+#[wasm_bindgen]
+pub fn sandbox_verify_items(items_json: &str) -> Result<String, String> {
+    let items: Vec<VerificationItem> =
+        serde_json::from_str(items_json).map_err(|e| e.to_string())?;
+    let verified: Vec<&VerificationItem> = items
+        .iter()
+        .filter(|item| SandboxVerifier.verify(&item.pubkey, &item.message_hash, &item.signature))
+        .collect();
+    serde_json::to_string(&verified).map_err(|e| e.to_string())
+}
@@ -0,0 +1,275 @@
+use crate::build::{BuildOptions, ChangeOptions};
+use crate::hashing::{digest_hex, HashAlgorithm};
+use crate::mockchain::MockChain;
+use crate::network::Network;
+use crate::session::SigningSession;
+use crate::tracking::ChainEvent;
+use crate::wallets::{Wallet, WalletDescriptor};
+use crate::{Lock, Note, Output, PkhCondition, PublicKey, Signature, Transaction};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Scenarios
+// ============================================================================
+
+// Drives a whole flow against a `MockChain` the way an app would, and keeps
+// what each step produced, so integration tests and demos can start from
+// any point of a realistic run instead of hand-building fixtures. The golden
+// path: create a wallet, fund it, build a draft paying `payments` (change
+// goes back to the wallet), collect signatures from the first `threshold`
+// signers, finalize, broadcast, and mine until confirmed. A failing step
+// ends the run; the steps before it are still returned.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GoldenPath {
+    #[serde(default = "default_label")]
+    pub label: String,
+    #[serde(default)]
+    pub network: Network,
+    pub threshold: usize,
+    pub signers: Vec<PublicKey>,
+    // One credited note per amount.
+    pub funding: Vec<u64>,
+    pub payments: Vec<Output>,
+    // Blocks to mine after the broadcast.
+    #[serde(default = "default_confirmations")]
+    pub confirmations: u64,
+}
+
+fn default_label() -> String {
+    "Scenario".into()
+}
+
+fn default_confirmations() -> u64 {
+    1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "step", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ScenarioStep {
+    CreateWallet {
+        wallet_id: String,
+        descriptor: WalletDescriptor,
+    },
+    Fund {
+        notes: Vec<Note>,
+        height: u64,
+    },
+    Build {
+        transaction: Transaction,
+        draft: String,
+    },
+    Sign {
+        pubkey: PublicKey,
+        spends: usize,
+        transaction: Transaction,
+    },
+    Finalize {
+        transaction: Transaction,
+        txid: String,
+    },
+    Broadcast {
+        txid: String,
+    },
+    Confirm {
+        height: u64,
+        confirmations: u64,
+        balance: u64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioFailure {
+    pub step: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioRun {
+    pub steps: Vec<ScenarioStep>,
+    // The wallet as it stands after the last step that ran.
+    pub wallet: Option<Wallet>,
+    pub failed: Option<ScenarioFailure>,
+}
+
+// Stands in for wallets in demos: a hash of the key and message that only
+// an unverifying chain (the `MockChain` default) accepts.
+pub fn placeholder_signature(pubkey: &PublicKey, message_hash: &str) -> Signature {
+    let bytes = [pubkey.0.as_bytes(), b"\n", message_hash.as_bytes()].concat();
+    let digest = digest_hex(HashAlgorithm::Sha256, &bytes).expect("sha256 is always available");
+    Signature(format!("placeholder-{}", digest))
+}
+
+impl GoldenPath {
+    // `sign` is called with each signer's key and the spend hash to sign.
+    pub fn run(
+        &self,
+        chain: &mut MockChain,
+        sign: &dyn Fn(&PublicKey, &str) -> Signature,
+    ) -> ScenarioRun {
+        let mut run = ScenarioRun {
+            steps: Vec::new(),
+            wallet: None,
+            failed: None,
+        };
+        if let Err((step, error)) = golden_path_steps(chain, self, sign, &mut run) {
+            run.failed = Some(ScenarioFailure {
+                step: step.into(),
+                error,
+            });
+        }
+        run
+    }
+}
+
+type StepResult = Result<(), (&'static str, String)>;
+
+fn golden_path_steps(
+    chain: &mut MockChain,
+    path: &GoldenPath,
+    sign: &dyn Fn(&PublicKey, &str) -> Signature,
+    run: &mut ScenarioRun,
+) -> StepResult {
+    let at = |step: &'static str| move |error: String| (step, error);
+
+    let descriptor = WalletDescriptor {
+        label: path.label.clone(),
+        lock: Lock::new(PkhCondition {
+            threshold: path.threshold,
+            pubkeys: path.signers.clone(),
+        }),
+    };
+    let wallet = Wallet::new(descriptor.clone()).map_err(at("create_wallet"))?;
+    run.steps.push(ScenarioStep::CreateWallet {
+        wallet_id: wallet.id.clone(),
+        descriptor: descriptor.clone(),
+    });
+    let wallet = run.wallet.insert(wallet);
+
+    let lock = &descriptor.lock;
+    for value in &path.funding {
+        chain.credit(lock, *value);
+    }
+    chain.mine();
+    let notes = chain.unspent_notes(lock).map_err(at("fund"))?;
+    for note in &notes {
+        wallet
+            .apply_chain_event(ChainEvent::Seen { note: note.clone() })
+            .map_err(at("fund"))?;
+    }
+    run.steps.push(ScenarioStep::Fund {
+        notes: notes.clone(),
+        height: chain.height(),
+    });
+
+    let options = BuildOptions {
+        change: Some(ChangeOptions {
+            recipient: wallet.id.clone(),
+            lock: lock.clone(),
+            randomize_position: false,
+            parts: 1,
+        }),
+        ..BuildOptions::default()
+    };
+    let mut transaction = Transaction::build_with(
+        wallet.free_notes(),
+        path.payments.clone(),
+        HashAlgorithm::default(),
+        &options,
+    )
+    .map_err(at("build"))?
+    .transaction;
+    transaction.set_network(path.network);
+    transaction.refresh_spend_hashes().map_err(at("build"))?;
+    let session = SigningSession::new(transaction.clone());
+    run.steps.push(ScenarioStep::Build {
+        transaction,
+        draft: session.draft_hash(),
+    });
+    let index = wallet.add_session(session).map_err(at("build"))?;
+
+    for pubkey in path.signers.iter().take(path.threshold) {
+        let session = &mut wallet.sessions[index];
+        let hashes: Vec<String> = session
+            .transaction
+            .spends
+            .iter()
+            .map(|s| s.seeds.message_hash.clone())
+            .collect();
+        for (i, hash) in hashes.iter().enumerate() {
+            session
+                .add_signature(i, pubkey.clone(), sign(pubkey, hash), None)
+                .map_err(at("sign"))?;
+        }
+        run.steps.push(ScenarioStep::Sign {
+            pubkey: pubkey.clone(),
+            spends: hashes.len(),
+            transaction: session.transaction.clone(),
+        });
+    }
+
+    let finalized = wallet.sessions[index]
+        .transaction
+        .finalize()
+        .map_err(at("finalize"))?;
+    let txid = finalized.txid().map_err(at("finalize"))?;
+    run.steps.push(ScenarioStep::Finalize {
+        transaction: finalized.clone(),
+        txid: txid.clone(),
+    });
+
+    chain.broadcast(&finalized).map_err(at("broadcast"))?;
+    run.steps
+        .push(ScenarioStep::Broadcast { txid: txid.clone() });
+
+    for _ in 0..path.confirmations {
+        chain.mine();
+    }
+    let confirmations = chain
+        .confirmations(&txid)
+        .map_err(at("confirm"))?
+        .filter(|n| *n > 0)
+        .ok_or_else(|| ("confirm", format!("Transaction {} did not confirm", txid)))?;
+    let height = chain.height() + 1 - confirmations;
+    for spend in &finalized.spends {
+        wallet
+            .apply_chain_event(ChainEvent::Spent {
+                name: spend.note.name.clone(),
+                txid: txid.clone(),
+                height: Some(height),
+            })
+            .map_err(at("confirm"))?;
+    }
+    // Change comes back as new notes.
+    for note in chain.unspent_notes(lock).map_err(at("confirm"))? {
+        if !wallet.notes.iter().any(|n| n.name == note.name) {
+            wallet
+                .apply_chain_event(ChainEvent::Seen { note })
+                .map_err(at("confirm"))?;
+        }
+    }
+    run.steps.push(ScenarioStep::Confirm {
+        height,
+        confirmations,
+        balance: wallet.balance(),
+    });
+    Ok(())
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+// Runs the golden path on a fresh mock chain with placeholder signatures and
+// returns every step's artifacts, e.g. to seed a demo or a UI test.
+#[wasm_bindgen]
+pub fn run_golden_path(path_json: &str) -> Result<String, String> {
+    let path: GoldenPath = serde_json::from_str(path_json).map_err(|e| e.to_string())?;
+    let mut chain = MockChain::new(path.network);
+    let run = path.run(&mut chain, &placeholder_signature);
+    serde_json::to_string(&run).map_err(|e| e.to_string())
+}
@@ -0,0 +1,294 @@
+use crate::hashing::HashAlgorithm;
+use crate::session::SigningSession;
+use crate::storage::{validate_segment, Notification, NotificationEvent, Storage, Tenant};
+use crate::template::DraftTemplate;
+use crate::PublicKey;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Cron Expressions
+// ============================================================================
+
+// Standard five-field cron (`minute hour day-of-month month day-of-week`,
+// UTC) with `*`, lists, ranges and `/step`. As in cron, when both day fields
+// are restricted a day matching either one fires.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+    minutes: u64,
+    hours: u32,
+    days: u32,
+    months: u16,
+    weekdays: u8,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+// Bitmask of the values in `min..=max` a field selects.
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let invalid = || format!("Invalid cron field '{}'", field);
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        let (from, to) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((a, b)) => (
+                    a.parse().map_err(|_| invalid())?,
+                    b.parse().map_err(|_| invalid())?,
+                ),
+                None => {
+                    let v = range.parse().map_err(|_| invalid())?;
+                    (v, if part.contains('/') { max } else { v })
+                }
+            },
+        };
+        if step == 0 || from < min || to > max || from > to {
+            return Err(invalid());
+        }
+        for v in (from..=to).step_by(step as usize) {
+            mask |= 1 << v;
+        }
+    }
+    Ok(mask)
+}
+
+// (year, month, day) of a day count since 1970-01-01.
+fn civil_from_days(days: u64) -> (u64, u32, u32) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+impl Cron {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "Cron expression needs 5 fields, got {}",
+                fields.len()
+            ));
+        };
+        let mut weekdays = parse_field(weekday, 0, 7)?;
+        // 7 is Sunday too.
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & 0x7f;
+        }
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)? as u32,
+            days: parse_field(day, 1, 31)? as u32,
+            months: parse_field(month, 1, 12)? as u16,
+            weekdays: weekdays as u8,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+
+    fn day_matches(&self, days: u64) -> bool {
+        let (_, month, day) = civil_from_days(days);
+        if self.months & (1 << month) == 0 {
+            return false;
+        }
+        // 1970-01-01 was a Thursday.
+        let weekday = (days + 4) % 7;
+        let day_ok = self.days & (1 << day) != 0;
+        let weekday_ok = self.weekdays & (1 << weekday) != 0;
+        match (self.any_day, self.any_weekday) {
+            (false, false) => day_ok || weekday_ok,
+            _ => day_ok && weekday_ok,
+        }
+    }
+
+    // First matching minute strictly after `after` (unix seconds).
+    pub fn next_after(&self, after: u64) -> Result<u64, String> {
+        let start = after / 60 + 1;
+        let first = start / 1440;
+        // Four years covers every calendar; anything beyond never fires.
+        for day in first..first + 1461 {
+            if !self.day_matches(day) {
+                continue;
+            }
+            let from = if day == first { start % 1440 } else { 0 };
+            for m in from..1440 {
+                if self.hours & (1 << (m / 60)) != 0 && self.minutes & (1 << (m % 60)) != 0 {
+                    return Ok((day * 1440 + m) * 60);
+                }
+            }
+        }
+        Err("Cron expression never fires".into())
+    }
+}
+
+// ============================================================================
+// Scheduled Drafts
+// ============================================================================
+
+// An approved template the coordinator turns into a draft on a schedule.
+// When a run comes due, the template is instantiated against the wallet's
+// notes that no open session is already spending, and the draft enters the
+// signing flow through `add_session`, so tenant limits and policies apply as
+// for any other draft. Each run leaves a notification in the tenant's outbox
+// naming the signers to ask, or on failure the keys that approved the
+// schedule. Runs missed while the coordinator was down are
+// not replayed; the schedule fires once and moves on.
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduledDraft {
+    pub id: String,
+    pub wallet: String,
+    pub template: DraftTemplate,
+    pub cron: String,
+    // Wallet signers who approved the standing instruction.
+    pub approved_by: Vec<PublicKey>,
+    pub next_run: u64,
+    #[serde(default)]
+    pub paused: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_run: Option<u64>,
+}
+
+fn schedule_key(id: &str) -> Result<String, String> {
+    validate_segment("schedule", id)?;
+    Ok(format!("schedules/{}", id))
+}
+
+impl<S: Storage> Tenant<'_, S> {
+    pub fn schedule_template(
+        &mut self,
+        id: &str,
+        wallet_id: &str,
+        template: DraftTemplate,
+        cron: &str,
+        approved_by: Vec<PublicKey>,
+        now: u64,
+    ) -> Result<ScheduledDraft, String> {
+        let wallet = self.wallet(wallet_id)?;
+        template.validate()?;
+        if approved_by.is_empty() {
+            return Err("A scheduled template needs at least one approval".into());
+        }
+        let participants = wallet.descriptor.participants();
+        if let Some(pk) = approved_by.iter().find(|pk| !participants.contains(pk)) {
+            return Err(format!("{} is not a signer on wallet {}", pk.0, wallet_id));
+        }
+        let schedule = ScheduledDraft {
+            id: id.to_string(),
+            wallet: wallet_id.to_string(),
+            template,
+            next_run: Cron::parse(cron)?.next_after(now)?,
+            cron: cron.to_string(),
+            approved_by,
+            paused: false,
+            last_run: None,
+        };
+        self.store(&schedule_key(id)?, &schedule)?;
+        Ok(schedule)
+    }
+
+    pub fn schedule(&self, id: &str) -> Result<ScheduledDraft, String> {
+        self.load(&schedule_key(id)?)?
+            .ok_or_else(|| format!("Unknown schedule {}", id))
+    }
+
+    pub fn schedules(&self) -> Result<Vec<ScheduledDraft>, String> {
+        self.list("schedules")?
+            .iter()
+            .map(|id| self.schedule(id))
+            .collect()
+    }
+
+    pub fn put_schedule(&mut self, schedule: &ScheduledDraft) -> Result<(), String> {
+        self.store(&schedule_key(&schedule.id)?, schedule)
+    }
+
+    // Resuming skips the runs that fell due while paused.
+    pub fn set_schedule_paused(
+        &mut self,
+        id: &str,
+        paused: bool,
+        now: u64,
+    ) -> Result<ScheduledDraft, String> {
+        let mut schedule = self.schedule(id)?;
+        if schedule.paused && !paused {
+            schedule.next_run = Cron::parse(&schedule.cron)?.next_after(now)?;
+        }
+        schedule.paused = paused;
+        self.put_schedule(&schedule)?;
+        Ok(schedule)
+    }
+
+    pub fn delete_schedule(&mut self, id: &str) -> Result<bool, String> {
+        self.remove(&schedule_key(id)?)
+    }
+
+    fn run_schedule(
+        &mut self,
+        schedule: &ScheduledDraft,
+        now: u64,
+    ) -> Result<NotificationEvent, String> {
+        let wallet = self.wallet(&schedule.wallet)?;
+        if wallet.archived {
+            return Err(format!("Wallet {} is archived", wallet.id));
+        }
+        let notes = wallet.free_notes();
+        if notes.is_empty() {
+            return Err("No notes free to spend".into());
+        }
+        let report = schedule
+            .template
+            .instantiate(notes, HashAlgorithm::default())?;
+        let session = SigningSession::new(report.transaction);
+        let draft = session.draft_hash();
+        let index = self.add_session(&schedule.wallet, session, now)?;
+        Ok(NotificationEvent::DraftCreated {
+            schedule: schedule.id.clone(),
+            session: index,
+            draft,
+        })
+    }
+
+    // Runs every schedule due at `now` and returns the notifications, which
+    // are also queued for `take_notifications`.
+    pub fn run_due_schedules(&mut self, now: u64) -> Result<Vec<Notification>, String> {
+        let mut notifications = Vec::new();
+        for mut schedule in self.schedules()? {
+            if schedule.paused || schedule.next_run > now {
+                continue;
+            }
+            let event = self.run_schedule(&schedule, now).unwrap_or_else(|error| {
+                NotificationEvent::RunFailed {
+                    schedule: schedule.id.clone(),
+                    error,
+                }
+            });
+            schedule.last_run = Some(now);
+            schedule.next_run = Cron::parse(&schedule.cron)?.next_after(now)?;
+            self.put_schedule(&schedule)?;
+            let notify = match &event {
+                NotificationEvent::RunFailed { .. } => schedule.approved_by.clone(),
+                _ => self.wallet(&schedule.wallet)?.descriptor.participants(),
+            };
+            notifications.push(Notification {
+                at: now,
+                wallet: schedule.wallet.clone(),
+                notify,
+                event,
+            });
+        }
+        self.notify(&notifications)?;
+        notifications.extend(self.run_reminders(now)?);
+        Ok(notifications)
+    }
+}
@@ -0,0 +1,1023 @@
+use crate::attestation::{DeviceAttestation, SignerDevice};
+use crate::audit::{AuditEvent, AuditLog};
+use crate::comments::Comment;
+use crate::delegation::{delegations_from, Delegation};
+use crate::emergency::IncidentAttestation;
+use crate::hashing::{Digester, HashAlgorithm};
+use crate::preapproval::{preapproval_from, PreApproval};
+use crate::reminders::ReminderSchedule;
+use crate::snapshot::SessionSnapshot;
+use crate::verify::{SignatureVerifier, UnverifiedSignatures, VerificationItem};
+use crate::{Output, PublicKey, Signature, Spend, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Signing Sessions
+// ============================================================================
+
+pub const SESSION_FORMAT_VERSION: u32 = 1;
+
+// A session is the unit cosigners pass around: the draft plus whatever
+// signatures have been collected so far. It is plain JSON so it survives
+// `postMessage`, files, QR, and any other transport unchanged.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SigningSession {
+    pub format: u32,
+    pub transaction: Transaction,
+    #[serde(default, skip_serializing_if = "AuditLog::is_empty")]
+    pub audit: AuditLog,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub devices: Vec<SignerDevice>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub comments: Vec<Comment>,
+    // When a coordinator first accepted the session.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub snapshots: Vec<SessionSnapshot>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reminders: Option<ReminderSchedule>,
+    // Repeat deliveries of a signature already applied (several relays
+    // forwarding the same submission), by `submission_id`. Counted here
+    // instead of filling the audit log with identical events.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub duplicate_deliveries: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SignatureDetails {
+    // Hash of the summary the signer approved (see `summary`).
+    #[serde(default)]
+    pub summary: Option<String>,
+    #[serde(default)]
+    pub device: Option<DeviceAttestation>,
+}
+
+const SUBMISSION_DOMAIN: &[u8] = b"nockchain-multisig/submission/v1";
+
+// Content address of a signature submission: the same spend, key,
+// signature and details always give the same id, whoever relays them.
+pub fn submission_id(
+    spend_index: usize,
+    pubkey: &PublicKey,
+    signature: &Signature,
+    details: &SignatureDetails,
+) -> String {
+    let details = serde_json::to_vec(details).expect("Serialization failed");
+    let mut digester = Digester::new(HashAlgorithm::Sha256).expect("sha256 is always available");
+    for part in [
+        SUBMISSION_DOMAIN,
+        &(spend_index as u64).to_be_bytes(),
+        pubkey.0.as_bytes(),
+        signature.0.as_bytes(),
+        &details,
+    ] {
+        digester.update(part);
+        digester.update(b"\n");
+    }
+    digester.finalize_hex()
+}
+
+impl SigningSession {
+    pub fn new(transaction: Transaction) -> Self {
+        Self {
+            format: SESSION_FORMAT_VERSION,
+            transaction,
+            audit: AuditLog::default(),
+            devices: Vec::new(),
+            comments: Vec::new(),
+            created_at: None,
+            snapshots: Vec::new(),
+            reminders: None,
+            duplicate_deliveries: BTreeMap::new(),
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let session: Self = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        if session.format > SESSION_FORMAT_VERSION {
+            return Err(format!(
+                "Unsupported session format {} (max {})",
+                session.format, SESSION_FORMAT_VERSION
+            ));
+        }
+        Ok(session)
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string(self).map_err(|e| e.to_string())
+    }
+
+    // Identifies the draft independently of collected signatures: a digest
+    // of every spend's message hash, in order.
+    pub fn draft_hash(&self) -> String {
+        let mut digester =
+            Digester::new(HashAlgorithm::Sha256).expect("sha256 is always available");
+        for spend in &self.transaction.spends {
+            digester.update(spend.seeds.message_hash.as_bytes());
+            digester.update(b"\n");
+        }
+        digester.finalize_hex()
+    }
+
+    // The content-derived id (`Transaction::draft_id`) sessions for the same
+    // payment share across clients and coordinators.
+    pub fn draft_id(&self) -> Result<String, String> {
+        self.transaction.draft_id()
+    }
+
+    // Folds signatures collected elsewhere into this session. Both sides must
+    // describe the same draft, i.e. agree on every spend's message hash.
+    pub fn merge(&mut self, other: &SigningSession) -> Result<usize, String> {
+        Ok(self.merge_verified(other, &UnverifiedSignatures)?.added)
+    }
+
+    pub fn merge_verified(
+        &mut self,
+        other: &SigningSession,
+        verifier: &dyn SignatureVerifier,
+    ) -> Result<MergeReport, String> {
+        let report =
+            merge_signatures_verified(&mut self.transaction, &other.transaction, verifier)?;
+        // Their device records follow the signatures we now share.
+        let signature_of = |tx: &Transaction, d: &SignerDevice| {
+            let spend = tx.spends.get(d.spend_index)?;
+            spend
+                .seeds
+                .signatures
+                .iter()
+                .find(|(pk, _)| pk == &d.pubkey)
+                .cloned()
+        };
+        for record in &other.devices {
+            let ours = signature_of(&self.transaction, record);
+            let theirs = signature_of(&other.transaction, record);
+            let known = self
+                .devices
+                .iter()
+                .any(|d| d.spend_index == record.spend_index && d.pubkey == record.pubkey);
+            if !known && ours.is_some() && ours == theirs {
+                self.devices.push(record.clone());
+            }
+        }
+        self.merge_comments(other);
+        let added = report.added + report.replaced;
+        if added > 0 {
+            self.audit
+                .record(AuditEvent::SignaturesMerged { added }, None);
+        }
+        Ok(report)
+    }
+
+    pub fn add_signature(
+        &mut self,
+        spend_index: usize,
+        pubkey: PublicKey,
+        signature: Signature,
+        at: Option<u64>,
+    ) -> Result<bool, String> {
+        self.add_signature_with(
+            spend_index,
+            pubkey,
+            signature,
+            SignatureDetails::default(),
+            at,
+        )
+    }
+
+    // As `add_signature`, also recording the hash of the summary the signer
+    // approved. It must match the draft now, and is checked again at
+    // finalization.
+    pub fn add_signature_with_summary(
+        &mut self,
+        spend_index: usize,
+        pubkey: PublicKey,
+        signature: Signature,
+        summary: String,
+        at: Option<u64>,
+    ) -> Result<bool, String> {
+        let details = SignatureDetails {
+            summary: Some(summary),
+            device: None,
+        };
+        self.add_signature_with(spend_index, pubkey, signature, details, at)
+    }
+
+    // Adds a signature with whatever the signer's wallet reported about it.
+    // A replaced signature takes its old device record with it. Returns
+    // false, and only counts the delivery, when the session already holds
+    // exactly this submission.
+    pub fn add_signature_with(
+        &mut self,
+        spend_index: usize,
+        pubkey: PublicKey,
+        signature: Signature,
+        details: SignatureDetails,
+        at: Option<u64>,
+    ) -> Result<bool, String> {
+        if self.holds_submission(spend_index, &pubkey, &signature, &details) {
+            let id = submission_id(spend_index, &pubkey, &signature, &details);
+            *self.duplicate_deliveries.entry(id).or_default() += 1;
+            return Ok(false);
+        }
+        let SignatureDetails { summary, device } = details;
+        if let Some(summary) = &summary {
+            if summary != &self.transaction.summary_hash() {
+                return Err("Signer approved a summary that does not match this draft".into());
+            }
+        }
+        if let Some(device) = &device {
+            device.validate()?;
+        }
+        let spend = self
+            .transaction
+            .spends
+            .get_mut(spend_index)
+            .ok_or("Invalid spend index")?;
+        if !spend.signers()?.pubkeys.contains(&pubkey) {
+            return Err("Public key not allowed for this spend".into());
+        }
+        spend.seeds.add_signature(pubkey.clone(), signature);
+        if let Some(summary) = summary.clone() {
+            spend.seeds.summaries.push((pubkey.clone(), summary));
+        }
+        self.devices
+            .retain(|d| d.spend_index != spend_index || d.pubkey != pubkey);
+        if let Some(device) = device.clone() {
+            self.devices.push(SignerDevice {
+                spend_index,
+                pubkey: pubkey.clone(),
+                device,
+            });
+        }
+        self.audit.record(
+            AuditEvent::SignatureAdded {
+                spend_index,
+                pubkey,
+                summary,
+                device,
+            },
+            at,
+        );
+        Ok(true)
+    }
+
+    // Whether applying the submission would change nothing: the same
+    // signature, summary and device record are already held, and no
+    // delegated, aggregate or pre-approved fill of the slot would be
+    // replaced.
+    fn holds_submission(
+        &self,
+        spend_index: usize,
+        pubkey: &PublicKey,
+        signature: &Signature,
+        details: &SignatureDetails,
+    ) -> bool {
+        let Some(spend) = self.transaction.spends.get(spend_index) else {
+            return false;
+        };
+        let seeds = &spend.seeds;
+        let summary = seeds
+            .summaries
+            .iter()
+            .find(|(pk, _)| pk == pubkey)
+            .map(|(_, s)| s);
+        let device = self
+            .devices
+            .iter()
+            .find(|d| d.spend_index == spend_index && &d.pubkey == pubkey)
+            .map(|d| &d.device);
+        seeds
+            .signatures
+            .iter()
+            .any(|(pk, sig)| pk == pubkey && sig == signature)
+            && summary == details.summary.as_ref()
+            && device == details.device.as_ref()
+            && !seeds.delegations.iter().any(|d| &d.signer == pubkey)
+            && !seeds.aggregated.contains(pubkey)
+            && !seeds.preapprovals.iter().any(|p| &p.signer == pubkey)
+    }
+
+    pub fn duplicate_delivery_count(&self) -> u64 {
+        self.duplicate_deliveries.values().sum()
+    }
+
+    // Device-key checks for every attestation that has one, for an external
+    // verifier.
+    pub fn device_verification_items(&self) -> Vec<VerificationItem> {
+        self.devices
+            .iter()
+            .filter_map(|d| {
+                let spend = self.transaction.spends.get(d.spend_index)?;
+                d.device
+                    .verification_item(&spend.seeds.message_hash, &d.pubkey)
+            })
+            .collect()
+    }
+
+    // Adds a signature made by the last delegate of `delegation.chain` and
+    // records the full chain in the audit log.
+    pub fn add_delegated_signature(
+        &mut self,
+        spend_index: usize,
+        delegation: Delegation,
+        signature: Signature,
+        at: Option<u64>,
+    ) -> Result<(), String> {
+        delegation.validate_chain()?;
+        delegation.permits(self.transaction.total_output(), None)?;
+
+        let spend = self
+            .transaction
+            .spends
+            .get_mut(spend_index)
+            .ok_or("Invalid spend index")?;
+        if !spend.signers()?.pubkeys.contains(&delegation.signer) {
+            return Err("Delegator is not allowed for this spend".into());
+        }
+
+        let event = AuditEvent::DelegatedSignatureAdded {
+            spend_index,
+            signer: delegation.signer.clone(),
+            chain: delegation
+                .chain
+                .iter()
+                .map(|g| g.delegate.clone())
+                .collect(),
+            grants: delegation.chain.iter().map(|g| g.message()).collect(),
+        };
+        spend.seeds.add_delegated_signature(delegation, signature);
+        self.audit.record(event, at);
+        Ok(())
+    }
+
+    // Fills every slot the pre-approval covers and records its terms in the
+    // audit log. Returns the spend indices filled.
+    pub fn apply_preapproval(
+        &mut self,
+        preapproval: &PreApproval,
+        height: Option<u64>,
+        at: Option<u64>,
+    ) -> Result<Vec<usize>, String> {
+        let spends = self.transaction.apply_preapproval(preapproval, height)?;
+        if !spends.is_empty() {
+            self.audit.record(
+                AuditEvent::PreApprovalApplied {
+                    spends: spends.clone(),
+                    signer: preapproval.signer.clone(),
+                    terms: preapproval.message(),
+                    valid_until_height: preapproval.valid_until_height,
+                },
+                at,
+            );
+        }
+        Ok(spends)
+    }
+
+    pub fn add_emergency_signature(
+        &mut self,
+        spend_index: usize,
+        signature: Signature,
+        incident: IncidentAttestation,
+        at: Option<u64>,
+    ) -> Result<(), String> {
+        let spend = self
+            .transaction
+            .spends
+            .get_mut(spend_index)
+            .ok_or("Invalid spend index")?;
+
+        let event = AuditEvent::EmergencySpend {
+            spend_index,
+            reporter: incident.reporter.clone(),
+            reason: incident.reason.clone(),
+            timestamp: incident.timestamp,
+            attestation: incident.message(&spend.seeds.message_hash),
+        };
+        spend.add_emergency_signature(signature, incident)?;
+        self.audit.record(event, at);
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Draft Editing
+// ============================================================================
+
+// Drafts carry no fee (inputs must equal outputs), so an edit replaces the
+// outputs. Every spend hash covers every output, so in practice an edit
+// withdraws all approvals; the signers listed in the report must review and
+// sign again.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DraftEdit {
+    pub outputs: Vec<Output>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EditReport {
+    pub session: SigningSession,
+    pub changed_spends: Vec<usize>,
+    // Signers whose approval was withdrawn and who must re-approve.
+    pub reapproval_required: Vec<PublicKey>,
+}
+
+impl SigningSession {
+    // Drafts with a lineage move to the next sequence, so the edited version
+    // supersedes the one signers saw.
+    pub fn edit(&mut self, edit: DraftEdit, at: Option<u64>) -> Result<EditReport, String> {
+        let previous_draft = self.draft_hash();
+        let mut tx = self.transaction.clone();
+        tx.set_outputs(edit.outputs);
+        tx.validate_balance()?;
+        tx.validate_network()?;
+        if let Some(lineage) = &mut tx.lineage {
+            lineage.sequence = lineage.sequence.checked_add(1).ok_or("Sequence overflow")?;
+        }
+        tx.refresh_spend_hashes()?;
+
+        let mut changed_spends = Vec::new();
+        let mut cleared: Vec<PublicKey> = Vec::new();
+        for (i, (spend, before)) in tx
+            .spends
+            .iter_mut()
+            .zip(&self.transaction.spends)
+            .enumerate()
+        {
+            if spend.seeds.message_hash == before.seeds.message_hash {
+                continue;
+            }
+            changed_spends.push(i);
+            let seeds = &mut spend.seeds;
+            for (pubkey, _) in seeds.signatures.drain(..) {
+                if !cleared.contains(&pubkey) {
+                    cleared.push(pubkey);
+                }
+            }
+            seeds.delegations.clear();
+            seeds.preapprovals.clear();
+            seeds.aggregated.clear();
+            seeds.summaries.clear();
+            seeds.incident = None;
+        }
+
+        self.transaction = tx;
+        self.devices
+            .retain(|d| !changed_spends.contains(&d.spend_index));
+        self.audit.record(
+            AuditEvent::DraftEdited {
+                previous_draft,
+                draft: self.draft_hash(),
+                changed_spends: changed_spends.clone(),
+                cleared: cleared.clone(),
+            },
+            at,
+        );
+        Ok(EditReport {
+            session: self.clone(),
+            changed_spends,
+            reapproval_required: cleared,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictKind {
+    BothValid,
+    NeitherValid,
+}
+
+// Two different signatures for one pubkey on one spend that verification
+// could not settle. The local one is kept.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SignatureConflict {
+    pub spend_index: usize,
+    pub pubkey: PublicKey,
+    pub ours: Signature,
+    pub theirs: Signature,
+    pub kind: ConflictKind,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MergeReport {
+    pub added: usize,
+    pub replaced: usize,
+    pub conflicts: Vec<SignatureConflict>,
+}
+
+fn verifies(
+    spend: &Spend,
+    pubkey: &PublicKey,
+    signature: &Signature,
+    aggregate_root: Option<&str>,
+    verifier: &dyn SignatureVerifier,
+) -> bool {
+    if let Some(preapproval) = preapproval_from(&spend.seeds.preapprovals, pubkey) {
+        return verifier.verify(pubkey, &preapproval.message(), signature);
+    }
+    let signer =
+        delegations_from(&spend.seeds.delegations, pubkey).map_or(pubkey, |d| d.effective_signer());
+    let message = aggregate_root
+        .filter(|_| spend.seeds.aggregated.contains(pubkey))
+        .unwrap_or(&spend.seeds.message_hash);
+    verifier.verify(signer, message, signature)
+}
+
+pub fn merge_signatures(into: &mut Transaction, from: &Transaction) -> Result<usize, String> {
+    Ok(merge_signatures_verified(into, from, &UnverifiedSignatures)?.added)
+}
+
+// When both sides hold different signatures for the same pubkey, each is
+// checked against the spend hash: a lone valid one wins, anything else is
+// reported as a conflict. A spend the other side moved to the emergency
+// branch moves ours with it, dropping signatures for the branch it leaves,
+// once its incident checks out; a bad incident rejects the whole merge.
+pub fn merge_signatures_verified(
+    into: &mut Transaction,
+    from: &Transaction,
+    verifier: &dyn SignatureVerifier,
+) -> Result<MergeReport, String> {
+    if into.spends.len() != from.spends.len() {
+        return Err("Cannot merge sessions with different spend counts".into());
+    }
+
+    for (i, (ours, theirs)) in into.spends.iter().zip(&from.spends).enumerate() {
+        if ours.seeds.message_hash != theirs.seeds.message_hash {
+            return Err(format!("Spend {} hash differs between sessions", i));
+        }
+        if let (Some(a), Some(b)) = (&ours.seeds.incident, &theirs.seeds.incident) {
+            if a != b || ours.seeds.branch != theirs.seeds.branch {
+                return Err(format!(
+                    "Spend {} carries conflicting incident attestations",
+                    i
+                ));
+            }
+        }
+    }
+
+    // A peer's incident moves the spend onto the emergency branch and drops
+    // our signatures, so check it fully before adopting anything.
+    for (i, (ours, theirs)) in into.spends.iter().zip(&from.spends).enumerate() {
+        let Some(incident) = &theirs.seeds.incident else {
+            continue;
+        };
+        if ours.seeds.incident.is_some() {
+            continue;
+        }
+        let branch = ours
+            .emergency_branch_for(&incident.reporter)
+            .map_err(|e| format!("Spend {} incident rejected: {}", i, e))?;
+        if theirs.seeds.branch != branch {
+            return Err(format!(
+                "Spend {} incident is not on the emergency branch",
+                i
+            ));
+        }
+        if incident.reason.trim().is_empty() {
+            return Err(format!("Spend {} incident has no reason", i));
+        }
+        let message = incident.message(&theirs.seeds.message_hash);
+        if !verifier.verify(&incident.reporter, &message, &incident.signature) {
+            return Err(format!(
+                "Spend {} incident attestation has an invalid signature",
+                i
+            ));
+        }
+    }
+
+    for (ours, theirs) in into.spends.iter_mut().zip(&from.spends) {
+        if ours.seeds.incident.is_some() {
+            continue;
+        }
+        if let Some(incident) = &theirs.seeds.incident {
+            ours.clear_for_branch(theirs.seeds.branch);
+            ours.seeds.incident = Some(incident.clone());
+        }
+    }
+
+    // Spend hashes match, so both sides share one aggregate digest.
+    let root = from.aggregate_digest().ok();
+    let root = root.as_deref();
+
+    let mut report = MergeReport::default();
+    for (i, (ours, theirs)) in into.spends.iter_mut().zip(&from.spends).enumerate() {
+        if ours.seeds.branch != theirs.seeds.branch {
+            continue;
+        }
+        for preimage in &theirs.seeds.preimages {
+            if !ours.seeds.preimages.contains(preimage) {
+                ours.seeds.preimages.push(preimage.clone());
+            }
+        }
+        for (pk, sig) in &theirs.seeds.signatures {
+            if !ours.signers()?.pubkeys.contains(pk) {
+                continue;
+            }
+            let existing = ours
+                .seeds
+                .signatures
+                .iter()
+                .find(|(p, _)| p == pk)
+                .map(|(_, s)| s.clone());
+            let delegation = delegations_from(&theirs.seeds.delegations, pk).cloned();
+            let preapproval = preapproval_from(&theirs.seeds.preapprovals, pk).cloned();
+            let aggregated = theirs.seeds.aggregated.contains(pk);
+            let summary = theirs.seeds.summary_of(pk).cloned();
+            let adopt = |ours: &mut Spend| {
+                match (delegation.clone(), preapproval.clone()) {
+                    (Some(d), _) => ours.seeds.add_delegated_signature(d, sig.clone()),
+                    (None, Some(p)) => ours.seeds.add_preapproval(p),
+                    (None, None) => ours.seeds.add_signature(pk.clone(), sig.clone()),
+                }
+                if aggregated {
+                    ours.seeds.aggregated.push(pk.clone());
+                }
+                if let Some(summary) = summary.clone() {
+                    ours.seeds.summaries.push((pk.clone(), summary));
+                }
+            };
+
+            match existing {
+                None => {
+                    adopt(ours);
+                    report.added += 1;
+                }
+                Some(existing) if &existing == sig => {}
+                Some(existing) => {
+                    let ours_valid = verifies(ours, pk, &existing, root, verifier);
+                    let theirs_valid = verifies(theirs, pk, sig, root, verifier);
+                    match (ours_valid, theirs_valid) {
+                        (true, false) => {}
+                        (false, true) => {
+                            adopt(ours);
+                            report.replaced += 1;
+                        }
+                        (both, _) => report.conflicts.push(SignatureConflict {
+                            spend_index: i,
+                            pubkey: pk.clone(),
+                            ours: existing,
+                            theirs: sig.clone(),
+                            kind: if both {
+                                ConflictKind::BothValid
+                            } else {
+                                ConflictKind::NeitherValid
+                            },
+                        }),
+                    }
+                }
+            }
+        }
+    }
+    Ok(report)
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+#[wasm_bindgen]
+pub fn session_add_signature(
+    session_json: &str,
+    spend_index: usize,
+    pubkey: &str,
+    signature: &str,
+) -> Result<String, String> {
+    let mut session = SigningSession::from_json(session_json)?;
+    session.add_signature(
+        spend_index,
+        PublicKey(pubkey.to_string()),
+        Signature(signature.to_string()),
+        None,
+    )?;
+    session.to_json()
+}
+
+// `summary_hash` is what `get_summary_hash` returned for the draft the
+// signer was shown.
+#[wasm_bindgen]
+pub fn session_add_signature_with_summary(
+    session_json: &str,
+    spend_index: usize,
+    pubkey: &str,
+    signature: &str,
+    summary_hash: &str,
+) -> Result<String, String> {
+    let mut session = SigningSession::from_json(session_json)?;
+    session.add_signature_with_summary(
+        spend_index,
+        PublicKey(pubkey.to_string()),
+        Signature(signature.to_string()),
+        summary_hash.to_string(),
+        None,
+    )?;
+    session.to_json()
+}
+
+// `device_json` describes the signing device (`{model, firmware}`, plus
+// `device_key` and `signature` when the device can vouch for itself).
+#[wasm_bindgen]
+pub fn session_add_attested_signature(
+    session_json: &str,
+    spend_index: usize,
+    pubkey: &str,
+    signature: &str,
+    device_json: &str,
+) -> Result<String, String> {
+    let mut session = SigningSession::from_json(session_json)?;
+    let device: DeviceAttestation = serde_json::from_str(device_json).map_err(|e| e.to_string())?;
+    let details = SignatureDetails {
+        summary: None,
+        device: Some(device),
+    };
+    session.add_signature_with(
+        spend_index,
+        PublicKey(pubkey.to_string()),
+        Signature(signature.to_string()),
+        details,
+        None,
+    )?;
+    session.to_json()
+}
+
+// The hash a device key signs to attest to a signature by `pubkey` on
+// `spend_index`.
+#[wasm_bindgen]
+pub fn get_device_attestation_message(
+    session_json: &str,
+    spend_index: usize,
+    pubkey: &str,
+    device_json: &str,
+) -> Result<String, String> {
+    let session = SigningSession::from_json(session_json)?;
+    let device: DeviceAttestation = serde_json::from_str(device_json).map_err(|e| e.to_string())?;
+    let spend = session
+        .transaction
+        .spends
+        .get(spend_index)
+        .ok_or("Invalid spend index")?;
+    device
+        .message(&spend.seeds.message_hash, &PublicKey(pubkey.to_string()))
+        .ok_or_else(|| "Device attestation has no device key".into())
+}
+
+#[wasm_bindgen]
+pub fn session_add_delegated_signature(
+    session_json: &str,
+    spend_index: usize,
+    delegation_json: &str,
+    signature: &str,
+) -> Result<String, String> {
+    let mut session = SigningSession::from_json(session_json)?;
+    let delegation: Delegation =
+        serde_json::from_str(delegation_json).map_err(|e| e.to_string())?;
+    session.add_delegated_signature(
+        spend_index,
+        delegation,
+        Signature(signature.to_string()),
+        None,
+    )?;
+    session.to_json()
+}
+
+#[wasm_bindgen]
+pub fn session_add_emergency_signature(
+    session_json: &str,
+    spend_index: usize,
+    signature: &str,
+    incident_json: &str,
+) -> Result<String, String> {
+    let mut session = SigningSession::from_json(session_json)?;
+    let incident: IncidentAttestation =
+        serde_json::from_str(incident_json).map_err(|e| e.to_string())?;
+    session.add_emergency_signature(
+        spend_index,
+        Signature(signature.to_string()),
+        incident,
+        None,
+    )?;
+    session.to_json()
+}
+
+// Replaces the draft's outputs (`{"outputs": [...]}`), clears every
+// signature the change invalidates, and records the edit in the audit log.
+// Returns `{session, changed_spends, reapproval_required}`.
+#[wasm_bindgen]
+pub fn edit_transaction(session_json: &str, edit_json: &str) -> Result<String, String> {
+    let mut session = SigningSession::from_json(session_json)?;
+    let edit: DraftEdit = serde_json::from_str(edit_json).map_err(|e| e.to_string())?;
+    let report = session.edit(edit, None)?;
+    serde_json::to_string(&report).map_err(|e| e.to_string())
+}
+
+// The hash a delegator signs to issue `grant_json` (its `signature` field is
+// ignored).
+#[wasm_bindgen]
+pub fn delegation_grant_message(grant_json: &str) -> Result<String, String> {
+    let grant: crate::delegation::DelegationGrant =
+        serde_json::from_str(grant_json).map_err(|e| e.to_string())?;
+    Ok(grant.message())
+}
+
+#[wasm_bindgen]
+pub fn validate_delegations_at(tx_json: &str, height: u64) -> Result<String, String> {
+    let tx: Transaction = serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+    tx.validate_delegations(Some(height))?;
+    Ok("All delegations are within scope".into())
+}
+
+// The hash a cosigner signs to issue `preapproval_json` (its `signature`
+// field is ignored).
+#[wasm_bindgen]
+pub fn get_preapproval_message(preapproval_json: &str) -> Result<String, String> {
+    let preapproval: PreApproval =
+        serde_json::from_str(preapproval_json).map_err(|e| e.to_string())?;
+    preapproval.validate()?;
+    Ok(preapproval.message())
+}
+
+// Fills the slots a signed pre-approval covers. Returns `{session, spends}`;
+// `spends` is empty when the draft does not match the terms.
+#[wasm_bindgen]
+pub fn session_apply_preapproval(
+    session_json: &str,
+    preapproval_json: &str,
+    height: u64,
+) -> Result<String, String> {
+    let mut session = SigningSession::from_json(session_json)?;
+    let preapproval: PreApproval =
+        serde_json::from_str(preapproval_json).map_err(|e| e.to_string())?;
+    let spends = session.apply_preapproval(&preapproval, Some(height), None)?;
+    serde_json::to_string(&serde_json::json!({ "session": session, "spends": spends }))
+        .map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+pub fn verify_audit_log(session_json: &str) -> Result<String, String> {
+    let session = SigningSession::from_json(session_json)?;
+    session.audit.verify()?;
+    Ok(format!(
+        "Audit log intact ({} entries)",
+        session.audit.entries.len()
+    ))
+}
+
+#[derive(Serialize)]
+struct AuditExport<'a> {
+    draft: String,
+    entries: &'a [crate::audit::AuditEntry],
+    devices: &'a [SignerDevice],
+}
+
+// The verified audit log with the device behind every attested signature,
+// for compliance archives.
+#[wasm_bindgen]
+pub fn export_audit_log(session_json: &str) -> Result<String, String> {
+    let session = SigningSession::from_json(session_json)?;
+    session.audit.verify()?;
+    let export = AuditExport {
+        draft: session.draft_hash(),
+        entries: &session.audit.entries,
+        devices: &session.devices,
+    };
+    serde_json::to_string(&export).map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+pub fn get_device_verification_items(session_json: &str) -> Result<String, String> {
+    let session = SigningSession::from_json(session_json)?;
+    serde_json::to_string(&session.device_verification_items()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::HashVerifier;
+
+    // One spend from a 2-of-2 lock with an emergency branch held by "e".
+    // "a" has already signed the primary branch.
+    fn draft() -> Transaction {
+        let mut tx = crate::testing::draft(
+            r#"{"pkh":{"threshold":2,"pubkeys":["a","b"]},"alternatives":[{"pkh":{"threshold":1,"pubkeys":["e"]},"emergency":true}]}"#,
+        );
+        let a = PublicKey("a".into());
+        let signature = HashVerifier::sign(&a, &tx.spends[0].seeds.message_hash);
+        tx.spends[0].seeds.add_signature(a, signature);
+        tx
+    }
+
+    fn incident(reporter: &str, message_hash: &str, forge: bool) -> IncidentAttestation {
+        let mut incident = IncidentAttestation {
+            reporter: PublicKey(reporter.into()),
+            reason: "key compromised".into(),
+            timestamp: 1,
+            signature: Signature(String::new()),
+        };
+        incident.signature = if forge {
+            Signature("00".repeat(32))
+        } else {
+            HashVerifier::sign(&incident.reporter, &incident.message(message_hash))
+        };
+        incident
+    }
+
+    fn with_emergency(forge: bool) -> Transaction {
+        let mut tx = draft();
+        let spend = &mut tx.spends[0];
+        let incident = incident("e", &spend.seeds.message_hash, forge);
+        let signature = HashVerifier::sign(&incident.reporter, &spend.seeds.message_hash);
+        spend.add_emergency_signature(signature, incident).unwrap();
+        tx
+    }
+
+    #[test]
+    fn merge_adopts_verified_incident() {
+        let mut ours = draft();
+        let theirs = with_emergency(false);
+        merge_signatures_verified(&mut ours, &theirs, &HashVerifier).unwrap();
+        assert_eq!(ours.spends[0].seeds.branch, 1);
+        assert_eq!(
+            ours.spends[0].seeds.incident,
+            theirs.spends[0].seeds.incident
+        );
+        assert!(!ours.spends[0].seeds.has_signature(&PublicKey("a".into())));
+        ours.verify_signatures(&HashVerifier).unwrap();
+    }
+
+    #[test]
+    fn merge_rejects_forged_incident() {
+        let mut ours = draft();
+        let err =
+            merge_signatures_verified(&mut ours, &with_emergency(true), &HashVerifier).unwrap_err();
+        assert!(err.contains("invalid signature"), "{err}");
+        assert_eq!(ours.spends[0].seeds.branch, 0);
+        assert!(ours.spends[0].seeds.incident.is_none());
+        assert!(ours.spends[0].seeds.has_signature(&PublicKey("a".into())));
+    }
+
+    #[test]
+    fn merge_rejects_incident_from_non_emergency_key() {
+        let mut ours = draft();
+        let mut theirs = with_emergency(false);
+        let hash = theirs.spends[0].seeds.message_hash.clone();
+        theirs.spends[0].seeds.incident = Some(incident("a", &hash, false));
+        let err = merge_signatures_verified(&mut ours, &theirs, &HashVerifier).unwrap_err();
+        assert!(err.contains("not an emergency key"), "{err}");
+        assert_eq!(ours.spends[0].seeds.branch, 0);
+    }
+
+    #[test]
+    fn merge_rejects_incident_off_the_emergency_branch() {
+        let mut ours = draft();
+        let mut theirs = with_emergency(false);
+        theirs.spends[0].seeds.branch = 0;
+        let err = merge_signatures_verified(&mut ours, &theirs, &HashVerifier).unwrap_err();
+        assert!(err.contains("not on the emergency branch"), "{err}");
+    }
+
+    #[test]
+    fn merge_rejects_conflicting_incidents() {
+        let mut ours = with_emergency(false);
+        let mut theirs = with_emergency(false);
+        let hash = theirs.spends[0].seeds.message_hash.clone();
+        let mut other = incident("e", &hash, false);
+        other.timestamp = 2;
+        other.signature = HashVerifier::sign(&other.reporter, &other.message(&hash));
+        theirs.spends[0].seeds.incident = Some(other);
+        let err = merge_signatures_verified(&mut ours, &theirs, &HashVerifier).unwrap_err();
+        assert!(err.contains("conflicting incident"), "{err}");
+    }
+
+    #[test]
+    fn emergency_signature_drops_other_branch_state() {
+        let mut tx = draft();
+        let a = PublicKey("a".into());
+        tx.spends[0].seeds.aggregated.push(a.clone());
+        tx.spends[0].seeds.summaries.push((a, "summary".into()));
+        let spend = &mut tx.spends[0];
+        let incident = incident("e", &spend.seeds.message_hash, false);
+        let signature = HashVerifier::sign(&incident.reporter, &spend.seeds.message_hash);
+        spend.add_emergency_signature(signature, incident).unwrap();
+        assert!(spend.seeds.aggregated.is_empty());
+        assert!(spend.seeds.summaries.is_empty());
+        assert_eq!(spend.seeds.signatures.len(), 1);
+    }
+
+    #[test]
+    fn emergency_signature_rejects_non_emergency_reporter() {
+        let mut tx = draft();
+        let spend = &mut tx.spends[0];
+        let incident = incident("b", &spend.seeds.message_hash, false);
+        let signature = HashVerifier::sign(&incident.reporter, &spend.seeds.message_hash);
+        assert!(spend.add_emergency_signature(signature, incident).is_err());
+        assert_eq!(spend.seeds.branch, 0);
+    }
+}
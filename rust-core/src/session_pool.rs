@@ -0,0 +1,173 @@
+use crate::session::SigningSession;
+use crate::{PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Session Pool
+// ============================================================================
+
+// Keeps signing sessions open in WASM memory behind numeric handles, so a
+// page juggling several drafts does not re-parse each one on every call.
+// The crate holds no statics or shared caches: each pool is a JS-owned
+// object, each session keeps its own spend-hash cache, and two pools (say,
+// one per wallet tab) never see each other's sessions. Handles are never
+// reused, so a handle kept after `close_session` fails instead of reaching
+// a session opened later.
+//
+// Open sessions stay in memory until closed. Long-lived apps should close a
+// session once it is persisted or broadcast, and `free()` the pool when done.
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OpenSession {
+    pub handle: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wallet: Option<String>,
+    pub draft: String,
+    pub draft_id: String,
+    pub signatures: usize,
+    pub complete: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Pooled {
+    wallet: Option<String>,
+    session: SigningSession,
+}
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default)]
+pub struct SessionPool {
+    next: u32,
+    open: BTreeMap<u32, Pooled>,
+}
+
+impl SessionPool {
+    pub fn open(&mut self, session: SigningSession, wallet: Option<String>) -> Result<u32, String> {
+        // Handles start at 1, so none is falsy in JS.
+        let handle = self
+            .next
+            .checked_add(1)
+            .ok_or("Session pool has run out of handles")?;
+        self.next = handle;
+        self.open.insert(handle, Pooled { wallet, session });
+        Ok(handle)
+    }
+
+    pub fn session(&self, handle: u32) -> Result<&SigningSession, String> {
+        self.open
+            .get(&handle)
+            .map(|p| &p.session)
+            .ok_or_else(|| format!("No open session {}", handle))
+    }
+
+    pub fn session_mut(&mut self, handle: u32) -> Result<&mut SigningSession, String> {
+        self.open
+            .get_mut(&handle)
+            .map(|p| &mut p.session)
+            .ok_or_else(|| format!("No open session {}", handle))
+    }
+
+    pub fn close(&mut self, handle: u32) -> Result<SigningSession, String> {
+        self.open
+            .remove(&handle)
+            .map(|p| p.session)
+            .ok_or_else(|| format!("No open session {}", handle))
+    }
+
+    pub fn list(&self) -> Result<Vec<OpenSession>, String> {
+        self.open
+            .iter()
+            .map(|(handle, pooled)| {
+                let tx = &pooled.session.transaction;
+                Ok(OpenSession {
+                    handle: *handle,
+                    wallet: pooled.wallet.clone(),
+                    draft: pooled.session.draft_hash(),
+                    draft_id: tx.draft_id()?,
+                    signatures: tx.spends.iter().map(|s| s.seeds.signature_count()).sum(),
+                    complete: tx.validate_signatures().is_ok(),
+                })
+            })
+            .collect()
+    }
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+#[wasm_bindgen]
+impl SessionPool {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // `wallet` optionally tags the session so `close_wallet_sessions` can
+    // release a wallet's sessions together.
+    pub fn open_session(
+        &mut self,
+        session_json: &str,
+        wallet: Option<String>,
+    ) -> Result<u32, String> {
+        self.open(SigningSession::from_json(session_json)?, wallet)
+    }
+
+    pub fn get_session(&self, handle: u32) -> Result<String, String> {
+        self.session(handle)?.to_json()
+    }
+
+    // False when the session already held this exact signature.
+    pub fn add_signature(
+        &mut self,
+        handle: u32,
+        spend_index: usize,
+        pubkey: &str,
+        signature: &str,
+    ) -> Result<bool, String> {
+        self.session_mut(handle)?.add_signature(
+            spend_index,
+            PublicKey(pubkey.to_string()),
+            Signature(signature.to_string()),
+            None,
+        )
+    }
+
+    // Folds in signatures from another copy of the draft; returns how many
+    // were new.
+    pub fn merge_session(&mut self, handle: u32, other_json: &str) -> Result<usize, String> {
+        let other = SigningSession::from_json(other_json)?;
+        self.session_mut(handle)?.merge(&other)
+    }
+
+    // `[{handle, wallet, draft, draft_id, signatures, complete}]`.
+    pub fn list_open_sessions(&self) -> Result<String, String> {
+        serde_json::to_string(&self.list()?).map_err(|e| e.to_string())
+    }
+
+    // Releases the session and returns its final state, for persisting.
+    pub fn close_session(&mut self, handle: u32) -> Result<String, String> {
+        self.close(handle)?.to_json()
+    }
+
+    // Returns the handles closed.
+    pub fn close_wallet_sessions(&mut self, wallet: &str) -> Vec<u32> {
+        let handles: Vec<u32> = self
+            .open
+            .iter()
+            .filter(|(_, p)| p.wallet.as_deref() == Some(wallet))
+            .map(|(handle, _)| *handle)
+            .collect();
+        for handle in &handles {
+            self.open.remove(handle);
+        }
+        handles
+    }
+
+    pub fn open_count(&self) -> usize {
+        self.open.len()
+    }
+}
@@ -0,0 +1,323 @@
+use crate::audit::AuditEvent;
+use crate::hashing::{digest_hex, HashAlgorithm};
+use crate::session::SigningSession;
+use crate::verify::{PrecomputedVerifier, SignatureVerifier, VerificationItem};
+use crate::{PublicKey, Signature, Transaction};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Session Snapshots
+// ============================================================================
+
+// A coordinator periodically signs what a draft looks like: the whole
+// transaction with the signatures collected so far, the head of the
+// session's audit log, and the previous snapshot's hash. Participants can
+// countersign a snapshot to agree with it. Taking one is itself recorded in
+// the audit log, so the two chains pin each other: history cannot be
+// rewritten behind a snapshot, and snapshots cannot be dropped from the log.
+// In a dispute, any party holding a snapshot can show what the draft was at
+// that time and who vouched for it, without the coordinator's cooperation.
+
+const SNAPSHOT_DOMAIN: &[u8] = b"nockchain-multisig/snapshot/v1";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SnapshotStatement {
+    pub sequence: u64,
+    pub at: u64,
+    pub draft: String,
+    // Txid of `transaction`, signatures included.
+    pub txid: String,
+    pub transaction: Transaction,
+    // The audit log's length and head before the snapshot was recorded.
+    pub audit_entries: u64,
+    pub audit_head: String,
+    // Hash of the previous snapshot's statement; empty for the first.
+    pub prev: String,
+    pub coordinator: PublicKey,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SessionSnapshot {
+    pub statement: SnapshotStatement,
+    pub signature: Signature,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub countersignatures: Vec<(PublicKey, Signature)>,
+}
+
+impl SnapshotStatement {
+    // What the coordinator and every countersigner sign.
+    pub fn message_hash(&self) -> String {
+        let bytes = serde_json::to_vec(self).expect("Serialization failed");
+        digest_hex(HashAlgorithm::Sha256, &[SNAPSHOT_DOMAIN, &bytes].concat())
+            .expect("sha256 is always available")
+    }
+}
+
+impl SessionSnapshot {
+    pub fn verification_items(&self) -> Vec<VerificationItem> {
+        let message_hash = self.statement.message_hash();
+        std::iter::once((&self.statement.coordinator, &self.signature))
+            .chain(self.countersignatures.iter().map(|(pk, sig)| (pk, sig)))
+            .map(|(pubkey, signature)| VerificationItem {
+                pubkey: pubkey.clone(),
+                message_hash: message_hash.clone(),
+                signature: signature.clone(),
+            })
+            .collect()
+    }
+
+    // Checks a snapshot on its own, as an arbiter would: its transaction is
+    // the one its txid names, and every signature on it is valid.
+    pub fn verify(&self, verifier: &dyn SignatureVerifier) -> Result<(), String> {
+        let statement = &self.statement;
+        if statement.transaction.txid()? != statement.txid {
+            return Err(format!(
+                "Snapshot {} transaction does not match its txid",
+                statement.sequence
+            ));
+        }
+        match self
+            .verification_items()
+            .into_iter()
+            .find(|item| !verifier.verify(&item.pubkey, &item.message_hash, &item.signature))
+        {
+            Some(item) => Err(format!(
+                "Snapshot {} has an invalid signature from {}",
+                statement.sequence, item.pubkey.0
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+impl SigningSession {
+    // The statement a coordinator signs to snapshot the session as it is now.
+    pub fn snapshot_statement(
+        &self,
+        coordinator: PublicKey,
+        at: u64,
+    ) -> Result<SnapshotStatement, String> {
+        Ok(SnapshotStatement {
+            sequence: self.snapshots.len() as u64,
+            at,
+            draft: self.draft_hash(),
+            txid: self.transaction.txid()?,
+            transaction: self.transaction.clone(),
+            audit_entries: self.audit.entries.len() as u64,
+            audit_head: self.audit.head().to_string(),
+            prev: self
+                .snapshots
+                .last()
+                .map(|s| s.statement.message_hash())
+                .unwrap_or_default(),
+            coordinator,
+        })
+    }
+
+    // The statement must describe the session exactly as it is, so anything
+    // that changed the session since it was prepared means preparing again.
+    pub fn add_snapshot(
+        &mut self,
+        statement: SnapshotStatement,
+        signature: Signature,
+    ) -> Result<u64, String> {
+        let current = self.snapshot_statement(statement.coordinator.clone(), statement.at)?;
+        if current.message_hash() != statement.message_hash() {
+            return Err("Snapshot does not match the session; prepare it again".into());
+        }
+        if let Some(last) = self.snapshots.last() {
+            if statement.at < last.statement.at {
+                return Err("Snapshot is older than the previous one".into());
+            }
+        }
+        if signature.0.is_empty() {
+            return Err("Snapshot is not signed".into());
+        }
+        let sequence = statement.sequence;
+        self.audit.record(
+            AuditEvent::SnapshotSigned {
+                index: sequence,
+                snapshot: statement.message_hash(),
+                coordinator: statement.coordinator.clone(),
+            },
+            Some(statement.at),
+        );
+        self.snapshots.push(SessionSnapshot {
+            statement,
+            signature,
+            countersignatures: Vec::new(),
+        });
+        Ok(sequence)
+    }
+
+    // A participant agrees the snapshot is what they saw.
+    pub fn countersign_snapshot(
+        &mut self,
+        sequence: u64,
+        pubkey: PublicKey,
+        signature: Signature,
+        at: Option<u64>,
+    ) -> Result<(), String> {
+        if !self.is_participant(&pubkey) {
+            return Err(format!("{} is not a signer on this draft", pubkey.0));
+        }
+        if signature.0.is_empty() {
+            return Err("Countersignature is empty".into());
+        }
+        let snapshot = self
+            .snapshots
+            .get_mut(sequence as usize)
+            .ok_or_else(|| format!("No snapshot {}", sequence))?;
+        if snapshot
+            .countersignatures
+            .iter()
+            .any(|(pk, _)| pk == &pubkey)
+        {
+            return Err(format!(
+                "{} already countersigned snapshot {}",
+                pubkey.0, sequence
+            ));
+        }
+        snapshot.countersignatures.push((pubkey.clone(), signature));
+        self.audit.record(
+            AuditEvent::SnapshotCountersigned {
+                index: sequence,
+                pubkey,
+            },
+            at,
+        );
+        Ok(())
+    }
+
+    pub fn snapshot_verification_items(&self) -> Vec<VerificationItem> {
+        self.snapshots
+            .iter()
+            .flat_map(SessionSnapshot::verification_items)
+            .collect()
+    }
+
+    // Checks every snapshot and that snapshots and audit log agree: each
+    // snapshot follows the previous one, names the audit head that preceded
+    // it, and is recorded in the log right after that head.
+    pub fn verify_snapshots(&self, verifier: &dyn SignatureVerifier) -> Result<(), String> {
+        self.audit.verify()?;
+        let mut prev = String::new();
+        for (i, snapshot) in self.snapshots.iter().enumerate() {
+            let statement = &snapshot.statement;
+            let hash = statement.message_hash();
+            if statement.sequence != i as u64 || statement.prev != prev {
+                return Err(format!("Snapshot chain broken at {}", i));
+            }
+            let entries = &self.audit.entries;
+            let n = statement.audit_entries as usize;
+            let head = if n == 0 {
+                ""
+            } else {
+                entries.get(n - 1).map_or("-", |e| e.hash.as_str())
+            };
+            let recorded = entries.get(n).is_some_and(|e| {
+                matches!(&e.event, AuditEvent::SnapshotSigned { snapshot, .. } if snapshot == &hash)
+            });
+            if head != statement.audit_head || !recorded {
+                return Err(format!("Snapshot {} is not in the audit log", i));
+            }
+            if let Some((pk, _)) = snapshot
+                .countersignatures
+                .iter()
+                .find(|(pk, _)| !self.is_participant(pk))
+            {
+                return Err(format!(
+                    "Snapshot {} countersigner {} is not a signer",
+                    i, pk.0
+                ));
+            }
+            snapshot.verify(verifier)?;
+            prev = hash;
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+// Returns the statement to sign; sign its `get_snapshot_message`.
+#[wasm_bindgen]
+pub fn session_snapshot_statement(
+    session_json: &str,
+    coordinator: &str,
+    at: u64,
+) -> Result<String, String> {
+    let session = SigningSession::from_json(session_json)?;
+    let statement = session.snapshot_statement(PublicKey(coordinator.to_string()), at)?;
+    serde_json::to_string(&statement).map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+pub fn get_snapshot_message(statement_json: &str) -> Result<String, String> {
+    let statement: SnapshotStatement =
+        serde_json::from_str(statement_json).map_err(|e| e.to_string())?;
+    Ok(statement.message_hash())
+}
+
+#[wasm_bindgen]
+pub fn session_add_snapshot(
+    session_json: &str,
+    statement_json: &str,
+    signature: &str,
+) -> Result<String, String> {
+    let mut session = SigningSession::from_json(session_json)?;
+    let statement: SnapshotStatement =
+        serde_json::from_str(statement_json).map_err(|e| e.to_string())?;
+    session.add_snapshot(statement, Signature(signature.to_string()))?;
+    session.to_json()
+}
+
+#[wasm_bindgen]
+pub fn session_countersign_snapshot(
+    session_json: &str,
+    sequence: u64,
+    pubkey: &str,
+    signature: &str,
+) -> Result<String, String> {
+    let mut session = SigningSession::from_json(session_json)?;
+    session.countersign_snapshot(
+        sequence,
+        PublicKey(pubkey.to_string()),
+        Signature(signature.to_string()),
+        None,
+    )?;
+    session.to_json()
+}
+
+#[wasm_bindgen]
+pub fn get_snapshot_verification_items(session_json: &str) -> Result<String, String> {
+    let session = SigningSession::from_json(session_json)?;
+    serde_json::to_string(&session.snapshot_verification_items()).map_err(|e| e.to_string())
+}
+
+// `verified_json` holds the items from `get_snapshot_verification_items`
+// that passed.
+#[wasm_bindgen]
+pub fn verify_session_snapshots(session_json: &str, verified_json: &str) -> Result<bool, String> {
+    let session = SigningSession::from_json(session_json)?;
+    let verified: Vec<VerificationItem> =
+        serde_json::from_str(verified_json).map_err(|e| e.to_string())?;
+    session.verify_snapshots(&PrecomputedVerifier::new(verified))?;
+    Ok(true)
+}
+
+// Checks one snapshot on its own, e.g. one a party kept for a dispute.
+#[wasm_bindgen]
+pub fn verify_snapshot(snapshot_json: &str, verified_json: &str) -> Result<bool, String> {
+    let snapshot: SessionSnapshot =
+        serde_json::from_str(snapshot_json).map_err(|e| e.to_string())?;
+    let verified: Vec<VerificationItem> =
+        serde_json::from_str(verified_json).map_err(|e| e.to_string())?;
+    snapshot.verify(&PrecomputedVerifier::new(verified))?;
+    Ok(true)
+}
@@ -0,0 +1,154 @@
+use crate::checkpoint::{state_hash, Checkpoint};
+use crate::network::Network;
+use crate::tracking::ChainEvent;
+use crate::wallets::Wallet;
+use crate::{Lock, Note};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// State Dumps
+// ============================================================================
+
+// A new coordinator can take its UTXO view from a file instead of scanning
+// the chain. A dump is JSON Lines: a header naming the block, then one
+// unspent note per line, so a dump of the whole chain never has to be held
+// as one document. The notes are hashed into the same `state_hash` a
+// checkpoint commits to, so a dump is only as trusted as the signed
+// checkpoint it matches. Nockchain's own state export is jammed nouns
+// hashed with Tip5, which this crate cannot read yet; the format here is the
+// crate's own.
+
+pub const STATE_DUMP_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StateDumpHeader {
+    pub format: u32,
+    #[serde(default, skip_serializing_if = "Network::is_default")]
+    pub network: Network,
+    pub height: u64,
+    pub block_hash: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StateDumpImport {
+    // The checkpoint the whole dump amounts to.
+    pub checkpoint: Checkpoint,
+    // Notes locked to the requested lock, confirmed no later than the dump.
+    pub notes: Vec<Note>,
+    // Notes in the dump.
+    pub scanned: usize,
+}
+
+pub fn write_state_dump(header: &StateDumpHeader, notes: &[Note]) -> Result<String, String> {
+    let mut out = serde_json::to_string(header).map_err(|e| e.to_string())?;
+    for note in notes {
+        out.push('\n');
+        out.push_str(&serde_json::to_string(note).map_err(|e| e.to_string())?);
+    }
+    out.push('\n');
+    Ok(out)
+}
+
+pub fn parse_state_dump(dump: &str, lock: &Lock) -> Result<StateDumpImport, String> {
+    let mut lines = dump
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let (_, first) = lines.next().ok_or("State dump is empty")?;
+    let header: StateDumpHeader =
+        serde_json::from_str(first).map_err(|e| format!("Line 1: {}", e))?;
+    if header.format > STATE_DUMP_FORMAT_VERSION {
+        return Err(format!(
+            "State dump format {} is newer than supported ({})",
+            header.format, STATE_DUMP_FORMAT_VERSION
+        ));
+    }
+
+    let mut all = Vec::new();
+    let mut seen = HashSet::new();
+    for (i, line) in lines {
+        let note: Note =
+            serde_json::from_str(line).map_err(|e| format!("Line {}: {}", i + 1, e))?;
+        if note.network != header.network {
+            return Err(format!(
+                "Line {}: note is on {}, dump is {}",
+                i + 1,
+                note.network.name(),
+                header.network.name()
+            ));
+        }
+        if note.confirmed_at.is_some_and(|h| h > header.height) {
+            return Err(format!(
+                "Line {}: note confirmed above the dump height {}",
+                i + 1,
+                header.height
+            ));
+        }
+        if !seen.insert((note.name.first.clone(), note.name.last.clone())) {
+            return Err(format!("Line {}: note is listed twice", i + 1));
+        }
+        all.push(note);
+    }
+
+    let checkpoint = Checkpoint {
+        network: header.network,
+        height: header.height,
+        block_hash: header.block_hash,
+        state_hash: state_hash(&all)?,
+    };
+    let scanned = all.len();
+    let notes = all
+        .into_iter()
+        .filter(|n| &n.lock == lock)
+        .map(|mut n| {
+            // Without a height, the dump's block is the latest it can be.
+            n.confirmed_at.get_or_insert(checkpoint.height);
+            n
+        })
+        .collect();
+    Ok(StateDumpImport {
+        checkpoint,
+        notes,
+        scanned,
+    })
+}
+
+impl Wallet {
+    // Starts the tracker at a checkpoint the caller has verified and seeds
+    // it with the dump's notes, which must add up to that checkpoint.
+    // Returns the first height to scan.
+    pub fn import_state_dump(
+        &mut self,
+        dump: &str,
+        checkpoint: &Checkpoint,
+    ) -> Result<u64, String> {
+        let import = parse_state_dump(dump, &self.descriptor.lock)?;
+        if &import.checkpoint != checkpoint {
+            return Err(format!(
+                "State dump does not match the checkpoint at {}",
+                checkpoint.height
+            ));
+        }
+        let scan_from = self.tracker.start_from(import.checkpoint)?;
+        for note in import.notes {
+            self.apply_chain_event(ChainEvent::Seen { note })?;
+        }
+        Ok(scan_from)
+    }
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+// Returns `{checkpoint, notes, scanned}`: the notes locked to `lock_json`
+// and the checkpoint to compare against a signed one.
+#[wasm_bindgen]
+pub fn read_state_dump(dump: &str, lock_json: &str) -> Result<String, String> {
+    let lock: Lock = serde_json::from_str(lock_json).map_err(|e| e.to_string())?;
+    serde_json::to_string(&parse_state_dump(dump, &lock)?).map_err(|e| e.to_string())
+}
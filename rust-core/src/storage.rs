@@ -0,0 +1,1134 @@
+use crate::comments::Comment;
+use crate::crypto::{open, seal, Sealed, SecretKey};
+use crate::limits::TransactionLimits;
+use crate::policy::{outgoing_value, PolicySet, SpendRecord, SpendingHistory};
+use crate::preapproval::PreApproval;
+use crate::session::SigningSession;
+use crate::snapshot::SnapshotStatement;
+use crate::tracking::ChainEvent;
+use crate::verify::{verify_collected, SignatureVerifier};
+use crate::wallets::{DraftConflict, TrackingReport, Wallet};
+use crate::{NoteName, PublicKey, Signature, Transaction};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// ============================================================================
+// Coordinator Storage
+// ============================================================================
+
+// A coordinator persists plain JSON documents under string keys, so any
+// key-value store can back it. Every key is scoped by tenant:
+//
+//   tenants/<tenant>/wallets/<wallet id>   Wallet
+//   tenants/<tenant>/policies              PolicySet
+//   tenants/<tenant>/metrics               TenantMetrics
+//   tenants/<tenant>/spending/<wallet id>  SpendingHistory
+//   tenants/<tenant>/preapprovals/<wallet id>  Vec<PreApproval>
+//   tenants/<tenant>/session_archive/<wallet id>  Vec<ArchivedSession>
+//   tenants/<tenant>/retention             RetentionPolicy
+//   tenants/<tenant>/schedules/<id>        ScheduledDraft
+//   tenants/<tenant>/inheritance/<id>      InheritancePlan
+//   tenants/<tenant>/treasuries/<id>       TreasuryTier
+//   tenants/<tenant>/notifications         Vec<Notification>
+//   tenants/<tenant>/alert_config          AlertConfig
+//   tenants/<tenant>/alerts                Vec<Alert>
+//   tenants/<tenant>/alert_outbox          Vec<AlertDelivery>
+//   tenants/<tenant>/signers               pubkey -> SignerCapabilities
+//   tenants/<tenant>/federation_key        PublicKey
+//   tenants/<tenant>/federation/<peer>     FederationPeer
+//   tenants/<tenant>/api_tokens/<id>       ApiToken
+//
+// One tenant can never name another tenant's keys, so organizations sharing
+// an instance are isolated by construction.
+pub trait Storage {
+    fn get(&self, key: &str) -> Result<Option<String>, String>;
+    fn put(&mut self, key: &str, value: String) -> Result<(), String>;
+    fn delete(&mut self, key: &str) -> Result<bool, String>;
+    fn keys(&self, prefix: &str) -> Result<Vec<String>, String>;
+
+    fn is_encrypted(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MemoryStorage {
+    entries: BTreeMap<String, String>,
+}
+
+impl Storage for MemoryStorage {
+    fn get(&self, key: &str) -> Result<Option<String>, String> {
+        Ok(self.entries.get(key).cloned())
+    }
+
+    fn put(&mut self, key: &str, value: String) -> Result<(), String> {
+        self.entries.insert(key.to_string(), value);
+        Ok(())
+    }
+
+    fn delete(&mut self, key: &str) -> Result<bool, String> {
+        Ok(self.entries.remove(key).is_some())
+    }
+
+    fn keys(&self, prefix: &str) -> Result<Vec<String>, String> {
+        Ok(self
+            .entries
+            .range(prefix.to_string()..)
+            .map(|(k, _)| k)
+            .take_while(|k| k.starts_with(prefix))
+            .cloned()
+            .collect())
+    }
+}
+
+// ============================================================================
+// Encryption at Rest
+// ============================================================================
+
+// Wraps any store so values are sealed before they reach it. The storage key
+// is bound into each value, so values cannot be moved between keys. Keys
+// themselves stay readable: they hold only tenant and wallet ids.
+#[derive(Debug)]
+pub struct EncryptedStorage<S: Storage> {
+    inner: S,
+    key: SecretKey,
+    // Still accepted for reads while a rotation is under way.
+    previous: Option<SecretKey>,
+}
+
+impl<S: Storage> EncryptedStorage<S> {
+    pub fn new(inner: S, key: SecretKey) -> Self {
+        Self {
+            inner,
+            key,
+            previous: None,
+        }
+    }
+
+    // For resuming a rotation that was interrupted part-way.
+    pub fn with_previous_key(mut self, previous: SecretKey) -> Self {
+        self.previous = Some(previous);
+        self
+    }
+
+    pub fn key_id(&self) -> String {
+        self.key.id()
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    // Re-encrypts every value under `new_key`, in place. If interrupted,
+    // reopen with `new_key` and `with_previous_key(old)` and rotate again.
+    pub fn rotate_key(&mut self, new_key: SecretKey) -> Result<usize, String> {
+        let old = std::mem::replace(&mut self.key, new_key);
+        self.previous = Some(old);
+
+        let mut rotated = 0;
+        for key in self.inner.keys("")? {
+            let sealed = self.load_sealed(&key)?;
+            if sealed.key_id == self.key.id() {
+                continue;
+            }
+            let value = self.open_sealed(&key, &sealed)?;
+            self.put(&key, value)?;
+            rotated += 1;
+        }
+        self.previous = None;
+        Ok(rotated)
+    }
+
+    fn load_sealed(&self, key: &str) -> Result<Sealed, String> {
+        let raw = self
+            .inner
+            .get(key)?
+            .ok_or_else(|| format!("{} disappeared during rotation", key))?;
+        serde_json::from_str(&raw).map_err(|_| format!("{} is not encrypted", key))
+    }
+
+    fn open_sealed(&self, key: &str, sealed: &Sealed) -> Result<String, String> {
+        let secret = if sealed.key_id == self.key.id() {
+            &self.key
+        } else {
+            self.previous
+                .as_ref()
+                .filter(|p| p.id() == sealed.key_id)
+                .ok_or_else(|| format!("{} is encrypted with unknown key {}", key, sealed.key_id))?
+        };
+        let bytes = open(secret, sealed, key.as_bytes())?;
+        String::from_utf8(bytes).map_err(|e| e.to_string())
+    }
+}
+
+impl<S: Storage> Storage for EncryptedStorage<S> {
+    fn get(&self, key: &str) -> Result<Option<String>, String> {
+        let Some(raw) = self.inner.get(key)? else {
+            return Ok(None);
+        };
+        let sealed: Sealed =
+            serde_json::from_str(&raw).map_err(|_| format!("{} is stored in plaintext", key))?;
+        self.open_sealed(key, &sealed).map(Some)
+    }
+
+    fn put(&mut self, key: &str, value: String) -> Result<(), String> {
+        let sealed = seal(&self.key, value.as_bytes(), key.as_bytes());
+        self.inner.put(
+            key,
+            serde_json::to_string(&sealed).map_err(|e| e.to_string())?,
+        )
+    }
+
+    fn delete(&mut self, key: &str) -> Result<bool, String> {
+        self.inner.delete(key)
+    }
+
+    fn keys(&self, prefix: &str) -> Result<Vec<String>, String> {
+        self.inner.keys(prefix)
+    }
+
+    fn is_encrypted(&self) -> bool {
+        true
+    }
+}
+
+// ============================================================================
+// Tenants
+// ============================================================================
+
+const TENANTS_PREFIX: &str = "tenants/";
+
+pub(crate) fn validate_segment(kind: &str, id: &str) -> Result<(), String> {
+    let ok = !id.is_empty()
+        && id.len() <= 64
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !ok {
+        return Err(format!("Invalid {} id '{}'", kind, id));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct PendingSnapshot {
+    pub wallet: String,
+    pub session: usize,
+    pub statement: SnapshotStatement,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingCheck {
+    pub wallet: String,
+    pub session: usize,
+    pub result: Result<(), String>,
+}
+
+// Something a tenant's keyholders should hear about, queued for whatever
+// delivers messages to them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Notification {
+    pub at: u64,
+    pub wallet: String,
+    // Keys to tell.
+    pub notify: Vec<PublicKey>,
+    #[serde(flatten)]
+    pub event: NotificationEvent,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case", deny_unknown_fields)]
+pub enum NotificationEvent {
+    // A scheduled template became a draft.
+    DraftCreated {
+        schedule: String,
+        session: usize,
+        draft: String,
+    },
+    RunFailed {
+        schedule: String,
+        error: String,
+    },
+    // The owners went silent; a recovery draft awaits the heirs.
+    RecoveryPrepared {
+        plan: String,
+        session: usize,
+        draft: String,
+    },
+    RecoveryFailed {
+        plan: String,
+        error: String,
+    },
+    // An owner checked in after the switch fired.
+    RecoveryCancelled {
+        plan: String,
+    },
+    // The hot wallet fell below its floor; a transfer from the cold wallet
+    // awaits its signers.
+    TopUpDrafted {
+        treasury: String,
+        session: usize,
+        draft: String,
+        amount: u64,
+    },
+    TopUpFailed {
+        treasury: String,
+        error: String,
+    },
+    // A note the draft spends went to a transaction from outside this
+    // coordinator; the draft was invalidated and needs no more signatures.
+    DraftConflicted {
+        draft: String,
+        note: NoteName,
+        txid: String,
+    },
+    // The draft still waits on `pending`; sent to them, or, when escalated,
+    // to the keys named by the reminder schedule.
+    SignatureReminder {
+        session: usize,
+        draft: String,
+        pending: Vec<PublicKey>,
+    },
+    SigningEscalated {
+        session: usize,
+        draft: String,
+        pending: Vec<PublicKey>,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TenantMetrics {
+    pub sessions_created: u64,
+    pub signatures_added: u64,
+    pub policy_rejections: u64,
+    #[serde(default)]
+    pub limit_rejections: u64,
+    #[serde(default)]
+    pub duplicate_signatures: u64,
+}
+
+#[derive(Debug)]
+pub struct Coordinator<S: Storage> {
+    storage: S,
+    limits: TransactionLimits,
+}
+
+impl<S: Storage> Coordinator<S> {
+    // Drafts and audit logs are sensitive, so unencrypted storage is refused
+    // here; use `with_plaintext_storage` to opt in deliberately.
+    pub fn new(storage: S) -> Result<Self, String> {
+        if !storage.is_encrypted() {
+            return Err(
+                "Refusing to start on plaintext storage; wrap it in EncryptedStorage \
+                 or use Coordinator::with_plaintext_storage"
+                    .into(),
+            );
+        }
+        Ok(Self::with_plaintext_storage(storage))
+    }
+
+    pub fn with_plaintext_storage(storage: S) -> Self {
+        Self {
+            storage,
+            limits: TransactionLimits::default(),
+        }
+    }
+
+    // Caps applied to every session the coordinator accepts or updates.
+    pub fn with_limits(mut self, limits: TransactionLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn limits(&self) -> &TransactionLimits {
+        &self.limits
+    }
+
+    pub fn storage_mut(&mut self) -> &mut S {
+        &mut self.storage
+    }
+
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    pub fn into_storage(self) -> S {
+        self.storage
+    }
+
+    pub fn tenant(&mut self, tenant: &str) -> Result<Tenant<'_, S>, String> {
+        validate_segment("tenant", tenant)?;
+        Ok(Tenant {
+            prefix: format!("{}{}/", TENANTS_PREFIX, tenant),
+            storage: &mut self.storage,
+            limits: self.limits,
+        })
+    }
+
+    pub fn tenants(&self) -> Result<Vec<String>, String> {
+        let mut tenants: Vec<String> = self
+            .storage
+            .keys(TENANTS_PREFIX)?
+            .iter()
+            .filter_map(|k| k[TENANTS_PREFIX.len()..].split('/').next())
+            .map(str::to_string)
+            .collect();
+        tenants.dedup();
+        Ok(tenants)
+    }
+}
+
+pub struct Tenant<'a, S: Storage> {
+    prefix: String,
+    storage: &'a mut S,
+    limits: TransactionLimits,
+}
+
+impl<S: Storage> Tenant<'_, S> {
+    pub(crate) fn load<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, String> {
+        self.storage
+            .get(&format!("{}{}", self.prefix, key))?
+            .map(|json| serde_json::from_str(&json).map_err(|e| e.to_string()))
+            .transpose()
+    }
+
+    pub(crate) fn store<T: Serialize>(&mut self, key: &str, value: &T) -> Result<(), String> {
+        let json = serde_json::to_string(value).map_err(|e| e.to_string())?;
+        self.storage.put(&format!("{}{}", self.prefix, key), json)
+    }
+
+    pub(crate) fn remove(&mut self, key: &str) -> Result<bool, String> {
+        self.storage.delete(&format!("{}{}", self.prefix, key))
+    }
+
+    // Names of the documents under `dir/`.
+    pub(crate) fn list(&self, dir: &str) -> Result<Vec<String>, String> {
+        let prefix = format!("{}{}/", self.prefix, dir);
+        Ok(self
+            .storage
+            .keys(&prefix)?
+            .into_iter()
+            .map(|k| k[prefix.len()..].to_string())
+            .collect())
+    }
+
+    fn wallet_key(id: &str) -> Result<String, String> {
+        validate_segment("wallet", id)?;
+        Ok(format!("wallets/{}", id))
+    }
+
+    pub fn policies(&self) -> Result<PolicySet, String> {
+        Ok(self.load("policies")?.unwrap_or_default())
+    }
+
+    pub fn set_policies(&mut self, policies: &PolicySet) -> Result<(), String> {
+        self.store("policies", policies)
+    }
+
+    pub fn metrics(&self) -> Result<TenantMetrics, String> {
+        Ok(self.load("metrics")?.unwrap_or_default())
+    }
+
+    pub fn set_metrics(&mut self, metrics: &TenantMetrics) -> Result<(), String> {
+        self.store("metrics", metrics)
+    }
+
+    pub(crate) fn record(&mut self, update: impl FnOnce(&mut TenantMetrics)) -> Result<(), String> {
+        let mut metrics = self.metrics()?;
+        update(&mut metrics);
+        self.store("metrics", &metrics)
+    }
+
+    pub fn wallet_ids(&self) -> Result<Vec<String>, String> {
+        self.list("wallets")
+    }
+
+    pub fn wallet(&self, id: &str) -> Result<Wallet, String> {
+        self.load(&Self::wallet_key(id)?)?
+            .ok_or_else(|| format!("Unknown wallet {}", id))
+    }
+
+    pub fn put_wallet(&mut self, wallet: &Wallet) -> Result<(), String> {
+        self.store(&Self::wallet_key(&wallet.id)?, wallet)
+    }
+
+    pub fn delete_wallet(&mut self, id: &str) -> Result<bool, String> {
+        let key = Self::wallet_key(id)?;
+        self.remove(&format!("spending/{}", id))?;
+        self.remove(&format!("preapprovals/{}", id))?;
+        self.remove(&format!("session_archive/{}", id))?;
+        self.remove(&key)
+    }
+
+    // Queues notifications for `take_notifications`.
+    pub(crate) fn notify(&mut self, notifications: &[Notification]) -> Result<(), String> {
+        if notifications.is_empty() {
+            return Ok(());
+        }
+        let mut outbox = self.notifications()?;
+        outbox.extend(notifications.iter().cloned());
+        self.store("notifications", &outbox)
+    }
+
+    pub fn notifications(&self) -> Result<Vec<Notification>, String> {
+        Ok(self.load("notifications")?.unwrap_or_default())
+    }
+
+    // Drains the outbox, for whatever delivers notifications.
+    pub fn take_notifications(&mut self) -> Result<Vec<Notification>, String> {
+        let outbox = self.notifications()?;
+        self.remove("notifications")?;
+        Ok(outbox)
+    }
+
+    // Limit violations fail with the JSON form of `LimitExceeded`.
+    fn check_limits(&mut self, tx: &Transaction) -> Result<(), String> {
+        if let Err(e) = self.limits.check(tx) {
+            self.record(|m| m.limit_rejections += 1)?;
+            return Err(e.to_json());
+        }
+        Ok(())
+    }
+
+    // Finalized spending of a wallet, for velocity rules.
+    pub fn spending(&self, wallet_id: &str) -> Result<SpendingHistory, String> {
+        validate_segment("wallet", wallet_id)?;
+        Ok(self
+            .load(&format!("spending/{}", wallet_id))?
+            .unwrap_or_default())
+    }
+
+    pub fn set_spending(
+        &mut self,
+        wallet_id: &str,
+        history: &SpendingHistory,
+    ) -> Result<(), String> {
+        validate_segment("wallet", wallet_id)?;
+        self.store(&format!("spending/{}", wallet_id), history)
+    }
+
+    pub fn spent_in(&self, wallet_id: &str, period: u64, now: u64) -> Result<u64, String> {
+        Ok(self.spending(wallet_id)?.spent_in(period, now))
+    }
+
+    // Signed pre-approvals waiting for a matching draft. Each one is used by
+    // the first draft it matches.
+    pub fn preapprovals(&self, wallet_id: &str) -> Result<Vec<PreApproval>, String> {
+        validate_segment("wallet", wallet_id)?;
+        Ok(self
+            .load(&format!("preapprovals/{}", wallet_id))?
+            .unwrap_or_default())
+    }
+
+    fn set_preapprovals(
+        &mut self,
+        wallet_id: &str,
+        preapprovals: &[PreApproval],
+    ) -> Result<(), String> {
+        let key = format!("preapprovals/{}", wallet_id);
+        if preapprovals.is_empty() {
+            return self.remove(&key).map(|_| ());
+        }
+        self.store(&key, &preapprovals)
+    }
+
+    pub fn add_preapproval(
+        &mut self,
+        wallet_id: &str,
+        preapproval: PreApproval,
+        verifier: &dyn SignatureVerifier,
+    ) -> Result<(), String> {
+        let wallet = self.wallet(wallet_id)?;
+        preapproval.validate()?;
+        if !wallet
+            .descriptor
+            .participants()
+            .contains(&preapproval.signer)
+        {
+            return Err(format!(
+                "{} is not a signer on wallet {}",
+                preapproval.signer.0, wallet_id
+            ));
+        }
+        if preapproval.lock != wallet.descriptor.lock.fingerprint() {
+            return Err(format!("Pre-approval is not for wallet {}", wallet_id));
+        }
+        if !verifier.verify(
+            &preapproval.signer,
+            &preapproval.message(),
+            &preapproval.signature,
+        ) {
+            return Err("Invalid pre-approval signature".into());
+        }
+        let mut pending = self.preapprovals(wallet_id)?;
+        if pending.contains(&preapproval) {
+            return Err("Pre-approval is already on file".into());
+        }
+        pending.push(preapproval);
+        self.set_preapprovals(wallet_id, &pending)
+    }
+
+    // Withdraws a pending pre-approval by the hash its signer signed.
+    pub fn revoke_preapproval(&mut self, wallet_id: &str, terms: &str) -> Result<bool, String> {
+        let mut pending = self.preapprovals(wallet_id)?;
+        let before = pending.len();
+        pending.retain(|p| p.message() != terms);
+        self.set_preapprovals(wallet_id, &pending)?;
+        Ok(pending.len() != before)
+    }
+
+    // Drops pre-approvals that expired before `height`.
+    pub fn prune_preapprovals(&mut self, wallet_id: &str, height: u64) -> Result<usize, String> {
+        let mut pending = self.preapprovals(wallet_id)?;
+        let before = pending.len();
+        pending.retain(|p| p.permits(Some(height)).is_ok());
+        self.set_preapprovals(wallet_id, &pending)?;
+        Ok(before - pending.len())
+    }
+
+    // Turns matching pre-approvals into signatures on a new draft, checking
+    // expiry against `height`, the height the draft would confirm at. Expired
+    // ones are dropped; with no height none apply.
+    fn apply_preapprovals(
+        &mut self,
+        wallet_id: &str,
+        session: &mut SigningSession,
+        height: Option<u64>,
+        now: u64,
+    ) -> Result<(), String> {
+        let mut pending = self.preapprovals(wallet_id)?;
+        let before = pending.len();
+        if let Some(height) = height {
+            pending.retain(|p| p.permits(Some(height)).is_ok());
+        }
+        let mut unused = Vec::new();
+        for preapproval in pending.drain(..) {
+            match session.apply_preapproval(&preapproval, height, Some(now)) {
+                Ok(spends) if !spends.is_empty() => {}
+                _ => unused.push(preapproval),
+            }
+        }
+        if unused.len() != before {
+            self.set_preapprovals(wallet_id, &unused)?;
+        }
+        Ok(())
+    }
+
+    // Feeds a node event to the wallet. Conflicts with transactions this
+    // tenant recorded as finalized are not external and are dropped; the
+    // rest notify every participant.
+    pub fn apply_chain_event(
+        &mut self,
+        wallet_id: &str,
+        event: ChainEvent,
+        now: u64,
+    ) -> Result<TrackingReport, String> {
+        let mut wallet = self.wallet(wallet_id)?;
+        let mut report = wallet.apply_chain_event(event)?;
+        let history = self.spending(wallet_id)?;
+        let external = |c: &DraftConflict| !history.records.iter().any(|r| r.txid == c.txid);
+        report.conflicts.retain(external);
+        wallet.conflicts.retain(external);
+        self.put_wallet(&wallet)?;
+
+        let notify = wallet.descriptor.participants();
+        let notifications: Vec<Notification> = report
+            .conflicts
+            .iter()
+            .map(|c| Notification {
+                at: now,
+                wallet: wallet_id.to_string(),
+                notify: notify.clone(),
+                event: NotificationEvent::DraftConflicted {
+                    draft: c.draft.clone(),
+                    note: c.note.clone(),
+                    txid: c.txid.clone(),
+                },
+            })
+            .collect();
+        self.notify(&notifications)?;
+        Ok(report)
+    }
+
+    // Records a broadcast transaction against its wallet's spending. The
+    // transaction must pass `finalize`; recording it twice is a no-op.
+    pub fn record_finalized(
+        &mut self,
+        wallet_id: &str,
+        tx: &Transaction,
+        at: u64,
+    ) -> Result<SpendRecord, String> {
+        let wallet = self.wallet(wallet_id)?;
+        let tx = tx.finalize_at(wallet.chain_height())?;
+        if tx
+            .spends
+            .iter()
+            .any(|s| s.note.lock != wallet.descriptor.lock)
+        {
+            return Err(format!(
+                "Transaction spends notes outside wallet {}",
+                wallet_id
+            ));
+        }
+        let record = SpendRecord {
+            at,
+            txid: tx.txid()?,
+            value: outgoing_value(&tx),
+        };
+        let mut history = self.spending(wallet_id)?;
+        history.record(record.clone());
+        self.set_spending(wallet_id, &history)?;
+        Ok(record)
+    }
+
+    // Tenant policies apply on top of the wallet's own, and velocity rules
+    // count the wallet's finalized spending up to `now`. A draft already open
+    // (same `draft_id`) is merged into its session, without new alerts.
+    pub fn add_session(
+        &mut self,
+        wallet_id: &str,
+        mut session: SigningSession,
+        now: u64,
+    ) -> Result<usize, String> {
+        self.check_limits(&session.transaction)?;
+        session.created_at.get_or_insert(now);
+        let draft = session.draft_hash();
+        let alerts = self.alert_config()?.check_draft(&session);
+        let mut wallet = self.wallet(wallet_id)?;
+        let duplicate = wallet.session_index(&session.draft_id()?)?.is_some();
+        let history = self.spending(wallet_id)?;
+        let tags = wallet.metadata.note_tags();
+        let checked = self
+            .policies()?
+            .check_with_tags(&session.transaction, &history, now, &tags)
+            .and_then(|_| {
+                wallet
+                    .policies
+                    .check_with_tags(&session.transaction, &history, now, &tags)
+            })
+            .and_then(|_| wallet.add_session(session));
+        let index = match checked {
+            Ok(index) => index,
+            Err(e) => {
+                self.record(|m| m.policy_rejections += 1)?;
+                return Err(e);
+            }
+        };
+        let height = wallet.chain_height().map(|h| h + 1);
+        self.apply_preapprovals(wallet_id, &mut wallet.sessions[index], height, now)?;
+        self.put_wallet(&wallet)?;
+        if !duplicate {
+            self.record(|m| m.sessions_created += 1)?;
+            self.raise_alerts(wallet_id, &draft, alerts, now)?;
+        }
+        Ok(index)
+    }
+
+    // The open session for a draft, by its content-derived id.
+    pub fn find_session(&self, wallet_id: &str, draft_id: &str) -> Result<Option<usize>, String> {
+        self.wallet(wallet_id)?.session_index(draft_id)
+    }
+
+    pub fn add_signature(
+        &mut self,
+        wallet_id: &str,
+        session_index: usize,
+        spend_index: usize,
+        pubkey: PublicKey,
+        signature: Signature,
+        now: u64,
+    ) -> Result<SigningSession, String> {
+        let mut wallet = self.wallet(wallet_id)?;
+        let session = wallet
+            .sessions
+            .get_mut(session_index)
+            .ok_or("Invalid session index")?;
+        let alerts = self.alert_config()?.check_signer(&pubkey);
+        let added = session.add_signature(spend_index, pubkey, signature, Some(now))?;
+        let session = session.clone();
+        self.check_limits(&session.transaction)?;
+
+        self.put_wallet(&wallet)?;
+        // A relay repeating a delivery is counted, not treated as news.
+        if !added {
+            self.record(|m| m.duplicate_signatures += 1)?;
+            return Ok(session);
+        }
+        self.record(|m| m.signatures_added += 1)?;
+        self.raise_alerts(wallet_id, &session.draft_hash(), alerts, now)?;
+        Ok(session)
+    }
+
+    // Comments are checked against the author's key before they are stored.
+    pub fn post_comment(
+        &mut self,
+        wallet_id: &str,
+        session_index: usize,
+        comment: Comment,
+        verifier: &dyn SignatureVerifier,
+    ) -> Result<Comment, String> {
+        let mut wallet = self.wallet(wallet_id)?;
+        let session = wallet
+            .sessions
+            .get_mut(session_index)
+            .ok_or("Invalid session index")?;
+        let comment = session.add_comment(comment)?;
+        let item = comment.verification_item();
+        if !verifier.verify(&item.pubkey, &item.message_hash, &item.signature) {
+            return Err(format!("Invalid signature on comment {}", comment.id));
+        }
+
+        self.put_wallet(&wallet)?;
+        Ok(comment)
+    }
+
+    pub fn comments(&self, wallet_id: &str, session_index: usize) -> Result<Vec<Comment>, String> {
+        let wallet = self.wallet(wallet_id)?;
+        let session = wallet
+            .sessions
+            .get(session_index)
+            .ok_or("Invalid session index")?;
+        Ok(session.comment_thread().into_iter().cloned().collect())
+    }
+
+    // Statements for every open session due a snapshot: never snapshotted,
+    // or changed since its last snapshot at least `interval` seconds ago.
+    // The host signs each with the coordinator key and hands it back to
+    // `add_snapshot`.
+    pub fn due_snapshots(
+        &self,
+        coordinator: &PublicKey,
+        now: u64,
+        interval: u64,
+    ) -> Result<Vec<PendingSnapshot>, String> {
+        let mut due = Vec::new();
+        for wallet_id in self.wallet_ids()? {
+            for (i, session) in self.wallet(&wallet_id)?.sessions.iter().enumerate() {
+                if let Some(last) = session.snapshots.last() {
+                    let waited = now >= last.statement.at.saturating_add(interval);
+                    if !waited || last.statement.txid == session.transaction.txid()? {
+                        continue;
+                    }
+                }
+                due.push(PendingSnapshot {
+                    wallet: wallet_id.clone(),
+                    session: i,
+                    statement: session.snapshot_statement(coordinator.clone(), now)?,
+                });
+            }
+        }
+        Ok(due)
+    }
+
+    pub fn add_snapshot(
+        &mut self,
+        wallet_id: &str,
+        session_index: usize,
+        statement: SnapshotStatement,
+        signature: Signature,
+        verifier: &dyn SignatureVerifier,
+    ) -> Result<u64, String> {
+        if !verifier.verify(
+            &statement.coordinator,
+            &statement.message_hash(),
+            &signature,
+        ) {
+            return Err("Invalid coordinator signature on snapshot".into());
+        }
+        let mut wallet = self.wallet(wallet_id)?;
+        let session = wallet
+            .sessions
+            .get_mut(session_index)
+            .ok_or("Invalid session index")?;
+        let sequence = session.add_snapshot(statement, signature)?;
+        self.put_wallet(&wallet)?;
+        Ok(sequence)
+    }
+
+    // Countersignatures are checked against the snapshot before they are
+    // stored.
+    #[allow(clippy::too_many_arguments)]
+    pub fn countersign_snapshot(
+        &mut self,
+        wallet_id: &str,
+        session_index: usize,
+        sequence: u64,
+        pubkey: PublicKey,
+        signature: Signature,
+        verifier: &dyn SignatureVerifier,
+        now: u64,
+    ) -> Result<(), String> {
+        let mut wallet = self.wallet(wallet_id)?;
+        let session = wallet
+            .sessions
+            .get_mut(session_index)
+            .ok_or("Invalid session index")?;
+        let message = session
+            .snapshots
+            .get(sequence as usize)
+            .ok_or_else(|| format!("No snapshot {}", sequence))?
+            .statement
+            .message_hash();
+        if !verifier.verify(&pubkey, &message, &signature) {
+            return Err(format!("Invalid countersignature from {}", pubkey.0));
+        }
+        session.countersign_snapshot(sequence, pubkey, signature, Some(now))?;
+        self.put_wallet(&wallet)
+    }
+
+    // Verifies the collected signatures of every open session in one batch.
+    pub fn verify_pending(
+        &self,
+        verifier: &dyn SignatureVerifier,
+    ) -> Result<Vec<PendingCheck>, String> {
+        let mut slots = Vec::new();
+        let mut txs = Vec::new();
+        for id in self.wallet_ids()? {
+            for (i, session) in self.wallet(&id)?.sessions.into_iter().enumerate() {
+                slots.push((id.clone(), i));
+                txs.push(session.transaction);
+            }
+        }
+
+        Ok(slots
+            .into_iter()
+            .zip(verify_collected(&txs, verifier))
+            .map(|((wallet, session), result)| PendingCheck {
+                wallet,
+                session,
+                result,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{draft, HashVerifier, MULTISIG_LOCK};
+    use crate::wallets::WalletDescriptor;
+    use crate::Lock;
+
+    fn coordinator() -> (Coordinator<MemoryStorage>, String) {
+        let mut coordinator = Coordinator::with_plaintext_storage(MemoryStorage::default());
+        let wallet = Wallet::new(WalletDescriptor {
+            label: "treasury".into(),
+            lock: serde_json::from_str::<Lock>(MULTISIG_LOCK).unwrap(),
+        })
+        .unwrap();
+        coordinator
+            .tenant("acme")
+            .unwrap()
+            .put_wallet(&wallet)
+            .unwrap();
+        (coordinator, wallet.id)
+    }
+
+    fn preapproval(signer: &str, valid_until_height: u64, forge: bool) -> PreApproval {
+        let tx = draft(MULTISIG_LOCK);
+        let mut preapproval = PreApproval {
+            signer: PublicKey(signer.into()),
+            lock: tx.spends[0].note.lock.fingerprint(),
+            outputs: tx.outputs.clone(),
+            valid_until_height,
+            signature: Signature(String::new()),
+        };
+        preapproval.signature = if forge {
+            Signature("00".repeat(32))
+        } else {
+            HashVerifier::sign(&preapproval.signer, &preapproval.message())
+        };
+        preapproval
+    }
+
+    fn set_tip(tenant: &mut Tenant<'_, MemoryStorage>, wallet: &str, height: u64) {
+        tenant
+            .apply_chain_event(wallet, ChainEvent::Tip { height }, 0)
+            .unwrap();
+    }
+
+    #[test]
+    fn rejects_invalid_ids() {
+        let (mut coordinator, _) = coordinator();
+        assert!(coordinator.tenant("").is_err());
+        assert!(coordinator.tenant("../acme").is_err());
+        let mut tenant = coordinator.tenant("acme").unwrap();
+        for id in ["", "a/b", "..", "wallets/x"] {
+            assert!(tenant.wallet(id).is_err(), "{id}");
+            assert!(tenant.delete_wallet(id).is_err(), "{id}");
+        }
+    }
+
+    #[test]
+    fn tenants_are_isolated() {
+        let (mut coordinator, wallet) = coordinator();
+        assert!(coordinator
+            .tenant("other")
+            .unwrap()
+            .wallet(&wallet)
+            .is_err());
+        assert!(coordinator
+            .tenant("other")
+            .unwrap()
+            .wallet_ids()
+            .unwrap()
+            .is_empty());
+        assert_eq!(coordinator.tenants().unwrap(), vec!["acme".to_string()]);
+    }
+
+    #[test]
+    fn delete_wallet_removes_its_documents() {
+        let (mut coordinator, wallet) = coordinator();
+        let mut tenant = coordinator.tenant("acme").unwrap();
+        tenant
+            .add_preapproval(&wallet, preapproval("b", 100, false), &HashVerifier)
+            .unwrap();
+        tenant
+            .set_spending(&wallet, &SpendingHistory::default())
+            .unwrap();
+        assert!(tenant.delete_wallet(&wallet).unwrap());
+        assert!(!tenant.delete_wallet(&wallet).unwrap());
+        assert!(coordinator
+            .storage()
+            .keys(TENANTS_PREFIX)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn add_session_merges_the_same_draft() {
+        let (mut coordinator, wallet) = coordinator();
+        let mut tenant = coordinator.tenant("acme").unwrap();
+        let mut signed = SigningSession::new(draft(MULTISIG_LOCK));
+        let a = PublicKey("a".into());
+        let signature = HashVerifier::sign(&a, &signed.transaction.spends[0].seeds.message_hash);
+        signed.add_signature(0, a, signature, None).unwrap();
+
+        let first = tenant
+            .add_session(&wallet, SigningSession::new(draft(MULTISIG_LOCK)), 1)
+            .unwrap();
+        assert_eq!(tenant.add_session(&wallet, signed, 2).unwrap(), first);
+        let stored = tenant.wallet(&wallet).unwrap();
+        assert_eq!(stored.sessions.len(), 1);
+        assert_eq!(
+            stored.sessions[0].transaction.spends[0]
+                .seeds
+                .signatures
+                .len(),
+            1
+        );
+        assert_eq!(tenant.metrics().unwrap().sessions_created, 1);
+    }
+
+    #[test]
+    fn add_session_rejects_other_locks() {
+        let (mut coordinator, wallet) = coordinator();
+        let mut tenant = coordinator.tenant("acme").unwrap();
+        let other = draft(r#"{"pkh":{"threshold":1,"pubkeys":["x"]}}"#);
+        let err = tenant
+            .add_session(&wallet, SigningSession::new(other), 1)
+            .unwrap_err();
+        assert!(err.contains("not locked to this wallet"), "{err}");
+        assert_eq!(tenant.metrics().unwrap().policy_rejections, 1);
+        assert!(tenant.wallet(&wallet).unwrap().sessions.is_empty());
+    }
+
+    #[test]
+    fn add_preapproval_checks_signer_lock_and_signature() {
+        let (mut coordinator, wallet) = coordinator();
+        let mut tenant = coordinator.tenant("acme").unwrap();
+        let forged = tenant.add_preapproval(&wallet, preapproval("b", 100, true), &HashVerifier);
+        assert_eq!(forged.unwrap_err(), "Invalid pre-approval signature");
+        let outsider = tenant.add_preapproval(&wallet, preapproval("x", 100, false), &HashVerifier);
+        assert!(outsider.unwrap_err().contains("not a signer"));
+        let mut other_lock = preapproval("b", 100, false);
+        other_lock.lock = "00".repeat(32);
+        other_lock.signature = HashVerifier::sign(&other_lock.signer, &other_lock.message());
+        assert!(tenant
+            .add_preapproval(&wallet, other_lock, &HashVerifier)
+            .is_err());
+
+        let valid = preapproval("b", 100, false);
+        tenant
+            .add_preapproval(&wallet, valid.clone(), &HashVerifier)
+            .unwrap();
+        assert!(tenant
+            .add_preapproval(&wallet, valid.clone(), &HashVerifier)
+            .is_err());
+        assert_eq!(tenant.preapprovals(&wallet).unwrap(), vec![valid]);
+    }
+
+    #[test]
+    fn preapproval_waits_without_a_chain_height() {
+        let (mut coordinator, wallet) = coordinator();
+        let mut tenant = coordinator.tenant("acme").unwrap();
+        tenant
+            .add_preapproval(&wallet, preapproval("b", 100, false), &HashVerifier)
+            .unwrap();
+
+        // Without a chain height expiry cannot be checked, so it stays on file.
+        let index = tenant
+            .add_session(&wallet, SigningSession::new(draft(MULTISIG_LOCK)), 1)
+            .unwrap();
+        let session = &tenant.wallet(&wallet).unwrap().sessions[index];
+        assert!(session.transaction.spends[0].seeds.preapprovals.is_empty());
+        assert_eq!(tenant.preapprovals(&wallet).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn preapproval_fills_a_draft_confirming_in_time() {
+        let (mut coordinator, wallet) = coordinator();
+        let mut tenant = coordinator.tenant("acme").unwrap();
+        set_tip(&mut tenant, &wallet, 50);
+        tenant
+            .add_preapproval(&wallet, preapproval("b", 100, false), &HashVerifier)
+            .unwrap();
+        let index = tenant
+            .add_session(&wallet, SigningSession::new(draft(MULTISIG_LOCK)), 1)
+            .unwrap();
+        let session = &tenant.wallet(&wallet).unwrap().sessions[index];
+        assert_eq!(session.transaction.spends[0].seeds.preapprovals.len(), 1);
+        assert!(tenant.preapprovals(&wallet).unwrap().is_empty());
+    }
+
+    #[test]
+    fn expired_preapproval_is_dropped() {
+        let (mut coordinator, wallet) = coordinator();
+        let mut tenant = coordinator.tenant("acme").unwrap();
+        set_tip(&mut tenant, &wallet, 100);
+        tenant
+            .add_preapproval(&wallet, preapproval("b", 100, false), &HashVerifier)
+            .unwrap();
+        // The draft would confirm at 101, past the pre-approval.
+        let index = tenant
+            .add_session(&wallet, SigningSession::new(draft(MULTISIG_LOCK)), 1)
+            .unwrap();
+        let session = &tenant.wallet(&wallet).unwrap().sessions[index];
+        assert!(session.transaction.spends[0].seeds.preapprovals.is_empty());
+        assert!(tenant.preapprovals(&wallet).unwrap().is_empty());
+    }
+
+    #[test]
+    fn record_finalized_checks_the_transaction() {
+        let (mut coordinator, wallet) = coordinator();
+        let mut tenant = coordinator.tenant("acme").unwrap();
+        let mut tx = draft(MULTISIG_LOCK);
+        for signer in ["a", "b"] {
+            let pubkey = PublicKey(signer.into());
+            let signature = HashVerifier::sign(&pubkey, &tx.spends[0].seeds.message_hash);
+            tx.spends[0].seeds.add_signature(pubkey, signature);
+        }
+
+        let mut tampered = tx.clone();
+        tampered.outputs[0].recipient = "mallory".into();
+        assert!(tenant.record_finalized(&wallet, &tampered, 1).is_err());
+
+        let record = tenant.record_finalized(&wallet, &tx, 1).unwrap();
+        assert_eq!(record.value, 5);
+        tenant.record_finalized(&wallet, &tx, 2).unwrap();
+        assert_eq!(tenant.spending(&wallet).unwrap().records.len(), 1);
+    }
+}
@@ -0,0 +1,508 @@
+use crate::hashing::HashAlgorithm;
+use crate::session::SigningSession;
+use crate::{
+    preimage_hash, HaxCondition, Lock, Note, Output, PublicKey, SpendCondition, TimCondition,
+    Transaction,
+};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Atomic Swaps
+// ============================================================================
+
+// Hash-time-locked swap of a nockchain leg against a leg on another chain.
+// The initiator knows the secret and locks with the longer timeout; the
+// participant locks second with the shorter one, so whoever reveals the
+// secret first always leaves the other side time to claim.
+//
+// The nockchain leg uses a two-branch lock: recipient + `%hax` preimage, or
+// sender after the `%tim` timeout. Adaptor signatures would remove the
+// on-chain preimage, but need a Schnorr verifier backend which this crate
+// does not ship yet.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapRole {
+    Initiator,
+    Participant,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapStage {
+    Offered,
+    Accepted,
+    Locked,
+    Revealed,
+    Claimed,
+    Refunded,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SwapTimeouts {
+    pub initiator: u64,
+    pub participant: u64,
+}
+
+impl SwapTimeouts {
+    // `claim_window` is how long either side needs to notice a reveal and get
+    // a claim confirmed; `confirmations` is the depth each lock must reach
+    // before the other side acts on it.
+    pub fn compute(current_height: u64, claim_window: u64, confirmations: u64) -> Self {
+        let participant = current_height + 2 * confirmations + claim_window;
+        let initiator = participant + claim_window + confirmations;
+        Self {
+            initiator,
+            participant,
+        }
+    }
+
+    pub fn validate(&self, claim_window: u64) -> Result<(), String> {
+        if self.initiator < self.participant + claim_window {
+            return Err(format!(
+                "Initiator timeout {} must be at least {} blocks after participant timeout {}",
+                self.initiator, claim_window, self.participant
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SwapTerms {
+    pub initiator: PublicKey,
+    pub participant: PublicKey,
+    pub initiator_sends_nock: bool,
+    pub amount: u64,
+    pub counter_leg: String,
+    pub secret_hash: String,
+    pub claim_window: u64,
+    pub timeouts: SwapTimeouts,
+}
+
+impl SwapTerms {
+    pub fn nock_sender(&self) -> &PublicKey {
+        if self.initiator_sends_nock {
+            &self.initiator
+        } else {
+            &self.participant
+        }
+    }
+
+    pub fn nock_recipient(&self) -> &PublicKey {
+        if self.initiator_sends_nock {
+            &self.participant
+        } else {
+            &self.initiator
+        }
+    }
+
+    pub fn nock_timeout(&self) -> u64 {
+        if self.initiator_sends_nock {
+            self.timeouts.initiator
+        } else {
+            self.timeouts.participant
+        }
+    }
+
+    pub fn htlc_lock(&self) -> Lock {
+        let mut lock = Lock::single(self.nock_recipient());
+        lock.hax = Some(HaxCondition {
+            hashes: vec![self.secret_hash.clone()],
+        });
+        lock.alternatives.push(SpendCondition {
+            pkh: Lock::single(self.nock_sender()).pkh,
+            hax: None,
+            tim: Some(TimCondition {
+                min_height: Some(self.nock_timeout()),
+                max_height: None,
+            }),
+            emergency: false,
+        });
+        lock
+    }
+}
+
+// Messages exchanged with the counterparty, over any session transport.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum SwapMessage {
+    Offer { terms: SwapTerms },
+    Accept { secret_hash: String },
+    Locked { secret_hash: String, note: Note },
+    Reveal { secret_hash: String, secret: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Swap {
+    pub role: SwapRole,
+    pub terms: SwapTerms,
+    pub stage: SwapStage,
+    #[serde(default)]
+    pub secret: Option<String>,
+    #[serde(default)]
+    pub lock_note: Option<Note>,
+    #[serde(default)]
+    pub settlement: Option<SigningSession>,
+}
+
+impl Swap {
+    #[allow(clippy::too_many_arguments)]
+    pub fn offer(
+        initiator: PublicKey,
+        participant: PublicKey,
+        initiator_sends_nock: bool,
+        amount: u64,
+        counter_leg: String,
+        secret: String,
+        current_height: u64,
+        claim_window: u64,
+        confirmations: u64,
+    ) -> Result<Self, String> {
+        if amount == 0 {
+            return Err("Swap amount must be positive".into());
+        }
+        if hex::decode(&secret).map_or(true, |b| b.len() < 16) {
+            return Err("Swap secret must be at least 16 hex-encoded bytes".into());
+        }
+        let timeouts = SwapTimeouts::compute(current_height, claim_window, confirmations);
+        let terms = SwapTerms {
+            initiator,
+            participant,
+            initiator_sends_nock,
+            amount,
+            counter_leg,
+            secret_hash: preimage_hash(&secret),
+            claim_window,
+            timeouts,
+        };
+        terms.htlc_lock().validate()?;
+        Ok(Self {
+            role: SwapRole::Initiator,
+            terms,
+            stage: SwapStage::Offered,
+            secret: Some(secret),
+            lock_note: None,
+            settlement: None,
+        })
+    }
+
+    pub fn from_offer(terms: SwapTerms, current_height: u64) -> Result<Self, String> {
+        terms.timeouts.validate(terms.claim_window)?;
+        if terms.timeouts.participant < current_height + terms.claim_window {
+            return Err("Participant timeout leaves no time to claim".into());
+        }
+        terms.htlc_lock().validate()?;
+        Ok(Self {
+            role: SwapRole::Participant,
+            terms,
+            stage: SwapStage::Offered,
+            secret: None,
+            lock_note: None,
+            settlement: None,
+        })
+    }
+
+    pub fn we_send_nock(&self) -> bool {
+        (self.role == SwapRole::Initiator) == self.terms.initiator_sends_nock
+    }
+
+    pub fn offer_message(&self) -> SwapMessage {
+        SwapMessage::Offer {
+            terms: self.terms.clone(),
+        }
+    }
+
+    pub fn accept(&mut self) -> Result<SwapMessage, String> {
+        if self.role != SwapRole::Participant || self.stage != SwapStage::Offered {
+            return Err("Only an offered swap can be accepted by the participant".into());
+        }
+        self.stage = SwapStage::Accepted;
+        Ok(SwapMessage::Accept {
+            secret_hash: self.terms.secret_hash.clone(),
+        })
+    }
+
+    pub fn receive(&mut self, message: SwapMessage) -> Result<(), String> {
+        match message {
+            SwapMessage::Offer { .. } => Err("Offers start a new swap".into()),
+            SwapMessage::Accept { secret_hash } => {
+                self.check_hash(&secret_hash)?;
+                if self.stage == SwapStage::Offered {
+                    self.stage = SwapStage::Accepted;
+                }
+                Ok(())
+            }
+            SwapMessage::Locked { secret_hash, note } => {
+                self.check_hash(&secret_hash)?;
+                self.record_lock(note)
+            }
+            SwapMessage::Reveal {
+                secret_hash,
+                secret,
+            } => {
+                self.check_hash(&secret_hash)?;
+                self.reveal(secret)
+            }
+        }
+    }
+
+    fn check_hash(&self, secret_hash: &str) -> Result<(), String> {
+        if secret_hash != self.terms.secret_hash {
+            return Err("Message belongs to a different swap".into());
+        }
+        Ok(())
+    }
+
+    // Draft moving the sender's notes into the HTLC, change back to sender.
+    pub fn build_lock(&self, notes: Vec<Note>) -> Result<SigningSession, String> {
+        if self.stage != SwapStage::Accepted {
+            return Err("Swap must be accepted before locking".into());
+        }
+        let sender = self.terms.nock_sender();
+        let input: u64 = notes.iter().map(|n| n.value).sum();
+        if input < self.terms.amount {
+            return Err("Insufficient notes to fund the swap".into());
+        }
+
+        let htlc = self.terms.htlc_lock();
+        let mut outputs = vec![Output {
+            recipient: htlc.fingerprint(),
+            value: self.terms.amount,
+            lock: htlc,
+        }];
+        if input > self.terms.amount {
+            outputs.push(Output {
+                recipient: sender.0.clone(),
+                value: input - self.terms.amount,
+                lock: Lock::single(sender),
+            });
+        }
+        let tx = Transaction::build(notes, outputs, HashAlgorithm::default())?;
+        Ok(SigningSession::new(tx))
+    }
+
+    pub fn record_lock(&mut self, note: Note) -> Result<(), String> {
+        if note.lock != self.terms.htlc_lock() || note.value != self.terms.amount {
+            return Err("Note does not match the swap lock".into());
+        }
+        self.lock_note = Some(note);
+        if self.stage == SwapStage::Accepted || self.stage == SwapStage::Offered {
+            self.stage = SwapStage::Locked;
+        }
+        Ok(())
+    }
+
+    pub fn locked_message(&self) -> Result<SwapMessage, String> {
+        Ok(SwapMessage::Locked {
+            secret_hash: self.terms.secret_hash.clone(),
+            note: self.lock_note.clone().ok_or("Swap lock not recorded")?,
+        })
+    }
+
+    pub fn reveal_message(&self) -> Result<SwapMessage, String> {
+        Ok(SwapMessage::Reveal {
+            secret_hash: self.terms.secret_hash.clone(),
+            secret: self.secret.clone().ok_or("Secret not known")?,
+        })
+    }
+
+    pub fn reveal(&mut self, secret: String) -> Result<(), String> {
+        if preimage_hash(&secret) != self.terms.secret_hash {
+            return Err("Secret does not match the swap hash".into());
+        }
+        self.secret = Some(secret);
+        if self.stage == SwapStage::Locked {
+            self.stage = SwapStage::Revealed;
+        }
+        Ok(())
+    }
+
+    fn settle(&mut self, branch: usize, payee: PublicKey) -> Result<&SigningSession, String> {
+        let note = self.lock_note.clone().ok_or("Swap lock not recorded")?;
+        let output = Output {
+            recipient: payee.0.clone(),
+            value: note.value,
+            lock: Lock::single(&payee),
+        };
+        let mut tx = Transaction::build(vec![note], vec![output], HashAlgorithm::default())?;
+        let seeds = &mut tx.spends[0].seeds;
+        seeds.branch = branch;
+        if branch == 0 {
+            seeds.preimages = vec![self.secret.clone().ok_or("Secret not known")?];
+        }
+        Ok(self.settlement.insert(SigningSession::new(tx)))
+    }
+
+    pub fn build_claim(&mut self) -> Result<&SigningSession, String> {
+        if self.we_send_nock() {
+            return Err("Only the nockchain recipient can claim".into());
+        }
+        let payee = self.terms.nock_recipient().clone();
+        self.settle(0, payee)
+    }
+
+    pub fn build_refund(&mut self, current_height: u64) -> Result<&SigningSession, String> {
+        if !self.we_send_nock() {
+            return Err("Only the nockchain sender can refund".into());
+        }
+        if current_height < self.terms.nock_timeout() {
+            return Err(format!(
+                "Refund not possible before height {}",
+                self.terms.nock_timeout()
+            ));
+        }
+        let payee = self.terms.nock_sender().clone();
+        self.settle(1, payee)
+    }
+
+    pub fn complete(&mut self) -> Result<(), String> {
+        let session = self.settlement.as_ref().ok_or("No settlement draft")?;
+        session.transaction.validate_signatures()?;
+        self.stage = if session.transaction.spends[0].seeds.branch == 0 {
+            SwapStage::Claimed
+        } else {
+            SwapStage::Refunded
+        };
+        Ok(())
+    }
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+fn parse_swap(swap_json: &str) -> Result<Swap, String> {
+    serde_json::from_str(swap_json).map_err(|e| e.to_string())
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, String> {
+    serde_json::to_string(value).map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn swap_offer(
+    initiator: &str,
+    participant: &str,
+    initiator_sends_nock: bool,
+    amount: u64,
+    counter_leg: &str,
+    secret_hex: &str,
+    current_height: u64,
+    claim_window: u64,
+    confirmations: u64,
+) -> Result<String, String> {
+    to_json(&Swap::offer(
+        PublicKey(initiator.to_string()),
+        PublicKey(participant.to_string()),
+        initiator_sends_nock,
+        amount,
+        counter_leg.to_string(),
+        secret_hex.to_string(),
+        current_height,
+        claim_window,
+        confirmations,
+    )?)
+}
+
+#[wasm_bindgen]
+pub fn swap_from_offer(message_json: &str, current_height: u64) -> Result<String, String> {
+    match serde_json::from_str(message_json).map_err(|e| e.to_string())? {
+        SwapMessage::Offer { terms } => to_json(&Swap::from_offer(terms, current_height)?),
+        _ => Err("Expected an offer message".into()),
+    }
+}
+
+// Returns `{"swap": ..., "message": ...}` so the caller can persist the swap
+// and forward the message in one step.
+#[wasm_bindgen]
+pub fn swap_outgoing_message(swap_json: &str) -> Result<String, String> {
+    let mut swap = parse_swap(swap_json)?;
+    let message = match (swap.role, swap.stage) {
+        (SwapRole::Initiator, SwapStage::Offered) => swap.offer_message(),
+        (SwapRole::Participant, SwapStage::Offered) => swap.accept()?,
+        (_, SwapStage::Locked) => swap.locked_message()?,
+        (_, SwapStage::Revealed) | (_, SwapStage::Claimed) => swap.reveal_message()?,
+        _ => return Err("Nothing to send at this stage".into()),
+    };
+    to_json(&serde_json::json!({ "swap": swap, "message": message }))
+}
+
+#[wasm_bindgen]
+pub fn swap_receive(swap_json: &str, message_json: &str) -> Result<String, String> {
+    let mut swap = parse_swap(swap_json)?;
+    let message: SwapMessage = serde_json::from_str(message_json).map_err(|e| e.to_string())?;
+    swap.receive(message)?;
+    to_json(&swap)
+}
+
+#[wasm_bindgen]
+pub fn swap_htlc_lock(swap_json: &str) -> Result<String, String> {
+    to_json(&parse_swap(swap_json)?.terms.htlc_lock())
+}
+
+#[wasm_bindgen]
+pub fn swap_build_lock(swap_json: &str, notes_json: &str) -> Result<String, String> {
+    let swap = parse_swap(swap_json)?;
+    let notes: Vec<Note> = serde_json::from_str(notes_json).map_err(|e| e.to_string())?;
+    to_json(&swap.build_lock(notes)?)
+}
+
+#[wasm_bindgen]
+pub fn swap_record_lock(swap_json: &str, note_json: &str) -> Result<String, String> {
+    let mut swap = parse_swap(swap_json)?;
+    let note: Note = serde_json::from_str(note_json).map_err(|e| e.to_string())?;
+    swap.record_lock(note)?;
+    to_json(&swap)
+}
+
+#[wasm_bindgen]
+pub fn swap_reveal(swap_json: &str, secret_hex: &str) -> Result<String, String> {
+    let mut swap = parse_swap(swap_json)?;
+    swap.reveal(secret_hex.to_string())?;
+    to_json(&swap)
+}
+
+#[wasm_bindgen]
+pub fn swap_build_claim(swap_json: &str) -> Result<String, String> {
+    let mut swap = parse_swap(swap_json)?;
+    swap.build_claim()?;
+    to_json(&swap)
+}
+
+#[wasm_bindgen]
+pub fn swap_build_refund(swap_json: &str, current_height: u64) -> Result<String, String> {
+    let mut swap = parse_swap(swap_json)?;
+    swap.build_refund(current_height)?;
+    to_json(&swap)
+}
+
+#[wasm_bindgen]
+pub fn swap_complete(swap_json: &str, session_json: &str) -> Result<String, String> {
+    let mut swap = parse_swap(swap_json)?;
+    let signed = SigningSession::from_json(session_json)?;
+    swap.settlement
+        .as_mut()
+        .ok_or("No settlement draft")?
+        .merge(&signed)?;
+    swap.complete()?;
+    to_json(&swap)
+}
+
+#[wasm_bindgen]
+pub fn swap_compute_timeouts(
+    current_height: u64,
+    claim_window: u64,
+    confirmations: u64,
+) -> Result<String, String> {
+    to_json(&SwapTimeouts::compute(
+        current_height,
+        claim_window,
+        confirmations,
+    ))
+}
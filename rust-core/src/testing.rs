@@ -0,0 +1,35 @@
+use crate::verify::SignatureVerifier;
+use crate::{build_transaction, PublicKey, Signature, Transaction};
+use sha2::{Digest, Sha256};
+
+// ============================================================================
+// Test Helpers
+// ============================================================================
+
+// Signature = sha256(pubkey || message), enough to tell forged from real.
+pub struct HashVerifier;
+
+impl HashVerifier {
+    pub fn sign(pubkey: &PublicKey, message_hash: &str) -> Signature {
+        let mut hasher = Sha256::new();
+        hasher.update(pubkey.0.as_bytes());
+        hasher.update(message_hash.as_bytes());
+        Signature(hex::encode(hasher.finalize()))
+    }
+}
+
+impl SignatureVerifier for HashVerifier {
+    fn verify(&self, pubkey: &PublicKey, message_hash: &str, signature: &Signature) -> bool {
+        Self::sign(pubkey, message_hash) == *signature
+    }
+}
+
+pub const MULTISIG_LOCK: &str = r#"{"pkh":{"threshold":2,"pubkeys":["a","b"]}}"#;
+
+// Unsigned draft spending one note of 5 under `lock` (JSON) to "r".
+pub fn draft(lock: &str) -> Transaction {
+    let notes = format!(r#"[{{"name":{{"first":"n0","last":"l"}},"value":5,"lock":{lock}}}]"#);
+    let outputs = r#"[{"recipient":"r","value":5,"lock":{"pkh":{"threshold":1,"pubkeys":["r"]}}}]"#;
+    serde_json::from_str(&build_transaction(&notes, outputs).expect("build failed"))
+        .expect("parse failed")
+}
@@ -0,0 +1,243 @@
+use crate::checkpoint::Checkpoint;
+use crate::{Note, NoteName};
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Note Tracking
+// ============================================================================
+
+// Follows each of a wallet's notes through its lifecycle as the node reports
+// it:
+//
+//   unconfirmed -> confirmed(height) -> spent(txid) -> ...
+//        ^              |
+//        +-- reorged_out <-- (block dropped by a reorg)
+//
+// A reorg below a note's confirmation height takes the note out of the
+// wallet; a reorg below its spend height returns it to the spendable set.
+// A reorged-out note that is mined again simply confirms again.
+//
+// A tracker started from a checkpoint takes its history up to the
+// checkpoint on trust, so scanning begins just above it and reorgs below it
+// are refused.
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case", deny_unknown_fields)]
+pub enum NoteState {
+    Unconfirmed,
+    Confirmed {
+        height: u64,
+    },
+    // `height` is None while the spend is in the mempool.
+    Spent {
+        txid: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        height: Option<u64>,
+    },
+    ReorgedOut,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackedNote {
+    pub note: Note,
+    #[serde(flatten)]
+    pub state: NoteState,
+}
+
+impl TrackedNote {
+    // Still exists and is not spent, whether or not it has confirmed.
+    pub fn is_live(&self) -> bool {
+        matches!(
+            self.state,
+            NoteState::Unconfirmed | NoteState::Confirmed { .. }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ChainEvent {
+    // The note was seen in the mempool or a block; `confirmed_at` on the
+    // note confirms it immediately.
+    Seen {
+        note: Note,
+    },
+    Confirmed {
+        name: NoteName,
+        height: u64,
+    },
+    Spent {
+        name: NoteName,
+        txid: String,
+        #[serde(default)]
+        height: Option<u64>,
+    },
+    // Blocks above `fork_height` were dropped.
+    Reorg {
+        fork_height: u64,
+    },
+    Tip {
+        height: u64,
+    },
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NoteTracker {
+    pub tip: u64,
+    pub notes: Vec<TrackedNote>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checkpoint: Option<Checkpoint>,
+}
+
+impl NoteTracker {
+    pub fn is_empty(&self) -> bool {
+        self.tip == 0 && self.notes.is_empty() && self.checkpoint.is_none()
+    }
+
+    // The checkpoint must be verified by the caller. Returns the first height
+    // to scan.
+    pub fn start_from(&mut self, checkpoint: Checkpoint) -> Result<u64, String> {
+        if self.tip > checkpoint.height {
+            return Err(format!(
+                "Already synced to {}, past the checkpoint at {}",
+                self.tip, checkpoint.height
+            ));
+        }
+        self.tip = checkpoint.height;
+        self.checkpoint = Some(checkpoint);
+        Ok(self.scan_from())
+    }
+
+    pub fn scan_from(&self) -> u64 {
+        self.checkpoint.as_ref().map_or(0, |c| c.height + 1)
+    }
+
+    pub fn get(&self, name: &NoteName) -> Option<&TrackedNote> {
+        self.notes.iter().find(|t| &t.note.name == name)
+    }
+
+    fn get_mut(&mut self, name: &NoteName) -> Result<&mut TrackedNote, String> {
+        self.notes
+            .iter_mut()
+            .find(|t| &t.note.name == name)
+            .ok_or_else(|| format!("Note {}/{} is not tracked", name.first, name.last))
+    }
+
+    pub fn apply(&mut self, event: ChainEvent) -> Result<(), String> {
+        match event {
+            ChainEvent::Seen { note } => {
+                let state = match note.confirmed_at {
+                    Some(height) => {
+                        self.tip = self.tip.max(height);
+                        NoteState::Confirmed { height }
+                    }
+                    None => NoteState::Unconfirmed,
+                };
+                match self.notes.iter_mut().find(|t| t.note.name == note.name) {
+                    Some(tracked)
+                        if tracked.is_live() || tracked.state == NoteState::ReorgedOut =>
+                    {
+                        tracked.note = note;
+                        tracked.state = state;
+                    }
+                    Some(_) => return Err("Note is already spent".into()),
+                    None => self.notes.push(TrackedNote { note, state }),
+                }
+            }
+            ChainEvent::Confirmed { name, height } => {
+                self.tip = self.tip.max(height);
+                let tracked = self.get_mut(&name)?;
+                match tracked.state {
+                    NoteState::Spent { .. } => {}
+                    _ => tracked.state = NoteState::Confirmed { height },
+                }
+                tracked.note.confirmed_at = Some(height);
+            }
+            ChainEvent::Spent { name, txid, height } => {
+                if let Some(height) = height {
+                    self.tip = self.tip.max(height);
+                }
+                let tracked = self.get_mut(&name)?;
+                if tracked.state == NoteState::ReorgedOut {
+                    return Err("Note no longer exists".into());
+                }
+                tracked.state = NoteState::Spent { txid, height };
+            }
+            ChainEvent::Reorg { fork_height } => {
+                if let Some(checkpoint) = &self.checkpoint {
+                    if fork_height < checkpoint.height {
+                        return Err(format!(
+                            "Reorg to {} goes below the checkpoint at {}",
+                            fork_height, checkpoint.height
+                        ));
+                    }
+                }
+                self.tip = fork_height;
+                for tracked in &mut self.notes {
+                    let orphaned = tracked.note.confirmed_at.is_some_and(|h| h > fork_height);
+                    if orphaned {
+                        tracked.note.confirmed_at = None;
+                        tracked.state = NoteState::ReorgedOut;
+                        continue;
+                    }
+                    if let NoteState::Spent {
+                        height: Some(spent_at),
+                        ..
+                    } = tracked.state
+                    {
+                        if spent_at > fork_height {
+                            tracked.state = match tracked.note.confirmed_at {
+                                Some(height) => NoteState::Confirmed { height },
+                                None => NoteState::Unconfirmed,
+                            };
+                        }
+                    }
+                }
+            }
+            ChainEvent::Tip { height } => self.tip = height,
+        }
+        Ok(())
+    }
+
+    // Confirmed notes that pass the maturity checks at the tip.
+    pub fn spendable(&self) -> Vec<&Note> {
+        self.notes
+            .iter()
+            .filter(|t| matches!(t.state, NoteState::Confirmed { .. }))
+            .map(|t| &t.note)
+            .filter(|n| n.spendable_at(self.tip).is_ok())
+            .collect()
+    }
+
+    // A draft is invalid once a note it spends was reorged out or spent by
+    // another transaction. Untracked notes are given the benefit of the
+    // doubt.
+    pub fn draft_is_valid(&self, tx: &crate::Transaction) -> bool {
+        let txid = tx.txid().ok();
+        tx.spends
+            .iter()
+            .all(|spend| match self.get(&spend.note.name).map(|t| &t.state) {
+                Some(NoteState::ReorgedOut) => false,
+                Some(NoteState::Spent { txid: spent_by, .. }) => txid.as_ref() == Some(spent_by),
+                _ => true,
+            })
+    }
+
+    // The notes of `tx` the node reports spent by some other transaction,
+    // with that transaction's txid.
+    pub fn competing_spends(&self, tx: &crate::Transaction) -> Vec<(NoteName, String)> {
+        let txid = tx.txid().ok();
+        tx.spends
+            .iter()
+            .filter_map(|spend| match self.get(&spend.note.name).map(|t| &t.state) {
+                Some(NoteState::Spent { txid: spent_by, .. })
+                    if txid.as_ref() != Some(spent_by) =>
+                {
+                    Some((spend.note.name.clone(), spent_by.clone()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
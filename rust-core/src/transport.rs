@@ -0,0 +1,320 @@
+use crate::schema::{negotiate, TRANSACTION_SCHEMA_VERSION};
+use crate::session::SigningSession;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Peer Transports
+// ============================================================================
+
+// Anything that moves strings between two cosigners: a WebRTC data channel,
+// a Bluetooth characteristic, an in-memory pipe. Sessions merge
+// commutatively, so peers only ever exchange whole sessions and fold in
+// what they receive; no ordering or delivery guarantees are needed beyond
+// messages arriving intact.
+pub trait Transport {
+    fn send(&mut self, message: String) -> Result<(), String>;
+    fn try_recv(&mut self) -> Option<String>;
+}
+
+// Transport whose I/O is done by the host: the host feeds received data in
+// with `push_incoming` and ships whatever `take_outgoing` returns. This is
+// how the WASM build talks to browser APIs without binding them.
+#[derive(Debug, Default)]
+pub struct QueuedTransport {
+    incoming: VecDeque<String>,
+    outgoing: VecDeque<String>,
+}
+
+impl QueuedTransport {
+    pub fn push_incoming(&mut self, message: String) {
+        self.incoming.push_back(message);
+    }
+
+    pub fn take_outgoing(&mut self) -> Vec<String> {
+        self.outgoing.drain(..).collect()
+    }
+}
+
+impl Transport for QueuedTransport {
+    fn send(&mut self, message: String) -> Result<(), String> {
+        self.outgoing.push_back(message);
+        Ok(())
+    }
+
+    fn try_recv(&mut self) -> Option<String> {
+        self.incoming.pop_front()
+    }
+}
+
+// Connected pair for native use and local tooling.
+#[derive(Debug, Default)]
+pub struct MemoryTransport {
+    inbox: Rc<RefCell<VecDeque<String>>>,
+    peer_inbox: Rc<RefCell<VecDeque<String>>>,
+}
+
+impl MemoryTransport {
+    pub fn pair() -> (Self, Self) {
+        let a = Rc::default();
+        let b = Rc::default();
+        (
+            Self {
+                inbox: Rc::clone(&a),
+                peer_inbox: Rc::clone(&b),
+            },
+            Self {
+                inbox: b,
+                peer_inbox: a,
+            },
+        )
+    }
+}
+
+impl Transport for MemoryTransport {
+    fn send(&mut self, message: String) -> Result<(), String> {
+        self.peer_inbox.borrow_mut().push_back(message);
+        Ok(())
+    }
+
+    fn try_recv(&mut self) -> Option<String> {
+        self.inbox.borrow_mut().pop_front()
+    }
+}
+
+// ============================================================================
+// Session Sync
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum PeerMessage {
+    Session {
+        // The newest transaction schema the sender reads; absent from peers
+        // older than the field, which read only version 1.
+        #[serde(default = "first_schema")]
+        schema_version: u32,
+        session: SigningSession,
+    },
+}
+
+fn first_schema() -> u32 {
+    1
+}
+
+#[derive(Debug)]
+pub struct PeerSync<T: Transport> {
+    pub session: SigningSession,
+    pub transport: T,
+    // What the peer said it reads, once it has said anything.
+    pub peer_schema: Option<u32>,
+}
+
+impl<T: Transport> PeerSync<T> {
+    pub fn new(session: SigningSession, transport: T) -> Self {
+        Self {
+            session,
+            transport,
+            peer_schema: None,
+        }
+    }
+
+    fn send_session(&mut self) -> Result<(), String> {
+        let version = self.session.transaction.schema_version.get();
+        if let Some(theirs) = self.peer_schema {
+            if negotiate(theirs)? < version {
+                return Err(format!(
+                    "Peer reads transaction schema up to {}, this draft uses {}; \
+                     the peer must update",
+                    theirs, version
+                ));
+            }
+        }
+        let message = PeerMessage::Session {
+            schema_version: TRANSACTION_SCHEMA_VERSION,
+            session: self.session.clone(),
+        };
+        self.transport
+            .send(serde_json::to_string(&message).map_err(|e| e.to_string())?)
+    }
+
+    // Call once the channel opens.
+    pub fn announce(&mut self) -> Result<(), String> {
+        self.send_session()
+    }
+
+    // Merges everything received so far and answers when the peer is behind,
+    // so two peers converge after a few messages and then go quiet. Returns
+    // how many signatures were learned.
+    pub fn poll(&mut self) -> Result<usize, String> {
+        let mut learned = 0;
+        while let Some(raw) = self.transport.try_recv() {
+            let PeerMessage::Session {
+                schema_version,
+                session: theirs,
+            } = serde_json::from_str(&raw).map_err(|e| e.to_string())?;
+            self.peer_schema = Some(schema_version);
+            if theirs.draft_hash() != self.session.draft_hash() {
+                return Err("Peer is signing a different draft".into());
+            }
+            learned += self.session.merge(&theirs)?;
+
+            let mut peer_view = theirs;
+            if peer_view.merge(&self.session)? > 0 {
+                self.send_session()?;
+            }
+        }
+        Ok(learned)
+    }
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+// WebRTC glue lives in JS; this object only speaks strings:
+//
+//   channel.onopen = () => { peer.announce(); flush(); };
+//   channel.onmessage = (e) => { peer.receive(e.data); flush(); };
+//   const flush = () => peer.take_outgoing().forEach((m) => channel.send(m));
+#[wasm_bindgen]
+pub struct WebRtcPeer {
+    sync: PeerSync<QueuedTransport>,
+}
+
+#[wasm_bindgen]
+impl WebRtcPeer {
+    #[wasm_bindgen(constructor)]
+    pub fn new(session_json: &str) -> Result<WebRtcPeer, String> {
+        Ok(Self {
+            sync: PeerSync::new(
+                SigningSession::from_json(session_json)?,
+                QueuedTransport::default(),
+            ),
+        })
+    }
+
+    pub fn announce(&mut self) -> Result<(), String> {
+        self.sync.announce()
+    }
+
+    // Returns the number of signatures learned from this message.
+    pub fn receive(&mut self, data: &str) -> Result<usize, String> {
+        self.sync.transport.push_incoming(data.to_string());
+        self.sync.poll()
+    }
+
+    pub fn take_outgoing(&mut self) -> Vec<String> {
+        self.sync.transport.take_outgoing()
+    }
+
+    pub fn session(&self) -> Result<String, String> {
+        self.sync.session.to_json()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{draft, MULTISIG_LOCK};
+    use crate::{PublicKey, Signature};
+
+    fn session() -> SigningSession {
+        SigningSession::new(draft(MULTISIG_LOCK))
+    }
+
+    fn signed_by(signer: &str) -> SigningSession {
+        let mut session = session();
+        session
+            .add_signature(
+                0,
+                PublicKey(signer.into()),
+                Signature(format!("sig-{signer}")),
+                None,
+            )
+            .unwrap();
+        session
+    }
+
+    fn message(session: &SigningSession) -> String {
+        serde_json::to_string(&PeerMessage::Session {
+            schema_version: TRANSACTION_SCHEMA_VERSION,
+            session: session.clone(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn peers_converge_and_go_quiet() {
+        let (left, right) = MemoryTransport::pair();
+        let mut alice = PeerSync::new(signed_by("a"), left);
+        let mut bob = PeerSync::new(signed_by("b"), right);
+        alice.announce().unwrap();
+        bob.announce().unwrap();
+        assert_eq!(alice.poll().unwrap(), 1);
+        assert_eq!(bob.poll().unwrap(), 1);
+        // Both were already up to date, so nothing else is in flight.
+        assert_eq!(alice.poll().unwrap(), 0);
+        assert_eq!(bob.poll().unwrap(), 0);
+        assert!(alice.transport.try_recv().is_none());
+        for session in [&alice.session, &bob.session] {
+            assert_eq!(session.transaction.spends[0].seeds.signatures.len(), 2);
+        }
+        assert_eq!(alice.peer_schema, Some(TRANSACTION_SCHEMA_VERSION));
+    }
+
+    #[test]
+    fn webrtc_peer_answers_a_peer_that_is_behind() {
+        let mut peer = WebRtcPeer::new(&signed_by("a").to_json().unwrap()).unwrap();
+        assert_eq!(peer.receive(&message(&session())).unwrap(), 0);
+        let outgoing = peer.take_outgoing();
+        assert_eq!(outgoing.len(), 1);
+        let PeerMessage::Session { session: sent, .. } =
+            serde_json::from_str(&outgoing[0]).unwrap();
+        assert_eq!(sent.transaction.spends[0].seeds.signatures.len(), 1);
+    }
+
+    #[test]
+    fn rejects_malformed_messages() {
+        let mut peer = WebRtcPeer::new(&session().to_json().unwrap()).unwrap();
+        assert!(peer.receive("not json").is_err());
+        assert!(peer.receive(r#"{"type":"hello"}"#).is_err());
+        assert!(peer.receive(r#"{"type":"session"}"#).is_err());
+        let mut extra: serde_json::Value = serde_json::from_str(&message(&session())).unwrap();
+        extra["unexpected"] = true.into();
+        assert!(peer.receive(&extra.to_string()).is_err());
+        assert!(peer.take_outgoing().is_empty());
+    }
+
+    #[test]
+    fn rejects_a_different_draft() {
+        let mut other = session();
+        other.transaction.outputs[0].recipient = "mallory".into();
+        other.transaction.refresh_spend_hashes().unwrap();
+        let mut peer = WebRtcPeer::new(&signed_by("a").to_json().unwrap()).unwrap();
+        let err = peer.receive(&message(&other)).unwrap_err();
+        assert_eq!(err, "Peer is signing a different draft");
+        assert_eq!(
+            peer.sync.session.transaction.spends[0]
+                .seeds
+                .signatures
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn refuses_to_answer_a_peer_with_an_unknown_schema() {
+        let mut peer = WebRtcPeer::new(&signed_by("a").to_json().unwrap()).unwrap();
+        let raw = serde_json::to_string(&PeerMessage::Session {
+            schema_version: 0,
+            session: session(),
+        })
+        .unwrap();
+        assert!(peer.receive(&raw).is_err());
+        assert!(peer.take_outgoing().is_empty());
+    }
+}
@@ -0,0 +1,244 @@
+use crate::build::{BuildOptions, ChangeOptions};
+use crate::hashing::HashAlgorithm;
+use crate::session::SigningSession;
+use crate::storage::{validate_segment, Notification, NotificationEvent, Storage, Tenant};
+use crate::{Note, Output, PublicKey, Transaction};
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Tiered Treasury
+// ============================================================================
+
+// Keeps a low-threshold "hot" wallet stocked from a high-threshold "cold"
+// one. When the hot wallet's balance falls below `floor`, the coordinator
+// drafts a transfer from the cold wallet bringing it back to `target` and
+// puts it into the cold wallet's signing flow, so the cold keys still approve
+// every top-up. Only one top-up is open at a time, and `cooldown` keeps the
+// next attempt from firing before the last top-up has had time to reach the
+// hot wallet (or, after a failure, before anyone could have fixed it).
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TreasuryTier {
+    pub id: String,
+    pub hot: String,
+    pub cold: String,
+    pub floor: u64,
+    pub target: u64,
+    // Largest single top-up, so one draft can never empty the cold wallet.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_top_up: Option<u64>,
+    // Seconds after an attempt before the next one.
+    #[serde(default)]
+    pub cooldown: u64,
+    // Cold signers who approved the standing instruction.
+    pub approved_by: Vec<PublicKey>,
+    #[serde(default)]
+    pub paused: bool,
+    // Draft hash of the open top-up, while the cold wallet still holds it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pending: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_run: Option<u64>,
+}
+
+fn treasury_key(id: &str) -> Result<String, String> {
+    validate_segment("treasury", id)?;
+    Ok(format!("treasuries/{}", id))
+}
+
+// Largest notes first, until `amount` is covered.
+fn select_notes(mut notes: Vec<Note>, amount: u64) -> Result<Vec<Note>, String> {
+    notes.sort_by_key(|n| std::cmp::Reverse(n.value));
+    let mut selected = Vec::new();
+    let mut total = 0u64;
+    for note in notes {
+        if total >= amount {
+            break;
+        }
+        total = total.saturating_add(note.value);
+        selected.push(note);
+    }
+    if total < amount {
+        return Err(format!(
+            "Cold wallet has {} free, top-up needs {}",
+            total, amount
+        ));
+    }
+    Ok(selected)
+}
+
+impl TreasuryTier {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.hot == self.cold {
+            return Err("Hot and cold wallets must differ".into());
+        }
+        if self.floor >= self.target {
+            return Err("Floor must be below the target balance".into());
+        }
+        if self.max_top_up == Some(0) {
+            return Err("Maximum top-up must be positive".into());
+        }
+        if self.approved_by.is_empty() {
+            return Err("A treasury tier needs at least one approval".into());
+        }
+        Ok(())
+    }
+
+    // How much to move given the hot wallet's balance, if anything.
+    pub fn top_up_amount(&self, hot_balance: u64) -> Option<u64> {
+        if hot_balance >= self.floor {
+            return None;
+        }
+        let amount = self.target - hot_balance;
+        Some(self.max_top_up.map_or(amount, |max| amount.min(max)))
+    }
+
+    fn is_cooling_down(&self, now: u64) -> bool {
+        self.last_run
+            .is_some_and(|at| now < at.saturating_add(self.cooldown))
+    }
+}
+
+impl<S: Storage> Tenant<'_, S> {
+    // The hot wallet must have a lower primary threshold than the cold one,
+    // and every approver must be a cold signer.
+    pub fn create_treasury(&mut self, tier: TreasuryTier) -> Result<TreasuryTier, String> {
+        tier.validate()?;
+        let hot = self.wallet(&tier.hot)?;
+        let cold = self.wallet(&tier.cold)?;
+        if hot.descriptor.lock.pkh.threshold >= cold.descriptor.lock.pkh.threshold {
+            return Err("Hot wallet must need fewer signatures than the cold wallet".into());
+        }
+        let participants = cold.descriptor.participants();
+        if let Some(pk) = tier
+            .approved_by
+            .iter()
+            .find(|pk| !participants.contains(pk))
+        {
+            return Err(format!("{} is not a signer on wallet {}", pk.0, tier.cold));
+        }
+        let tier = TreasuryTier {
+            pending: None,
+            last_run: None,
+            ..tier
+        };
+        self.put_treasury(&tier)?;
+        Ok(tier)
+    }
+
+    pub fn treasury(&self, id: &str) -> Result<TreasuryTier, String> {
+        self.load(&treasury_key(id)?)?
+            .ok_or_else(|| format!("Unknown treasury tier {}", id))
+    }
+
+    pub fn treasuries(&self) -> Result<Vec<TreasuryTier>, String> {
+        self.list("treasuries")?
+            .iter()
+            .map(|id| self.treasury(id))
+            .collect()
+    }
+
+    pub fn put_treasury(&mut self, tier: &TreasuryTier) -> Result<(), String> {
+        self.store(&treasury_key(&tier.id)?, tier)
+    }
+
+    pub fn delete_treasury(&mut self, id: &str) -> Result<bool, String> {
+        self.remove(&treasury_key(id)?)
+    }
+
+    fn draft_top_up(
+        &mut self,
+        tier: &TreasuryTier,
+        amount: u64,
+        now: u64,
+    ) -> Result<NotificationEvent, String> {
+        let hot = self.wallet(&tier.hot)?;
+        let cold = self.wallet(&tier.cold)?;
+        if hot.archived || cold.archived {
+            return Err(format!(
+                "Wallet {} is archived",
+                if hot.archived { &hot.id } else { &cold.id }
+            ));
+        }
+        let notes = select_notes(cold.free_notes(), amount)?;
+        let output = Output {
+            recipient: hot.id.clone(),
+            value: amount,
+            lock: hot.descriptor.lock.clone(),
+        };
+        let options = BuildOptions {
+            change: Some(ChangeOptions {
+                recipient: cold.id.clone(),
+                lock: cold.descriptor.lock.clone(),
+                randomize_position: false,
+                parts: 1,
+            }),
+            ..BuildOptions::default()
+        };
+        let report =
+            Transaction::build_with(notes, vec![output], HashAlgorithm::default(), &options)?;
+        let session = SigningSession::new(report.transaction);
+        let draft = session.draft_hash();
+        let session = self.add_session(&tier.cold, session, now)?;
+        Ok(NotificationEvent::TopUpDrafted {
+            treasury: tier.id.clone(),
+            session,
+            draft,
+            amount,
+        })
+    }
+
+    // Checks every tier and drafts the top-ups that are due. Returns the
+    // notifications, which are also queued for `take_notifications`.
+    pub fn run_treasuries(&mut self, now: u64) -> Result<Vec<Notification>, String> {
+        let mut notifications = Vec::new();
+        for mut tier in self.treasuries()? {
+            if tier.paused {
+                continue;
+            }
+            if let Some(draft) = &tier.pending {
+                let open = self
+                    .wallet(&tier.cold)?
+                    .sessions
+                    .iter()
+                    .any(|s| &s.draft_hash() == draft);
+                if open {
+                    continue;
+                }
+                tier.pending = None;
+                self.put_treasury(&tier)?;
+            }
+            if tier.is_cooling_down(now) {
+                continue;
+            }
+            let Some(amount) = tier.top_up_amount(self.wallet(&tier.hot)?.balance()) else {
+                continue;
+            };
+
+            let event = self
+                .draft_top_up(&tier, amount, now)
+                .unwrap_or_else(|error| NotificationEvent::TopUpFailed {
+                    treasury: tier.id.clone(),
+                    error,
+                });
+            let notify = match &event {
+                NotificationEvent::TopUpDrafted { draft, .. } => {
+                    tier.pending = Some(draft.clone());
+                    self.wallet(&tier.cold)?.descriptor.participants()
+                }
+                _ => tier.approved_by.clone(),
+            };
+            tier.last_run = Some(now);
+            self.put_treasury(&tier)?;
+            notifications.push(Notification {
+                at: now,
+                wallet: tier.cold.clone(),
+                notify,
+                event,
+            });
+        }
+        self.notify(&notifications)?;
+        Ok(notifications)
+    }
+}
@@ -0,0 +1,148 @@
+use crate::crypto::{open, seal, Sealed, SecretKey};
+use crate::rng::RandomSource;
+use crate::{Lock, Note};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// View Keys
+// ============================================================================
+
+// A view key lets someone outside the signer set (an accountant, an auditor)
+// read a treasury's notes and amounts. The coordinator seals each note it
+// tracks under the key and publishes the records; holders of the key scan
+// them. It carries no signing material, so it grants no spend rights, and
+// records name only the key id, not the wallet.
+//
+//   nmv1.<lock fingerprint>.<key hex>
+
+const CREDENTIAL_PREFIX: &str = "nmv1";
+
+#[derive(Debug, Clone)]
+pub struct ViewKey {
+    pub wallet: String,
+    key: SecretKey,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ViewRecord {
+    pub sealed: Sealed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScanReport {
+    pub wallet: String,
+    pub notes: Vec<Note>,
+    pub balance: u64,
+    // Records sealed under other keys.
+    pub skipped: usize,
+}
+
+impl ViewKey {
+    pub fn new(lock: &Lock, key: SecretKey) -> Self {
+        Self {
+            wallet: lock.fingerprint(),
+            key,
+        }
+    }
+
+    pub fn generate(lock: &Lock, rng: &mut dyn RandomSource) -> Result<Self, String> {
+        Ok(Self::new(lock, SecretKey::generate(rng)?))
+    }
+
+    pub fn encode(&self) -> String {
+        format!(
+            "{}.{}.{}",
+            CREDENTIAL_PREFIX,
+            self.wallet,
+            self.key.to_hex()
+        )
+    }
+
+    pub fn decode(credential: &str) -> Result<Self, String> {
+        let mut parts = credential.trim().splitn(3, '.');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(CREDENTIAL_PREFIX), Some(wallet), Some(key)) => Ok(Self {
+                wallet: wallet.to_string(),
+                key: SecretKey::from_hex(key)?,
+            }),
+            (Some(prefix), Some(_), Some(_)) => {
+                Err(format!("Unsupported view key version '{}'", prefix))
+            }
+            _ => Err("Malformed view key".into()),
+        }
+    }
+
+    pub fn seal_note(&self, note: &Note) -> Result<ViewRecord, String> {
+        if note.lock.fingerprint() != self.wallet {
+            return Err(format!(
+                "Note {}/{} is not held by this wallet",
+                note.name.first, note.name.last
+            ));
+        }
+        let bytes = serde_json::to_vec(note).map_err(|e| e.to_string())?;
+        Ok(ViewRecord {
+            sealed: seal(&self.key, &bytes, self.wallet.as_bytes()),
+        })
+    }
+
+    pub fn scan(&self, records: &[ViewRecord]) -> Result<ScanReport, String> {
+        let key_id = self.key.id();
+        let mut notes = Vec::new();
+        let mut skipped = 0;
+        for record in records {
+            if record.sealed.key_id != key_id {
+                skipped += 1;
+                continue;
+            }
+            let bytes = open(&self.key, &record.sealed, self.wallet.as_bytes())?;
+            let note: Note = serde_json::from_slice(&bytes).map_err(|e| e.to_string())?;
+            if note.lock.fingerprint() != self.wallet {
+                return Err("View record holds a note from another wallet".into());
+            }
+            if !notes.iter().any(|n: &Note| n.name == note.name) {
+                notes.push(note);
+            }
+        }
+        Ok(ScanReport {
+            wallet: self.wallet.clone(),
+            balance: notes.iter().map(|n| n.value).sum(),
+            notes,
+            skipped,
+        })
+    }
+}
+
+// ============================================================================
+// WASM Interface
+// ============================================================================
+
+// `key_hex` is 32 random bytes from the caller, e.g. crypto.getRandomValues.
+#[wasm_bindgen]
+pub fn create_view_key(lock_json: &str, key_hex: &str) -> Result<String, String> {
+    let lock: Lock = serde_json::from_str(lock_json).map_err(|e| e.to_string())?;
+    Ok(ViewKey::new(&lock, SecretKey::from_hex(key_hex)?).encode())
+}
+
+#[wasm_bindgen]
+pub fn seal_notes_for_view(view_key: &str, notes_json: &str) -> Result<String, String> {
+    let view_key = ViewKey::decode(view_key)?;
+    let notes: Vec<Note> = serde_json::from_str(notes_json).map_err(|e| e.to_string())?;
+
+    let records = notes
+        .iter()
+        .map(|n| view_key.seal_note(n))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    serde_json::to_string(&records).map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+pub fn scan_view_records(view_key: &str, records_json: &str) -> Result<String, String> {
+    let view_key = ViewKey::decode(view_key)?;
+    let records: Vec<ViewRecord> = serde_json::from_str(records_json).map_err(|e| e.to_string())?;
+
+    serde_json::to_string(&view_key.scan(&records)?).map_err(|e| e.to_string())
+}
@@ -0,0 +1,160 @@
+use crate::crypto::{open, seal, Sealed, SecretKey};
+use crate::hashing::{constant_time_eq, hmac_sha256};
+use crate::identity::{self, IdentityAttestation, IdentityCheck};
+use crate::metadata::WalletMetadata;
+use crate::policy::PolicySet;
+use crate::verify::SignatureVerifier;
+use crate::wallets::{Wallet, WalletDescriptor};
+use crate::PublicKey;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Wallet Backups
+// ============================================================================
+
+// Disaster recovery for one wallet's coordination state: the descriptor,
+// who its participants are and any identity attestations binding their
+// keys, its policies, and its labels and contacts.
+// Notes are not included; a restored wallet rescans for them. The file
+// names its own kind and format so it can be recognised on its own.
+//
+// Every file carries an HMAC. Without a passphrase the MAC key is public,
+// so it catches corruption and truncation but not deliberate edits; with a
+// passphrase the backup is sealed (ChaCha20, HMAC-SHA256 tag) under a PBKDF2
+// key and both are covered. The salt is supplied by the caller since the
+// crate has no randomness of its own.
+
+pub const WALLET_BACKUP_KIND: &str = "nockchain-multisig/wallet-backup";
+pub const WALLET_BACKUP_FORMAT_VERSION: u32 = 1;
+pub const WALLET_BACKUP_KDF_ITERATIONS: u32 = 100_000;
+const WALLET_BACKUP_DOMAIN: &[u8] = b"nockchain-multisig/wallet-backup/v1";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WalletBackup {
+    pub kind: String,
+    pub format: u32,
+    pub wallet_id: String,
+    pub descriptor: WalletDescriptor,
+    // Every key that can sign on any branch of the lock, for display.
+    pub participants: Vec<PublicKey>,
+    #[serde(default)]
+    pub policies: PolicySet,
+    #[serde(default, skip_serializing_if = "WalletMetadata::is_empty")]
+    pub metadata: WalletMetadata,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub identities: Vec<IdentityAttestation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "encoding", rename_all = "snake_case", deny_unknown_fields)]
+pub enum WalletBackupFile {
+    Plain {
+        backup: Box<WalletBackup>,
+        mac: String,
+    },
+    Encrypted {
+        kind: String,
+        salt: String,
+        iterations: u32,
+        sealed: Sealed,
+    },
+}
+
+fn plain_mac(backup: &WalletBackup) -> Result<[u8; 32], String> {
+    let json = serde_json::to_vec(backup).map_err(|e| e.to_string())?;
+    Ok(hmac_sha256(WALLET_BACKUP_DOMAIN, &json))
+}
+
+impl WalletBackup {
+    pub fn from_wallet(wallet: &Wallet) -> Self {
+        Self {
+            kind: WALLET_BACKUP_KIND.into(),
+            format: WALLET_BACKUP_FORMAT_VERSION,
+            wallet_id: wallet.id.clone(),
+            descriptor: wallet.descriptor.clone(),
+            participants: wallet.descriptor.participants(),
+            policies: wallet.policies.clone(),
+            metadata: wallet.metadata.clone(),
+            identities: wallet.identities.clone(),
+        }
+    }
+
+    pub fn to_file(&self, passphrase: Option<(&str, &[u8])>) -> Result<WalletBackupFile, String> {
+        let Some((passphrase, salt)) = passphrase else {
+            return Ok(WalletBackupFile::Plain {
+                backup: Box::new(self.clone()),
+                mac: hex::encode(plain_mac(self)?),
+            });
+        };
+        if salt.len() < 16 {
+            return Err("Backup salt must be at least 16 random bytes".into());
+        }
+        let key = SecretKey::from_passphrase(passphrase, salt, WALLET_BACKUP_KDF_ITERATIONS);
+        let json = serde_json::to_vec(self).map_err(|e| e.to_string())?;
+        Ok(WalletBackupFile::Encrypted {
+            kind: WALLET_BACKUP_KIND.into(),
+            salt: hex::encode(salt),
+            iterations: WALLET_BACKUP_KDF_ITERATIONS,
+            sealed: seal(&key, &json, WALLET_BACKUP_DOMAIN),
+        })
+    }
+
+    pub fn from_file(file: WalletBackupFile, passphrase: Option<&str>) -> Result<Self, String> {
+        let backup = match file {
+            WalletBackupFile::Plain { backup, mac } => {
+                let mac = hex::decode(mac).map_err(|_| "Backup MAC must be hex")?;
+                if !constant_time_eq(&mac, &plain_mac(&backup)?) {
+                    return Err("Backup is corrupt: MAC does not match".into());
+                }
+                *backup
+            }
+            WalletBackupFile::Encrypted {
+                salt,
+                iterations,
+                sealed,
+                ..
+            } => {
+                let passphrase =
+                    passphrase.ok_or("Backup is encrypted; a passphrase is required")?;
+                let salt = hex::decode(salt).map_err(|e| e.to_string())?;
+                let key = SecretKey::from_passphrase(passphrase, &salt, iterations);
+                let json = open(&key, &sealed, WALLET_BACKUP_DOMAIN)?;
+                serde_json::from_slice(&json).map_err(|e| e.to_string())?
+            }
+        };
+        backup.validate()?;
+        Ok(backup)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.kind != WALLET_BACKUP_KIND {
+            return Err(format!("Not a wallet backup ({})", self.kind));
+        }
+        if self.format > WALLET_BACKUP_FORMAT_VERSION {
+            return Err(format!("Unsupported wallet backup format {}", self.format));
+        }
+        if self.descriptor.id() != self.wallet_id {
+            return Err("Wallet id does not match descriptor".into());
+        }
+        Ok(())
+    }
+
+    // Run before restoring: every attestation must be about a participant
+    // and check out against `verifier`.
+    pub fn verify_identities(
+        &self,
+        verifier: &dyn SignatureVerifier,
+    ) -> Result<Vec<IdentityCheck>, String> {
+        let participants = self.descriptor.participants();
+        identity::verify_identities(&self.identities, &participants, verifier)
+    }
+
+    pub fn into_wallet(self) -> Result<Wallet, String> {
+        let mut wallet = Wallet::new(self.descriptor)?;
+        wallet.policies = self.policies;
+        wallet.merge_metadata(&self.metadata);
+        wallet.identities = self.identities;
+        Ok(wallet)
+    }
+}
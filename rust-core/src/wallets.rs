@@ -0,0 +1,980 @@
+use crate::checkpoint::SignedCheckpoint;
+use crate::identity::{verify_identities, IdentityAttestation, IdentityCheck};
+use crate::keyset::{audit_keyset, KeysetAuditOptions};
+use crate::metadata::{Contact, MetadataMergeReport, WalletMetadata};
+use crate::policy::{PolicySet, SpendingHistory};
+use crate::reconcile::NodeSnapshot;
+use crate::recovery::{KeyReplacement, ReplacementApproval};
+use crate::relock::LockChange;
+use crate::session::SigningSession;
+use crate::tracking::{ChainEvent, NoteTracker};
+use crate::verify::{
+    PrecomputedVerifier, SignatureVerifier, UnverifiedSignatures, VerificationItem,
+};
+use crate::wallet_backup::{WalletBackup, WalletBackupFile};
+use crate::{Lock, Note, NoteName, PkhCondition, PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Wallets
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WalletDescriptor {
+    pub label: String,
+    pub lock: Lock,
+}
+
+impl WalletDescriptor {
+    // Ids are derived from the lock, so the same multisig set always maps to
+    // the same wallet regardless of label or device.
+    pub fn id(&self) -> String {
+        self.lock.fingerprint()[..16].to_string()
+    }
+
+    // Every key that can sign on any branch of the lock.
+    pub fn participants(&self) -> Vec<PublicKey> {
+        let mut participants: Vec<PublicKey> = Vec::new();
+        for condition in self.lock.conditions() {
+            for pubkey in &condition.pkh.pubkeys {
+                if !participants.contains(pubkey) {
+                    participants.push(pubkey.clone());
+                }
+            }
+        }
+        participants
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Wallet {
+    pub id: String,
+    pub descriptor: WalletDescriptor,
+    #[serde(default)]
+    pub notes: Vec<Note>,
+    #[serde(default)]
+    pub sessions: Vec<SigningSession>,
+    #[serde(default)]
+    pub policies: PolicySet,
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default)]
+    pub watch_only: bool,
+    #[serde(default, skip_serializing_if = "NoteTracker::is_empty")]
+    pub tracker: NoteTracker,
+    // Drafts whose notes were reorged out or spent elsewhere, or that were
+    // replaced by a newer version; kept for the record, never offered for
+    // signing again.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub invalidated: Vec<SigningSession>,
+    // Invalidated drafts whose notes were spent by a transaction that did not
+    // come from this wallet.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<DraftConflict>,
+    #[serde(default, skip_serializing_if = "WalletMetadata::is_empty")]
+    pub metadata: WalletMetadata,
+    // Verified bindings of participant keys to their holders.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub identities: Vec<IdentityAttestation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DraftConflict {
+    pub draft: String,
+    pub note: NoteName,
+    // The competing transaction.
+    pub txid: String,
+}
+
+// Which notes coin selection may use, by their tags: a note must carry
+// every `require`d tag and none of the `exclude`d ones. General payouts
+// exclude restricted tags; a grant payout requires its grant's tag.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TagFilter {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub require: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+}
+
+impl TagFilter {
+    pub fn matches(&self, tags: &[String]) -> bool {
+        self.require.iter().all(|t| tags.contains(t))
+            && !self.exclude.iter().any(|t| tags.contains(t))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TrackingReport {
+    pub added: Vec<Note>,
+    pub removed: Vec<Note>,
+    // Draft hashes of the sessions moved to `invalidated`.
+    pub invalidated: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<DraftConflict>,
+}
+
+impl Wallet {
+    pub fn new(descriptor: WalletDescriptor) -> Result<Self, String> {
+        descriptor.lock.validate()?;
+        Ok(Self {
+            id: descriptor.id(),
+            descriptor,
+            notes: Vec::new(),
+            sessions: Vec::new(),
+            policies: PolicySet::default(),
+            archived: false,
+            watch_only: false,
+            tracker: NoteTracker::default(),
+            invalidated: Vec::new(),
+            conflicts: Vec::new(),
+            metadata: WalletMetadata::default(),
+            identities: Vec::new(),
+        })
+    }
+
+    pub fn rename(&mut self, name: String, device: &str, at: u64) {
+        self.metadata.set_name(name.clone(), device, at);
+        self.descriptor.label = name;
+    }
+
+    // Folds in labels, contacts and the name as edited on another device.
+    pub fn merge_metadata(&mut self, other: &WalletMetadata) -> MetadataMergeReport {
+        let report = self.metadata.merge(other);
+        if let Some(name) = self.metadata.name.as_ref().and_then(|e| e.value.clone()) {
+            self.descriptor.label = name;
+        }
+        report
+    }
+
+    // Replaces any earlier attestation for the same key.
+    pub fn attach_identity(
+        &mut self,
+        attestation: IdentityAttestation,
+        verifier: &dyn SignatureVerifier,
+    ) -> Result<IdentityCheck, String> {
+        let participants = self.descriptor.participants();
+        let check = verify_identities(std::slice::from_ref(&attestation), &participants, verifier)?
+            .remove(0);
+        self.identities
+            .retain(|a| a.claim.pubkey != attestation.claim.pubkey);
+        self.identities.push(attestation);
+        Ok(check)
+    }
+
+    pub fn balance(&self) -> u64 {
+        self.notes.iter().map(|n| n.value).sum()
+    }
+
+    // Notes no open session is spending.
+    pub fn free_notes(&self) -> Vec<Note> {
+        self.notes
+            .iter()
+            .filter(|note| {
+                !self.sessions.iter().any(|s| {
+                    s.transaction
+                        .spends
+                        .iter()
+                        .any(|spend| spend.note.name == note.name)
+                })
+            })
+            .cloned()
+            .collect()
+    }
+
+    pub fn free_notes_matching(&self, filter: &TagFilter) -> Vec<Note> {
+        self.free_notes()
+            .into_iter()
+            .filter(|note| filter.matches(self.metadata.tags_of(&note.name)))
+            .collect()
+    }
+
+    // Largest matching free notes first, until `amount` is covered.
+    pub fn select_notes(&self, amount: u64, filter: &TagFilter) -> Result<Vec<Note>, String> {
+        let mut notes = self.free_notes_matching(filter);
+        notes.sort_by_key(|n| std::cmp::Reverse(n.value));
+        let mut selected = Vec::new();
+        let mut total = 0u64;
+        for note in notes {
+            if total >= amount {
+                break;
+            }
+            total = total.saturating_add(note.value);
+            selected.push(note);
+        }
+        if total < amount {
+            return Err(format!(
+                "Wallet has {} in matching free notes, payment needs {}",
+                total, amount
+            ));
+        }
+        Ok(selected)
+    }
+
+    pub fn add_note(&mut self, note: Note) -> Result<(), String> {
+        if note.lock != self.descriptor.lock {
+            return Err("Note is not locked to this wallet".into());
+        }
+        if self.notes.iter().any(|n| n.name == note.name) {
+            return Err("Note already tracked by this wallet".into());
+        }
+        self.notes.push(note);
+        Ok(())
+    }
+
+    // The tip reported by chain events, or None before any arrived.
+    pub fn chain_height(&self) -> Option<u64> {
+        (self.tracker.tip > 0).then_some(self.tracker.tip)
+    }
+
+    // Feeds a node event to the tracker, then brings `notes` in line with it:
+    // notes that stopped being live leave the wallet, notes that became live
+    // (including ones whose spend was reorged away) come back, and drafts
+    // that can no longer land are moved to `invalidated`. A draft beaten by a
+    // spend that is none of the wallet's own drafts is also recorded as a
+    // conflict. Notes added by hand and never reported by the node are left
+    // alone.
+    pub fn apply_chain_event(&mut self, event: ChainEvent) -> Result<TrackingReport, String> {
+        if let ChainEvent::Seen { note } = &event {
+            if note.lock != self.descriptor.lock {
+                return Err("Note is not locked to this wallet".into());
+            }
+        }
+        self.tracker.apply(event)?;
+
+        let mut report = TrackingReport::default();
+        for tracked in &self.tracker.notes {
+            let held = self.notes.iter().position(|n| n.name == tracked.note.name);
+            match (held, tracked.is_live()) {
+                (None, true) => {
+                    self.notes.push(tracked.note.clone());
+                    report.added.push(tracked.note.clone());
+                }
+                (Some(i), false) => report.removed.push(self.notes.remove(i)),
+                (Some(i), true) => self.notes[i] = tracked.note.clone(),
+                (None, false) => {}
+            }
+        }
+
+        let (valid, stale): (Vec<_>, Vec<_>) = std::mem::take(&mut self.sessions)
+            .into_iter()
+            .partition(|s| self.tracker.draft_is_valid(&s.transaction));
+        let local: Vec<String> = valid
+            .iter()
+            .chain(&stale)
+            .chain(&self.invalidated)
+            .filter_map(|s| s.transaction.txid().ok())
+            .collect();
+        for session in &stale {
+            let draft = session.draft_hash();
+            for (note, txid) in self.tracker.competing_spends(&session.transaction) {
+                if !local.contains(&txid) {
+                    report.conflicts.push(DraftConflict {
+                        draft: draft.clone(),
+                        note,
+                        txid,
+                    });
+                }
+            }
+        }
+        self.sessions = valid;
+        report.invalidated = stale.iter().map(SigningSession::draft_hash).collect();
+        self.invalidated.extend(stale);
+        self.conflicts.extend(report.conflicts.iter().cloned());
+        Ok(report)
+    }
+
+    // The open session with this `draft_id`.
+    pub fn session_index(&self, draft_id: &str) -> Result<Option<usize>, String> {
+        for (i, session) in self.sessions.iter().enumerate() {
+            if session.draft_id()? == draft_id {
+                return Ok(Some(i));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn add_session(&mut self, session: SigningSession) -> Result<usize, String> {
+        if self.archived {
+            return Err("Wallet is archived".into());
+        }
+        if self.watch_only {
+            return Err("Wallet is watch-only".into());
+        }
+        for (i, spend) in session.transaction.spends.iter().enumerate() {
+            if spend.note.lock != self.descriptor.lock {
+                return Err(format!("Spend {} is not locked to this wallet", i));
+            }
+        }
+        self.policies.check_with_tags(
+            &session.transaction,
+            &SpendingHistory::default(),
+            0,
+            &self.metadata.note_tags(),
+        )?;
+
+        // The same draft built elsewhere joins the session already open.
+        if let Some(i) = self.session_index(&session.draft_id()?)? {
+            self.sessions[i].merge(&session)?;
+            return Ok(i);
+        }
+
+        // A newer version of a draft retires the one it replaces.
+        if let Some(lineage) = &session.transaction.lineage {
+            let previous = self
+                .sessions
+                .iter()
+                .position(|s| s.transaction.effective_lineage().id == lineage.id);
+            if let Some(i) = previous {
+                let sequence = self.sessions[i].transaction.effective_lineage().sequence;
+                if sequence >= lineage.sequence {
+                    return Err(format!(
+                        "Wallet already has sequence {} of this draft",
+                        sequence
+                    ));
+                }
+                let retired = self.sessions.remove(i);
+                self.invalidated.push(retired);
+            }
+        }
+
+        self.sessions.push(session);
+        Ok(self.sessions.len() - 1)
+    }
+}
+
+// ============================================================================
+// Watch-only Export
+// ============================================================================
+
+pub const WATCH_ONLY_FORMAT_VERSION: u32 = 1;
+
+// Everything needed to monitor a wallet and nothing needed to spend from it.
+// The crate never holds private keys, so the only thing to leave out is the
+// ability to start new sessions: imports are flagged `watch_only`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WatchOnlyExport {
+    pub format: u32,
+    pub wallet_id: String,
+    pub descriptor: WalletDescriptor,
+    pub notes: Vec<Note>,
+    pub history: Vec<SigningSession>,
+    #[serde(default, skip_serializing_if = "WalletMetadata::is_empty")]
+    pub metadata: WalletMetadata,
+}
+
+impl WatchOnlyExport {
+    pub fn from_wallet(wallet: &Wallet) -> Self {
+        Self {
+            format: WATCH_ONLY_FORMAT_VERSION,
+            wallet_id: wallet.id.clone(),
+            descriptor: wallet.descriptor.clone(),
+            notes: wallet.notes.clone(),
+            history: wallet.sessions.clone(),
+            metadata: wallet.metadata.clone(),
+        }
+    }
+
+    pub fn into_wallet(self) -> Result<Wallet, String> {
+        if self.format > WATCH_ONLY_FORMAT_VERSION {
+            return Err(format!("Unsupported watch-only format {}", self.format));
+        }
+        if self.descriptor.id() != self.wallet_id {
+            return Err("Wallet id does not match descriptor".into());
+        }
+
+        let mut wallet = Wallet::new(self.descriptor)?;
+        wallet.watch_only = true;
+        for note in self.notes {
+            wallet.add_note(note)?;
+        }
+        for (i, session) in self.history.iter().enumerate() {
+            for spend in &session.transaction.spends {
+                if spend.note.lock != wallet.descriptor.lock {
+                    return Err(format!("History entry {} spends a foreign note", i));
+                }
+            }
+        }
+        wallet.sessions = self.history;
+        wallet.merge_metadata(&self.metadata);
+        Ok(wallet)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WalletSummary {
+    pub id: String,
+    pub label: String,
+    pub threshold: usize,
+    pub signers: usize,
+    pub balance: u64,
+    pub notes: usize,
+    pub sessions: usize,
+    pub archived: bool,
+    pub watch_only: bool,
+    pub active: bool,
+}
+
+// Owned by the caller (a JS object on the web), never a global, so several
+// managers can coexist in one page.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WalletManager {
+    wallets: Vec<Wallet>,
+    active: Option<String>,
+}
+
+impl WalletManager {
+    pub fn wallet(&self, id: &str) -> Result<&Wallet, String> {
+        self.wallets
+            .iter()
+            .find(|w| w.id == id)
+            .ok_or_else(|| format!("Unknown wallet {}", id))
+    }
+
+    pub fn wallet_mut(&mut self, id: &str) -> Result<&mut Wallet, String> {
+        self.wallets
+            .iter_mut()
+            .find(|w| w.id == id)
+            .ok_or_else(|| format!("Unknown wallet {}", id))
+    }
+
+    pub fn active_wallet_mut(&mut self) -> Result<&mut Wallet, String> {
+        let id = self.active.clone().ok_or("No active wallet")?;
+        self.wallet_mut(&id)
+    }
+
+    pub fn insert(&mut self, wallet: Wallet) -> Result<String, String> {
+        if self.wallets.iter().any(|w| w.id == wallet.id) {
+            return Err(format!("Wallet {} already exists", wallet.id));
+        }
+        let id = wallet.id.clone();
+        self.wallets.push(wallet);
+        if self.active.is_none() {
+            self.active = Some(id.clone());
+        }
+        Ok(id)
+    }
+
+    pub fn wallets(&self) -> &[Wallet] {
+        &self.wallets
+    }
+
+    pub fn summaries(&self) -> Vec<WalletSummary> {
+        self.wallets
+            .iter()
+            .map(|w| WalletSummary {
+                id: w.id.clone(),
+                label: w.descriptor.label.clone(),
+                threshold: w.descriptor.lock.pkh.threshold,
+                signers: w.descriptor.lock.pkh.pubkeys.len(),
+                balance: w.balance(),
+                notes: w.notes.len(),
+                sessions: w.sessions.len(),
+                archived: w.archived,
+                watch_only: w.watch_only,
+                active: self.active.as_deref() == Some(w.id.as_str()),
+            })
+            .collect()
+    }
+}
+
+#[wasm_bindgen]
+impl WalletManager {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_json(json: &str) -> Result<WalletManager, String> {
+        serde_json::from_str(json).map_err(|e| e.to_string())
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string(self).map_err(|e| e.to_string())
+    }
+
+    pub fn create_wallet(&mut self, descriptor_json: &str) -> Result<String, String> {
+        let descriptor: WalletDescriptor =
+            serde_json::from_str(descriptor_json).map_err(|e| e.to_string())?;
+        self.insert(Wallet::new(descriptor)?)
+    }
+
+    pub fn list_wallets(&self, include_archived: bool) -> Result<String, String> {
+        let summaries: Vec<WalletSummary> = self
+            .summaries()
+            .into_iter()
+            .filter(|s| include_archived || !s.archived)
+            .collect();
+        serde_json::to_string(&summaries).map_err(|e| e.to_string())
+    }
+
+    pub fn get_wallet(&self, id: &str) -> Result<String, String> {
+        serde_json::to_string(self.wallet(id)?).map_err(|e| e.to_string())
+    }
+
+    pub fn switch_wallet(&mut self, id: &str) -> Result<(), String> {
+        if self.wallet(id)?.archived {
+            return Err("Cannot switch to an archived wallet".into());
+        }
+        self.active = Some(id.to_string());
+        Ok(())
+    }
+
+    pub fn active_wallet_id(&self) -> Option<String> {
+        self.active.clone()
+    }
+
+    pub fn archive_wallet(&mut self, id: &str) -> Result<(), String> {
+        self.wallet_mut(id)?.archived = true;
+        if self.active.as_deref() == Some(id) {
+            self.active = self.wallets.iter().find(|w| !w.archived).map(|w| w.id.clone());
+        }
+        Ok(())
+    }
+
+    pub fn unarchive_wallet(&mut self, id: &str) -> Result<(), String> {
+        self.wallet_mut(id)?.archived = false;
+        Ok(())
+    }
+
+    pub fn add_note(&mut self, id: &str, note_json: &str) -> Result<(), String> {
+        let note: Note = serde_json::from_str(note_json).map_err(|e| e.to_string())?;
+        self.wallet_mut(id)?.add_note(note)
+    }
+
+    pub fn apply_chain_event(&mut self, id: &str, event_json: &str) -> Result<String, String> {
+        let event: ChainEvent = serde_json::from_str(event_json).map_err(|e| e.to_string())?;
+        let report = self.wallet_mut(id)?.apply_chain_event(event)?;
+        serde_json::to_string(&report).map_err(|e| e.to_string())
+    }
+
+    // Starts the wallet's scanner at a checkpoint signed by a threshold of
+    // `signers_json` (a pkh condition), with signatures checked by the
+    // caller (e.g. WebCrypto over `get_checkpoint_verification_items`).
+    // Returns the first height to scan.
+    pub fn start_from_checkpoint(
+        &mut self,
+        id: &str,
+        signed_json: &str,
+        signers_json: &str,
+        verified_json: &str,
+    ) -> Result<u64, String> {
+        let signed: SignedCheckpoint =
+            serde_json::from_str(signed_json).map_err(|e| e.to_string())?;
+        let signers: PkhCondition =
+            serde_json::from_str(signers_json).map_err(|e| e.to_string())?;
+        let verified: Vec<VerificationItem> =
+            serde_json::from_str(verified_json).map_err(|e| e.to_string())?;
+
+        let checkpoint = signed.verify(&signers, &PrecomputedVerifier::new(verified))?;
+        self.wallet_mut(id)?.tracker.start_from(checkpoint.clone())
+    }
+
+    // Bootstraps the wallet's notes from a state dump (see `read_state_dump`)
+    // matching a checkpoint verified as in `start_from_checkpoint`. Returns
+    // the first height to scan.
+    pub fn import_state_dump(
+        &mut self,
+        id: &str,
+        dump: &str,
+        signed_json: &str,
+        signers_json: &str,
+        verified_json: &str,
+    ) -> Result<u64, String> {
+        let signed: SignedCheckpoint =
+            serde_json::from_str(signed_json).map_err(|e| e.to_string())?;
+        let signers: PkhCondition =
+            serde_json::from_str(signers_json).map_err(|e| e.to_string())?;
+        let verified: Vec<VerificationItem> =
+            serde_json::from_str(verified_json).map_err(|e| e.to_string())?;
+
+        let checkpoint = signed.verify(&signers, &PrecomputedVerifier::new(verified))?;
+        self.wallet_mut(id)?.import_state_dump(dump, checkpoint)
+    }
+
+    pub fn get_note_states(&self, id: &str) -> Result<String, String> {
+        serde_json::to_string(&self.wallet(id)?.tracker).map_err(|e| e.to_string())
+    }
+
+    // Compares the wallet against `snapshot_json`, the node's view of its
+    // lock as fetched by the host. Returns a `ReconciliationReport`.
+    pub fn reconcile(&self, id: &str, snapshot_json: &str) -> Result<String, String> {
+        let snapshot: NodeSnapshot =
+            serde_json::from_str(snapshot_json).map_err(|e| e.to_string())?;
+        let report = self.wallet(id)?.reconcile(&snapshot)?;
+        serde_json::to_string(&report).map_err(|e| e.to_string())
+    }
+
+    // Index of the open session with this `draft_id`, if any.
+    pub fn find_session(&self, id: &str, draft_id: &str) -> Result<Option<usize>, String> {
+        self.wallet(id)?.session_index(draft_id)
+    }
+
+    // Adding a draft that is already open merges its signatures into that
+    // session and returns its index.
+    pub fn add_session(&mut self, id: &str, session_json: &str) -> Result<usize, String> {
+        let session = SigningSession::from_json(session_json)?;
+        self.wallet_mut(id)?.add_session(session)
+    }
+
+    pub fn set_policies(&mut self, id: &str, policies_json: &str) -> Result<(), String> {
+        let policies: PolicySet = serde_json::from_str(policies_json).map_err(|e| e.to_string())?;
+        self.wallet_mut(id)?.policies = policies;
+        Ok(())
+    }
+
+    // `at` is the device clock (e.g. `Date.now()`); `device` any stable id
+    // for this install.
+    pub fn rename_wallet(
+        &mut self,
+        id: &str,
+        name: &str,
+        device: &str,
+        at: u64,
+    ) -> Result<(), String> {
+        self.wallet_mut(id)?.rename(name.to_string(), device, at);
+        Ok(())
+    }
+
+    // An empty label removes it.
+    pub fn set_label(
+        &mut self,
+        id: &str,
+        key: &str,
+        label: &str,
+        device: &str,
+        at: u64,
+    ) -> Result<(), String> {
+        self.wallet_mut(id)?
+            .metadata
+            .set_label(key, label.to_string(), device, at);
+        Ok(())
+    }
+
+    // Returns the contact's key, its lock fingerprint.
+    pub fn set_contact(
+        &mut self,
+        id: &str,
+        contact_json: &str,
+        device: &str,
+        at: u64,
+    ) -> Result<String, String> {
+        let contact: Contact = serde_json::from_str(contact_json).map_err(|e| e.to_string())?;
+        contact.lock.validate()?;
+        Ok(self
+            .wallet_mut(id)?
+            .metadata
+            .set_contact(contact, device, at))
+    }
+
+    pub fn remove_contact(
+        &mut self,
+        id: &str,
+        key: &str,
+        device: &str,
+        at: u64,
+    ) -> Result<(), String> {
+        self.wallet_mut(id)?
+            .metadata
+            .remove_contact(key, device, at)
+    }
+
+    // `name_json` is the note's name; an empty `tags_json` list untags it.
+    pub fn set_note_tags(
+        &mut self,
+        id: &str,
+        name_json: &str,
+        tags_json: &str,
+        device: &str,
+        at: u64,
+    ) -> Result<(), String> {
+        let name: NoteName = serde_json::from_str(name_json).map_err(|e| e.to_string())?;
+        let tags: Vec<String> = serde_json::from_str(tags_json).map_err(|e| e.to_string())?;
+        self.wallet_mut(id)?
+            .metadata
+            .set_note_tags(&name, tags, device, at)
+    }
+
+    // `{"<first>/<last>": [tags]}` for every tagged note.
+    pub fn get_note_tags(&self, id: &str) -> Result<String, String> {
+        serde_json::to_string(&self.wallet(id)?.metadata.note_tags()).map_err(|e| e.to_string())
+    }
+
+    // Picks free notes covering `amount` that pass `filter_json`
+    // (`{require, exclude}`), to pass to the builder.
+    pub fn select_notes(&self, id: &str, amount: u64, filter_json: &str) -> Result<String, String> {
+        let filter: TagFilter = serde_json::from_str(filter_json).map_err(|e| e.to_string())?;
+        let notes = self.wallet(id)?.select_notes(amount, &filter)?;
+        serde_json::to_string(&notes).map_err(|e| e.to_string())
+    }
+
+    pub fn export_metadata(&self, id: &str) -> Result<String, String> {
+        serde_json::to_string(&self.wallet(id)?.metadata).map_err(|e| e.to_string())
+    }
+
+    // Merges metadata exported on another device; safe to repeat and to run
+    // in either direction. Returns `{updated, superseded}`.
+    pub fn merge_metadata(&mut self, id: &str, metadata_json: &str) -> Result<String, String> {
+        let metadata: WalletMetadata =
+            serde_json::from_str(metadata_json).map_err(|e| e.to_string())?;
+        let report = self.wallet_mut(id)?.merge_metadata(&metadata);
+        serde_json::to_string(&report).map_err(|e| e.to_string())
+    }
+
+    pub fn export_watch_only(&self, id: &str) -> Result<String, String> {
+        let export = WatchOnlyExport::from_wallet(self.wallet(id)?);
+        serde_json::to_string(&export).map_err(|e| e.to_string())
+    }
+
+    // An empty passphrase writes a plain file, MAC-protected against
+    // corruption only; otherwise `salt_hex` must be 16+ random bytes.
+    pub fn export_wallet_backup(
+        &self,
+        id: &str,
+        passphrase: &str,
+        salt_hex: &str,
+    ) -> Result<String, String> {
+        let backup = WalletBackup::from_wallet(self.wallet(id)?);
+        let salt = hex::decode(salt_hex).map_err(|_| "Salt must be hex")?;
+        let passphrase = (!passphrase.is_empty()).then_some((passphrase, salt.as_slice()));
+        serde_json::to_string(&backup.to_file(passphrase)?).map_err(|e| e.to_string())
+    }
+
+    // Restoring over an existing wallet only merges its labels, contacts and
+    // identities; its policies are kept. Returns the wallet id. Identity
+    // attestations in the backup must all be in `verified_json` (see
+    // `get_identity_verification_items`).
+    pub fn import_wallet_backup(
+        &mut self,
+        file_json: &str,
+        passphrase: &str,
+        verified_json: Option<String>,
+    ) -> Result<String, String> {
+        let file: WalletBackupFile = serde_json::from_str(file_json).map_err(|e| e.to_string())?;
+        let passphrase = (!passphrase.is_empty()).then_some(passphrase);
+        let backup = WalletBackup::from_file(file, passphrase)?;
+        let verified: Vec<VerificationItem> = match verified_json {
+            Some(json) => serde_json::from_str(&json).map_err(|e| e.to_string())?,
+            None => Vec::new(),
+        };
+        backup.verify_identities(&PrecomputedVerifier::new(verified))?;
+
+        match self.wallets.iter_mut().find(|w| w.id == backup.wallet_id) {
+            Some(existing) => {
+                existing.merge_metadata(&backup.metadata);
+                for attestation in backup.identities {
+                    let pubkey = &attestation.claim.pubkey;
+                    if !existing
+                        .identities
+                        .iter()
+                        .any(|a| &a.claim.pubkey == pubkey)
+                    {
+                        existing.identities.push(attestation);
+                    }
+                }
+                Ok(existing.id.clone())
+            }
+            None => self.insert(backup.into_wallet()?),
+        }
+    }
+
+    // Importing over an existing wallet refreshes its notes and history but
+    // never downgrades a full wallet to watch-only.
+    pub fn import_watch_only(&mut self, export_json: &str) -> Result<String, String> {
+        let export: WatchOnlyExport =
+            serde_json::from_str(export_json).map_err(|e| e.to_string())?;
+        let imported = export.into_wallet()?;
+
+        match self.wallets.iter_mut().find(|w| w.id == imported.id) {
+            Some(existing) => {
+                for note in imported.notes {
+                    if !existing.notes.iter().any(|n| n.name == note.name) {
+                        existing.notes.push(note);
+                    }
+                }
+                if existing.watch_only {
+                    existing.sessions = imported.sessions;
+                }
+                existing.merge_metadata(&imported.metadata);
+                Ok(existing.id.clone())
+            }
+            None => self.insert(imported),
+        }
+    }
+
+    // `verified_json` lists the attestation's verification items that
+    // checked out.
+    pub fn attach_identity(
+        &mut self,
+        id: &str,
+        attestation_json: &str,
+        verified_json: &str,
+    ) -> Result<String, String> {
+        let attestation: IdentityAttestation =
+            serde_json::from_str(attestation_json).map_err(|e| e.to_string())?;
+        let verified: Vec<VerificationItem> =
+            serde_json::from_str(verified_json).map_err(|e| e.to_string())?;
+        let check = self
+            .wallet_mut(id)?
+            .attach_identity(attestation, &PrecomputedVerifier::new(verified))?;
+        serde_json::to_string(&check).map_err(|e| e.to_string())
+    }
+
+    pub fn get_identities(&self, id: &str) -> Result<String, String> {
+        serde_json::to_string(&self.wallet(id)?.identities).map_err(|e| e.to_string())
+    }
+
+    // Reuse is checked against every other wallet in the manager.
+    pub fn audit_keyset(&self, id: &str, options_json: &str) -> Result<String, String> {
+        let options: KeysetAuditOptions =
+            serde_json::from_str(options_json).map_err(|e| e.to_string())?;
+        let report = audit_keyset(self.wallet(id)?, &self.wallets, &options);
+        serde_json::to_string(&report).map_err(|e| e.to_string())
+    }
+
+    pub fn start_key_replacement(
+        &self,
+        id: &str,
+        lost: &str,
+        replacement: &str,
+    ) -> Result<String, String> {
+        let replacement = KeyReplacement::new(
+            self.wallet(id)?,
+            PublicKey(lost.to_string()),
+            PublicKey(replacement.to_string()),
+        )?;
+        serde_json::to_string(&replacement).map_err(|e| e.to_string())
+    }
+
+    pub fn approve_key_replacement(
+        &self,
+        replacement_json: &str,
+        signer: &str,
+        signature: &str,
+    ) -> Result<String, String> {
+        let mut replacement: KeyReplacement =
+            serde_json::from_str(replacement_json).map_err(|e| e.to_string())?;
+        let approval = ReplacementApproval {
+            signer: PublicKey(signer.to_string()),
+            signature: Signature(signature.to_string()),
+        };
+        replacement.approve(
+            self.wallet(&replacement.wallet)?,
+            approval,
+            &UnverifiedSignatures,
+        )?;
+        serde_json::to_string(&replacement).map_err(|e| e.to_string())
+    }
+
+    // The sweep becomes the old wallet's newest session.
+    pub fn build_key_replacement_sweep(
+        &mut self,
+        replacement_json: &str,
+    ) -> Result<String, String> {
+        let mut replacement: KeyReplacement =
+            serde_json::from_str(replacement_json).map_err(|e| e.to_string())?;
+        replacement.build_sweep(self.wallet_mut(&replacement.wallet.clone())?)?;
+        serde_json::to_string(&replacement).map_err(|e| e.to_string())
+    }
+
+    pub fn verify_key_replacement(&self, replacement_json: &str) -> Result<(), String> {
+        let replacement: KeyReplacement =
+            serde_json::from_str(replacement_json).map_err(|e| e.to_string())?;
+        replacement.verify_complete(self.wallet(&replacement.wallet)?)
+    }
+
+    // Archives the old wallet and registers its successor.
+    pub fn complete_key_replacement(&mut self, replacement_json: &str) -> Result<String, String> {
+        let mut replacement: KeyReplacement =
+            serde_json::from_str(replacement_json).map_err(|e| e.to_string())?;
+        let successor = replacement.new_wallet_id();
+        if self.wallet(&successor).is_ok() {
+            return Err(format!("Wallet {} already exists", successor));
+        }
+        let successor = replacement.complete(self.wallet_mut(&replacement.wallet.clone())?)?;
+        let successor = self.insert(successor)?;
+        if self.active.as_deref() == Some(replacement.wallet.as_str()) {
+            self.active = Some(successor);
+        }
+        serde_json::to_string(&replacement).map_err(|e| e.to_string())
+    }
+
+    // `reason` may be empty.
+    pub fn propose_lock_change(
+        &self,
+        id: &str,
+        new_lock_json: &str,
+        reason: &str,
+    ) -> Result<String, String> {
+        let new_lock: Lock = serde_json::from_str(new_lock_json).map_err(|e| e.to_string())?;
+        let reason = Some(reason.to_string()).filter(|r| !r.is_empty());
+        let change = LockChange::new(self.wallet(id)?, new_lock, reason)?;
+        serde_json::to_string(&change).map_err(|e| e.to_string())
+    }
+
+    // Threshold and key changes on the primary branch, for approvers.
+    pub fn get_lock_change_summary(&self, change_json: &str) -> Result<String, String> {
+        let change: LockChange = serde_json::from_str(change_json).map_err(|e| e.to_string())?;
+        let summary = change.summary(self.wallet(&change.wallet)?)?;
+        serde_json::to_string(&summary).map_err(|e| e.to_string())
+    }
+
+    pub fn approve_lock_change(
+        &self,
+        change_json: &str,
+        signer: &str,
+        signature: &str,
+    ) -> Result<String, String> {
+        let mut change: LockChange =
+            serde_json::from_str(change_json).map_err(|e| e.to_string())?;
+        let approval = ReplacementApproval {
+            signer: PublicKey(signer.to_string()),
+            signature: Signature(signature.to_string()),
+        };
+        change.approve(
+            self.wallet(&change.wallet)?,
+            approval,
+            &UnverifiedSignatures,
+        )?;
+        serde_json::to_string(&change).map_err(|e| e.to_string())
+    }
+
+    // The sweep becomes the old wallet's newest session.
+    pub fn build_lock_change_sweep(&mut self, change_json: &str) -> Result<String, String> {
+        let mut change: LockChange =
+            serde_json::from_str(change_json).map_err(|e| e.to_string())?;
+        change.build_sweep(self.wallet_mut(&change.wallet.clone())?)?;
+        serde_json::to_string(&change).map_err(|e| e.to_string())
+    }
+
+    pub fn verify_lock_change(&self, change_json: &str) -> Result<(), String> {
+        let change: LockChange = serde_json::from_str(change_json).map_err(|e| e.to_string())?;
+        change.verify_complete(self.wallet(&change.wallet)?)
+    }
+
+    // Archives the old wallet and registers its successor.
+    pub fn complete_lock_change(&mut self, change_json: &str) -> Result<String, String> {
+        let mut change: LockChange =
+            serde_json::from_str(change_json).map_err(|e| e.to_string())?;
+        let successor = change.new_wallet_id();
+        if self.wallet(&successor).is_ok() {
+            return Err(format!("Wallet {} already exists", successor));
+        }
+        let successor = change.complete(self.wallet_mut(&change.wallet.clone())?)?;
+        let successor = self.insert(successor)?;
+        if self.active.as_deref() == Some(change.wallet.as_str()) {
+            self.active = Some(successor);
+        }
+        serde_json::to_string(&change).map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,101 @@
+use crate::session::{SignatureConflict, SigningSession};
+use crate::verify::UnverifiedSignatures;
+use crate::{signing_status, SigningStatus};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Worker Interface
+// ============================================================================
+
+// Every call takes the full session in and hands the full session back, so a
+// Web Worker can own the WASM instance without any state living between
+// messages. Requests and responses are JSON strings, which structured clone
+// copies cheaply across `postMessage`.
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case", deny_unknown_fields)]
+pub enum WorkerRequest {
+    RefreshHashes { session: SigningSession },
+    Validate { session: SigningSession },
+    Merge { session: SigningSession, other: Box<SigningSession> },
+    SigningStatus { session: SigningSession, spend_index: usize },
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct WorkerResponse {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session: Option<SigningSession>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merged: Option<usize>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<SignatureConflict>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<SigningStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl WorkerResponse {
+    fn failed(error: String) -> Self {
+        Self {
+            error: Some(error),
+            ..Self::default()
+        }
+    }
+}
+
+pub fn handle(request: WorkerRequest) -> Result<WorkerResponse, String> {
+    let mut response = WorkerResponse {
+        ok: true,
+        ..WorkerResponse::default()
+    };
+
+    match request {
+        WorkerRequest::RefreshHashes { mut session } => {
+            session.transaction.refresh_spend_hashes()?;
+            response.session = Some(session);
+        }
+        WorkerRequest::Validate { session } => {
+            session.transaction.validate_hash_algorithm()?;
+            session.transaction.validate_balance()?;
+            session.transaction.validate_signatures()?;
+            response.session = Some(session);
+        }
+        WorkerRequest::Merge { mut session, other } => {
+            let report = session.merge_verified(&other, &UnverifiedSignatures)?;
+            response.merged = Some(report.added + report.replaced);
+            response.conflicts = report.conflicts;
+            response.session = Some(session);
+        }
+        WorkerRequest::SigningStatus {
+            session,
+            spend_index,
+        } => {
+            if spend_index >= session.transaction.spends.len() {
+                return Err("Spend index out of bounds".into());
+            }
+            response.status = Some(signing_status(spend_index, &session.transaction)?);
+        }
+    }
+    Ok(response)
+}
+
+// Never throws: failures come back as `{"ok":false,"error":...}` so the worker
+// can forward the result verbatim.
+#[wasm_bindgen]
+pub fn worker_dispatch(request_json: &str) -> String {
+    let response = serde_json::from_str::<WorkerRequest>(request_json)
+        .map_err(|e| e.to_string())
+        .and_then(handle)
+        .unwrap_or_else(WorkerResponse::failed);
+
+    serde_json::to_string(&response).expect("Serialization failed")
+}
+
+#[wasm_bindgen]
+pub fn new_session(tx_json: &str) -> Result<String, String> {
+    let tx = serde_json::from_str(tx_json).map_err(|e| e.to_string())?;
+    SigningSession::new(tx).to_json()
+}